@@ -0,0 +1,508 @@
+//! Columns exported through the Arrow C stream interface (`__arrow_c_stream__`), so polars,
+//! pyarrow, duckdb and other Arrow consumers take the buffers built here as they are, without
+//! another copy. Only what `extract_data` produces is covered: `Utf8View` string columns,
+//! large lists of them and dictionary-encoded `Utf8` columns, in a single record batch.
+
+use ahash::AHashMap;
+use pyo3::prelude::*;
+use pyo3::types::PyCapsule;
+use std::any::Any;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::ptr;
+use std::sync::Arc;
+
+/// Strings up to this many bytes are stored inside their view
+const INLINE_LEN: usize = 12;
+
+/// Data buffers are addressed by i32 offsets in views
+const MAX_BUFFER_LEN: usize = i32::MAX as usize;
+
+/// A column dictionary-encoded on request for low cardinality keeps its dictionary while it
+/// has at most this many distinct values, or at most one per two rows
+const DICTIONARY_MIN_VALUES: usize = 64;
+
+#[repr(C)]
+struct ArrowSchema {
+    format: *const c_char,
+    name: *const c_char,
+    metadata: *const c_char,
+    flags: i64,
+    n_children: i64,
+    children: *mut *mut ArrowSchema,
+    dictionary: *mut ArrowSchema,
+    release: Option<unsafe extern "C" fn(*mut ArrowSchema)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArray {
+    length: i64,
+    null_count: i64,
+    offset: i64,
+    n_buffers: i64,
+    n_children: i64,
+    buffers: *mut *const c_void,
+    children: *mut *mut ArrowArray,
+    dictionary: *mut ArrowArray,
+    release: Option<unsafe extern "C" fn(*mut ArrowArray)>,
+    private_data: *mut c_void,
+}
+
+#[repr(C)]
+struct ArrowArrayStream {
+    get_schema: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowSchema) -> c_int>,
+    get_next: Option<unsafe extern "C" fn(*mut ArrowArrayStream, *mut ArrowArray) -> c_int>,
+    get_last_error: Option<unsafe extern "C" fn(*mut ArrowArrayStream) -> *const c_char>,
+    release: Option<unsafe extern "C" fn(*mut ArrowArrayStream)>,
+    private_data: *mut c_void,
+}
+
+// SAFETY: the stream owns everything its pointers reach, and consumers may move it between
+// threads as the C stream interface allows
+unsafe impl Send for ArrowArrayStream {}
+
+/// A `Utf8View` array under construction: one 16-byte view per string, longer strings copied
+/// once into shared data buffers
+#[derive(Default)]
+pub struct StringColumn {
+    views: Vec<u128>,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl StringColumn {
+    pub fn push(&mut self, value: &str) {
+        let bytes = value.as_bytes();
+        let mut view = [0u8; 16];
+        view[..4].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+        if bytes.len() <= INLINE_LEN {
+            view[4..4 + bytes.len()].copy_from_slice(bytes);
+        } else {
+            if self.buffers.last().is_none_or(|buffer| buffer.len() + bytes.len() > MAX_BUFFER_LEN) {
+                self.buffers.push(Vec::new());
+            }
+            let index = self.buffers.len() - 1;
+            let buffer = &mut self.buffers[index];
+            view[4..8].copy_from_slice(&bytes[..4]);
+            view[8..12].copy_from_slice(&(index as u32).to_le_bytes());
+            view[12..16].copy_from_slice(&(buffer.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(bytes);
+        }
+        self.views.push(u128::from_le_bytes(view));
+    }
+
+    fn len(&self) -> usize {
+        self.views.len()
+    }
+}
+
+/// A dictionary-encoded string column under construction: an `i32` index per row into
+/// `Utf8` values, each distinct value stored once
+pub struct DictionaryColumn {
+    indices: Vec<i32>,
+    /// Value `i` is `data[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<i32>,
+    data: Vec<u8>,
+    lookup: AHashMap<Box<str>, i32>,
+}
+
+impl DictionaryColumn {
+    fn new() -> Self {
+        DictionaryColumn { indices: Vec::new(), offsets: vec![0], data: Vec::new(), lookup: AHashMap::new() }
+    }
+
+    fn push(&mut self, value: &str) {
+        let index = match self.lookup.get(value) {
+            Some(&index) => index,
+            None => {
+                let index = self.lookup.len() as i32;
+                self.data.extend_from_slice(value.as_bytes());
+                self.offsets.push(self.data.len() as i32);
+                self.lookup.insert(value.into(), index);
+                index
+            }
+        };
+        self.indices.push(index);
+    }
+
+    /// Whether the values are few enough for a dictionary to pay off
+    fn low_cardinality(&self) -> bool {
+        self.lookup.len() <= DICTIONARY_MIN_VALUES || self.lookup.len() * 2 <= self.indices.len()
+    }
+
+    /// Whether `value` can be added within the `i32` offsets of `Utf8` values
+    fn fits(&self, value: &str) -> bool {
+        self.lookup.contains_key(value) || self.data.len() + value.len() <= MAX_BUFFER_LEN
+    }
+
+    fn decode(&self) -> StringColumn {
+        let mut column = StringColumn::default();
+        for &index in &self.indices {
+            let (start, end) = (self.offsets[index as usize] as usize, self.offsets[index as usize + 1] as usize);
+            // Every value was pushed as a whole str
+            column.push(std::str::from_utf8(&self.data[start..end]).unwrap());
+        }
+        column
+    }
+}
+
+/// One column of a table
+pub enum Column {
+    Strings(StringColumn),
+    /// A list of strings per row: row `i` holds `values[offsets[i]..offsets[i + 1]]`
+    Lists { offsets: Vec<i64>, values: StringColumn },
+    /// Strings dictionary-encoded; with `auto` the column turns back into `Strings` once it
+    /// has too many distinct values
+    Dictionary { column: DictionaryColumn, auto: bool },
+}
+
+impl Column {
+    pub fn strings() -> Self {
+        Column::Strings(StringColumn::default())
+    }
+
+    pub fn lists() -> Self {
+        Column::Lists { offsets: vec![0], values: StringColumn::default() }
+    }
+
+    /// A dictionary-encoded string column; with `auto` only while its cardinality stays low
+    pub fn dictionary(auto: bool) -> Self {
+        Column::Dictionary { column: DictionaryColumn::new(), auto }
+    }
+
+    /// Append one row holding `values`; a string column takes the first or an empty string
+    pub fn push<'a>(&mut self, values: impl IntoIterator<Item = &'a str>) {
+        match self {
+            Column::Strings(column) => column.push(values.into_iter().next().unwrap_or_default()),
+            Column::Lists { offsets, values: column } => {
+                values.into_iter().for_each(|value| column.push(value));
+                offsets.push(column.len() as i64);
+            }
+            Column::Dictionary { column, auto } => {
+                let value = values.into_iter().next().unwrap_or_default();
+                let fits = column.fits(value);
+                if fits {
+                    column.push(value);
+                }
+                if !fits || (*auto && !column.low_cardinality()) {
+                    let mut strings = column.decode();
+                    if !fits {
+                        strings.push(value);
+                    }
+                    *self = Column::Strings(strings);
+                }
+            }
+        }
+    }
+
+    /// Turn an `auto` dictionary column whose values turned out not to repeat back into plain
+    /// strings, now that all its rows are in
+    fn settle(&mut self) {
+        if let Column::Dictionary { column, auto: true } = self {
+            if column.lookup.len() * 2 > column.indices.len() {
+                *self = Column::Strings(column.decode());
+            }
+        }
+    }
+}
+
+/// Named columns of equal length, handed out as a single record batch
+pub struct Table {
+    names: Vec<String>,
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl Table {
+    pub fn new(names: Vec<String>, columns: Vec<Column>) -> Self {
+        Table { names, columns, rows: 0 }
+    }
+
+    pub fn columns_mut(&mut self) -> &mut [Column] {
+        &mut self.columns
+    }
+
+    /// Count a row once every column has been given its value
+    pub fn finish_row(&mut self) {
+        self.rows += 1;
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Settle `auto` dictionary columns once every row is in
+    fn finish(mut self) -> Self {
+        self.columns.iter_mut().for_each(Column::settle);
+        self
+    }
+}
+
+/// Owned by an exported schema: the strings and children its pointers reach
+struct SchemaPrivate {
+    format: CString,
+    name: CString,
+    children: Vec<*mut ArrowSchema>,
+    /// Null unless set by `with_dictionary_schema`
+    dictionary: *mut ArrowSchema,
+}
+
+fn export_schema(format: &str, name: &str, children: Vec<ArrowSchema>) -> ArrowSchema {
+    let mut private = Box::new(SchemaPrivate {
+        format: CString::new(format).unwrap(),
+        name: CString::new(name.replace('\0', "")).unwrap(),
+        children: children.into_iter().map(|child| Box::into_raw(Box::new(child))).collect(),
+        dictionary: ptr::null_mut(),
+    });
+    ArrowSchema {
+        format: private.format.as_ptr(),
+        name: private.name.as_ptr(),
+        metadata: ptr::null(),
+        flags: 0,
+        n_children: private.children.len() as i64,
+        children: private.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_schema),
+        private_data: Box::into_raw(private).cast(),
+    }
+}
+
+unsafe extern "C" fn release_schema(schema: *mut ArrowSchema) {
+    let Some(schema) = schema.as_mut() else { return };
+    if schema.release.is_none() {
+        return;
+    }
+    let private = Box::from_raw(schema.private_data.cast::<SchemaPrivate>());
+    for &child in private.children.iter().chain(Some(&private.dictionary).filter(|dictionary| !dictionary.is_null())) {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    schema.release = None;
+}
+
+/// `schema`, of the indices, with `dictionary` as the schema of its values
+fn with_dictionary_schema(mut schema: ArrowSchema, dictionary: ArrowSchema) -> ArrowSchema {
+    // SAFETY: `schema` was just exported, so its private data is a live `SchemaPrivate`
+    let private = unsafe { &mut *schema.private_data.cast::<SchemaPrivate>() };
+    private.dictionary = Box::into_raw(Box::new(dictionary));
+    schema.dictionary = private.dictionary;
+    schema
+}
+
+fn field_schema(name: &str, column: &Column) -> ArrowSchema {
+    match column {
+        Column::Strings(_) => export_schema("vu", name, Vec::new()),
+        Column::Lists { .. } => export_schema("+L", name, vec![export_schema("vu", "item", Vec::new())]),
+        Column::Dictionary { .. } => with_dictionary_schema(export_schema("i", name, Vec::new()), export_schema("u", "", Vec::new())),
+    }
+}
+
+/// Owned by an exported array: the buffers and children its pointers reach
+struct ArrayPrivate {
+    buffers: Vec<*const c_void>,
+    children: Vec<*mut ArrowArray>,
+    /// Null unless set by `with_dictionary_array`
+    dictionary: *mut ArrowArray,
+    /// Keeps the memory `buffers` points into alive; moving the vectors in leaves it in place
+    _owned: Box<dyn Any>,
+}
+
+fn export_array(length: usize, buffers: Vec<*const c_void>, children: Vec<ArrowArray>, owned: Box<dyn Any>) -> ArrowArray {
+    let mut private = Box::new(ArrayPrivate {
+        buffers,
+        children: children.into_iter().map(|child| Box::into_raw(Box::new(child))).collect(),
+        dictionary: ptr::null_mut(),
+        _owned: owned,
+    });
+    ArrowArray {
+        length: length as i64,
+        null_count: 0,
+        offset: 0,
+        n_buffers: private.buffers.len() as i64,
+        n_children: private.children.len() as i64,
+        buffers: private.buffers.as_mut_ptr(),
+        children: private.children.as_mut_ptr(),
+        dictionary: ptr::null_mut(),
+        release: Some(release_array),
+        private_data: Box::into_raw(private).cast(),
+    }
+}
+
+unsafe extern "C" fn release_array(array: *mut ArrowArray) {
+    let Some(array) = array.as_mut() else { return };
+    if array.release.is_none() {
+        return;
+    }
+    let private = Box::from_raw(array.private_data.cast::<ArrayPrivate>());
+    for &child in private.children.iter().chain(Some(&private.dictionary).filter(|dictionary| !dictionary.is_null())) {
+        if let Some(release) = (*child).release {
+            release(child);
+        }
+        drop(Box::from_raw(child));
+    }
+    array.release = None;
+}
+
+/// Arrays point into `table`'s column vectors, which the clone of `table` they own keeps alive
+fn string_array(column: &StringColumn, table: &Arc<Table>) -> ArrowArray {
+    let sizes: Vec<i64> = column.buffers.iter().map(|buffer| buffer.len() as i64).collect();
+    // Validity (none: no nulls), views, each data buffer, then the data buffer sizes
+    let mut pointers: Vec<*const c_void> = vec![ptr::null(), column.views.as_ptr().cast()];
+    pointers.extend(column.buffers.iter().map(|buffer| buffer.as_ptr().cast::<c_void>()));
+    pointers.push(sizes.as_ptr().cast());
+    export_array(column.len(), pointers, Vec::new(), Box::new((table.clone(), sizes)))
+}
+
+/// `array`, of the indices, with `dictionary` as the array of its values
+fn with_dictionary_array(mut array: ArrowArray, dictionary: ArrowArray) -> ArrowArray {
+    // SAFETY: `array` was just exported, so its private data is a live `ArrayPrivate`
+    let private = unsafe { &mut *array.private_data.cast::<ArrayPrivate>() };
+    private.dictionary = Box::into_raw(Box::new(dictionary));
+    array.dictionary = private.dictionary;
+    array
+}
+
+fn column_array(column: &Column, table: &Arc<Table>) -> ArrowArray {
+    match column {
+        Column::Strings(column) => string_array(column, table),
+        Column::Lists { offsets, values } => {
+            let pointers = vec![ptr::null(), offsets.as_ptr().cast()];
+            export_array(offsets.len() - 1, pointers, vec![string_array(values, table)], Box::new(table.clone()))
+        }
+        Column::Dictionary { column, .. } => {
+            let indices = export_array(column.indices.len(), vec![ptr::null(), column.indices.as_ptr().cast()], Vec::new(), Box::new(table.clone()));
+            // Validity (none), offsets, then the value bytes
+            let pointers = vec![ptr::null(), column.offsets.as_ptr().cast(), column.data.as_ptr().cast()];
+            let values = export_array(column.offsets.len() - 1, pointers, Vec::new(), Box::new(table.clone()));
+            with_dictionary_array(indices, values)
+        }
+    }
+}
+
+/// What a stream hands out: the schema on every request, the table's one batch once
+struct StreamPrivate {
+    table: Arc<Table>,
+    done: bool,
+}
+
+impl StreamPrivate {
+    fn schema(&self) -> ArrowSchema {
+        let children = self
+            .table
+            .names
+            .iter()
+            .zip(&self.table.columns)
+            .map(|(name, column)| field_schema(name, column))
+            .collect();
+        export_schema("+s", "", children)
+    }
+}
+
+unsafe extern "C" fn stream_get_schema(stream: *mut ArrowArrayStream, out: *mut ArrowSchema) -> c_int {
+    let private = &*(*stream).private_data.cast::<StreamPrivate>();
+    out.write(private.schema());
+    0
+}
+
+unsafe extern "C" fn stream_get_next(stream: *mut ArrowArrayStream, out: *mut ArrowArray) -> c_int {
+    let private = &mut *(*stream).private_data.cast::<StreamPrivate>();
+    if !private.done {
+        private.done = true;
+        let table = &private.table;
+        let children = table.columns.iter().map(|column| column_array(column, table)).collect();
+        out.write(export_array(table.rows, vec![ptr::null()], children, Box::new(())));
+    } else {
+        // A released array marks the end of the stream
+        out.write(ArrowArray {
+            length: 0,
+            null_count: 0,
+            offset: 0,
+            n_buffers: 0,
+            n_children: 0,
+            buffers: ptr::null_mut(),
+            children: ptr::null_mut(),
+            dictionary: ptr::null_mut(),
+            release: None,
+            private_data: ptr::null_mut(),
+        });
+    }
+    0
+}
+
+unsafe extern "C" fn stream_get_last_error(_stream: *mut ArrowArrayStream) -> *const c_char {
+    ptr::null()
+}
+
+unsafe extern "C" fn stream_release(stream: *mut ArrowArrayStream) {
+    let Some(stream) = stream.as_mut() else { return };
+    if stream.release.is_none() {
+        return;
+    }
+    drop(Box::from_raw(stream.private_data.cast::<StreamPrivate>()));
+    stream.release = None;
+}
+
+/// Columns returned by `extract_data(..., output="arrow")`, exposed through the Arrow
+/// PyCapsule interface. Each `__arrow_c_stream__` call hands out a new stream over the same
+/// buffers, so the table can be read by several consumers or scanned more than once.
+#[pyclass(frozen, module = "rusticsoup")]
+pub struct ArrowTable {
+    table: Arc<Table>,
+}
+
+impl ArrowTable {
+    pub fn new(table: Table) -> Self {
+        ArrowTable { table: Arc::new(table.finish()) }
+    }
+}
+
+#[pymethods]
+impl ArrowTable {
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__<'py>(&self, py: Python<'py>, requested_schema: Option<PyObject>) -> PyResult<Bound<'py, PyCapsule>> {
+        // The schema is fixed; consumers cast afterwards if they asked for another one
+        let _ = requested_schema;
+        let private = Box::new(StreamPrivate { table: self.table.clone(), done: false });
+        let stream = ArrowArrayStream {
+            get_schema: Some(stream_get_schema),
+            get_next: Some(stream_get_next),
+            get_last_error: Some(stream_get_last_error),
+            release: Some(stream_release),
+            private_data: Box::into_raw(private).cast(),
+        };
+        // A consumer that takes the stream moves it out and clears `release` in the capsule
+        PyCapsule::new_bound_with_destructor(py, stream, Some(CString::new("arrow_array_stream").unwrap()), |mut stream, _| {
+            if let Some(release) = stream.release {
+                // SAFETY: the stream was not taken, so it still owns its private data
+                unsafe { release(&mut stream) };
+            }
+        })
+    }
+
+    /// Column names, nested fields under their dotted paths
+    #[getter]
+    fn column_names(&self) -> Vec<String> {
+        self.table.names.clone()
+    }
+
+    /// Names of the dictionary-encoded columns, which polars reads as `Categorical`
+    #[getter]
+    fn categorical_columns(&self) -> Vec<String> {
+        self.table
+            .names
+            .iter()
+            .zip(&self.table.columns)
+            .filter(|(_, column)| matches!(column, Column::Dictionary { .. }))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.table.rows
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<ArrowTable rows={} columns={}>", self.table.rows, self.table.names.len())
+    }
+}
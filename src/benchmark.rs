@@ -0,0 +1,157 @@
+//! `benchmark_against()`: times `extract_data` and other Python HTML libraries on the same
+//! pages and fields, so users can compare on their own markup instead of our sample pages.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use std::time::Instant;
+
+use crate::universal_extractor::{extract_data, FieldMappings};
+
+/// The drivers for named competitors, in Python since that is how the competitors are called
+const DRIVERS: &str = include_str!("benchmark_drivers.py");
+
+const DEFAULT_COMPETITORS: [&str; 2] = ["bs4+lxml", "selectolax"];
+
+/// One entry of `competitors`: a driver built in under a name, or the caller's own callable
+struct Competitor<'py> {
+    name: String,
+    run: PyResult<Bound<'py, PyAny>>,
+}
+
+fn competitors<'py>(py: Python<'py>, competitors: Option<Vec<Bound<'py, PyAny>>>) -> PyResult<Vec<Competitor<'py>>> {
+    let drivers = PyModule::from_code_bound(py, DRIVERS, "benchmark_drivers.py", "rusticsoup._benchmark_drivers")?.getattr("DRIVERS")?;
+    let drivers = drivers.downcast::<PyDict>()?;
+    let competitors = match competitors {
+        Some(competitors) => competitors,
+        None => DEFAULT_COMPETITORS.iter().map(|name| PyString::new_bound(py, name).into_any()).collect(),
+    };
+    competitors
+        .into_iter()
+        .map(|competitor| {
+            if let Ok(name) = competitor.extract::<String>() {
+                let Some(driver) = drivers.get_item(&name)? else {
+                    let known: Vec<String> = drivers.keys().extract()?;
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Unknown competitor: '{}'. Use one of {}, or a callable",
+                        name,
+                        known.join(", ")
+                    )));
+                };
+                // a missing library is reported in the results, not raised
+                Ok(Competitor { name, run: driver.call0() })
+            } else if competitor.is_callable() {
+                let name = competitor.getattr("__name__").and_then(|name| name.extract()).unwrap_or_else(|_| competitor.to_string());
+                Ok(Competitor { name, run: Ok(competitor) })
+            } else {
+                Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                    "Competitor {} is neither a name nor a callable",
+                    competitor.repr()?
+                )))
+            }
+        })
+        .collect()
+}
+
+/// Best of `repeat` runs of `run` over every page, with the items of the last run
+fn time<'py>(
+    pages: &[Bound<'py, PyAny>],
+    repeat: usize,
+    mut run: impl FnMut(&Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>>,
+) -> PyResult<(f64, Vec<Bound<'py, PyAny>>)> {
+    let mut best = f64::INFINITY;
+    let mut outputs = Vec::with_capacity(pages.len());
+    for _ in 0..repeat {
+        outputs.clear();
+        let started = Instant::now();
+        for page in pages {
+            outputs.push(run(page)?);
+        }
+        best = best.min(started.elapsed().as_secs_f64());
+    }
+    Ok((best, outputs))
+}
+
+fn count_items(outputs: &[Bound<'_, PyAny>]) -> PyResult<usize> {
+    outputs.iter().map(|items| items.len()).sum()
+}
+
+/// Time `extract_data` and other HTML libraries on the same pages and fields.
+///
+/// `competitors` are names of built-in drivers (`"bs4+lxml"`, `"bs4+html.parser"`,
+/// `"selectolax"`, `"selectolax+lexbor"`, `"lxml"`) or callables taking
+/// `(html, container_selector, field_mappings)` and returning a list of dicts. Each runs over
+/// every page `repeat` times and the best total counts. Returns a dict with `pages`, `repeat`
+/// and one `results` entry per contender, rusticsoup first: `name`, `seconds`,
+/// `ms_per_page`, `items`, `relative` (its time over rusticsoup's), `same_output` (whether it
+/// returned what `extract_data` did) and `error`, which holds the message when a competitor
+/// is not installed or fails, with the other fields `None`.
+#[pyfunction]
+#[pyo3(signature = (html_pages, container_selector, field_mappings, competitors=None, repeat=3))]
+pub fn benchmark_against<'py>(
+    py: Python<'py>,
+    html_pages: Vec<Bound<'py, PyAny>>,
+    container_selector: &str,
+    field_mappings: FieldMappings<'py>,
+    competitors: Option<Vec<Bound<'py, PyAny>>>,
+    repeat: usize,
+) -> PyResult<Bound<'py, PyDict>> {
+    if repeat == 0 {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("repeat must be at least 1"));
+    }
+    let competitors = self::competitors(py, competitors)?;
+    let mappings = field_mappings.dict();
+    let extract = wrap_pyfunction_bound!(extract_data, py)?;
+    let (baseline, expected) = time(&html_pages, repeat, |page| {
+        extract.call1((page, container_selector, match &field_mappings {
+            FieldMappings::Compiled(compiled) => compiled.as_any(),
+            FieldMappings::Dict(mappings) => mappings.as_any(),
+        }))
+    })?;
+
+    let per_page = |seconds: f64| seconds * 1000.0 / html_pages.len().max(1) as f64;
+    let results = PyList::empty_bound(py);
+    let entry = PyDict::new_bound(py);
+    entry.set_item("name", "rusticsoup")?;
+    entry.set_item("seconds", baseline)?;
+    entry.set_item("ms_per_page", per_page(baseline))?;
+    entry.set_item("items", count_items(&expected)?)?;
+    entry.set_item("relative", 1.0)?;
+    entry.set_item("same_output", true)?;
+    entry.set_item("error", py.None())?;
+    results.append(entry)?;
+
+    for competitor in competitors {
+        let entry = PyDict::new_bound(py);
+        entry.set_item("name", &competitor.name)?;
+        let timed = competitor
+            .run
+            .and_then(|run| time(&html_pages, repeat, |page| run.call1((page, container_selector, &mappings))));
+        match timed {
+            Ok((seconds, outputs)) => {
+                let mut same = outputs.len() == expected.len();
+                for (output, expected) in outputs.iter().zip(&expected) {
+                    same = same && output.eq(expected)?;
+                }
+                entry.set_item("seconds", seconds)?;
+                entry.set_item("ms_per_page", per_page(seconds))?;
+                entry.set_item("items", count_items(&outputs)?)?;
+                entry.set_item("relative", if baseline > 0.0 { seconds / baseline } else { f64::INFINITY })?;
+                entry.set_item("same_output", same)?;
+                entry.set_item("error", py.None())?;
+            }
+            Err(err) => {
+                for key in ["seconds", "ms_per_page", "items", "relative", "same_output"] {
+                    entry.set_item(key, py.None())?;
+                }
+                entry.set_item("error", format!("{}: {}", err.get_type_bound(py).name()?, err.value_bound(py)))?;
+            }
+        }
+        results.append(entry)?;
+    }
+
+    let report = PyDict::new_bound(py);
+    report.set_item("pages", html_pages.len())?;
+    report.set_item("repeat", repeat)?;
+    report.set_item("results", results)?;
+    Ok(report)
+}
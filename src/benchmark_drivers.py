@@ -0,0 +1,121 @@
+"""Competitor drivers for rusticsoup.benchmark_against().
+
+Each driver factory imports its library and returns run(html, container_selector,
+field_mappings), building the same list of dicts extract_data() does for the field specs
+other libraries can express: "css", "css@attribute", "css@get_all", "css@html" and nested
+dicts. Text is the element's text nodes joined with spaces and stripped, as in extract_data.
+"""
+
+
+def _split(spec):
+    if not isinstance(spec, str):
+        raise NotImplementedError(f"field spec {spec!r} has no equivalent outside rusticsoup")
+    if spec.startswith("xpath:"):
+        raise NotImplementedError(f"field spec {spec!r} has no equivalent outside rusticsoup")
+    selector, _, extraction = spec.partition("@")
+    return selector.strip(), extraction
+
+
+def _is_nested(spec):
+    return isinstance(spec, dict) and set(spec) != {"selector", "type"}
+
+
+def _extract(container, field_mappings, select, text, attr, html):
+    item = {}
+    for name, spec in field_mappings.items():
+        if _is_nested(spec):
+            item[name] = _extract(container, spec, select, text, attr, html)
+            continue
+        selector, extraction = _split(spec)
+        matches = select(container, selector) if selector else [container]
+        if extraction == "get_all":
+            item[name] = [text(match) for match in matches]
+        elif not matches:
+            item[name] = ""
+        elif extraction == "html":
+            item[name] = html(matches[0])
+        elif extraction:
+            item[name] = attr(matches[0], extraction)
+        else:
+            item[name] = text(matches[0])
+    return item
+
+
+def _attribute(value):
+    if value is None:
+        return ""
+    # bs4 returns multi-valued attributes such as class as lists
+    return value if isinstance(value, str) else " ".join(value)
+
+
+def bs4(parser):
+    from bs4 import BeautifulSoup
+
+    def run(html, container_selector, field_mappings):
+        soup = BeautifulSoup(html, parser)
+        return [
+            _extract(
+                container,
+                field_mappings,
+                lambda node, selector: node.select(selector),
+                lambda node: " ".join(node.strings).strip(),
+                lambda node, name: _attribute(node.get(name)),
+                str,
+            )
+            for container in soup.select(container_selector)
+        ]
+
+    return run
+
+
+def selectolax(lexbor):
+    if lexbor:
+        from selectolax.lexbor import LexborHTMLParser as Parser
+    else:
+        from selectolax.parser import HTMLParser as Parser
+
+    def run(html, container_selector, field_mappings):
+        tree = Parser(html)
+        return [
+            _extract(
+                container,
+                field_mappings,
+                lambda node, selector: node.css(selector),
+                lambda node: node.text(separator=" ").strip(),
+                lambda node, name: node.attributes.get(name) or "",
+                lambda node: node.html,
+            )
+            for container in tree.css(container_selector)
+        ]
+
+    return run
+
+
+def lxml():
+    import lxml.html
+    import cssselect  # noqa: F401 - lxml's cssselect() needs it
+
+    def run(html, container_selector, field_mappings):
+        root = lxml.html.fromstring(html)
+        return [
+            _extract(
+                container,
+                field_mappings,
+                lambda node, selector: node.cssselect(selector),
+                lambda node: " ".join(node.itertext()).strip(),
+                lambda node, name: node.get(name, ""),
+                lambda node: lxml.html.tostring(node, encoding="unicode", with_tail=False),
+            )
+            for container in root.cssselect(container_selector)
+        ]
+
+    return run
+
+
+DRIVERS = {
+    "bs4+lxml": lambda: bs4("lxml"),
+    "bs4+html.parser": lambda: bs4("html.parser"),
+    "selectolax": lambda: selectolax(False),
+    "selectolax+lexbor": lambda: selectolax(True),
+    "lxml": lxml,
+}
@@ -1,34 +1,72 @@
 use pyo3::prelude::*;
-use pyo3::types::PyType;
+use pyo3::types::{PyBytes, PyDict, PyType};
 
-use crate::encoding::decode_bytes_to_string;
-use crate::scraper::{WebScraper, Element};
-use crate::scraper::parse_html;
+use crate::encoding::{decode_bytes, encode_string, DecodeOptions, HtmlInput};
+use crate::limits::Limits;
+use crate::markup::SerializeOptions;
+use crate::scraper::{ParseOptions, WebScraper, Element};
 
 /// A minimal BeautifulSoup-like facade to begin API alignment.
-#[pyclass(unsendable)]
+#[pyclass(unsendable, module = "rusticsoup")]
 pub struct RusticSoup {
     scraper: WebScraper,
 }
 
 #[pymethods]
 impl RusticSoup {
-    /// Create from str or bytes. Bytes are decoded as UTF-8 with optional BOM for now.
+    /// Create from str or bytes. Bytes are decoded like bs4's UnicodeDammit:
+    /// `from_encoding` forces a codec, `exclude_encodings` steers detection away from some.
     #[new]
-    pub fn new(html: &str) -> PyResult<Self> {
-        Ok(Self { scraper: parse_html(html) })
+    #[pyo3(signature = (html, from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        html: HtmlInput,
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+        fix_mojibake: bool,
+        detailed_warnings: bool,
+        timeout: Option<f64>,
+        max_size: Option<usize>,
+        max_depth: Option<usize>,
+        max_nodes: Option<usize>,
+    ) -> PyResult<Self> {
+        let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, fix_mojibake)?;
+        let limits = Limits { max_size, max_depth, max_nodes };
+        Ok(Self { scraper: WebScraper::from_input(html, &options, &ParseOptions::new(detailed_warnings, timeout, limits)?)? })
     }
 
-    /// Alternative constructor from bytes (UTF-8/BOM only for now)
+    /// Alternative constructor from bytes; the encoding is detected from a BOM or meta declaration
     #[classmethod]
-    pub fn from_bytes(_cls: &Bound<PyType>, data: &[u8]) -> PyResult<Self> {
-        let s = decode_bytes_to_string(data)?;
-        Ok(Self { scraper: parse_html(&s) })
+    #[pyo3(signature = (data, from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_bytes(
+        _cls: &Bound<PyType>,
+        data: &[u8],
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+        fix_mojibake: bool,
+        detailed_warnings: bool,
+        timeout: Option<f64>,
+        max_size: Option<usize>,
+        max_depth: Option<usize>,
+        max_nodes: Option<usize>,
+    ) -> PyResult<Self> {
+        let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, fix_mojibake)?;
+        let limits = Limits { max_size, max_depth, max_nodes };
+        // Refuse oversized input before decoding doubles its footprint
+        limits.check_size(data.len())?;
+        let decoded = decode_bytes(data, &options)?;
+        Ok(Self { scraper: WebScraper::from_decoded(decoded, &ParseOptions::new(detailed_warnings, timeout, limits)?)? })
     }
 
-    /// CSS select all (alias to underlying engine)
-    pub fn select(&self, selector: &str) -> PyResult<Vec<Element>> {
-        self.scraper.select(selector)
+    /// CSS select all (alias to underlying engine); `explain=True` also returns the plan
+    #[pyo3(signature = (selector, explain=false))]
+    pub fn select(&self, py: Python, selector: &str, explain: bool) -> PyResult<PyObject> {
+        self.scraper.select(py, selector, explain)
     }
 
     /// CSS select first (alias)
@@ -47,16 +85,99 @@ impl RusticSoup {
     #[pyo3(signature = (name=None, limit=None))]
     pub fn find_all(&self, name: Option<&str>, limit: Option<usize>) -> PyResult<Vec<Element>> {
         let selector = name.unwrap_or("*");
-        let mut elems = self.scraper.select(selector)?;
+        let mut elems = self.scraper.select_all(selector)?;
         if let Some(l) = limit {
             if elems.len() > l { elems.truncate(l); }
         }
         Ok(elems)
     }
 
+    /// Comment texts, like `find_all(string=lambda s: isinstance(s, Comment))` in bs4.
+    /// `pattern` is a str to look for or a compiled regex.
+    #[pyo3(signature = (pattern=None, limit=None))]
+    pub fn find_all_comments(&self, pattern: Option<&Bound<'_, PyAny>>, limit: Option<usize>) -> PyResult<Vec<String>> {
+        self.scraper.comments(pattern, limit)
+    }
+
+    /// First comment text matching `pattern`, if any
+    #[pyo3(signature = (pattern=None))]
+    pub fn find_comment(&self, pattern: Option<&Bound<'_, PyAny>>) -> PyResult<Option<String>> {
+        Ok(self.scraper.comments(pattern, Some(1))?.into_iter().next())
+    }
+
     /// Get all document text (whitespace-normalized)
     #[getter]
-    pub fn text(&self) -> String {
-        self.scraper.text()
+    pub fn text(&self) -> PyResult<String> {
+        self.scraper.text(None)
+    }
+
+    /// Serialize the document to bytes, like bs4's `encode()`.
+    /// Characters the target encoding cannot represent become numeric character references.
+    /// Takes the formatting options of `decode()`.
+    #[pyo3(signature = (encoding="utf-8", indent=None, quote=None, entities=None, void=None))]
+    pub fn encode<'py>(
+        &self,
+        py: Python<'py>,
+        encoding: &str,
+        indent: Option<usize>,
+        quote: Option<&str>,
+        entities: Option<&str>,
+        void: Option<&str>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let html = self.decode(indent, quote, entities, void)?;
+        Ok(PyBytes::new_bound(py, &encode_string(&html, encoding)?))
+    }
+
+    /// Serialize the document to str, like bs4's `decode()`, with the formatting options of
+    /// `WebScraper.html()`
+    #[pyo3(signature = (indent=None, quote=None, entities=None, void=None))]
+    pub fn decode(&self, indent: Option<usize>, quote: Option<&str>, entities: Option<&str>, void: Option<&str>) -> PyResult<String> {
+        self.scraper.formatted_html(SerializeOptions::from_args(indent, quote, entities, void)?)
+    }
+
+    /// Parse errors reported by the tree builder
+    #[getter]
+    pub fn warnings(&self) -> Vec<String> {
+        self.scraper.warnings()
+    }
+
+    /// The parsed `<!DOCTYPE>` as `{"name", "public_id", "system_id"}`, or None
+    #[getter]
+    pub fn doctype<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.scraper.doctype(py)
+    }
+
+    /// "no-quirks", "quirks" or "limited-quirks"
+    #[getter]
+    pub fn quirks_mode(&self) -> &'static str {
+        self.scraper.quirks_mode()
+    }
+
+    /// Encoding the document was decoded from (None for str input), like bs4's attribute
+    #[getter]
+    pub fn original_encoding(&self) -> Option<String> {
+        self.scraper.original_encoding()
+    }
+
+    /// How the encoding was determined: "bom", "override", "header", "meta" or "heuristic"
+    #[getter]
+    pub fn encoding_source(&self) -> Option<&'static str> {
+        self.scraper.encoding_source()
+    }
+
+    /// Confidence in the detected encoding, from 0.0 to 1.0
+    #[getter]
+    pub fn encoding_confidence(&self) -> Option<f32> {
+        self.scraper.encoding_confidence()
+    }
+
+    /// Pickle support, as for `WebScraper`: serialized HTML, parsed again on load
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (&'static str,), Bound<'py, PyDict>)> {
+        Ok((slf.get_type().into_any(), ("",), slf.borrow().scraper.pickle_state(slf.py())?))
+    }
+
+    fn __setstate__(&mut self, state: &Bound<'_, PyDict>) -> PyResult<()> {
+        self.scraper = WebScraper::from_pickle_state(state)?;
+        Ok(())
     }
 }
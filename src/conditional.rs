@@ -0,0 +1,108 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node};
+
+use crate::limits::Deadline;
+use crate::markup;
+use crate::scraper::{compact, graft, parse_fragment_in, ParseOptions};
+
+/// The condition and markup of a downlevel-hidden comment, `<!--[if IE 6]>...<![endif]-->`
+fn hidden(data: &str) -> Option<(&str, &str)> {
+    let rest = data.trim_start().strip_prefix("[if ")?;
+    let (condition, inner) = rest.split_once("]>")?;
+    // `<!--[if !IE]><!-->` opens a revealed block; it hides nothing
+    if inner == "<!" {
+        return None;
+    }
+    let inner = inner.trim_end().strip_suffix("<![endif]")?;
+    Some((condition.trim(), inner))
+}
+
+/// The condition of a comment opening a downlevel-revealed block: `<![if !IE]>`, which the
+/// parser reads as a comment, or the valid-HTML form `<!--[if !IE]><!-->`
+fn revealed_start(data: &str) -> Option<&str> {
+    let rest = data.trim().strip_prefix("[if ")?;
+    let condition = rest.strip_suffix("]").or_else(|| rest.strip_suffix("]><!"))?;
+    Some(condition.trim())
+}
+
+fn is_revealed_end(data: &str) -> bool {
+    matches!(data.trim(), "[endif]" | "<![endif]")
+}
+
+fn serialize(node: NodeRef<'_, Node>, out: &mut String) {
+    match node.value() {
+        Node::Element(_) => out.push_str(&ElementRef::wrap(node).unwrap().html()),
+        Node::Text(text) => markup::escape_text(text, out),
+        Node::Comment(comment) => out.push_str(&format!("<!--{}-->", &**comment)),
+        _ => {}
+    }
+}
+
+/// Every conditional comment in `document`, in order, as `{condition, html, revealed}`.
+/// Hidden blocks give the markup inside the comment; revealed blocks the markup between
+/// their opening and closing comments, which the parser already treats as content.
+pub(crate) fn conditional_comments<'py>(py: Python<'py>, document: &Html) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty_bound(py);
+    for node in document.tree.root().descendants() {
+        let Node::Comment(comment) = node.value() else { continue };
+        let (condition, html, revealed) = if let Some((condition, inner)) = hidden(comment) {
+            (condition, inner.to_string(), false)
+        } else if let Some(condition) = revealed_start(comment) {
+            let mut html = String::new();
+            let mut sibling = node.next_sibling();
+            while let Some(current) = sibling {
+                if matches!(current.value(), Node::Comment(end) if is_revealed_end(end)) {
+                    break;
+                }
+                serialize(current, &mut html);
+                sibling = current.next_sibling();
+            }
+            (condition, html, true)
+        } else {
+            continue;
+        };
+        let item = PyDict::new_bound(py);
+        item.set_item("condition", condition)?;
+        item.set_item("html", html)?;
+        item.set_item("revealed", revealed)?;
+        list.append(item)?;
+    }
+    Ok(list)
+}
+
+/// Replace each downlevel-hidden comment with the markup inside it, parsed in the context
+/// of the comment's parent element. `fragment_context` stands in for the synthetic `<html>`
+/// a fragment's top-level nodes sit in.
+pub(crate) fn expand(document: &mut Html, fragment_context: Option<&str>) -> PyResult<()> {
+    let comments: Vec<(NodeId, String, String)> = document
+        .tree
+        .nodes()
+        .filter_map(|node| {
+            let Node::Comment(comment) = node.value() else { return None };
+            let (_, inner) = hidden(comment)?;
+            let parent = node.parent().and_then(ElementRef::wrap);
+            let context = match parent {
+                Some(parent) if parent.parent().is_some_and(|p| p.value().is_fragment()) => fragment_context.unwrap_or("body"),
+                Some(parent) => parent.value().name(),
+                None => "body",
+            };
+            Some((node.id(), inner.to_string(), context.to_string()))
+        })
+        .collect();
+    if comments.is_empty() {
+        return Ok(());
+    }
+    for (comment, inner, context) in comments {
+        let fragment = parse_fragment_in(&inner, &context, &ParseOptions::default(), &Deadline::start(None))?;
+        let copies: Vec<NodeId> = fragment.root_element().children().map(|child| graft(&mut document.tree, child)).collect();
+        let mut comment = document.tree.get_mut(comment).unwrap();
+        for id in copies {
+            comment.insert_id_before(id);
+        }
+        comment.detach();
+    }
+    *document = compact(document);
+    Ok(())
+}
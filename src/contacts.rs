@@ -0,0 +1,237 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use scraper::{Html, Selector};
+use once_cell::sync::Lazy;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::render;
+
+static HREF_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("a[href], area[href]").unwrap());
+static CFEMAIL_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("[data-cfemail]").unwrap());
+
+/// Country calling codes for `default_region`
+const CALLING_CODES: [(&str, &str); 28] = [
+    ("US", "1"), ("CA", "1"), ("GB", "44"), ("IE", "353"), ("DE", "49"), ("FR", "33"), ("ES", "34"),
+    ("IT", "39"), ("NL", "31"), ("BE", "32"), ("CH", "41"), ("AT", "43"), ("SE", "46"), ("NO", "47"),
+    ("DK", "45"), ("FI", "358"), ("PL", "48"), ("PT", "351"), ("AU", "61"), ("NZ", "64"), ("IN", "91"),
+    ("JP", "81"), ("CN", "86"), ("BR", "55"), ("MX", "52"), ("ZA", "27"), ("SG", "65"), ("HK", "852"),
+];
+
+/// Extensions that follow `@` in retina image names (`logo@2x.png`), never top-level domains
+const FILE_EXTENSIONS: [&str; 9] = ["png", "jpg", "jpeg", "gif", "webp", "svg", "css", "js", "avif"];
+
+fn decode_percent(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Cloudflare's email obfuscation: hex bytes XORed with the first byte
+fn decode_cfemail(hex: &str) -> Option<String> {
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+        .collect::<Option<_>>()?;
+    let (key, rest) = bytes.split_first()?;
+    String::from_utf8(rest.iter().map(|b| b ^ key).collect()).ok()
+}
+
+/// `local@domain.tld` with the domain lowercased, or `None` when it is not an address
+fn normalize_email(candidate: &str) -> Option<String> {
+    let (local, domain) = candidate.trim().rsplit_once('@')?;
+    let valid_local = !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && local.chars().all(|c| c.is_ascii_alphanumeric() || "._%+-'".contains(c));
+    let tld = domain.rsplit_once('.')?.1;
+    let valid_domain = domain.split('.').all(|label| {
+        !label.is_empty() && !label.starts_with('-') && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }) && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str());
+    (valid_local && valid_domain).then(|| format!("{}@{}", local, domain.to_ascii_lowercase()))
+}
+
+/// Addresses in running text: each `@` widened to the address characters around it
+fn emails_in_text(text: &str) -> Vec<String> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+    let mut found = Vec::new();
+    for (at, _) in text.match_indices('@') {
+        let start = text[..at].rfind(|c: char| !is_local(c)).map_or(0, |i| i + text[i..].chars().next().unwrap().len_utf8());
+        let end = text[at + 1..].find(|c: char| !is_domain(c)).map_or(text.len(), |i| at + 1 + i);
+        // Sentence punctuation is not part of the domain
+        let candidate = text[start..end].trim_end_matches(['.', '-']);
+        found.extend(normalize_email(candidate));
+    }
+    found
+}
+
+/// A phone number in E.164 (`+14155550123`) when the country is known from a `+`/`00` prefix
+/// or `region`, else its national digits
+fn normalize_phone(raw: &str, region: Option<&str>) -> Option<String> {
+    let digits: String = raw.chars().filter(char::is_ascii_digit).collect();
+    if !(7..=15).contains(&digits.len()) {
+        return None;
+    }
+    if raw.trim_start().starts_with('+') {
+        return Some(format!("+{}", digits));
+    }
+    let code = region.and_then(|region| CALLING_CODES.iter().find(|(r, _)| *r == region).map(|(_, code)| *code));
+    match code {
+        Some("1") => match digits.len() {
+            10 => Some(format!("+1{}", digits)),
+            11 if digits.starts_with('1') => Some(format!("+{}", digits)),
+            _ if digits.starts_with("011") => Some(format!("+{}", &digits[3..])),
+            _ => Some(digits),
+        },
+        _ if digits.starts_with("00") => Some(format!("+{}", &digits[2..])),
+        // Italian numbers keep their leading zero after the country code
+        Some("39") => Some(format!("+39{}", digits)),
+        Some(code) => Some(format!("+{}{}", code, digits.strip_prefix('0').unwrap_or(&digits))),
+        None => Some(digits),
+    }
+}
+
+/// Runs of digits and phone punctuation with 7 to 15 digits that do not look like dates,
+/// amounts or parts of longer codes
+fn phones_in_text(text: &str) -> Vec<&str> {
+    let is_phone_char = |c: char| c.is_ascii_digit() || " -.()/+\u{a0}".contains(c);
+    let mut found = Vec::new();
+    let mut rest = text;
+    let mut offset = 0;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit() || c == '+' || c == '(') {
+        let begin = offset + start;
+        let len = text[begin..].find(|c: char| !is_phone_char(c)).unwrap_or(text.len() - begin);
+        let candidate = text[begin..begin + len].trim_end_matches(|c: char| !c.is_ascii_digit());
+        let glued = text[..begin].chars().next_back().is_some_and(char::is_alphanumeric)
+            || text[begin + candidate.len()..].chars().next().is_some_and(char::is_alphanumeric);
+        if !glued && looks_like_phone(candidate) {
+            found.push(candidate);
+        }
+        offset = begin + len.max(1);
+        rest = &text[offset..];
+    }
+    found
+}
+
+fn looks_like_phone(candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) {
+        return false;
+    }
+    let groups: Vec<&str> = candidate.split(|c: char| !c.is_ascii_digit()).filter(|g| !g.is_empty()).collect();
+    // `2024-05-07`, `07/05/2024`
+    let date = groups.len() == 3
+        && !candidate.contains(' ')
+        && ((groups[0].len() == 4 && groups[1].len() <= 2 && groups[2].len() <= 2)
+            || (groups[0].len() <= 2 && groups[1].len() <= 2 && matches!(groups[2].len(), 2 | 4)));
+    // `1.234.567` or `1,234,567`
+    let amount = !candidate.contains([' ', '-', '(', '+', '/'])
+        && groups.len() > 1
+        && groups[1..].iter().all(|g| g.len() == 3);
+    !date && !amount
+}
+
+fn add_email(emails: &mut Vec<String>, email: String) {
+    if !emails.contains(&email) {
+        emails.push(email);
+    }
+}
+
+fn add_phone(phones: &mut Vec<(String, String)>, number: String, raw: &str) {
+    if !phones.iter().any(|(existing, _)| *existing == number) {
+        phones.push((number, raw.trim().to_string()));
+    }
+}
+
+/// Emails and phone numbers from `mailto:`/`tel:` links, Cloudflare-obfuscated addresses and
+/// visible text. Returns `{"emails": [...], "phones": [{"number", "raw"}]}`, deduplicated in
+/// order found. Email domains are lowercased. Phone numbers are normalized to E.164 when their
+/// country is known from a `+` or `00` prefix or from `default_region` (e.g. `"GB"`, which
+/// also drops the national `0` prefix); otherwise `number` holds the digits as written. Text
+/// matches are heuristic: dates and grouped amounts are skipped.
+#[pyfunction]
+#[pyo3(signature = (html, default_region=None))]
+pub fn extract_contacts(py: Python, html: HtmlInput, default_region: Option<&str>) -> PyResult<Py<PyDict>> {
+    let region = default_region.map(|r| r.trim().to_ascii_uppercase());
+    if let Some(region) = &region {
+        if !CALLING_CODES.iter().any(|(r, _)| r == region) {
+            let known: Vec<&str> = CALLING_CODES.iter().map(|(r, _)| *r).collect();
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Unsupported default_region: {}. Use one of {}", region, known.join(", "))
+            ));
+        }
+    }
+    let region = region.as_deref();
+    let source = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&source);
+    let mut emails: Vec<String> = Vec::new();
+    let mut phones: Vec<(String, String)> = Vec::new();
+
+    for link in document.select(&HREF_SELECTOR) {
+        let href = link.value().attr("href").unwrap_or("").trim();
+        let scheme = href.split_once(':').map(|(scheme, rest)| (scheme.to_ascii_lowercase(), rest));
+        match scheme {
+            Some((scheme, rest)) if scheme == "mailto" => {
+                let addresses = rest.split('?').next().unwrap_or("");
+                for address in decode_percent(addresses).split(',') {
+                    if let Some(email) = normalize_email(address) {
+                        add_email(&mut emails, email);
+                    }
+                }
+            }
+            Some((scheme, rest)) if scheme == "tel" => {
+                let raw = decode_percent(rest.split(';').next().unwrap_or(""));
+                if let Some(number) = normalize_phone(&raw, region) {
+                    add_phone(&mut phones, number, &raw);
+                }
+            }
+            _ => {}
+        }
+    }
+    for elem in document.select(&CFEMAIL_SELECTOR) {
+        if let Some(email) = elem.value().attr("data-cfemail").and_then(decode_cfemail).as_deref().and_then(normalize_email) {
+            add_email(&mut emails, email);
+        }
+    }
+
+    let text = render::render_document(&document, false);
+    for email in emails_in_text(&text) {
+        add_email(&mut emails, email);
+    }
+    for raw in phones_in_text(&text) {
+        if let Some(number) = normalize_phone(raw, region) {
+            add_phone(&mut phones, number, raw);
+        }
+    }
+
+    let result = PyDict::new_bound(py);
+    result.set_item("emails", emails)?;
+    let phone_list = PyList::empty_bound(py);
+    for (number, raw) in phones {
+        let phone = PyDict::new_bound(py);
+        phone.set_item("number", number)?;
+        phone.set_item("raw", raw)?;
+        phone_list.append(phone)?;
+    }
+    result.set_item("phones", phone_list)?;
+    Ok(result.unbind())
+}
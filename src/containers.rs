@@ -0,0 +1,204 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::collections::{HashMap, HashSet};
+use scraper::{ElementRef, Html, Selector};
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::text;
+
+/// Descendant levels below an item that make up its shape and field paths
+const SHAPE_DEPTH: usize = 4;
+
+/// Elements whose children are never items
+const SKIP: [&str; 6] = ["head", "script", "style", "noscript", "template", "svg"];
+
+/// Whether `name` can be written in a selector without escaping
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '-')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// `tag.class` with the element's first usable class, `tag` without one
+fn step(elem: ElementRef<'_>) -> String {
+    match elem.value().classes().find(|class| is_ident(class)) {
+        Some(class) => format!("{}.{}", elem.value().name(), class),
+        None => elem.value().name().to_string(),
+    }
+}
+
+/// Selector for `elem`: from the nearest ancestor with a usable id, else from `html`
+fn anchored_path(elem: ElementRef<'_>) -> String {
+    let mut steps = Vec::new();
+    for node in std::iter::once(elem).chain(elem.ancestors().filter_map(ElementRef::wrap)) {
+        if let Some(id) = node.value().id().filter(|id| is_ident(id)) {
+            steps.push(format!("#{}", id));
+            break;
+        }
+        let name = node.value().name();
+        let same = node.parent().map_or(0, |parent| {
+            parent.children().filter(|c| c.value().as_element().is_some_and(|e| e.name() == name)).count()
+        });
+        steps.push(match same {
+            0 | 1 => name.to_string(),
+            _ => format!("{}:nth-of-type({})", name, node.prev_siblings().filter(|c| c.value().as_element().is_some_and(|e| e.name() == name)).count() + 1),
+        });
+    }
+    steps.reverse();
+    steps.join(" > ")
+}
+
+/// Relative paths (`h2.title`, `div.info > span`) of an item's descendants, with the first
+/// element found at each
+fn shape(item: ElementRef<'_>) -> HashMap<String, ElementRef<'_>> {
+    fn walk<'a>(elem: ElementRef<'a>, prefix: &str, depth: usize, out: &mut HashMap<String, ElementRef<'a>>) {
+        if depth == SHAPE_DEPTH {
+            return;
+        }
+        for child in elem.children().filter_map(ElementRef::wrap) {
+            let path = match prefix {
+                "" => step(child),
+                _ => format!("{} > {}", prefix, step(child)),
+            };
+            walk(child, &path, depth + 1, out);
+            out.entry(path).or_insert(child);
+        }
+    }
+    let mut paths = HashMap::new();
+    walk(item, "", 0, &mut paths);
+    paths
+}
+
+/// Field specs worth suggesting at `path`, read from the sample elements of every item:
+/// its text when most items have some, and `href`/`src` of links and images
+fn field_specs(path: &str, samples: &[ElementRef<'_>]) -> Vec<String> {
+    let mut specs = Vec::new();
+    let with_text = samples.iter().filter(|e| !text::element_text(**e, true).is_empty()).count();
+    if with_text * 2 >= samples.len() && with_text > 0 {
+        specs.push(path.to_string());
+    }
+    let attr = match samples[0].value().name() {
+        "a" => Some("href"),
+        "img" => Some("src"),
+        _ => None,
+    };
+    if let Some(attr) = attr.filter(|attr| samples.iter().all(|e| e.value().attr(attr).is_some())) {
+        specs.push(format!("{}@{}", path, attr));
+    }
+    specs
+}
+
+/// A field name from a path's last step: its class, else its tag
+fn field_name(path: &str, spec: &str, taken: &HashSet<String>) -> String {
+    let last = path.rsplit(" > ").next().unwrap_or(path);
+    let base = match (last.split_once('.'), spec.rsplit_once('@')) {
+        (_, Some((_, attr))) => format!("{}_{}", last.split('.').next_back().unwrap_or(last), attr),
+        (Some((_, class)), None) => class.to_string(),
+        (None, None) => last.to_string(),
+    }
+    .replace('-', "_");
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("{}_{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+struct Candidate {
+    selector: String,
+    count: usize,
+    score: f64,
+    fields: Vec<(String, String)>,
+}
+
+/// Score a group of sibling items and suggest fields; None when they share no useful field
+fn candidate(parent: ElementRef<'_>, items: &[ElementRef<'_>]) -> Option<Candidate> {
+    let shapes: Vec<HashMap<String, ElementRef>> = items.iter().map(|item| shape(*item)).collect();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for shape in &shapes {
+        for path in shape.keys() {
+            *seen.entry(path).or_default() += 1;
+        }
+    }
+    // Paths most items have describe the template the items are rendered from
+    let mut common: Vec<&str> = seen.iter().filter(|(_, &n)| n * 2 >= items.len()).map(|(path, _)| *path).collect();
+    common.sort_unstable();
+    let similarity = shapes
+        .iter()
+        .map(|shape| {
+            let shared = common.iter().filter(|path| shape.contains_key(**path)).count();
+            let union = shape.len() + common.len() - shared;
+            if union == 0 { 1.0 } else { shared as f64 / union as f64 }
+        })
+        .sum::<f64>()
+        / items.len() as f64;
+
+    let mut fields = Vec::new();
+    let mut taken = HashSet::new();
+    // Shallow paths first, so a field reads from the outermost element that has it
+    common.sort_by_key(|path| (path.matches(" > ").count(), *path));
+    for path in common {
+        let samples: Vec<ElementRef> = shapes.iter().filter_map(|shape| shape.get(path).copied()).collect();
+        for spec in field_specs(path, &samples) {
+            let name = field_name(path, &spec, &taken);
+            taken.insert(name.clone());
+            fields.push((name, spec));
+        }
+    }
+    if fields.is_empty() {
+        return None;
+    }
+    let selector = format!("{} > {}", anchored_path(parent), step(items[0]));
+    Selector::parse(&selector).ok()?;
+    let score = items.len() as f64 * similarity * fields.len() as f64;
+    Some(Candidate { selector, count: items.len(), score, fields })
+}
+
+/// Find the likeliest repeating items on an unknown page, to bootstrap an extraction schema.
+/// Sibling elements sharing a tag and first class form a group when there are at least
+/// `min_items`; groups are scored by size, how alike the items' subtrees are and how many
+/// fields they share. Returns up to `limit` candidates, best first, as
+/// `{"selector", "count", "score", "fields"}`; `fields` maps suggested names to field specs
+/// (text, `a@href`, `img@src`) and can be passed to `extract_data` with the selector.
+#[pyfunction]
+#[pyo3(signature = (html, min_items=3, limit=5))]
+pub fn detect_containers<'py>(py: Python<'py>, html: HtmlInput, min_items: usize, limit: usize) -> PyResult<Bound<'py, PyList>> {
+    let document = Html::parse_document(&html.decode(&DecodeOptions::default())?);
+    let mut candidates = Vec::new();
+    for parent in document.root_element().descendants().filter_map(ElementRef::wrap) {
+        if parent.ancestors().chain(std::iter::once(*parent)).filter_map(ElementRef::wrap).any(|e| SKIP.contains(&e.value().name())) {
+            continue;
+        }
+        let mut groups: Vec<(String, Vec<ElementRef>)> = Vec::new();
+        for child in parent.children().filter_map(ElementRef::wrap) {
+            let key = step(child);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, items)) => items.push(child),
+                None => groups.push((key, vec![child])),
+            }
+        }
+        for (_, items) in groups {
+            if items.len() >= min_items.max(2) {
+                candidates.extend(candidate(parent, &items));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let results = PyList::empty_bound(py);
+    for candidate in candidates.into_iter().take(limit) {
+        let item = PyDict::new_bound(py);
+        item.set_item("selector", candidate.selector)?;
+        item.set_item("count", candidate.count)?;
+        item.set_item("score", (candidate.score * 100.0).round() / 100.0)?;
+        let fields = PyDict::new_bound(py);
+        for (name, spec) in candidate.fields {
+            fields.set_item(name, spec)?;
+        }
+        item.set_item("fields", fields)?;
+        results.append(item)?;
+    }
+    Ok(results)
+}
@@ -0,0 +1,419 @@
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+
+/// A calendar date and time as written; `offset` (seconds east of UTC) is `None` when the
+/// text gives no zone
+pub struct DateTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    microsecond: u32,
+    offset: Option<i32>,
+}
+
+impl DateTime {
+    fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        // Python's datetime covers years 1 to 9999
+        if !(1..=9999).contains(&year) || !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(DateTime { year, month, day, hour: 0, minute: 0, second: 0, microsecond: 0, offset: None })
+    }
+
+    fn with_clock(mut self, (hour, minute, second, microsecond): Clock) -> Option<Self> {
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        self.hour = hour;
+        self.minute = minute;
+        self.second = second;
+        self.microsecond = microsecond;
+        Some(self)
+    }
+}
+
+/// Hour, minute, second and microsecond
+type Clock = (u32, u32, u32, u32);
+
+/// What a date string denotes
+pub enum Parsed {
+    Absolute(DateTime),
+    /// Seconds since the Unix epoch
+    Timestamp(f64),
+    /// An offset from the reference time: whole months, then seconds
+    Relative { months: i32, seconds: i64 },
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parse an absolute date (ISO 8601, RFC 822, written-out or numeric), a Unix timestamp or a
+/// relative phrase. `dayfirst` reads ambiguous numeric dates like `03/04/2024` as day/month;
+/// dotted dates (`03.04.2024`) are always day first.
+pub fn parse(text: &str, dayfirst: bool) -> Option<Parsed> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Some(timestamp) = parse_timestamp(text) {
+        return Some(Parsed::Timestamp(timestamp));
+    }
+    if let Some(datetime) = parse_iso(text) {
+        return Some(Parsed::Absolute(datetime));
+    }
+    if let Some(relative) = parse_relative(&text.to_lowercase()) {
+        return Some(relative);
+    }
+    parse_written(text, dayfirst).map(Parsed::Absolute)
+}
+
+/// 10-digit seconds or 13-digit milliseconds since the epoch
+fn parse_timestamp(text: &str) -> Option<f64> {
+    if !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match text.len() {
+        10 => text.parse().ok(),
+        13 => text.parse::<f64>().ok().map(|ms| ms / 1000.0),
+        _ => None,
+    }
+}
+
+/// `2024-05-07`, optionally followed by `T` or a space, a time and a zone
+fn parse_iso(text: &str) -> Option<DateTime> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let datetime = DateTime::new(text.get(..4)?.parse().ok()?, text.get(5..7)?.parse().ok()?, text.get(8..10)?.parse().ok()?)?;
+    let rest = text.get(10..)?;
+    if rest.is_empty() {
+        return Some(datetime);
+    }
+    let rest = rest.strip_prefix(['T', 't', ' '])?;
+    let (clock, zone) = rest.split_at(rest.find(['Z', 'z', '+', '-']).unwrap_or(rest.len()));
+    let mut datetime = datetime.with_clock(parse_clock(clock.trim())?)?;
+    if !zone.is_empty() {
+        datetime.offset = Some(zone_offset(zone)?);
+    }
+    Some(datetime)
+}
+
+/// `HH:MM`, `HH:MM:SS` or `HH:MM:SS.ffffff`
+fn parse_clock(clock: &str) -> Option<Clock> {
+    let mut parts = clock.split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let (second, microsecond) = match parts.next() {
+        None => (0, 0),
+        Some(seconds) => {
+            let (whole, fraction) = seconds.split_once(['.', ',']).unwrap_or((seconds, ""));
+            if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let micros = format!("{:0<6}", &fraction[..fraction.len().min(6)]);
+            (whole.parse().ok()?, micros.parse().ok()?)
+        }
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((hour, minute, second, microsecond))
+}
+
+/// Seconds east of UTC for `Z`, `+02:00`, `-0500`, `+02`, `UTC`/`GMT` (optionally with an
+/// offset, `GMT+2`) and the North American abbreviations common in feeds
+pub fn zone_offset(zone: &str) -> Option<i32> {
+    let zone = zone.trim().to_ascii_uppercase();
+    let named = match zone.as_str() {
+        "Z" | "UT" | "UTC" | "GMT" => Some(0),
+        "EST" => Some(-5),
+        "EDT" | "AST" => Some(-4),
+        "CST" => Some(-6),
+        "CDT" => Some(-5),
+        "MST" => Some(-7),
+        "MDT" => Some(-6),
+        "PST" => Some(-8),
+        "PDT" => Some(-7),
+        _ => None,
+    };
+    if let Some(hours) = named {
+        return Some(hours * 3600);
+    }
+    let numeric = zone.strip_prefix("UTC").or_else(|| zone.strip_prefix("GMT")).unwrap_or(&zone);
+    let first = numeric.chars().next()?;
+    let sign = match first {
+        '+' => 1,
+        '-' | '−' => -1,
+        _ => return None,
+    };
+    let digits: String = numeric[first.len_utf8()..].chars().filter(|&c| c != ':').collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes): (i32, i32) = match digits.len() {
+        1 | 2 => (digits.parse().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+        _ => return None,
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// `just now`, `yesterday`, `3 days ago`, `2h ago`, `an hour ago`, `in 5 minutes`, `last week`
+fn parse_relative(text: &str) -> Option<Parsed> {
+    let words: Vec<&str> = text.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()).collect();
+    let seconds = |seconds| Some(Parsed::Relative { months: 0, seconds });
+    match words.as_slice() {
+        ["now"] | ["just", "now"] | ["right", "now"] | ["today"] => return seconds(0),
+        ["yesterday"] => return seconds(-86400),
+        ["tomorrow"] => return seconds(86400),
+        [direction @ ("last" | "next"), unit] => {
+            let sign = if *direction == "last" { -1 } else { 1 };
+            return span(unit)?.times(sign);
+        }
+        _ => {}
+    }
+    let (sign, body) = match words.as_slice() {
+        [body @ .., "ago"] => (-1, body),
+        ["in", body @ ..] => (1, body),
+        _ => return None,
+    };
+    let body = match body {
+        ["about" | "almost" | "over" | "around", rest @ ..] => rest,
+        body => body,
+    };
+    let (count, unit) = match body {
+        [count, unit] => (*count, *unit),
+        // Compact forms: `2d`, `3hrs`
+        [compact] => compact.split_at(compact.find(|c: char| !c.is_ascii_digit())?),
+        _ => return None,
+    };
+    let count: i64 = match count {
+        "a" | "an" | "one" => 1,
+        digits => digits.parse().ok()?,
+    };
+    span(unit)?.times(sign * count)
+}
+
+/// A unit of relative time
+enum Span {
+    Seconds(i64),
+    Months(i32),
+}
+
+impl Span {
+    fn times(self, count: i64) -> Option<Parsed> {
+        Some(match self {
+            Span::Seconds(seconds) => Parsed::Relative { months: 0, seconds: seconds.checked_mul(count)? },
+            Span::Months(months) => Parsed::Relative { months: months.checked_mul(i32::try_from(count).ok()?)?, seconds: 0 },
+        })
+    }
+}
+
+fn span(unit: &str) -> Option<Span> {
+    Some(match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Span::Seconds(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Span::Seconds(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Span::Seconds(3600),
+        "d" | "day" | "days" => Span::Seconds(86400),
+        "w" | "wk" | "wks" | "week" | "weeks" => Span::Seconds(7 * 86400),
+        "mo" | "mos" | "month" | "months" => Span::Months(1),
+        "y" | "yr" | "yrs" | "year" | "years" => Span::Months(12),
+        _ => return None,
+    })
+}
+
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november",
+    "december",
+];
+
+/// 1-12 for an English month name or an abbreviation of at least three letters
+fn month_number(word: &str) -> Option<u32> {
+    let word = word.trim_end_matches('.');
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS.iter().position(|month| month.starts_with(word)).map(|i| i as u32 + 1)
+}
+
+fn full_year(year: u32, digits: usize) -> i32 {
+    match (digits, year) {
+        (2, 0..=49) => 2000 + year as i32,
+        (2, _) => 1900 + year as i32,
+        _ => year as i32,
+    }
+}
+
+/// Written-out and numeric dates with an optional time and zone, e.g.
+/// `Tue, 07 May 2024 09:30:00 GMT`, `May 7th, 2024 at 5:30 PM`, `07.05.2024 14:00`. Words
+/// that are not part of a date (`Posted on`, weekdays) are skipped; a year is required.
+fn parse_written(text: &str, dayfirst: bool) -> Option<DateTime> {
+    let (mut year, mut month, mut day) = (None, None, None);
+    let mut clock: Option<Clock> = None;
+    let mut meridiem: Option<bool> = None;
+    let mut offset = None;
+    for token in text.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()) {
+        let lower = token.to_lowercase();
+        let first_digit = token.starts_with(|c: char| c.is_ascii_digit());
+        if let Some(pm) = meridiem_of(&lower) {
+            meridiem = Some(pm);
+        } else if let Some(number) = month_number(&lower).filter(|_| month.is_none()) {
+            month = Some(number);
+        } else if first_digit && token.contains(':') {
+            // `9:30`, `9:30pm`
+            let split = token.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(token.len());
+            clock = Some(parse_clock(&token[..split])?);
+            if split < token.len() {
+                meridiem = Some(meridiem_of(&lower[split..])?);
+            }
+        } else if let Some((y, m, d)) = first_digit.then(|| numeric_date(token, dayfirst)).flatten() {
+            (year, month, day) = (Some(y), Some(m), Some(d));
+        } else if first_digit {
+            let digits = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+            let (number, suffix) = token.split_at(digits);
+            let number: u32 = number.parse().ok()?;
+            match suffix.to_lowercase().as_str() {
+                "" if digits == 4 => year = Some(number as i32),
+                "" | "st" | "nd" | "rd" | "th" if day.is_none() && (1..=31).contains(&number) => day = Some(number),
+                // `5pm`
+                suffix if meridiem_of(suffix).is_some() => {
+                    clock = Some((number, 0, 0, 0));
+                    meridiem = meridiem_of(suffix);
+                }
+                _ => {}
+            }
+        } else if let Some(seconds) = zone_offset(token).filter(|_| offset.is_none()) {
+            offset = Some(seconds);
+        }
+    }
+
+    let mut datetime = DateTime::new(year?, month?, day?)?;
+    if let Some((hour, minute, second, microsecond)) = clock {
+        let hour = match meridiem {
+            Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+            Some(_) => return None,
+            None => hour,
+        };
+        datetime = datetime.with_clock((hour, minute, second, microsecond))?;
+    }
+    datetime.offset = offset;
+    Some(datetime)
+}
+
+/// `Some(true)` for pm, `Some(false)` for am
+fn meridiem_of(word: &str) -> Option<bool> {
+    match word.trim_end_matches('.') {
+        "am" | "a.m" => Some(false),
+        "pm" | "p.m" => Some(true),
+        _ => None,
+    }
+}
+
+/// `2024/05/07`, `07/05/2024`, `05-07-24`, `07.05.2024` as year, month and day
+fn numeric_date(token: &str, dayfirst: bool) -> Option<(i32, u32, u32)> {
+    let separator = token.chars().find(|c| matches!(c, '/' | '.' | '-'))?;
+    let parts: Vec<&str> = token.split(separator).collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    if parts[0].len() == 4 {
+        return Some((numbers[0] as i32, numbers[1], numbers[2]));
+    }
+    let year = full_year(numbers[2], parts[2].len());
+    let (a, b) = (numbers[0], numbers[1]);
+    let day_first = a > 12 || (b <= 12 && (dayfirst || separator == '.'));
+    Some(if day_first { (year, b, a) } else { (year, a, b) })
+}
+
+/// Build a timezone-aware Python `datetime`. Times without a zone, and a naive `reference`,
+/// are taken to be in `default_tz` (UTC when `None`); relative times count from `reference`,
+/// or now.
+pub fn to_py(py: Python, parsed: Parsed, reference: Option<&Bound<'_, PyAny>>, default_tz: Option<&Bound<'_, PyAny>>) -> PyResult<PyObject> {
+    let module = py.import_bound("datetime")?;
+    let datetime = module.getattr("datetime")?;
+    let timezone = module.getattr("timezone")?;
+    let tz = match default_tz {
+        Some(tz) => tz.clone(),
+        None => timezone.getattr("utc")?,
+    };
+    match parsed {
+        Parsed::Absolute(dt) => {
+            let tzinfo = match dt.offset {
+                Some(0) => timezone.getattr("utc")?,
+                Some(seconds) => {
+                    let delta = module.getattr("timedelta")?.call((), Some(&[("seconds", seconds)].into_py_dict_bound(py)))?;
+                    timezone.call1((delta,))?
+                }
+                None => tz,
+            };
+            let kwargs = PyDict::new_bound(py);
+            kwargs.set_item("tzinfo", tzinfo)?;
+            let args = (dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second, dt.microsecond);
+            Ok(datetime.call(args, Some(&kwargs))?.into())
+        }
+        Parsed::Timestamp(seconds) => {
+            Ok(datetime.call_method1("fromtimestamp", (seconds, tz))?.into())
+        }
+        Parsed::Relative { months, seconds } => {
+            let mut base = match reference {
+                Some(reference) if reference.getattr("tzinfo")?.is_none() => {
+                    let kwargs = PyDict::new_bound(py);
+                    kwargs.set_item("tzinfo", &tz)?;
+                    reference.call_method("replace", (), Some(&kwargs))?
+                }
+                Some(reference) => reference.clone(),
+                None => datetime.call_method1("now", (&tz,))?,
+            };
+            if months != 0 {
+                let year: i32 = base.getattr("year")?.extract()?;
+                let month: i32 = base.getattr("month")?.extract()?;
+                let day: u32 = base.getattr("day")?.extract()?;
+                let total = year * 12 + (month - 1) + months;
+                let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+                let kwargs = PyDict::new_bound(py);
+                kwargs.set_item("year", year)?;
+                kwargs.set_item("month", month)?;
+                kwargs.set_item("day", day.min(days_in_month(year, month)))?;
+                base = base.call_method("replace", (), Some(&kwargs))?;
+            }
+            let delta = module.getattr("timedelta")?.call((), Some(&[("seconds", seconds)].into_py_dict_bound(py)))?;
+            Ok(base.call_method1("__add__", (delta,))?.into())
+        }
+    }
+}
+
+/// Parse a date or time into a timezone-aware `datetime`, or `None` when the text holds none.
+/// Understands ISO 8601 (as in `<time datetime>`), RFC 822 and written-out dates in English
+/// (`May 7th, 2024 at 5:30 PM EST`), numeric dates (`07/05/2024`; month first unless
+/// `dayfirst=True`, dotted dates always day first), Unix timestamps, and relative phrases
+/// (`2 days ago`, `an hour ago`, `yesterday`, `in 5 minutes`) counted from `reference`
+/// (default: now). Times without a zone are taken to be in `default_tz`, a `tzinfo`
+/// (default: UTC).
+#[pyfunction]
+#[pyo3(signature = (text, reference=None, dayfirst=false, default_tz=None))]
+pub fn parse_datetime(
+    py: Python,
+    text: &str,
+    reference: Option<&Bound<'_, PyAny>>,
+    dayfirst: bool,
+    default_tz: Option<&Bound<'_, PyAny>>,
+) -> PyResult<PyObject> {
+    match parse(text, dayfirst) {
+        Some(parsed) => to_py(py, parsed, reference, default_tz),
+        None => Ok(py.None()),
+    }
+}
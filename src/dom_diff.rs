@@ -0,0 +1,252 @@
+use pyo3::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use pyo3::types::{PyDict, PyList};
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node};
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+
+/// A node taking part in the comparison: an element or a non-blank text node
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    Element(ElementRef<'a>),
+    Text(&'a str),
+}
+
+impl Item<'_> {
+    /// Items with the same key are paired up and compared; others are added or removed.
+    /// Elements pair on tag name and id, text nodes with each other.
+    fn key(&self) -> (&str, Option<&str>) {
+        match self {
+            Item::Element(elem) => (elem.value().name(), elem.value().id()),
+            Item::Text(_) => ("#text", None),
+        }
+    }
+}
+
+fn items<'a>(elem: ElementRef<'a>, normalize_whitespace: bool) -> Vec<Item<'a>> {
+    elem.children()
+        .filter_map(|child| match child.value() {
+            Node::Element(_) => ElementRef::wrap(child).map(Item::Element),
+            Node::Text(text) if !text.trim().is_empty() || !normalize_whitespace => Some(Item::Text(text)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `html > body > div:nth-of-type(2) > p`; `:nth-of-type` only where a sibling shares the tag
+fn css_path(elem: ElementRef<'_>) -> String {
+    let mut steps: Vec<String> = std::iter::once(*elem)
+        .chain(elem.ancestors())
+        .filter_map(ElementRef::wrap)
+        .map(|elem| {
+            let name = elem.value().name();
+            let same = |node: NodeRef<'_, Node>| node.value().as_element().is_some_and(|e| e.name() == name);
+            let before = elem.prev_siblings().filter(|&n| same(n)).count();
+            let after = elem.next_siblings().filter(|&n| same(n)).count();
+            match before + after {
+                0 => name.to_string(),
+                _ => format!("{}:nth-of-type({})", name, before + 1),
+            }
+        })
+        .collect();
+    steps.reverse();
+    steps.join(" > ")
+}
+
+/// Pairs of indexes `(i, j)` with `same(i, j)`, in order: a longest common subsequence.
+/// The common prefix and suffix are taken first so the quadratic part only sees the edit.
+fn align(n: usize, m: usize, same: impl Fn(usize, usize) -> bool) -> Vec<(usize, usize)> {
+    let prefix = (0..n.min(m)).take_while(|&k| same(k, k)).count();
+    let suffix = (0..(n - prefix).min(m - prefix)).take_while(|&k| same(n - 1 - k, m - 1 - k)).count();
+    let (rows, cols) = (n - prefix - suffix, m - prefix - suffix);
+    // lengths[i][j]: LCS length of the middles from row i and column j on
+    let mut lengths = vec![vec![0usize; cols + 1]; rows + 1];
+    for i in (0..rows).rev() {
+        for j in (0..cols).rev() {
+            lengths[i][j] = if same(prefix + i, prefix + j) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|k| (k, k)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < rows && j < cols {
+        if same(prefix + i, prefix + j) {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).rev().map(|k| (n - 1 - k, m - 1 - k)));
+    pairs
+}
+
+/// Hash of every element's subtree as the comparison sees it, so identical subtrees pair up
+/// before similar ones and need no further walk
+fn subtree_hashes(document: &Html, normalize_whitespace: bool) -> HashMap<NodeId, u64> {
+    fn visit(elem: ElementRef<'_>, normalize_whitespace: bool, hashes: &mut HashMap<NodeId, u64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        elem.value().name().hash(&mut hasher);
+        let mut attrs: Vec<_> = elem.value().attrs().collect();
+        attrs.sort_unstable();
+        attrs.hash(&mut hasher);
+        for item in items(elem, normalize_whitespace) {
+            match item {
+                Item::Element(child) => visit(child, normalize_whitespace, hashes).hash(&mut hasher),
+                Item::Text(text) => normalized(text, normalize_whitespace).hash(&mut hasher),
+            }
+        }
+        let hash = hasher.finish();
+        hashes.insert(elem.id(), hash);
+        hash
+    }
+    let mut hashes = HashMap::new();
+    visit(document.root_element(), normalize_whitespace, &mut hashes);
+    hashes
+}
+
+fn normalized(text: &str, normalize_whitespace: bool) -> String {
+    match normalize_whitespace {
+        true => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        false => text.to_string(),
+    }
+}
+
+struct Differ<'py> {
+    py: Python<'py>,
+    changes: Bound<'py, PyList>,
+    normalize_whitespace: bool,
+    hashes_a: HashMap<NodeId, u64>,
+    hashes_b: HashMap<NodeId, u64>,
+}
+
+impl<'py> Differ<'py> {
+    fn text(&self, text: &str) -> String {
+        normalized(text, self.normalize_whitespace)
+    }
+
+    /// Whether two items are identical, subtrees included
+    fn identical(&self, a: Item<'_>, b: Item<'_>) -> bool {
+        match (a, b) {
+            (Item::Element(x), Item::Element(y)) => self.hashes_a.get(&x.id()) == self.hashes_b.get(&y.id()),
+            (Item::Text(x), Item::Text(y)) => self.text(x) == self.text(y),
+            _ => false,
+        }
+    }
+
+    /// Pair children: identical ones first, then by key within each run between them
+    fn pair(&self, a: &[Item<'_>], b: &[Item<'_>]) -> Vec<(usize, usize)> {
+        let exact = align(a.len(), b.len(), |i, j| self.identical(a[i], b[j]));
+        let mut pairs = Vec::new();
+        let (mut next_a, mut next_b) = (0, 0);
+        for &(i, j) in exact.iter().chain(std::iter::once(&(a.len(), b.len()))) {
+            let (gap_a, gap_b) = (&a[next_a..i], &b[next_b..j]);
+            let similar = align(gap_a.len(), gap_b.len(), |x, y| gap_a[x].key() == gap_b[y].key());
+            pairs.extend(similar.into_iter().map(|(x, y)| (next_a + x, next_b + y)));
+            if i < a.len() {
+                pairs.push((i, j));
+            }
+            (next_a, next_b) = (i + 1, j + 1);
+        }
+        pairs
+    }
+
+    fn push(&self, op: &str, path: String, fields: &[(&str, PyObject)]) -> PyResult<()> {
+        let change = PyDict::new_bound(self.py);
+        change.set_item("op", op)?;
+        change.set_item("path", path)?;
+        for (key, value) in fields {
+            change.set_item(*key, value)?;
+        }
+        self.changes.append(change)
+    }
+
+    /// Report an unpaired item; `parent` locates text nodes
+    fn added_or_removed(&self, op: &str, item: Item<'_>, parent: ElementRef<'_>) -> PyResult<()> {
+        let py = self.py;
+        match item {
+            Item::Element(elem) => self.push(op, css_path(elem), &[("node", "element".into_py(py)), ("html", elem.html().into_py(py))]),
+            Item::Text(text) => self.push(op, css_path(parent), &[("node", "text".into_py(py)), ("html", self.text(text).into_py(py))]),
+        }
+    }
+
+    fn changed(&self, path: String, attribute: Option<&str>, old: Option<String>, new: Option<String>) -> PyResult<()> {
+        let py = self.py;
+        let node = if attribute.is_some() { "attribute" } else { "text" };
+        self.push("changed", path, &[
+            ("node", node.into_py(py)),
+            ("attribute", attribute.into_py(py)),
+            ("old", old.into_py(py)),
+            ("new", new.into_py(py)),
+        ])
+    }
+
+    /// Compare two elements already paired up: attributes first, then children in order
+    fn compare(&self, a: ElementRef<'_>, b: ElementRef<'_>) -> PyResult<()> {
+        let path = css_path(a);
+        for (name, old) in a.value().attrs() {
+            match b.value().attr(name) {
+                Some(new) if new == old => {}
+                new => self.changed(path.clone(), Some(name), Some(old.to_string()), new.map(str::to_string))?,
+            }
+        }
+        for (name, new) in b.value().attrs() {
+            if a.value().attr(name).is_none() {
+                self.changed(path.clone(), Some(name), None, Some(new.to_string()))?;
+            }
+        }
+
+        let (items_a, items_b) = (items(a, self.normalize_whitespace), items(b, self.normalize_whitespace));
+        let pairs = self.pair(&items_a, &items_b);
+        let (mut next_a, mut next_b) = (0, 0);
+        for &(i, j) in pairs.iter().chain(std::iter::once(&(items_a.len(), items_b.len()))) {
+            for &item in &items_a[next_a..i] {
+                self.added_or_removed("removed", item, a)?;
+            }
+            for &item in &items_b[next_b..j] {
+                self.added_or_removed("added", item, b)?;
+            }
+            match (items_a.get(i), items_b.get(j)) {
+                (Some(&x), Some(&y)) if self.identical(x, y) => {}
+                (Some(Item::Element(x)), Some(Item::Element(y))) => self.compare(*x, *y)?,
+                (Some(Item::Text(x)), Some(Item::Text(y))) => {
+                    let (old, new) = (self.text(x), self.text(y));
+                    if old != new {
+                        self.changed(path.clone(), None, Some(old), Some(new))?;
+                    }
+                }
+                _ => {}
+            }
+            (next_a, next_b) = (i + 1, j + 1);
+        }
+        Ok(())
+    }
+}
+
+/// What changed between two versions of a page, as a list of
+/// `{"op", "path", "node", ...}` dicts in document order. `op` is "added" or "removed" for
+/// elements and text nodes, with their `html`, and "changed" for text and attribute values,
+/// with `attribute` (None for text), `old` and `new`; an attribute that appears or goes away
+/// has `old` or `new` None. `path` is a CSS selector for the element (the parent, for text),
+/// located in `html_a` except for added nodes. Children are matched by tag name and id, so
+/// an element whose tag changes is removed and added. Comments are ignored, and so are
+/// whitespace-only text and runs of whitespace unless `normalize_whitespace=False`.
+#[pyfunction]
+#[pyo3(signature = (html_a, html_b, normalize_whitespace=true))]
+pub fn diff<'py>(py: Python<'py>, html_a: HtmlInput, html_b: HtmlInput, normalize_whitespace: bool) -> PyResult<Bound<'py, PyList>> {
+    let a = Html::parse_document(&html_a.decode(&DecodeOptions::default())?);
+    let b = Html::parse_document(&html_b.decode(&DecodeOptions::default())?);
+    let (hashes_a, hashes_b) = (subtree_hashes(&a, normalize_whitespace), subtree_hashes(&b, normalize_whitespace));
+    let differ = Differ { py, changes: PyList::empty_bound(py), normalize_whitespace, hashes_a, hashes_b };
+    differ.compare(a.root_element(), b.root_element())?;
+    Ok(differ.changes)
+}
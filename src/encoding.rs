@@ -1,45 +1,1035 @@
 use pyo3::prelude::*;
-use encoding_rs_io::DecodeReaderBytesBuilder;
-use std::io::Read;
+use pyo3::buffer::PyBuffer;
+use pyo3::types::{PyBytes, PyDict, PyMemoryView, PyString};
+use std::borrow::Cow;
+use chardetng::EncodingDetector;
+use encoding_rs::{DecoderResult, Encoding};
 
-/// Decodes a byte slice into a String, attempting to auto-detect the encoding.
+/// Number of bytes inspected by the HTML5 prescan, as browsers do.
+const PRESCAN_LIMIT: usize = 1024;
+
+/// Unicode encodings that can be identified by a byte order mark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bom {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Bom {
+    /// Length of the byte order mark in bytes.
+    pub fn len(self) -> usize {
+        match self {
+            Bom::Utf8 => 3,
+            Bom::Utf16Le | Bom::Utf16Be => 2,
+            Bom::Utf32Le | Bom::Utf32Be => 4,
+        }
+    }
+
+    /// Canonical label of the encoding announced by the BOM.
+    pub fn label(self) -> &'static str {
+        match self {
+            Bom::Utf8 => "utf-8",
+            Bom::Utf16Le => "utf-16le",
+            Bom::Utf16Be => "utf-16be",
+            Bom::Utf32Le => "utf-32le",
+            Bom::Utf32Be => "utf-32be",
+        }
+    }
+}
+
+/// Detects a leading byte order mark.
+///
+/// UTF-32LE is checked before UTF-16LE since its BOM starts with the UTF-16LE one.
+pub fn detect_bom(data: &[u8]) -> Option<Bom> {
+    match data {
+        [0xFF, 0xFE, 0x00, 0x00, ..] => Some(Bom::Utf32Le),
+        [0x00, 0x00, 0xFE, 0xFF, ..] => Some(Bom::Utf32Be),
+        [0xEF, 0xBB, 0xBF, ..] => Some(Bom::Utf8),
+        [0xFF, 0xFE, ..] => Some(Bom::Utf16Le),
+        [0xFE, 0xFF, ..] => Some(Bom::Utf16Be),
+        _ => None,
+    }
+}
+
+/// HTML accepted from Python as `str`, undecoded `bytes`, or any other object exposing bytes
+/// through the buffer protocol (`memoryview`, `mmap`, `bytearray`, numpy `uint8` arrays).
+#[derive(FromPyObject)]
+pub enum HtmlInput<'py> {
+    #[pyo3(transparent, annotation = "str")]
+    Str(Bound<'py, PyString>),
+    #[pyo3(transparent, annotation = "bytes")]
+    Bytes(Bound<'py, PyBytes>),
+    #[pyo3(transparent, annotation = "Buffer")]
+    Buffer(BufferInput),
+}
+
+impl HtmlInput<'_> {
+    /// Returns the HTML text, decoding bytes input according to `options`.
+    pub fn decode(&self, options: &DecodeOptions) -> PyResult<Cow<'_, str>> {
+        Ok(self.decode_with_info(options)?.0)
+    }
+
+    /// Like `decode`, also reporting how bytes input was decoded (`None` for `str` input).
+    /// Bytes that are already UTF-8 are borrowed rather than copied.
+    pub fn decode_with_info(&self, options: &DecodeOptions) -> PyResult<(Cow<'_, str>, Option<EncodingInfo>)> {
+        let bytes = match self {
+            HtmlInput::Str(s) => {
+                let text = s.to_str()?;
+                return if options.fix_mojibake {
+                    Ok((Cow::Owned(fix_mojibake(text).into_owned()), None))
+                } else {
+                    Ok((Cow::Borrowed(text), None))
+                };
+            }
+            HtmlInput::Bytes(b) => b.as_bytes(),
+            HtmlInput::Buffer(buffer) => buffer.as_bytes(),
+        };
+        let (text, info) = decode_slice(bytes, options)?;
+        Ok((text, Some(info)))
+    }
+}
+
+/// Bytes read through the buffer protocol. C-contiguous buffers over memory nothing can
+/// write to (a `memoryview` of `bytes`, an `mmap` opened with `ACCESS_READ`) are borrowed in
+/// place for as long as the input lives; all others are copied once, since Python code run
+/// mid-parse, such as a logging handler, could otherwise change them under the parser. A
+/// read-only export is not enough: `memoryview(bytearray).toreadonly()` and numpy arrays with
+/// `writeable=False` still sit on memory their owner can write.
+pub struct BufferInput(BufferBytes);
+
+enum BufferBytes {
+    Borrowed(PyBuffer<u8>),
+    Copied(Vec<u8>),
+}
+
+impl BufferInput {
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.0 {
+            BufferBytes::Borrowed(buffer) if buffer.len_bytes() == 0 => &[],
+            // SAFETY: the buffer is C-contiguous and `len_bytes` long, its exporter is kept
+            // alive and cannot resize it while the export is held, and `immutable_exporter`
+            // vouched that nothing can write to its memory.
+            BufferBytes::Borrowed(buffer) => unsafe {
+                std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.len_bytes())
+            },
+            BufferBytes::Copied(bytes) => bytes,
+        }
+    }
+}
+
+impl<'py> FromPyObject<'py> for BufferInput {
+    fn extract_bound(obj: &Bound<'py, PyAny>) -> PyResult<Self> {
+        let buffer = PyBuffer::<u8>::get_bound(obj)?;
+        if buffer.is_c_contiguous() && immutable_exporter(obj)? {
+            Ok(BufferInput(BufferBytes::Borrowed(buffer)))
+        } else {
+            Ok(BufferInput(BufferBytes::Copied(buffer.to_vec(obj.py())?)))
+        }
+    }
+}
+
+/// Whether the memory `obj` exports can never be written: it is, or is a `memoryview` of,
+/// a `bytes` object or an `mmap` opened with `ACCESS_READ`. An `mmap` exports read-only
+/// buffers only then, so a fresh export of it tells the two apart.
+fn immutable_exporter(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let exporter = match obj.downcast::<PyMemoryView>() {
+        Ok(view) => view.getattr("obj")?,
+        Err(_) => obj.clone(),
+    };
+    if exporter.is_exact_instance_of::<PyBytes>() {
+        return Ok(true);
+    }
+    let mmap = obj.py().import_bound("mmap")?.getattr("mmap")?;
+    Ok(exporter.get_type().is(&mmap) && PyBuffer::<u8>::get_bound(&exporter)?.readonly())
+}
+
+/// Caller-supplied knobs steering how bytes input is decoded, mirroring bs4's UnicodeDammit.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// Encoding forced by the caller; only a BOM takes precedence over it.
+    pub from_encoding: Option<&'static Encoding>,
+    /// Encodings that detection must never settle on.
+    pub exclude_encodings: Vec<&'static Encoding>,
+    /// Charset taken from a transport-level `Content-Type` header, if any.
+    pub content_type: Option<&'static Encoding>,
+    /// What to do with byte sequences that are malformed in the chosen encoding.
+    pub errors: ErrorMode,
+    /// Repair UTF-8 that was previously mis-decoded as windows-1252 (`Ã©` -> `é`).
+    pub fix_mojibake: bool,
+}
+
+impl DecodeOptions {
+    /// Builds options from the Python-facing decoding arguments.
+    ///
+    /// An unrecognized charset in `content_type` is ignored, as browsers do, while unknown
+    /// labels passed explicitly as `from_encoding` or `exclude_encodings` raise `EncodingError`.
+    pub fn new(
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+        fix_mojibake: bool,
+    ) -> PyResult<Self> {
+        Ok(DecodeOptions {
+            from_encoding: from_encoding.map(lookup_encoding).transpose()?,
+            exclude_encodings: exclude_encodings
+                .unwrap_or_default()
+                .iter()
+                .map(|label| lookup_encoding(label))
+                .collect::<PyResult<_>>()?,
+            content_type: content_type.and_then(charset_from_content_type),
+            errors: errors.map(ErrorMode::parse).transpose()?.unwrap_or_default(),
+            fix_mojibake,
+        })
+    }
+
+    fn allows(&self, encoding: &'static Encoding) -> bool {
+        !self.exclude_encodings.contains(&encoding)
+    }
+}
+
+/// Handling of malformed byte sequences, named after Python's codec `errors` argument.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorMode {
+    /// Raise `EncodingError` at the first malformed sequence.
+    Strict,
+    /// Substitute U+FFFD for each malformed sequence.
+    #[default]
+    Replace,
+    /// Drop malformed sequences.
+    Ignore,
+}
+
+impl ErrorMode {
+    pub fn parse(mode: &str) -> PyResult<Self> {
+        match mode {
+            "strict" => Ok(ErrorMode::Strict),
+            "replace" => Ok(ErrorMode::Replace),
+            "ignore" => Ok(ErrorMode::Ignore),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid errors mode: {}. Use 'strict', 'replace' or 'ignore'", mode)
+            )),
+        }
+    }
+}
+
+/// Extracts the encoding from a header value such as `text/html; charset=ISO-8859-1`.
+pub fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    extract_charset_from_content(content_type.as_bytes()).and_then(Encoding::for_label)
+}
+
+/// Resolves an encoding label (e.g. "shift_jis", "latin-1") using the WHATWG label table,
+/// also accepting Python codec spellings such as "utf_8" or "latin-1".
+pub fn lookup_encoding(label: &str) -> PyResult<&'static Encoding> {
+    let label = label.trim();
+    Encoding::for_label(label.as_bytes())
+        .or_else(|| Encoding::for_label(label.replace('_', "-").as_bytes()))
+        .or_else(|| Encoding::for_label(label.replace(['_', '-'], "").as_bytes()))
+        .ok_or_else(|| {
+            PyErr::new::<crate::errors::EncodingError, _>(format!("Unknown encoding: {}", label))
+        })
+}
+
+/// How the encoding of a bytes input was determined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodingSource {
+    Bom,
+    Override,
+    Header,
+    Meta,
+    Heuristic,
+}
+
+impl EncodingSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EncodingSource::Bom => "bom",
+            EncodingSource::Override => "override",
+            EncodingSource::Header => "header",
+            EncodingSource::Meta => "meta",
+            EncodingSource::Heuristic => "heuristic",
+        }
+    }
+
+    /// The source named by `as_str`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bom" => Some(EncodingSource::Bom),
+            "override" => Some(EncodingSource::Override),
+            "header" => Some(EncodingSource::Header),
+            "meta" => Some(EncodingSource::Meta),
+            "heuristic" => Some(EncodingSource::Heuristic),
+            _ => None,
+        }
+    }
+}
+
+/// Audit record describing how a bytes input was decoded.
+#[derive(Clone, Debug)]
+pub struct EncodingInfo {
+    /// Lowercase encoding name, e.g. "utf-8" or "shift_jis".
+    pub encoding: String,
+    pub source: EncodingSource,
+    /// Rough confidence in the decision, from 0.0 to 1.0.
+    pub confidence: f32,
+}
+
+impl EncodingInfo {
+    fn new(encoding: &'static Encoding, source: EncodingSource, confidence: f32) -> Self {
+        EncodingInfo { encoding: encoding.name().to_ascii_lowercase(), source, confidence }
+    }
+}
+
+/// Decoded text together with the decision that produced it.
+pub struct Decoded {
+    pub text: String,
+    pub info: EncodingInfo,
+}
+
+/// Decoder picked for a bytes input.
+#[derive(Clone, Copy, Debug)]
+pub enum Decoder {
+    Bom(Bom),
+    Encoding(&'static Encoding),
+}
+
+/// Outcome of encoding detection, before any text is produced.
+#[derive(Clone, Debug)]
+pub struct Detection {
+    pub decoder: Decoder,
+    pub info: EncodingInfo,
+}
+
+/// Determines the encoding of a byte slice without decoding it into a String.
 ///
 /// The process is as follows:
-/// 1. Check for and strip the UTF-8 BOM.
-/// 2. Try to decode as UTF-8.
-/// 3. If UTF-8 fails, inspect the HTML for a meta tag specifying the charset.
-/// 4. If a charset is found, use it to decode.
-/// 5. If no charset is found, fall back to Latin-1 (ISO-8859-1) as a last resort.
-pub fn decode_bytes_to_string(data: &[u8]) -> PyResult<String> {
-    // 1. Strip UTF-8 BOM if present
-    let bytes = if data.len() >= 3 && data[0] == 0xEF && data[1] == 0xBB && data[2] == 0xBF {
-        &data[3..]
-    } else {
-        data
+/// 1. If a UTF-8, UTF-16 or UTF-32 BOM is present, use that encoding.
+/// 2. If the caller forced an encoding with `from_encoding`, use it.
+/// 3. Use the charset of a `Content-Type` header hint, if one was given.
+/// 4. Otherwise prescan the first 1024 bytes for a `<meta>` charset declaration.
+/// 5. Use UTF-8 if the bytes are valid UTF-8.
+/// 6. Run a statistical detector over the bytes and use its guess.
+/// 7. If that guess cannot decode the bytes, fall back to windows-1252 as a last resort.
+///
+/// Steps 3-7 skip any encoding listed in `exclude_encodings`. The guess of step 6 is only
+/// accepted with malformed sequences when `errors` is not strict.
+pub fn detect(data: &[u8], options: &DecodeOptions) -> PyResult<Detection> {
+    let found = |encoding: &'static Encoding, source, confidence| Detection {
+        decoder: Decoder::Encoding(encoding),
+        info: EncodingInfo::new(encoding, source, confidence),
     };
 
-    // 2. Try decoding as UTF-8 first
-    if let Ok(s) = std::str::from_utf8(bytes) {
-        return Ok(s.to_string());
+    // 1. A BOM is authoritative
+    if let Some(bom) = detect_bom(data) {
+        return Ok(Detection {
+            decoder: Decoder::Bom(bom),
+            info: EncodingInfo { encoding: bom.label().to_string(), source: EncodingSource::Bom, confidence: 1.0 },
+        });
+    }
+
+    // 2. Caller override
+    if let Some(encoding) = options.from_encoding {
+        return Ok(found(encoding, EncodingSource::Override, 1.0));
     }
 
-    // 3. Sniff from meta tag if UTF-8 fails
-    let mut decoder = DecodeReaderBytesBuilder::new()
-        .encoding(None)
-        .build(bytes);
+    // 3. Transport-level header
+    if let Some(encoding) = options.content_type.filter(|e| options.allows(e)) {
+        let confidence = if decodes_cleanly(encoding, data) { 0.95 } else { 0.5 };
+        return Ok(found(encoding, EncodingSource::Header, confidence));
+    }
+
+    // 4. A meta declaration wins over content sniffing, matching browser behavior
+    if let Some(encoding) = prescan_meta_charset(data).filter(|e| options.allows(e)) {
+        let confidence = if decodes_cleanly(encoding, data) { 0.95 } else { 0.5 };
+        return Ok(found(encoding, EncodingSource::Meta, confidence));
+    }
 
-    let mut decoded_string = String::new();
-    if decoder.read_to_string(&mut decoded_string).is_ok() {
-        return Ok(decoded_string);
+    // 5. Valid UTF-8
+    if options.allows(encoding_rs::UTF_8) && std::str::from_utf8(data).is_ok() {
+        // Valid multi-byte UTF-8 is very unlikely to be accidental; pure ASCII says little
+        let confidence = if data.is_ascii() { 0.9 } else { 0.99 };
+        return Ok(found(encoding_rs::UTF_8, EncodingSource::Heuristic, confidence));
     }
 
-    // 5. Fallback to WINDOWS_1252 if everything else fails
-    let (cow, _, had_errors) = encoding_rs::WINDOWS_1252.decode(bytes);
-    if had_errors {
+    // 6. Statistical detection for undeclared legacy pages (CJK, Cyrillic, ...)
+    let (guess, confident) = sniff_encoding(data);
+    if options.allows(guess) {
+        let clean = decodes_cleanly(guess, data);
+        // A wrong guess under strict decoding lets the fallback below have a go
+        if clean || options.errors != ErrorMode::Strict {
+            let confidence = match (confident, clean) {
+                (_, false) => 0.3,
+                (true, true) => 0.8,
+                (false, true) => 0.5,
+            };
+            return Ok(found(guess, EncodingSource::Heuristic, confidence));
+        }
+    }
+
+    // 7. Fallback to WINDOWS_1252 if everything else fails
+    if !options.allows(encoding_rs::WINDOWS_1252) || !decodes_cleanly(encoding_rs::WINDOWS_1252, data) {
         return Err(PyErr::new::<crate::errors::EncodingError, _>(
             "Failed to decode bytes with any supported encoding".to_string()
         ));
     }
+    Ok(found(encoding_rs::WINDOWS_1252, EncodingSource::Heuristic, 0.1))
+}
+
+/// Decodes a byte slice into a String, attempting to auto-detect the encoding
+/// and recording which encoding was used and why (see `detect`).
+///
+/// Malformed sequences are handled according to `errors`. With `fix_mojibake`, the
+/// decoded text is finally passed through `fix_mojibake`.
+pub fn decode_bytes(data: &[u8], options: &DecodeOptions) -> PyResult<Decoded> {
+    let (text, info) = decode_slice(data, options)?;
+    Ok(Decoded { text: text.into_owned(), info })
+}
+
+/// `decode_bytes` that borrows `data` when it is already well-formed UTF-8, instead of
+/// copying multi-megabyte pages into a new String.
+pub fn decode_slice<'a>(data: &'a [u8], options: &DecodeOptions) -> PyResult<(Cow<'a, str>, EncodingInfo)> {
+    let Detection { decoder, info } = detect(data, options)?;
+    let utf8 = match decoder {
+        Decoder::Bom(Bom::Utf8) => Some(&data[Bom::Utf8.len()..]),
+        Decoder::Encoding(encoding) if encoding == encoding_rs::UTF_8 => Some(data),
+        _ => None,
+    };
+    let mut text = match utf8.map(std::str::from_utf8) {
+        Some(Ok(text)) => Cow::Borrowed(text),
+        _ => Cow::Owned(match decoder {
+            Decoder::Bom(bom) => decode_with_bom(bom, &data[bom.len()..], options.errors)?.0,
+            Decoder::Encoding(encoding) => decode_with_mode(encoding, data, options.errors)?.0,
+        }),
+    };
+    if options.fix_mojibake {
+        if let Cow::Owned(fixed) = fix_mojibake(&text) {
+            text = Cow::Owned(fixed);
+        }
+    }
+    Ok((text, info))
+}
+
+/// Checks whether `data` decodes without malformed sequences, without allocating output.
+fn decodes_cleanly(encoding: &'static Encoding, data: &[u8]) -> bool {
+    if encoding == encoding_rs::UTF_8 {
+        return std::str::from_utf8(data).is_ok();
+    }
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut scratch = [0u8; 4096];
+    let mut pos = 0;
+    loop {
+        let (result, read, _) = decoder.decode_to_utf8_without_replacement(&data[pos..], &mut scratch, true);
+        pos += read;
+        match result {
+            DecoderResult::InputEmpty => return true,
+            DecoderResult::OutputFull => {}
+            DecoderResult::Malformed(..) => return false,
+        }
+    }
+}
+
+/// Detects the encoding of raw bytes without parsing them, returning a dict with the
+/// `encoding` label, a `confidence` from 0.0 to 1.0 and the detection `source`
+/// ("bom", "override", "header", "meta" or "heuristic").
+#[pyfunction]
+#[pyo3(signature = (data, exclude_encodings=None, content_type=None))]
+pub fn detect_encoding<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    exclude_encodings: Option<Vec<String>>,
+    content_type: Option<&str>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let options = DecodeOptions::new(None, exclude_encodings, None, content_type, false)?;
+    let info = detect(data, &options)?.info;
+    let result = PyDict::new_bound(py);
+    result.set_item("encoding", info.encoding)?;
+    result.set_item("confidence", info.confidence)?;
+    result.set_item("source", info.source.as_str())?;
+    Ok(result)
+}
+
+/// Encodes text for output in the given encoding. Characters the encoding cannot represent
+/// are written as HTML numeric character references (`&#8364;`), keeping the markup valid.
+pub fn encode_string(text: &str, label: &str) -> PyResult<Vec<u8>> {
+    let encoding = lookup_encoding(label)?;
+    if encoding == encoding_rs::UTF_16LE {
+        return Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect());
+    }
+    if encoding == encoding_rs::UTF_16BE {
+        return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+    if encoding == encoding_rs::REPLACEMENT || encoding == encoding_rs::X_USER_DEFINED {
+        return Err(PyErr::new::<crate::errors::EncodingError, _>(
+            format!("Cannot encode output as {}", label)
+        ));
+    }
+    let (bytes, _, _) = encoding.encode(text);
+    Ok(bytes.into_owned())
+}
+
+/// Characters windows-1252 assigns to bytes 0x80-0x9F. The five unassigned bytes decode
+/// to the matching C1 control, as in the WHATWG encoding standard.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Maps a character back to the byte windows-1252 (or Latin-1) would have decoded it from.
+fn windows_1252_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        cp @ (0x00..=0x7F | 0xA0..=0xFF) => Some(cp as u8),
+        // Latin-1 readings of C1 bytes show up too
+        cp @ 0x80..=0x9F => Some(cp as u8),
+        _ => WINDOWS_1252_HIGH.iter().position(|&h| h == c).map(|i| 0x80 + i as u8),
+    }
+}
+
+/// Repairs UTF-8 text that was decoded as windows-1252 or Latin-1 somewhere upstream,
+/// e.g. `CafÃ©` -> `Café`, `â€™` -> `’`, `Â£` -> `£`.
+///
+/// Only character runs that re-encode to a complete, valid UTF-8 multi-byte sequence are
+/// replaced, so legitimate accented text is left alone. Double-encoded text is unwound by
+/// repeating the pass a few times.
+pub fn fix_mojibake(text: &str) -> Cow<'_, str> {
+    let mut current = Cow::Borrowed(text);
+    for _ in 0..3 {
+        match fix_mojibake_pass(&current) {
+            Some(fixed) => current = Cow::Owned(fixed),
+            None => break,
+        }
+    }
+    current
+}
+
+fn fix_mojibake_pass(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let width = match windows_1252_byte(c) {
+            Some(0xC2..=0xDF) => 2,
+            Some(0xE0..=0xEF) => 3,
+            Some(0xF0..=0xF4) => 4,
+            _ => 0,
+        };
+        if width > 0 && i + width <= chars.len() {
+            let bytes: Option<Vec<u8>> = chars[i..i + width]
+                .iter()
+                .map(|&ch| windows_1252_byte(ch))
+                .collect();
+            if let Some(Ok(s)) = bytes.as_deref().map(std::str::from_utf8) {
+                out.push_str(s);
+                changed = true;
+                i += width;
+                continue;
+            }
+        }
+        out.push(c);
+        i += 1;
+    }
+
+    if changed { Some(out) } else { None }
+}
+
+/// Guesses the encoding of undeclared bytes with chardetng, the detector used by Firefox.
+/// The flag reports whether the detector considers its guess reliable.
+pub fn sniff_encoding(data: &[u8]) -> (&'static Encoding, bool) {
+    let mut detector = EncodingDetector::new();
+    detector.feed(data, true);
+    detector.guess_assess(None, true)
+}
+
+/// Decodes the bytes following a BOM. Malformed sequences become U+FFFD.
+fn decode_with_bom(bom: Bom, bytes: &[u8], mode: ErrorMode) -> PyResult<(String, bool)> {
+    match bom {
+        Bom::Utf8 => decode_with_mode(encoding_rs::UTF_8, bytes, mode),
+        Bom::Utf16Le => decode_with_mode(encoding_rs::UTF_16LE, bytes, mode),
+        Bom::Utf16Be => decode_with_mode(encoding_rs::UTF_16BE, bytes, mode),
+        Bom::Utf32Le => decode_utf32(bytes, u32::from_le_bytes, mode),
+        Bom::Utf32Be => decode_utf32(bytes, u32::from_be_bytes, mode),
+    }
+}
+
+/// Decodes without BOM handling, treating malformed sequences according to `mode`.
+/// Also reports whether any malformed sequence was met.
+pub fn decode_with_mode(encoding: &'static Encoding, bytes: &[u8], mode: ErrorMode) -> PyResult<(String, bool)> {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder
+        .max_utf8_buffer_length_without_replacement(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut out = String::with_capacity(capacity);
+    let had_errors = decode_into(&mut decoder, bytes, &mut out, true, mode, 0)?;
+    Ok((out, had_errors))
+}
+
+/// Runs a (possibly mid-stream) decoder over `bytes`, appending to `out`.
+/// `offset` is the stream position of `bytes`, used in error messages.
+fn decode_into(
+    decoder: &mut encoding_rs::Decoder,
+    bytes: &[u8],
+    out: &mut String,
+    last: bool,
+    mode: ErrorMode,
+    offset: usize,
+) -> PyResult<bool> {
+    let mut had_errors = false;
+    let mut pos = 0;
+
+    loop {
+        let (result, read) = decoder.decode_to_string_without_replacement(&bytes[pos..], out, last);
+        pos += read;
+        match result {
+            DecoderResult::InputEmpty => return Ok(had_errors),
+            DecoderResult::OutputFull => out.reserve(bytes.len() - pos + 16),
+            DecoderResult::Malformed(bad, consumed_after) => {
+                had_errors = true;
+                match mode {
+                    ErrorMode::Strict => {
+                        // Malformed sequences split across chunks can start before this one
+                        let start = (offset + pos).saturating_sub(bad as usize + consumed_after as usize);
+                        return Err(malformed_error(decoder.encoding().name(), start));
+                    }
+                    ErrorMode::Replace => out.push(char::REPLACEMENT_CHARACTER),
+                    ErrorMode::Ignore => {}
+                }
+            }
+        }
+    }
+}
+
+fn malformed_error(encoding: &str, offset: usize) -> PyErr {
+    let err = PyErr::new::<crate::errors::EncodingError, _>(
+        format!("Invalid {} byte sequence at offset {}", encoding.to_ascii_lowercase(), offset)
+    );
+    crate::errors::with_detail(err, "position", offset)
+}
+
+/// UTF-32 is not part of the WHATWG encoding set, so encoding_rs cannot decode it.
+fn decode_utf32(bytes: &[u8], read: fn([u8; 4]) -> u32, mode: ErrorMode) -> PyResult<(String, bool)> {
+    let mut out = String::with_capacity(bytes.len() / 4);
+    let (mut had_errors, used) = decode_utf32_into(bytes, read, &mut out, mode, 0)?;
+    if used < bytes.len() {
+        had_errors = true;
+        push_utf32_error(&mut out, mode, used)?;
+    }
+    Ok((out, had_errors))
+}
+
+/// Decodes the complete code units of `bytes`, returning whether any was invalid and how
+/// many bytes were used; an incomplete trailing unit is left for the caller.
+fn decode_utf32_into(
+    bytes: &[u8],
+    read: fn([u8; 4]) -> u32,
+    out: &mut String,
+    mode: ErrorMode,
+    offset: usize,
+) -> PyResult<(bool, usize)> {
+    let mut had_errors = false;
+    for (i, c) in bytes.chunks_exact(4).enumerate() {
+        match char::from_u32(read([c[0], c[1], c[2], c[3]])) {
+            Some(ch) => out.push(ch),
+            None => {
+                had_errors = true;
+                push_utf32_error(out, mode, offset + i * 4)?;
+            }
+        }
+    }
+    Ok((had_errors, bytes.len() - bytes.len() % 4))
+}
+
+fn push_utf32_error(out: &mut String, mode: ErrorMode, offset: usize) -> PyResult<()> {
+    match mode {
+        ErrorMode::Strict => return Err(malformed_error("utf-32", offset)),
+        ErrorMode::Replace => out.push(char::REPLACEMENT_CHARACTER),
+        ErrorMode::Ignore => {}
+    }
+    Ok(())
+}
+
+enum StreamState {
+    /// Buffering until enough bytes have arrived to detect the encoding.
+    Sniffing(Vec<u8>),
+    Decoding(Box<encoding_rs::Decoder>),
+    Utf32 { read: fn([u8; 4]) -> u32, pending: Vec<u8> },
+    Closed,
+}
+
+/// Incremental decoder for bytes arriving in chunks, e.g. from a streaming HTTP response.
+///
+/// The first chunks are buffered until 1024 bytes (the meta prescan window) are available,
+/// then the encoding is detected as for whole documents and text is produced as chunks
+/// arrive. Multi-byte sequences split across chunk boundaries are reassembled.
+#[pyclass]
+pub struct StreamDecoder {
+    options: DecodeOptions,
+    state: StreamState,
+    info: Option<EncodingInfo>,
+    /// Bytes consumed so far, for error offsets.
+    consumed: usize,
+}
+
+impl StreamDecoder {
+    pub fn with_options(options: DecodeOptions) -> Self {
+        StreamDecoder { options, state: StreamState::Sniffing(Vec::new()), info: None, consumed: 0 }
+    }
+
+    /// Feeds a chunk, returning the text that could be decoded so far.
+    pub fn feed_bytes(&mut self, data: &[u8]) -> PyResult<String> {
+        if let StreamState::Sniffing(buffer) = &mut self.state {
+            buffer.extend_from_slice(data);
+            // An explicit encoding only has to wait long enough to rule out a BOM
+            let explicit = self.options.from_encoding.is_some() || self.options.content_type.is_some();
+            if buffer.len() < PRESCAN_LIMIT && !(explicit && buffer.len() >= 4) {
+                return Ok(String::new());
+            }
+            let buffer = std::mem::take(buffer);
+            return self.start(&buffer, false);
+        }
+        self.decode_chunk(data, false)
+    }
+
+    /// How the stream is being decoded, once enough bytes have arrived to tell.
+    pub fn info(&self) -> Option<&EncodingInfo> {
+        self.info.as_ref()
+    }
+
+    /// Signals the end of input, returning any remaining text.
+    pub fn finish(&mut self) -> PyResult<String> {
+        let text = match &mut self.state {
+            StreamState::Sniffing(buffer) => {
+                let buffer = std::mem::take(buffer);
+                self.start(&buffer, true)?
+            }
+            _ => self.decode_chunk(&[], true)?,
+        };
+        self.state = StreamState::Closed;
+        Ok(text)
+    }
+
+    /// Detects the encoding from the buffered prefix and decodes it.
+    fn start(&mut self, prefix: &[u8], last: bool) -> PyResult<String> {
+        // A prefix cut inside a UTF-8 sequence must not rule out UTF-8
+        let sniff_len = match std::str::from_utf8(prefix) {
+            Err(e) if !last && e.error_len().is_none() => e.valid_up_to(),
+            _ => prefix.len(),
+        };
+        let Detection { decoder, info } = detect(&prefix[..sniff_len], &self.options)?;
+        self.info = Some(info);
+
+        let body = match decoder {
+            Decoder::Bom(Bom::Utf32Le) => {
+                self.state = StreamState::Utf32 { read: u32::from_le_bytes, pending: Vec::new() };
+                &prefix[4..]
+            }
+            Decoder::Bom(Bom::Utf32Be) => {
+                self.state = StreamState::Utf32 { read: u32::from_be_bytes, pending: Vec::new() };
+                &prefix[4..]
+            }
+            Decoder::Bom(bom) => {
+                let encoding = match bom {
+                    Bom::Utf16Le => encoding_rs::UTF_16LE,
+                    Bom::Utf16Be => encoding_rs::UTF_16BE,
+                    _ => encoding_rs::UTF_8,
+                };
+                self.state = StreamState::Decoding(Box::new(encoding.new_decoder_without_bom_handling()));
+                &prefix[bom.len()..]
+            }
+            Decoder::Encoding(encoding) => {
+                self.state = StreamState::Decoding(Box::new(encoding.new_decoder_without_bom_handling()));
+                prefix
+            }
+        };
+        self.consumed = prefix.len() - body.len();
+        self.decode_chunk(body, last)
+    }
+
+    fn decode_chunk(&mut self, data: &[u8], last: bool) -> PyResult<String> {
+        let mode = self.options.errors;
+        let offset = self.consumed;
+        let mut out = String::with_capacity(data.len());
+        match &mut self.state {
+            StreamState::Decoding(decoder) => {
+                decode_into(decoder, data, &mut out, last, mode, offset)?;
+            }
+            StreamState::Utf32 { read, pending } => {
+                // Complete the code unit left over from the previous chunk first
+                let offset = offset - pending.len();
+                pending.extend_from_slice(data);
+                let (_, used) = decode_utf32_into(pending, *read, &mut out, mode, offset)?;
+                pending.drain(..used);
+                if last && !pending.is_empty() {
+                    push_utf32_error(&mut out, mode, offset + used)?;
+                }
+            }
+            StreamState::Closed => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "StreamDecoder is already closed"
+                ));
+            }
+            StreamState::Sniffing(_) => unreachable!("decode_chunk runs after detection"),
+        }
+        self.consumed += data.len();
+        Ok(out)
+    }
+}
+
+#[pymethods]
+impl StreamDecoder {
+    #[new]
+    #[pyo3(signature = (from_encoding=None, exclude_encodings=None, errors=None, content_type=None))]
+    fn new(
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+    ) -> PyResult<Self> {
+        let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, false)?;
+        Ok(StreamDecoder::with_options(options))
+    }
+
+    /// Feed a chunk of bytes; returns the text decoded so far ("" while still detecting)
+    fn feed(&mut self, data: &[u8]) -> PyResult<String> {
+        self.feed_bytes(data)
+    }
+
+    /// Flush buffered bytes at end of input and return the remaining text
+    fn close(&mut self) -> PyResult<String> {
+        self.finish()
+    }
+
+    /// Detected encoding, or None until enough bytes have been fed
+    #[getter]
+    fn original_encoding(&self) -> Option<String> {
+        self.info.as_ref().map(|info| info.encoding.clone())
+    }
+
+    /// How the encoding was determined: "bom", "override", "header", "meta" or "heuristic"
+    #[getter]
+    fn encoding_source(&self) -> Option<&'static str> {
+        self.info.as_ref().map(|info| info.source.as_str())
+    }
+
+    /// Confidence in the detected encoding, from 0.0 to 1.0
+    #[getter]
+    fn encoding_confidence(&self) -> Option<f32> {
+        self.info.as_ref().map(|info| info.confidence)
+    }
+}
+
+/// Prescans the start of a byte stream for a `<meta charset>` or
+/// `<meta http-equiv="content-type" content="...;charset=...">` declaration.
+///
+/// Follows the "prescan a byte stream to determine its encoding" algorithm
+/// from the HTML standard, limited to the first 1024 bytes.
+pub fn prescan_meta_charset(data: &[u8]) -> Option<&'static Encoding> {
+    let bytes = &data[..data.len().min(PRESCAN_LIMIT)];
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            // Comments are skipped wholesale; the closing dashes may overlap the opener
+            pos = match find(&bytes[pos + 2..], b"-->") {
+                Some(i) => pos + 2 + i + 3,
+                None => return None,
+            };
+        } else if starts_with_ignore_case(rest, b"<meta")
+            && rest.get(5).is_some_and(|&b| is_space(b) || b == b'/')
+        {
+            pos += 6;
+            if let Some(encoding) = prescan_meta_attributes(bytes, &mut pos) {
+                return Some(encoding);
+            }
+        } else if rest.len() >= 2
+            && rest[0] == b'<'
+            && (rest[1].is_ascii_alphabetic()
+                || (rest[1] == b'/' && rest.get(2).is_some_and(|b| b.is_ascii_alphabetic())))
+        {
+            // Other tags: skip the name, then consume (and ignore) their attributes
+            pos += 1;
+            while pos < bytes.len() && !is_space(bytes[pos]) && bytes[pos] != b'>' {
+                pos += 1;
+            }
+            while get_attribute(bytes, &mut pos).is_some() {}
+            pos += 1;
+        } else if rest.starts_with(b"<!") || rest.starts_with(b"</") || rest.starts_with(b"<?") {
+            pos = match bytes[pos..].iter().position(|&b| b == b'>') {
+                Some(i) => pos + i + 1,
+                None => return None,
+            };
+        } else {
+            pos += 1;
+        }
+    }
+
+    None
+}
+
+/// Processes the attributes of a `<meta` tag, returning the declared encoding if it is usable.
+fn prescan_meta_attributes(bytes: &[u8], pos: &mut usize) -> Option<&'static Encoding> {
+    let mut seen: Vec<Vec<u8>> = Vec::new();
+    let mut got_pragma = false;
+    let mut need_pragma: Option<bool> = None;
+    let mut charset: Option<&'static Encoding> = None;
+
+    while let Some((name, value)) = get_attribute(bytes, pos) {
+        if seen.contains(&name) {
+            continue;
+        }
+        match name.as_slice() {
+            b"http-equiv" if value.eq_ignore_ascii_case(b"content-type") => {
+                got_pragma = true;
+            }
+            b"content" if charset.is_none() => {
+                if let Some(encoding) = extract_charset_from_content(&value).and_then(Encoding::for_label) {
+                    charset = Some(encoding);
+                    need_pragma = Some(true);
+                }
+            }
+            b"charset" if charset.is_none() => {
+                charset = Encoding::for_label(&value);
+                need_pragma = Some(false);
+            }
+            _ => {}
+        }
+        seen.push(name);
+    }
+
+    match need_pragma {
+        None => None,
+        Some(true) if !got_pragma => None,
+        _ => charset.map(|encoding| {
+            if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+                // A document that can be prescanned as ASCII cannot really be UTF-16
+                encoding_rs::UTF_8
+            } else if encoding == encoding_rs::X_USER_DEFINED {
+                encoding_rs::WINDOWS_1252
+            } else {
+                encoding
+            }
+        }),
+    }
+}
+
+/// The "get an attribute" step of the prescan. Returns the lowercased name and value.
+fn get_attribute(bytes: &[u8], pos: &mut usize) -> Option<(Vec<u8>, Vec<u8>)> {
+    while *pos < bytes.len() && (is_space(bytes[*pos]) || bytes[*pos] == b'/') {
+        *pos += 1;
+    }
+    if *pos >= bytes.len() || bytes[*pos] == b'>' {
+        return None;
+    }
+
+    let mut name = Vec::new();
+    let mut value = Vec::new();
+
+    // Attribute name
+    loop {
+        let b = *bytes.get(*pos)?;
+        if b == b'=' && !name.is_empty() {
+            *pos += 1;
+            break;
+        }
+        if is_space(b) {
+            while *pos < bytes.len() && is_space(bytes[*pos]) {
+                *pos += 1;
+            }
+            if bytes.get(*pos) != Some(&b'=') {
+                return Some((name, value));
+            }
+            *pos += 1;
+            break;
+        }
+        if b == b'/' || b == b'>' {
+            return Some((name, value));
+        }
+        name.push(b.to_ascii_lowercase());
+        *pos += 1;
+    }
+
+    // Attribute value
+    while *pos < bytes.len() && is_space(bytes[*pos]) {
+        *pos += 1;
+    }
+    let b = *bytes.get(*pos)?;
+    if b == b'"' || b == b'\'' {
+        *pos += 1;
+        loop {
+            let c = *bytes.get(*pos)?;
+            *pos += 1;
+            if c == b {
+                return Some((name, value));
+            }
+            value.push(c.to_ascii_lowercase());
+        }
+    }
+    if b == b'>' {
+        return Some((name, value));
+    }
+    loop {
+        let c = *bytes.get(*pos)?;
+        if is_space(c) || c == b'>' {
+            return Some((name, value));
+        }
+        value.push(c.to_ascii_lowercase());
+        *pos += 1;
+    }
+}
+
+/// Extracts the charset label from a `content="text/html; charset=..."` value.
+fn extract_charset_from_content(content: &[u8]) -> Option<&[u8]> {
+    let mut pos = 0;
+    loop {
+        let i = find_ignore_case(&content[pos..], b"charset")?;
+        pos += i + 7;
+        while pos < content.len() && is_space(content[pos]) {
+            pos += 1;
+        }
+        if content.get(pos) == Some(&b'=') {
+            pos += 1;
+            break;
+        }
+    }
+    while pos < content.len() && is_space(content[pos]) {
+        pos += 1;
+    }
+
+    match *content.get(pos)? {
+        quote @ (b'"' | b'\'') => {
+            let start = pos + 1;
+            let len = content[start..].iter().position(|&b| b == quote)?;
+            Some(&content[start..start + len])
+        }
+        _ => {
+            let len = content[pos..]
+                .iter()
+                .position(|&b| is_space(b) || b == b';')
+                .unwrap_or(content.len() - pos);
+            if len == 0 { None } else { Some(&content[pos..pos + len]) }
+        }
+    }
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w.eq_ignore_ascii_case(needle))
+}
 
-    Ok(cow.into_owned())
+fn starts_with_ignore_case(haystack: &[u8], prefix: &[u8]) -> bool {
+    haystack.len() >= prefix.len() && haystack[..prefix.len()].eq_ignore_ascii_case(prefix)
 }
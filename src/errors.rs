@@ -1,5 +1,6 @@
 use pyo3::create_exception;
 use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
 
 // Define Python-visible exception classes. For now, they all inherit from PyException.
 // We can later make them share a common base if needed.
@@ -7,3 +8,27 @@ create_exception!(rusticsoup, RusticSoupError, PyException);
 create_exception!(rusticsoup, HTMLParseError, RusticSoupError);
 create_exception!(rusticsoup, SelectorError, RusticSoupError);
 create_exception!(rusticsoup, EncodingError, RusticSoupError);
+create_exception!(rusticsoup, ParseTimeoutError, RusticSoupError);
+create_exception!(rusticsoup, LimitExceededError, RusticSoupError);
+
+/// Machine-readable attributes carried by every `RusticSoupError`. They default to `None` on
+/// the class and are set on the instance when the detail is known.
+pub const DETAIL_ATTRIBUTES: [&str; 4] = ["selector", "field", "page_index", "position"];
+
+/// Install the `None` defaults for `DETAIL_ATTRIBUTES` on the base exception class.
+pub fn init_detail_attributes(py: Python<'_>) -> PyResult<()> {
+    let base = py.get_type_bound::<RusticSoupError>();
+    for name in DETAIL_ATTRIBUTES {
+        base.setattr(name, py.None())?;
+    }
+    Ok(())
+}
+
+/// Set a detail attribute (see `DETAIL_ATTRIBUTES`) on the exception instance in `err`.
+pub fn with_detail(err: PyErr, name: &str, value: impl ToPyObject) -> PyErr {
+    Python::with_gil(|py| {
+        // Only fails if the instance forbids attribute assignment; the error itself still stands
+        let _ = err.value_bound(py).setattr(name, value.to_object(py));
+    });
+    err
+}
@@ -0,0 +1,497 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use ahash::{AHashMap, AHashSet};
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::selector::{self, Matcher, Query};
+
+/// Most near-miss suggestions reported per selector.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Class and id names within this edit distance count as near misses.
+const MAX_EDIT_DISTANCE: usize = 2;
+
+/// One simple selector within a compound (`div`, `.price`, `#main`, `[href]`, `:first-child`)
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Tag(String),
+    Class(String),
+    Id(String),
+    Other(String),
+}
+
+impl Part {
+    fn to_css(&self) -> String {
+        match self {
+            Part::Tag(name) => name.clone(),
+            Part::Class(name) => format!(".{}", name),
+            Part::Id(name) => format!("#{}", name),
+            Part::Other(raw) => raw.clone(),
+        }
+    }
+}
+
+/// A complex selector split into compound steps joined by combinators
+struct Steps {
+    compounds: Vec<String>,
+    /// `combinators[i]` joins `compounds[i]` to `compounds[i + 1]`
+    combinators: Vec<char>,
+}
+
+impl Steps {
+    /// The selector formed by the first `len` compounds, with `compounds[index]` replaced
+    fn prefix(&self, len: usize, replace: Option<(usize, &str)>) -> String {
+        let mut out = String::new();
+        for i in 0..len {
+            if i > 0 {
+                match self.combinators[i - 1] {
+                    ' ' => out.push(' '),
+                    c => {
+                        out.push(' ');
+                        out.push(c);
+                        out.push(' ');
+                    }
+                }
+            }
+            match replace {
+                Some((index, compound)) if index == i => out.push_str(compound),
+                _ => out.push_str(&self.compounds[i]),
+            }
+        }
+        out
+    }
+}
+
+/// Explain why `selector` matches what it does in `document`: how many elements survive each
+/// compound step, which step eliminated every candidate, and near-miss alternatives.
+pub fn explain(py: Python<'_>, document: &Html, selector_str: &str) -> PyResult<PyObject> {
+    // Surface syntax errors the same way select() does
+    let compiled = selector::compile(selector_str)?;
+
+    let alternatives = split_top_level(selector_str, |c| c == ',');
+    if alternatives.len() == 1 {
+        return Ok(explain_complex(py, document, selector_str.trim())?.into_any().unbind());
+    }
+    let result = PyDict::new_bound(py);
+    let parts = PyList::empty_bound(py);
+    for alternative in &alternatives {
+        parts.append(explain_complex(py, document, alternative.trim())?)?;
+    }
+    result.set_item("selector", selector_str)?;
+    result.set_item("matches", compiled.select(document).count())?;
+    result.set_item("alternatives", parts)?;
+    Ok(result.into())
+}
+
+fn explain_complex<'py>(py: Python<'py>, document: &Html, selector_str: &str) -> PyResult<Bound<'py, PyDict>> {
+    let steps = split_steps(selector_str);
+    let result = PyDict::new_bound(py);
+    let step_list = PyList::empty_bound(py);
+
+    let mut previous: Option<Vec<ElementRef>> = None;
+    let mut failed_step = None;
+    for len in 1..=steps.compounds.len() {
+        let prefix = steps.prefix(len, None);
+        let matched: Vec<ElementRef> = selector::compile(&prefix)?.select(document).collect();
+        let step = PyDict::new_bound(py);
+        step.set_item("selector", &prefix)?;
+        step.set_item("compound", &steps.compounds[len - 1])?;
+        step.set_item("matches", matched.len())?;
+        step_list.append(step)?;
+        if matched.is_empty() {
+            failed_step = Some(len - 1);
+            break;
+        }
+        previous = Some(matched);
+    }
+
+    let matches = match failed_step {
+        Some(_) => 0,
+        None => previous.as_ref().map_or(0, Vec::len),
+    };
+    result.set_item("selector", selector_str)?;
+    result.set_item("matches", matches)?;
+    result.set_item("steps", step_list)?;
+    result.set_item("failed_step", failed_step)?;
+
+    let suggestions = PyList::empty_bound(py);
+    if let Some(index) = failed_step {
+        let pool = candidate_pool(document, previous.as_deref(), index.checked_sub(1).map(|i| steps.combinators[i]));
+        for (compound, reason) in near_misses(&steps.compounds[index], &pool) {
+            // Prefer the whole corrected selector; if later steps still fail, report the
+            // corrected prefix so the next problem can be explained in turn
+            let full = steps.prefix(steps.compounds.len(), Some((index, &compound)));
+            let prefix = steps.prefix(index + 1, Some((index, &compound)));
+            let Some((suggested, count)) = [full, prefix].into_iter()
+                .filter_map(|candidate| {
+                    let sel = Selector::parse(&candidate).ok()?;
+                    let count = document.select(&sel).count();
+                    (count > 0).then_some((candidate, count))
+                })
+                .next() else { continue };
+            let suggestion = PyDict::new_bound(py);
+            suggestion.set_item("selector", suggested)?;
+            suggestion.set_item("matches", count)?;
+            suggestion.set_item("reason", reason)?;
+            suggestions.append(suggestion)?;
+            if suggestions.len() >= MAX_SUGGESTIONS {
+                break;
+            }
+        }
+    }
+    result.set_item("suggestions", suggestions)?;
+    Ok(result)
+}
+
+/// Outcome of matching a selector suffix at one element, as the engine tracks it: a failure
+/// up a descendant chain means no element further up can succeed either
+enum Outcome {
+    Matched,
+    Failed,
+    FailedGlobally,
+}
+
+/// Per-compound test counts while replaying the engine's matching order
+struct Replay<'q> {
+    compounds: Vec<Matcher<'q>>,
+    combinators: Vec<char>,
+    considered: Vec<usize>,
+    rejected: Vec<usize>,
+}
+
+impl Replay<'_> {
+    /// Match compounds `..=k` with `elem` as the element for compound `k`, right to left
+    fn matches(&mut self, k: usize, elem: ElementRef<'_>) -> Outcome {
+        self.considered[k] += 1;
+        if !self.compounds[k].matches(&elem) {
+            self.rejected[k] += 1;
+            return Outcome::Failed;
+        }
+        if k == 0 {
+            return Outcome::Matched;
+        }
+        match self.combinators[k - 1] {
+            '>' => match elem.parent().and_then(ElementRef::wrap) {
+                Some(parent) => self.matches(k - 1, parent),
+                None => Outcome::FailedGlobally,
+            },
+            '+' => match elem.prev_siblings().find_map(ElementRef::wrap) {
+                Some(sibling) => match self.matches(k - 1, sibling) {
+                    Outcome::FailedGlobally => Outcome::FailedGlobally,
+                    outcome => outcome,
+                },
+                None => Outcome::Failed,
+            },
+            '~' => {
+                for sibling in elem.prev_siblings().filter_map(ElementRef::wrap) {
+                    match self.matches(k - 1, sibling) {
+                        Outcome::Failed => continue,
+                        outcome => return outcome,
+                    }
+                }
+                Outcome::Failed
+            }
+            _ => {
+                for ancestor in elem.ancestors().filter_map(ElementRef::wrap) {
+                    match self.matches(k - 1, ancestor) {
+                        Outcome::Failed => continue,
+                        outcome => return outcome,
+                    }
+                }
+                Outcome::FailedGlobally
+            }
+        }
+    }
+}
+
+/// The cost of `select(selector)` by compound step, for `select(..., explain=True)`.
+/// The engine matches right to left: every element is tested against the last compound,
+/// and survivors walk to parents, ancestors or siblings to test the compound before.
+/// `considered` counts those tests per step and `rejected` the failures, so a broad
+/// last compound or a long descendant walk stands out.
+pub fn plan<'py>(py: Python<'py>, document: &Html, selector_str: &str, matches: usize, elapsed_ms: f64) -> PyResult<Bound<'py, PyDict>> {
+    let result = PyDict::new_bound(py);
+    result.set_item("selector", selector_str)?;
+    result.set_item("matches", matches)?;
+    result.set_item("elapsed_ms", elapsed_ms)?;
+    let alternatives = split_top_level(selector_str, |c| c == ',');
+    if alternatives.len() == 1 {
+        result.set_item("steps", plan_steps(py, document, selector_str)?)?;
+        return Ok(result);
+    }
+    let parts = PyList::empty_bound(py);
+    for alternative in &alternatives {
+        let part = PyDict::new_bound(py);
+        part.set_item("selector", alternative.trim())?;
+        part.set_item("steps", plan_steps(py, document, alternative)?)?;
+        parts.append(part)?;
+    }
+    result.set_item("alternatives", parts)?;
+    Ok(result)
+}
+
+fn plan_steps<'py>(py: Python<'py>, document: &Html, selector_str: &str) -> PyResult<Bound<'py, PyList>> {
+    let steps = split_steps(selector_str);
+    let compounds = steps.compounds.iter().map(|compound| selector::compile(compound)).collect::<PyResult<Vec<_>>>()?;
+    let len = compounds.len();
+    let mut replay = Replay { compounds: compounds.iter().map(Query::matcher).collect(), combinators: steps.combinators.clone(), considered: vec![0; len], rejected: vec![0; len] };
+    for elem in document.root_element().descendants().filter_map(ElementRef::wrap) {
+        replay.matches(len - 1, elem);
+    }
+    let list = PyList::empty_bound(py);
+    for (i, compound) in steps.compounds.iter().enumerate() {
+        let step = PyDict::new_bound(py);
+        step.set_item("compound", compound)?;
+        step.set_item("combinator", i.checked_sub(1).map(|j| steps.combinators[j].to_string()))?;
+        step.set_item("considered", replay.considered[i])?;
+        step.set_item("rejected", replay.rejected[i])?;
+        list.append(step)?;
+    }
+    Ok(list)
+}
+
+/// Elements the failing compound was tested against: everything reachable from the previous
+/// step's matches through `combinator`, or the whole document for the first step
+fn candidate_pool<'a>(document: &'a Html, previous: Option<&[ElementRef<'a>]>, combinator: Option<char>) -> Vec<ElementRef<'a>> {
+    let (Some(previous), Some(combinator)) = (previous, combinator) else {
+        return document.root_element().descendants().filter_map(ElementRef::wrap).collect();
+    };
+    let mut seen: AHashSet<NodeId> = AHashSet::new();
+    let mut pool = Vec::new();
+    for elem in previous {
+        let reachable: Box<dyn Iterator<Item = ElementRef<'a>>> = match combinator {
+            '>' => Box::new(elem.children().filter_map(ElementRef::wrap)),
+            '+' => Box::new(elem.next_siblings().filter_map(ElementRef::wrap).take(1)),
+            '~' => Box::new(elem.next_siblings().filter_map(ElementRef::wrap)),
+            _ => Box::new(elem.descendants().skip(1).filter_map(ElementRef::wrap)),
+        };
+        for candidate in reachable {
+            if seen.insert(candidate.id()) {
+                pool.push(candidate);
+            }
+        }
+    }
+    pool
+}
+
+/// Variants of `compound` that match something in `pool`, with a human-readable reason
+fn near_misses(compound: &str, pool: &[ElementRef]) -> Vec<(String, String)> {
+    let parts = split_compound(compound);
+    let mut classes: AHashMap<&str, usize> = AHashMap::new();
+    let mut ids: AHashSet<&str> = AHashSet::new();
+    let mut tags: AHashSet<&str> = AHashSet::new();
+    for elem in pool {
+        for class in elem.value().classes() {
+            *classes.entry(class).or_default() += 1;
+        }
+        if let Some(id) = elem.value().id() {
+            ids.insert(id);
+        }
+        tags.insert(elem.value().name());
+    }
+
+    let mut out: Vec<(String, String)> = Vec::new();
+    let mut push = |variant: Vec<Part>, reason: String| {
+        let css: String = variant.iter().map(Part::to_css).collect();
+        let css = if css.is_empty() { "*".to_string() } else { css };
+        if css != compound && !out.iter().any(|(existing, _)| *existing == css) {
+            out.push((css, reason));
+        }
+    };
+
+    for (i, part) in parts.iter().enumerate() {
+        let replace = |with: Part| {
+            let mut variant = parts.clone();
+            variant[i] = with;
+            variant
+        };
+        match part {
+            Part::Class(name) if !classes.contains_key(name.as_str()) => {
+                let mut near: Vec<(&str, usize)> = classes.keys()
+                    .map(|candidate| (*candidate, similarity(name, candidate)))
+                    .filter(|(_, distance)| is_near(name, *distance))
+                    .collect();
+                near.sort_by_key(|(candidate, distance)| (*distance, std::cmp::Reverse(classes[candidate])));
+                for (candidate, _) in near {
+                    push(replace(Part::Class(candidate.to_string())), format!("class '{}' not found; did you mean '{}'?", name, candidate));
+                }
+            }
+            Part::Id(name) if !ids.contains(name.as_str()) => {
+                for candidate in ids.iter().filter(|candidate| is_near(name, similarity(name, candidate))) {
+                    push(replace(Part::Id(candidate.to_string())), format!("id '{}' not found; did you mean '{}'?", name, candidate));
+                }
+            }
+            Part::Tag(name) if name != "*" && !tags.contains(name.to_ascii_lowercase().as_str()) => {
+                for candidate in tags.iter().filter(|candidate| is_near(name, similarity(name, candidate))) {
+                    push(replace(Part::Tag(candidate.to_string())), format!("no <{}> here; did you mean <{}>?", name, candidate));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Dropping one simple selector at a time shows which one is over-constraining
+    if parts.len() > 1 {
+        for (i, part) in parts.iter().enumerate() {
+            let mut variant = parts.clone();
+            variant.remove(i);
+            let css: String = variant.iter().map(Part::to_css).collect();
+            let Ok(sel) = Selector::parse(&css) else { continue };
+            if pool.iter().any(|elem| sel.matches(elem)) {
+                push(variant, format!("matches without '{}'", part.to_css()));
+            }
+        }
+    }
+    out
+}
+
+/// Whether `distance` is close enough to call a name a typo of `name`; very short names
+/// are all within a couple of edits of each other
+fn is_near(name: &str, distance: usize) -> bool {
+    distance <= MAX_EDIT_DISTANCE && distance < name.chars().count()
+}
+
+/// Edit distance between two names, treating `-` and `_` as interchangeable. Names sharing a
+/// stem (`items` / `item-list`) count as distance 1.
+fn similarity(a: &str, b: &str) -> usize {
+    let normalize = |s: &str| s.to_ascii_lowercase().replace('_', "-");
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 0;
+    }
+    let stem = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    if stem >= 4 && stem + 1 >= a.chars().count().min(b.chars().count()) {
+        return 1;
+    }
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Split on `is_separator` outside brackets, parentheses and quotes
+fn split_top_level(input: &str, is_separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in input.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth = depth.saturating_sub(1),
+                _ if depth == 0 && is_separator(c) => {
+                    parts.push(std::mem::take(&mut current));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+/// Split a complex selector into compounds and the combinators between them
+fn split_steps(selector: &str) -> Steps {
+    let mut compounds = Vec::new();
+    let mut combinators = Vec::new();
+    let mut current = String::new();
+    let mut combinator: Option<char> = None;
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in selector.trim().chars() {
+        let top_level = depth == 0 && quote.is_none() && !escaped;
+        if top_level && (c.is_whitespace() || matches!(c, '>' | '+' | '~')) {
+            if !current.is_empty() {
+                compounds.push(std::mem::take(&mut current));
+                combinator = Some(' ');
+            }
+            if !c.is_whitespace() {
+                combinator = Some(c);
+            }
+            continue;
+        }
+        if let Some(c) = combinator.take() {
+            combinators.push(c);
+        }
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+        } else {
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        compounds.push(current);
+    }
+    Steps { compounds, combinators }
+}
+
+/// Split a compound selector into its simple selectors
+fn split_compound(compound: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let flush = |current: &mut String, parts: &mut Vec<Part>| {
+        if current.is_empty() {
+            return;
+        }
+        let part = match current.chars().next() {
+            Some('.') => Part::Class(current[1..].to_string()),
+            Some('#') => Part::Id(current[1..].to_string()),
+            Some('[') | Some(':') => Part::Other(current.clone()),
+            _ => Part::Tag(current.clone()),
+        };
+        parts.push(part);
+        current.clear();
+    };
+    let mut previous = '\0';
+    for c in compound.chars() {
+        let starts_part = depth == 0 && matches!(c, '.' | '#' | '[' | ':') && !(c == ':' && previous == ':');
+        if starts_part {
+            flush(&mut current, &mut parts);
+        }
+        match c {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        current.push(c);
+        previous = c;
+    }
+    flush(&mut current, &mut parts);
+    parts
+}
@@ -0,0 +1,294 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use flate2::read::GzDecoder;
+use std::borrow::Cow;
+use std::io::Read;
+use sxd_document::{dom, Package};
+
+use crate::dates;
+use crate::encoding::HtmlInput;
+use crate::errors;
+use crate::limits::Limits;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// XML text from `str` or `bytes` input. Bytes are gunzipped when they carry the gzip magic
+/// number and decoded as UTF-8 (with BOM sniffing); `max_size` bounds the decompressed size so
+/// a gzip bomb cannot exhaust memory.
+pub(crate) fn xml_text(input: &HtmlInput, max_size: Option<usize>) -> PyResult<String> {
+    let limits = Limits { max_size, ..Limits::default() };
+    let bytes = match input {
+        HtmlInput::Str(s) => {
+            let text = s.to_str()?;
+            limits.check_size(text.len())?;
+            return Ok(text.to_string());
+        }
+        HtmlInput::Bytes(b) => b.as_bytes(),
+        HtmlInput::Buffer(buffer) => buffer.as_bytes(),
+    };
+    let raw = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut out = Vec::new();
+        let cap = max_size.map_or(u64::MAX, |max| max as u64 + 1);
+        GzDecoder::new(bytes).take(cap).read_to_end(&mut out).map_err(|err| {
+            PyErr::new::<errors::EncodingError, _>(format!("Invalid gzip data: {}", err))
+        })?;
+        out
+    } else {
+        bytes.to_vec()
+    };
+    limits.check_size(raw.len())?;
+    Ok(encoding_rs::UTF_8.decode(&raw).0.into_owned())
+}
+
+/// Parse XML, raising `HTMLParseError` with `.position` set to the failing byte offset
+pub(crate) fn parse_xml(text: &str, what: &str) -> PyResult<Package> {
+    sxd_document::parser::parse(text).map_err(|err| {
+        let location = err.location();
+        let err = PyErr::new::<errors::HTMLParseError, _>(format!("Invalid {} XML: {}", what, err));
+        errors::with_detail(err, "position", location)
+    })
+}
+
+fn root_element(package: &Package) -> Option<dom::Element<'_>> {
+    package.as_document().root().children().into_iter().find_map(|child| child.element())
+}
+
+/// Child elements named `local` in any namespace
+fn children_named<'d>(elem: dom::Element<'d>, local: &'d str) -> impl Iterator<Item = dom::Element<'d>> + 'd {
+    elem.children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .filter(move |child| child.name().local_part() == local)
+}
+
+/// Concatenated text of an element and its descendants
+fn text_of(elem: dom::Element<'_>) -> String {
+    let mut out = String::new();
+    for child in elem.children() {
+        match child {
+            dom::ChildOfElement::Text(text) => out.push_str(text.text()),
+            dom::ChildOfElement::Element(inner) => out.push_str(&text_of(inner)),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Trimmed text of the first non-blank child named `local`
+fn child_text(elem: dom::Element<'_>, local: &str) -> Option<String> {
+    elem.children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .filter(|child| child.name().local_part() == local)
+        .map(|child| text_of(child).trim().to_string())
+        .find(|text| !text.is_empty())
+}
+
+/// Parse a sitemap (`urlset`) or sitemap index (`sitemapindex`), from `str` or `bytes`
+/// (gzipped or not). Returns `{"type": "urlset" | "sitemapindex", "urls": [...]}` where each
+/// entry has `loc`, `lastmod`, `changefreq` and `priority` (a float); index entries only
+/// carry `loc` and `lastmod`. Missing values are `None`.
+#[pyfunction]
+#[pyo3(signature = (xml, max_size=None))]
+pub fn parse_sitemap(py: Python, xml: HtmlInput, max_size: Option<usize>) -> PyResult<Py<PyDict>> {
+    let text = xml_text(&xml, max_size)?;
+    let package = parse_xml(&text, "sitemap")?;
+    let root = root_element(&package);
+    let kind = root.map(|root| root.name().local_part()).unwrap_or("");
+    let entry_name = match kind {
+        "urlset" => "url",
+        "sitemapindex" => "sitemap",
+        other => {
+            return Err(PyErr::new::<errors::HTMLParseError, _>(format!(
+                "Not a sitemap: expected <urlset> or <sitemapindex>, found <{}>", other
+            )))
+        }
+    };
+
+    let urls = PyList::empty_bound(py);
+    for entry in children_named(root.unwrap(), entry_name) {
+        let Some(loc) = child_text(entry, "loc") else { continue };
+        let item = PyDict::new_bound(py);
+        item.set_item("loc", loc)?;
+        item.set_item("lastmod", child_text(entry, "lastmod"))?;
+        if kind == "urlset" {
+            item.set_item("changefreq", child_text(entry, "changefreq").map(|c| c.to_ascii_lowercase()))?;
+            item.set_item("priority", child_text(entry, "priority").and_then(|p| p.parse::<f64>().ok()))?;
+        }
+        urls.append(item)?;
+    }
+
+    let result = PyDict::new_bound(py);
+    result.set_item("type", kind)?;
+    result.set_item("urls", urls)?;
+    Ok(result.unbind())
+}
+
+/// Make feed XML parseable despite the HTML habits feeds are written with: HTML named entities
+/// (`&nbsp;`, `&eacute;`) become numeric references and a bare `&` (`AT&T`) becomes `&amp;`.
+/// CDATA sections are left alone.
+fn repair_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(['&', '<']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with("<![CDATA[") {
+            let end = rest.find("]]>").map_or(rest.len(), |end| end + 3);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if rest.starts_with('<') {
+            out.push('<');
+            rest = &rest[1..];
+            continue;
+        }
+        // `rest` starts with '&'
+        let name_len = rest[1..].find(|c: char| !c.is_ascii_alphanumeric() && c != '#').unwrap_or(rest.len() - 1);
+        let name = &rest[1..1 + name_len];
+        let terminated = rest[1 + name_len..].starts_with(';');
+        if terminated && (name.starts_with('#') || matches!(name, "amp" | "lt" | "gt" | "quot" | "apos")) {
+            out.push_str(&rest[..name_len + 2]);
+            rest = &rest[name_len + 2..];
+            continue;
+        }
+        let html_entity = if terminated {
+            html5ever::data::NAMED_ENTITIES.get(&rest[1..name_len + 2]).filter(|(first, _)| *first != 0)
+        } else {
+            None
+        };
+        match html_entity {
+            Some((first, second)) => {
+                out.push_str(&format!("&#{};", first));
+                if *second != 0 {
+                    out.push_str(&format!("&#{};", second));
+                }
+                rest = &rest[name_len + 2..];
+            }
+            None => {
+                out.push_str("&amp;");
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// RSS dates are RFC 822 (`Mon, 02 Jan 2006 15:04:05 GMT`); return them as ISO 8601 like Atom
+/// dates, or `None` when the value does not parse.
+fn rfc822_to_iso(date: &str) -> Option<String> {
+    let date = date.trim();
+    let date = date.split_once(',').map_or(date, |(_, rest)| rest).trim();
+    let mut parts = date.split_whitespace();
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = match parts.next()?.get(..3)?.to_ascii_lowercase().as_str() {
+        "jan" => 1, "feb" => 2, "mar" => 3, "apr" => 4, "may" => 5, "jun" => 6,
+        "jul" => 7, "aug" => 8, "sep" => 9, "oct" => 10, "nov" => 11, "dec" => 12,
+        _ => return None,
+    };
+    let year: u32 = match parts.next()?.parse::<u32>().ok()? {
+        y @ 0..=49 => 2000 + y,
+        y @ 50..=99 => 1900 + y,
+        y => y,
+    };
+    let mut time = parts.next()?.split(':').map(|n| n.parse::<u32>());
+    let (hour, minute) = (time.next()?.ok()?, time.next()?.ok()?);
+    let second = match time.next() {
+        Some(s) => s.ok()?,
+        None => 0,
+    };
+    let offset = match parts.next() {
+        None => String::new(),
+        Some(zone) => {
+            let seconds = dates::zone_offset(zone)?;
+            let sign = if seconds < 0 { '-' } else { '+' };
+            format!("{}{:02}:{:02}", sign, seconds.abs() / 3600, seconds.abs() % 3600 / 60)
+        }
+    };
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}", year, month, day, hour, minute, second, offset))
+}
+
+/// Parse an RSS 2.0, RSS 1.0 (RDF) or Atom feed from `str` or `bytes` (gzipped or not). Returns
+/// `{"type": "rss" | "atom", "title", "link", "entries": [...]}`; each entry has `title`,
+/// `link`, `id`, `published`, `updated`, `author`, `summary` and `content`, `None` when the
+/// feed does not give them. RSS dates are converted to ISO 8601 (kept as-is when they do not
+/// parse). HTML entities and bare `&` are repaired before parsing.
+#[pyfunction]
+#[pyo3(signature = (xml, max_size=None))]
+pub fn parse_feed(py: Python, xml: HtmlInput, max_size: Option<usize>) -> PyResult<Py<PyDict>> {
+    let text = xml_text(&xml, max_size)?;
+    let package = parse_xml(&repair_entities(&text), "feed")?;
+    let root = root_element(&package);
+    let kind = root.map(|root| root.name().local_part()).unwrap_or("");
+
+    let result = PyDict::new_bound(py);
+    let entries = PyList::empty_bound(py);
+    match kind {
+        "feed" => {
+            let feed = root.unwrap();
+            result.set_item("type", "atom")?;
+            result.set_item("title", child_text(feed, "title"))?;
+            result.set_item("link", atom_link(feed))?;
+            for entry in children_named(feed, "entry") {
+                let item = PyDict::new_bound(py);
+                item.set_item("title", child_text(entry, "title"))?;
+                item.set_item("link", atom_link(entry))?;
+                item.set_item("id", child_text(entry, "id"))?;
+                item.set_item("published", child_text(entry, "published").or_else(|| child_text(entry, "issued")))?;
+                item.set_item("updated", child_text(entry, "updated").or_else(|| child_text(entry, "modified")))?;
+                item.set_item("author", children_named(entry, "author").find_map(|author| child_text(author, "name")))?;
+                item.set_item("summary", child_text(entry, "summary"))?;
+                item.set_item("content", child_text(entry, "content"))?;
+                entries.append(item)?;
+            }
+        }
+        "rss" | "RDF" => {
+            let root = root.unwrap();
+            let channel = children_named(root, "channel").next();
+            result.set_item("type", "rss")?;
+            result.set_item("title", channel.and_then(|c| child_text(c, "title")))?;
+            result.set_item("link", channel.and_then(|c| child_text(c, "link")))?;
+            // RSS 2.0 nests items in <channel>; RSS 1.0 puts them next to it
+            let items: Vec<dom::Element> = match (kind, channel) {
+                ("rss", Some(channel)) => children_named(channel, "item").collect(),
+                _ => children_named(root, "item").collect(),
+            };
+            for entry in items {
+                let date = |name: &str| child_text(entry, name).map(|d| rfc822_to_iso(&d).unwrap_or(d));
+                let item = PyDict::new_bound(py);
+                item.set_item("title", child_text(entry, "title"))?;
+                item.set_item("link", child_text(entry, "link"))?;
+                item.set_item("id", child_text(entry, "guid").or_else(|| entry.attribute_value(("http://www.w3.org/1999/02/22-rdf-syntax-ns#", "about")).map(str::to_string)))?;
+                item.set_item("published", date("pubDate").or_else(|| child_text(entry, "date")))?;
+                item.set_item("updated", child_text(entry, "modified"))?;
+                item.set_item("author", child_text(entry, "creator").or_else(|| child_text(entry, "author")))?;
+                item.set_item("summary", child_text(entry, "description"))?;
+                item.set_item("content", child_text(entry, "encoded"))?;
+                entries.append(item)?;
+            }
+        }
+        other => {
+            return Err(PyErr::new::<errors::HTMLParseError, _>(format!(
+                "Not a feed: expected <rss>, <rdf:RDF> or <feed>, found <{}>", other
+            )))
+        }
+    }
+    result.set_item("entries", entries)?;
+    Ok(result.unbind())
+}
+
+/// An Atom element's `alternate` link (a `<link>` without `rel` counts as one)
+fn atom_link(elem: dom::Element<'_>) -> Option<String> {
+    children_named(elem, "link")
+        .find(|link| link.attribute_value("rel").is_none_or(|rel| rel == "alternate"))
+        .and_then(|link| link.attribute_value("href"))
+        .map(|href| href.trim().to_string())
+}
@@ -0,0 +1,88 @@
+use pyo3::prelude::*;
+use scraper::Html;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::render;
+
+/// Words per shingle
+const SHINGLE_SIZE: usize = 3;
+
+/// Hash functions in a MinHash signature
+const MINHASH_PERMUTATIONS: usize = 64;
+
+/// Lowercased, NFKC-normalized words of the page's visible text; punctuation separates words
+fn words(document: &Html) -> Vec<String> {
+    let text: String = render::render_document(document, false).nfkc().flat_map(char::to_lowercase).collect();
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+/// Hashes of overlapping `SHINGLE_SIZE`-word windows; a shorter text is one shingle
+fn shingle_hashes(words: &[String]) -> Vec<u64> {
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let size = SHINGLE_SIZE.min(words.len());
+    words.windows(size).map(|window| fnv1a(window.join(" ").as_bytes())).collect()
+}
+
+/// FNV-1a: stable across runs, platforms and versions, so fingerprints can be stored
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// splitmix64 finalizer, used to derive independent hash functions from one shingle hash
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// 64-bit SimHash: each bit is the majority vote of that bit across shingle hashes
+fn simhash(hashes: &[u64]) -> u64 {
+    let mut votes = [0i64; 64];
+    for &hash in hashes {
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *vote += 1;
+            } else {
+                *vote -= 1;
+            }
+        }
+    }
+    votes.iter().enumerate().filter(|(_, &v)| v > 0).fold(0, |acc, (bit, _)| acc | 1 << bit)
+}
+
+/// MinHash signature: the minimum of each derived hash function over the shingles
+fn minhash(hashes: &[u64]) -> Vec<u64> {
+    (0..MINHASH_PERMUTATIONS as u64)
+        .map(|seed| {
+            let salt = mix(seed.wrapping_add(0x9e37_79b9_7f4a_7c15));
+            hashes.iter().map(|&h| mix(h ^ salt)).min().unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// Fingerprint a page's visible text for near-duplicate detection. The text is rendered like
+/// `to_text`, NFKC-normalized, lowercased and split into 3-word shingles. `"simhash"` returns
+/// a 64-bit int; near-duplicates differ in few bits (compare with `(a ^ b).bit_count()`).
+/// `"minhash"` returns 64 ints; the fraction of equal positions estimates the Jaccard
+/// similarity of the two shingle sets. Both are stable across runs and machines.
+#[pyfunction]
+#[pyo3(signature = (html, algorithm="simhash"))]
+pub fn content_hash(py: Python<'_>, html: HtmlInput, algorithm: &str) -> PyResult<PyObject> {
+    let source = html.decode(&DecodeOptions::default())?;
+    let hashes = shingle_hashes(&words(&Html::parse_document(&source)));
+    match algorithm.to_ascii_lowercase().as_str() {
+        "simhash" => Ok(simhash(&hashes).into_py(py)),
+        "minhash" => Ok(minhash(&hashes).into_py(py)),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid algorithm: {}. Use 'simhash' or 'minhash'", algorithm)
+        )),
+    }
+}
@@ -0,0 +1,155 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use scraper::{ElementRef, Html, Selector};
+use once_cell::sync::Lazy;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::links::{collapsed_text, document_base, resolve};
+use crate::structured::insert_repeated;
+
+static FORM_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("form").unwrap());
+static CONTROL_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("input, select, textarea, button").unwrap());
+static OPTION_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("option").unwrap());
+
+/// Every `<form>` as a dict of `action` (resolved against `base_url` and `<base href>` when
+/// possible; an empty action submits to the page itself), `method` (uppercase, default `GET`),
+/// `enctype`, `id`, `name`, `fields` and `data`. `fields` lists every control with its `name`,
+/// `type`, `value`, `required` and `disabled` flags, `checked` for checkboxes and radios and
+/// `options` for selects. `data` is what submitting the form unchanged would send: named,
+/// enabled controls with their defaults, checked boxes only and selected options, with
+/// repeated names as lists. Controls outside the form that point at it with `form="id"` count.
+#[pyfunction]
+#[pyo3(signature = (html, base_url=None))]
+pub fn extract_forms(py: Python, html: HtmlInput, base_url: Option<&str>) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let base = document_base(&document, base_url)?;
+
+    let forms = PyList::empty_bound(py);
+    for form in document.select(&FORM_SELECTOR) {
+        let value = form.value();
+        let action = value.attr("action").unwrap_or("").trim();
+        let action = if action.is_empty() {
+            base_url.map(str::to_string).unwrap_or_default()
+        } else {
+            resolve(base.as_ref(), action).map(String::from).unwrap_or_else(|| action.to_string())
+        };
+
+        // Controls inside the form that belong to it, then outside controls linked by form="id"
+        let mut controls: Vec<ElementRef> = form
+            .select(&CONTROL_SELECTOR)
+            .filter(|control| control.value().attr("form").is_none_or(|owner| Some(owner) == value.id()))
+            .collect();
+        if let Some(id) = value.id() {
+            controls.extend(document.select(&CONTROL_SELECTOR).filter(|control| {
+                control.value().attr("form") == Some(id) && !control.ancestors().any(|a| a.id() == form.id())
+            }));
+        }
+
+        let fields = PyList::empty_bound(py);
+        let data = PyDict::new_bound(py);
+        for control in controls {
+            let field = field_dict(py, control, &data)?;
+            fields.append(field)?;
+        }
+
+        let item = PyDict::new_bound(py);
+        item.set_item("action", action)?;
+        item.set_item("method", value.attr("method").map(|m| m.trim().to_ascii_uppercase()).filter(|m| !m.is_empty()).unwrap_or_else(|| "GET".to_string()))?;
+        item.set_item("enctype", value.attr("enctype").map(str::trim).filter(|e| !e.is_empty()).unwrap_or("application/x-www-form-urlencoded"))?;
+        item.set_item("id", value.id())?;
+        item.set_item("name", value.attr("name"))?;
+        item.set_item("fields", fields)?;
+        item.set_item("data", data)?;
+        forms.append(item)?;
+    }
+    Ok(forms.unbind())
+}
+
+/// Describe one control, adding what it would submit to `data`
+fn field_dict<'py>(py: Python<'py>, control: ElementRef, data: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyDict>> {
+    let value = control.value();
+    let name = value.attr("name").filter(|n| !n.is_empty());
+    let disabled = value.attr("disabled").is_some();
+    let field = PyDict::new_bound(py);
+    field.set_item("name", name)?;
+    field.set_item("required", value.attr("required").is_some())?;
+    field.set_item("disabled", disabled)?;
+    let submit = |v: &str| -> PyResult<()> {
+        match name {
+            Some(name) if !disabled => insert_repeated(data, name, v.into_py(py)),
+            _ => Ok(()),
+        }
+    };
+
+    match value.name() {
+        "select" => {
+            let multiple = value.attr("multiple").is_some();
+            field.set_item("type", if multiple { "select-multiple" } else { "select" })?;
+            let options = PyList::empty_bound(py);
+            let mut selected = Vec::new();
+            let mut first_enabled = None;
+            for option in control.select(&OPTION_SELECTOR) {
+                let text = collapsed_text(option);
+                let option_value = option.value().attr("value").map(str::to_string).unwrap_or_else(|| text.clone());
+                let is_selected = option.value().attr("selected").is_some();
+                if is_selected {
+                    selected.push(option_value.clone());
+                }
+                if first_enabled.is_none() && option.value().attr("disabled").is_none() {
+                    first_enabled = Some(option_value.clone());
+                }
+                let entry = PyDict::new_bound(py);
+                entry.set_item("value", option_value)?;
+                entry.set_item("text", text)?;
+                entry.set_item("selected", is_selected)?;
+                options.append(entry)?;
+            }
+            // A single select with nothing marked selected submits its first enabled option
+            if selected.is_empty() && !multiple {
+                selected.extend(first_enabled);
+            }
+            if !multiple {
+                selected.truncate(1);
+            }
+            for v in &selected {
+                submit(v)?;
+            }
+            field.set_item("value", if multiple { selected.into_py(py) } else { selected.into_iter().next().into_py(py) })?;
+            field.set_item("options", options)?;
+        }
+        "textarea" => {
+            // The parser already dropped the newline right after <textarea>
+            let text: String = control.text().collect();
+            field.set_item("type", "textarea")?;
+            field.set_item("value", &text)?;
+            submit(&text)?;
+        }
+        "button" => {
+            field.set_item("type", value.attr("type").map(|t| t.trim().to_ascii_lowercase()).unwrap_or_else(|| "submit".to_string()))?;
+            field.set_item("value", value.attr("value").unwrap_or(""))?;
+        }
+        _ => {
+            let kind = value.attr("type").map(|t| t.trim().to_ascii_lowercase()).filter(|t| !t.is_empty()).unwrap_or_else(|| "text".to_string());
+            let input_value = value.attr("value").unwrap_or(if kind == "checkbox" || kind == "radio" { "on" } else { "" });
+            field.set_item("value", input_value)?;
+            match kind.as_str() {
+                "checkbox" | "radio" => {
+                    let checked = value.attr("checked").is_some();
+                    field.set_item("checked", checked)?;
+                    if checked {
+                        submit(input_value)?;
+                    }
+                }
+                // Buttons only submit when clicked; files have no default
+                "submit" | "reset" | "button" | "image" | "file" => {}
+                _ => submit(input_value)?,
+            }
+            field.set_item("type", kind)?;
+        }
+    }
+    Ok(field)
+}
@@ -0,0 +1,357 @@
+//! A parser for small snippets of plain HTML (API responses, serialized containers) that
+//! builds the tree directly instead of running html5ever's tokenizer and tree builder.
+//!
+//! It understands a conservative subset: common flow and phrasing elements, void elements,
+//! comments, the character references serializers emit, and nesting that needs no error
+//! recovery beyond the end tags HTML lets authors omit (`</p>`, `</li>`, `</dd>`, `</dt>`).
+//! Within it the result is node for node what html5ever builds, in the same arena order,
+//! with the same warnings; anything else returns None so the caller falls back to html5ever.
+
+use std::borrow::Cow;
+
+use ego_tree::NodeId;
+use html5ever::tendril::{StrTendril, Tendril};
+use html5ever::tree_builder::QuirksMode;
+use html5ever::{namespace_url, ns, Attribute, LocalName, QualName};
+use scraper::node::{Comment, Element, Text};
+use scraper::{Html, Node};
+
+/// Inputs up to this many bytes go to this parser under `mode="auto"`
+pub const AUTO_MAX_LEN: usize = 1024;
+
+/// Elements whose start tag closes an open `<p>`
+const CLOSES_P: &[&str] = &[
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p",
+    "section", "ul",
+];
+
+const VOID: &[&str] = &["br", "hr", "img", "input", "source", "wbr"];
+
+/// Formatting elements; their end tags run the adoption agency algorithm, so they must close
+/// exactly
+const FORMATTING: &[&str] = &["a", "b", "code", "em", "i", "s", "small", "strong", "u"];
+
+/// Elements inserted as they come, with no special tree-building rules
+const PHRASING: &[&str] = &[
+    "abbr", "bdi", "cite", "data", "del", "dfn", "ins", "kbd", "label", "mark", "picture", "q",
+    "samp", "span", "sub", "sup", "time", "var",
+];
+
+/// Elements the end tag of another may close implicitly
+const IMPLIED_END: &[&str] = &["dd", "dt", "li", "p"];
+
+const HEADINGS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// In html5ever's sense: elements that end the search for an open `<li>`, `<dd>` or `<dt>`
+fn is_special(name: &str) -> bool {
+    (CLOSES_P.contains(&name) || VOID.contains(&name)) && !matches!(name, "address" | "div" | "p")
+}
+
+/// `html` as a full document, as `parse_document` builds it
+pub fn parse_document(html: &str) -> Option<Html> {
+    let body = html.trim_start_matches(is_whitespace);
+    // leading comments and empty input go elsewhere in the tree
+    if body.is_empty() || body.starts_with("<!") {
+        return None;
+    }
+    let mut document = Html::new_document();
+    // a document without a doctype is in quirks mode, reported as the missing doctype
+    document.quirks_mode = QuirksMode::Quirks;
+    document.errors.push(Cow::Borrowed("Unexpected token"));
+    let root = document.tree.root().id();
+    let html_elem = append(&mut document, root, element("html", Vec::new()));
+    append(&mut document, html_elem, element("head", Vec::new()));
+    let body_elem = append(&mut document, html_elem, element("body", Vec::new()));
+    Builder::new(document, body, body_elem).run()
+}
+
+/// `html` as the content of `<body>`, as `reparse` builds it
+pub fn parse_body_fragment(html: &str) -> Option<Html> {
+    let mut document = Html::new_fragment();
+    // html5ever allocates the context element without attaching it
+    document.tree.orphan(element("body", Vec::new()));
+    let root = document.tree.root().id();
+    let html_elem = append(&mut document, root, element("html", Vec::new()));
+    Builder::new(document, html, html_elem).run()
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0C')
+}
+
+fn element(name: &str, attrs: Vec<Attribute>) -> Node {
+    Node::Element(Element::new(QualName::new(None, ns!(html), LocalName::from(name)), attrs))
+}
+
+fn append(document: &mut Html, parent: NodeId, node: Node) -> NodeId {
+    document.tree.get_mut(parent).unwrap().append(node).id()
+}
+
+struct Builder<'a> {
+    document: Html,
+    input: &'a str,
+    pos: usize,
+    /// Open elements below the insertion root, innermost last
+    stack: Vec<(NodeId, &'static str)>,
+    root: NodeId,
+    text: String,
+}
+
+impl<'a> Builder<'a> {
+    fn new(document: Html, input: &'a str, root: NodeId) -> Self {
+        Builder { document, input, pos: 0, stack: Vec::new(), root, text: String::new() }
+    }
+
+    fn run(mut self) -> Option<Html> {
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                '<' => self.markup()?,
+                '&' => {
+                    self.pos += 1;
+                    let decoded = self.char_ref()?;
+                    self.text.push_str(&decoded);
+                }
+                '\0' | '\r' => return None,
+                _ => {
+                    let len = self.rest().find(['<', '&', '\0', '\r']).unwrap_or(self.rest().len());
+                    self.text.push_str(&self.rest()[..len]);
+                    self.pos += len;
+                }
+            }
+        }
+        self.flush_text();
+        // html5ever reports any other element left open at the end
+        if self.stack.iter().any(|(_, name)| !IMPLIED_END.contains(name)) {
+            return None;
+        }
+        Some(self.document)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn current(&self) -> NodeId {
+        self.stack.last().map_or(self.root, |(id, _)| *id)
+    }
+
+    fn flush_text(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let text = std::mem::take(&mut self.text);
+        let mut parent = self.document.tree.get_mut(self.current()).unwrap();
+        // adjacent text becomes one node, as in scraper's tree sink
+        if let Some(mut last) = parent.last_child() {
+            if let Node::Text(existing) = last.value() {
+                existing.text.push_slice(&text);
+                return;
+            }
+        }
+        parent.append(Node::Text(Text { text: Tendril::from_slice(text.as_str()) }));
+    }
+
+    fn markup(&mut self) -> Option<()> {
+        let rest = self.rest();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->")?;
+            let data = &comment[..end];
+            if data.starts_with('>') || data.starts_with("->") || data.contains("<!-") || data.contains(['\0', '\r']) {
+                return None;
+            }
+            self.flush_text();
+            let current = self.current();
+            append(&mut self.document, current, Node::Comment(Comment { comment: Tendril::from_slice(data) }));
+            self.pos += 4 + end + 3;
+            Some(())
+        } else if let Some(tag) = rest.strip_prefix("</") {
+            let name_len = tag.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(tag.len());
+            let name = tag[..name_len].to_ascii_lowercase();
+            let after = tag[name_len..].trim_start_matches(is_whitespace);
+            if name.is_empty() || !after.starts_with('>') {
+                return None;
+            }
+            self.pos = self.input.len() - after.len() + 1;
+            self.flush_text();
+            self.end_tag(&name)
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+            let (name, attrs, self_closing) = self.tag()?;
+            self.flush_text();
+            self.start_tag(&name, attrs, self_closing)
+        } else {
+            None
+        }
+    }
+
+    /// A start tag after its `<`: name, attributes and whether it ended in `/>`
+    fn tag(&mut self) -> Option<(String, Vec<Attribute>, bool)> {
+        let name_len = self.rest().find(|c: char| is_whitespace(c) || c == '/' || c == '>').unwrap_or(self.rest().len());
+        let name = self.rest()[..name_len].to_ascii_lowercase();
+        self.pos += name_len;
+        let mut attrs: Vec<Attribute> = Vec::new();
+        loop {
+            self.pos += self.rest().len() - self.rest().trim_start_matches(is_whitespace).len();
+            let rest = self.rest();
+            if rest.starts_with('>') {
+                self.pos += 1;
+                return Some((name, attrs, false));
+            }
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Some((name, attrs, true));
+            }
+            let name_len = rest.find(|c: char| is_whitespace(c) || matches!(c, '/' | '=' | '>')).unwrap_or(rest.len());
+            let attr_name = &rest[..name_len];
+            if attr_name.is_empty() || attr_name.contains(['"', '\'', '<', '\0', '\r']) {
+                return None;
+            }
+            let attr_name = LocalName::from(attr_name.to_ascii_lowercase());
+            if attrs.iter().any(|attr| attr.name.local == attr_name) {
+                return None;
+            }
+            self.pos += name_len;
+            let after_name = self.rest().trim_start_matches(is_whitespace);
+            let value = match after_name.strip_prefix('=') {
+                Some(value) => {
+                    self.pos = self.input.len() - value.len();
+                    self.pos += value.len() - value.trim_start_matches(is_whitespace).len();
+                    self.attr_value()?
+                }
+                None => String::new(),
+            };
+            attrs.push(Attribute { name: QualName::new(None, ns!(), attr_name), value: StrTendril::from(value) });
+        }
+    }
+
+    fn attr_value(&mut self) -> Option<String> {
+        let quote = self.rest().chars().next()?;
+        let mut value = String::new();
+        if quote == '"' || quote == '\'' {
+            self.pos += 1;
+            loop {
+                let len = self.rest().find([quote, '&', '\0', '\r'])?;
+                value.push_str(&self.rest()[..len]);
+                self.pos += len;
+                match self.rest().as_bytes()[0] {
+                    b'&' => {
+                        self.pos += 1;
+                        value.push_str(&self.char_ref()?);
+                    }
+                    b'\0' | b'\r' => return None,
+                    _ => break,
+                }
+            }
+            self.pos += 1;
+            // the next attribute must be separated by whitespace
+            return self.rest().starts_with(|c: char| is_whitespace(c) || c == '/' || c == '>').then_some(value);
+        }
+        let len = self.rest().find(|c: char| is_whitespace(c) || c == '>').unwrap_or(self.rest().len());
+        let raw = &self.rest()[..len];
+        if raw.is_empty() || raw.contains(['"', '\'', '<', '=', '`', '&', '\0', '\r']) {
+            return None;
+        }
+        self.pos += len;
+        Some(raw.to_string())
+    }
+
+    /// A character reference after its `&`; a bare `&` stays as written
+    fn char_ref(&mut self) -> Option<Cow<'static, str>> {
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '#') {
+            return Some(Cow::Borrowed("&"));
+        }
+        let end = rest.find(';')?;
+        let reference = &rest[..end];
+        let decoded = match reference {
+            "amp" => Cow::Borrowed("&"),
+            "lt" => Cow::Borrowed("<"),
+            "gt" => Cow::Borrowed(">"),
+            "quot" => Cow::Borrowed("\""),
+            "apos" => Cow::Borrowed("'"),
+            "nbsp" => Cow::Borrowed("\u{a0}"),
+            _ => {
+                let number = reference.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) if !hex.is_empty() && hex.len() <= 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => u32::from_str_radix(hex, 16).ok()?,
+                    None if !number.is_empty() && number.len() <= 7 && number.bytes().all(|b| b.is_ascii_digit()) => number.parse().ok()?,
+                    _ => return None,
+                };
+                // references html5ever replaces or reports
+                let plain = matches!(code, 0x09 | 0x0A | 0x0C | 0x20..=0x7E) || (code >= 0xA0 && !(0xFDD0..=0xFDEF).contains(&code) && code & 0xFFFE != 0xFFFE);
+                if !plain {
+                    return None;
+                }
+                Cow::Owned(char::from_u32(code)?.to_string())
+            }
+        };
+        self.pos += end + 1;
+        Some(decoded)
+    }
+
+    fn start_tag(&mut self, name: &str, attrs: Vec<Attribute>, self_closing: bool) -> Option<()> {
+        let name: &'static str = [CLOSES_P, VOID, FORMATTING, PHRASING].iter().flat_map(|names| names.iter()).find(|known| **known == name)?;
+        if self_closing && !VOID.contains(&name) {
+            return None;
+        }
+        if name == "a" && self.stack.iter().any(|(_, open)| *open == "a") {
+            return None;
+        }
+        match name {
+            "li" => self.close_list_item(&["li"])?,
+            "dd" | "dt" => self.close_list_item(&["dd", "dt"])?,
+            _ => {}
+        }
+        if CLOSES_P.contains(&name) {
+            if let Some(index) = self.stack.iter().rposition(|(_, open)| *open == "p") {
+                self.close(index)?;
+            }
+        }
+        if HEADINGS.contains(&name) && self.stack.last().is_some_and(|(_, open)| HEADINGS.contains(open)) {
+            return None;
+        }
+        let current = self.current();
+        let id = append(&mut self.document, current, element(name, attrs));
+        if !VOID.contains(&name) {
+            self.stack.push((id, name));
+        }
+        Some(())
+    }
+
+    /// Close an open item of `names` before a new one, as the `<li>`, `<dd>` and `<dt>` start
+    /// tags do
+    fn close_list_item(&mut self, names: &[&str]) -> Option<()> {
+        for index in (0..self.stack.len()).rev() {
+            let open = self.stack[index].1;
+            if names.contains(&open) {
+                return self.close(index);
+            }
+            if is_special(open) {
+                break;
+            }
+        }
+        Some(())
+    }
+
+    fn end_tag(&mut self, name: &str) -> Option<()> {
+        let index = self.stack.iter().rposition(|(_, open)| *open == name)?;
+        // formatting elements run the adoption agency algorithm, and other phrasing elements
+        // stop at any open `<p>` or list item
+        if (FORMATTING.contains(&name) || PHRASING.contains(&name)) && index + 1 != self.stack.len() {
+            return None;
+        }
+        self.close(index)
+    }
+
+    /// Pop the open element at `index` and those above it, which may only be ones whose end
+    /// tags can be omitted; anything else would be a parse error
+    fn close(&mut self, index: usize) -> Option<()> {
+        if !self.stack[index + 1..].iter().all(|(_, open)| IMPLIED_END.contains(open)) {
+            return None;
+        }
+        self.stack.truncate(index);
+        Some(())
+    }
+}
+
@@ -0,0 +1,218 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use scraper::{Html, Selector};
+use once_cell::sync::Lazy;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::render;
+
+static META_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[content]").unwrap());
+
+/// Confidence given to a language declared on `<html>` or in `<meta>` when the text is silent
+const LANG_ATTR_CONFIDENCE: f64 = 0.8;
+const META_CONFIDENCE: f64 = 0.7;
+
+/// Frequent short words per language, for Latin-script text
+const STOPWORDS: [(&str, &[&str]); 12] = [
+    ("en", &["the", "and", "of", "to", "is", "in", "that", "it", "for", "with", "was", "are", "this", "you"]),
+    ("es", &["el", "la", "de", "que", "y", "en", "los", "las", "por", "con", "una", "para", "es", "del"]),
+    ("fr", &["le", "la", "les", "de", "et", "est", "des", "une", "pour", "dans", "que", "qui", "pas", "du", "sur"]),
+    ("de", &["der", "die", "und", "das", "ist", "nicht", "mit", "ein", "eine", "den", "zu", "von", "auf", "sich"]),
+    ("it", &["il", "di", "che", "e", "la", "per", "una", "sono", "non", "del", "della", "con", "gli", "le"]),
+    ("pt", &["de", "que", "o", "a", "os", "e", "do", "da", "em", "um", "uma", "para", "não", "com", "são"]),
+    ("nl", &["de", "het", "een", "en", "van", "is", "dat", "niet", "op", "te", "voor", "met", "zijn", "ik"]),
+    ("sv", &["och", "att", "det", "som", "en", "är", "på", "för", "med", "inte", "jag", "av", "den", "till"]),
+    ("pl", &["i", "w", "na", "nie", "się", "że", "z", "do", "jest", "to", "jak", "co", "ale", "tak"]),
+    ("tr", &["ve", "bir", "bu", "da", "de", "için", "ile", "çok", "ne", "gibi", "ama", "olarak", "daha"]),
+    ("id", &["dan", "yang", "di", "itu", "dengan", "untuk", "tidak", "ini", "dari", "dalam", "akan", "ada"]),
+    ("da", &["og", "at", "det", "er", "en", "til", "på", "med", "af", "ikke", "som", "har", "jeg", "de"]),
+];
+
+/// Writing systems that identify a language on their own, or narrow it to a family
+#[derive(Clone, Copy)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Han,
+    Kana,
+    Hangul,
+    Thai,
+    Devanagari,
+}
+
+const SCRIPTS: [Script; 10] = [
+    Script::Latin, Script::Cyrillic, Script::Greek, Script::Arabic, Script::Hebrew,
+    Script::Han, Script::Kana, Script::Hangul, Script::Thai, Script::Devanagari,
+];
+
+fn script_of(c: char) -> Option<Script> {
+    match c as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Some(Script::Latin),
+        0x370..=0x3FF => Some(Script::Greek),
+        0x400..=0x4FF => Some(Script::Cyrillic),
+        0x590..=0x5FF => Some(Script::Hebrew),
+        0x600..=0x6FF | 0x750..=0x77F => Some(Script::Arabic),
+        0x900..=0x97F => Some(Script::Devanagari),
+        0xE00..=0xE7F => Some(Script::Thai),
+        0x3040..=0x30FF => Some(Script::Kana),
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF => Some(Script::Han),
+        0xAC00..=0xD7AF | 0x1100..=0x11FF => Some(Script::Hangul),
+        _ => None,
+    }
+}
+
+/// Canonical BCP-47 casing (`en-US`, `zh-Hant-TW`); `None` when the tag is malformed
+fn normalize_tag(tag: &str) -> Option<String> {
+    let mut parts = tag.trim().split(['-', '_']);
+    let primary = parts.next()?;
+    if !(2..=8).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut out = primary.to_ascii_lowercase();
+    for part in parts {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        out.push('-');
+        match part.len() {
+            2 => out.push_str(&part.to_ascii_uppercase()),
+            4 if part.chars().all(|c| c.is_ascii_alphabetic()) => {
+                out.push_str(&part[..1].to_ascii_uppercase());
+                out.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => out.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Some(out)
+}
+
+fn primary(tag: &str) -> &str {
+    tag.split('-').next().unwrap_or(tag)
+}
+
+/// The language the page declares, with the confidence its source deserves: `<html lang>`
+/// (or `xml:lang`), then `Content-Language`, `<meta name="language">` and `og:locale`
+fn declared_language(document: &Html) -> Option<(String, f64)> {
+    let root = document.root_element().value();
+    let attr = root.attr("lang").or_else(|| root.attr("xml:lang"));
+    if let Some(tag) = attr.and_then(normalize_tag) {
+        return Some((tag, LANG_ATTR_CONFIDENCE));
+    }
+    for meta in document.select(&META_SELECTOR) {
+        let key = meta.value().attr("http-equiv").or_else(|| meta.value().attr("name")).or_else(|| meta.value().attr("property"));
+        let Some(key) = key else { continue };
+        if matches!(key.to_ascii_lowercase().as_str(), "content-language" | "language" | "og:locale") {
+            // Content-Language may list several; the first is the primary audience
+            let content = meta.value().attr("content").unwrap_or_default();
+            if let Some(tag) = content.split(',').next().and_then(normalize_tag) {
+                return Some((tag, META_CONFIDENCE));
+            }
+        }
+    }
+    None
+}
+
+/// Guess the language of visible text: by writing system where it is distinctive, by
+/// stopword frequency for Latin script
+fn detected_language(text: &str) -> Option<(String, f64)> {
+    let mut counts = [0usize; SCRIPTS.len()];
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        if let Some(script) = script_of(c) {
+            counts[script as usize] += 1;
+        }
+    }
+    let letters: usize = counts.iter().sum();
+    if letters == 0 {
+        return None;
+    }
+    let count = |script: Script| counts[script as usize];
+    // Japanese mixes kana with Han; kana alone marks it
+    let (script, share) = if count(Script::Kana) > 0 {
+        (Script::Kana, (count(Script::Kana) + count(Script::Han)) as f64 / letters as f64)
+    } else {
+        let best = *SCRIPTS.iter().max_by_key(|&&s| count(s)).unwrap();
+        (best, count(best) as f64 / letters as f64)
+    };
+    // Short samples are weak evidence
+    let sample = (letters as f64 / 20.0).min(1.0);
+    let code = match script {
+        Script::Latin => return latin_language(text).map(|(code, conf)| (code.to_string(), conf * share)),
+        Script::Cyrillic if text.contains(['і', 'ї', 'є', 'ґ']) => "uk",
+        Script::Cyrillic => "ru",
+        Script::Greek => "el",
+        Script::Arabic if text.contains(['پ', 'چ', 'ژ', 'گ']) => "fa",
+        Script::Arabic => "ar",
+        Script::Hebrew => "he",
+        Script::Han => "zh",
+        Script::Kana => "ja",
+        Script::Hangul => "ko",
+        Script::Thai => "th",
+        Script::Devanagari => "hi",
+    };
+    Some((code.to_string(), share * sample))
+}
+
+/// Stopword vote among Latin-script languages; confidence is the winner's share of the top
+/// two votes, scaled down when few stopwords were seen
+fn latin_language(text: &str) -> Option<(&'static str, f64)> {
+    let lowered = text.to_lowercase();
+    let words: Vec<&str> = lowered.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()).collect();
+    let mut scores: Vec<(&str, usize)> = STOPWORDS
+        .iter()
+        .map(|(code, stopwords)| (*code, words.iter().filter(|w| stopwords.contains(w)).count()))
+        .collect();
+    scores.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    let (code, best) = scores[0];
+    if best == 0 {
+        return None;
+    }
+    let second = scores[1].1;
+    let margin = best as f64 / (best + second) as f64;
+    Some((code, margin * (best as f64 / 8.0).min(1.0)))
+}
+
+fn round(confidence: f64) -> f64 {
+    (confidence * 100.0).round() / 100.0
+}
+
+/// Detect a page's language for routing to locale-specific handling. Combines the declared
+/// language (`<html lang>`, `Content-Language`, `<meta name="language">`, `og:locale`) with a
+/// heuristic over visible text: the writing system where it is distinctive, stopword
+/// frequency for Latin script. Returns `language` (a BCP-47 tag, or `None`), `confidence`
+/// (0 to 1), and the `declared` and `detected` tags it was based on. Agreement raises the
+/// confidence and keeps the declared tag's region; disagreement lowers it.
+#[pyfunction]
+pub fn detect_language(py: Python, html: HtmlInput) -> PyResult<Py<PyDict>> {
+    let source = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&source);
+    let declared = declared_language(&document);
+    let detected = detected_language(&render::render_document(&document, false))
+        .filter(|(_, confidence)| *confidence > 0.0);
+
+    let (language, confidence) = match (&declared, &detected) {
+        (Some((tag, a)), Some((code, b))) if primary(tag) == primary(code) => {
+            (Some(tag.clone()), 1.0 - (1.0 - a) * (1.0 - b))
+        }
+        (Some((tag, a)), Some((code, b))) => {
+            if a >= b {
+                (Some(tag.clone()), a - b / 2.0)
+            } else {
+                (Some(code.clone()), b - a / 2.0)
+            }
+        }
+        (Some((tag, a)), None) => (Some(tag.clone()), *a),
+        (None, Some((code, b))) => (Some(code.clone()), *b),
+        (None, None) => (None, 0.0),
+    };
+
+    let dict = PyDict::new_bound(py);
+    dict.set_item("language", language)?;
+    dict.set_item("confidence", round(confidence))?;
+    dict.set_item("declared", declared.map(|(tag, _)| tag))?;
+    dict.set_item("detected", detected.map(|(code, _)| code))?;
+    Ok(dict.unbind())
+}
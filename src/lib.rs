@@ -35,26 +35,124 @@ data = rusticsoup.extract_data(html, "div.item", {
 
 - `extract_data()` - Universal HTML data extraction
 - `extract_data_bulk()` - Parallel processing of multiple pages
+- `compile_selectors()` - Field mappings compiled once, for `extract_data` on hot paths
+- `detect_containers()` - Likeliest repeating item selector and field suggestions for unknown pages
+- `match_template()` - Bind `{{name}}` placeholders of an annotated HTML template to matching elements
 - `parse_html()` - Low-level HTML parsing and DOM access
-- `bulk_parse_google_shopping()` - Optimized Google Shopping parser
+- `parse_fragment()` - Snippets such as bare `<td>` or `<li>` parsed in an explicit context element
+- `parse_xml()` - XML documents (namespaces, case-sensitive names) behind the same selector API
+- `StreamingParser` - Incremental parsing of network chunks with `feed()`/`close()`
+- `tokenize()` - Streaming start/end/text/comment events without building a DOM
+- `css_to_xpath()` - XPath 1.0 equivalent of a CSS selector, for XPath-based tools
+- `register_extractor()` / `extract_with()` - Named site parsers (compiled rules or callables) picked at runtime
+- `register_pseudo_class()` - Named pseudo-classes such as `:price-like` backed by a Python predicate or regex
+- `duplicate_attributes()` - Repeated attributes the parser drops (first one wins), read from the source
+- `extract_json_ld()` - schema.org JSON-LD blocks as Python objects
+- `extract_microdata()` - schema.org microdata items as nested dicts
+- `extract_script_json()` - SPA state such as `__NEXT_DATA__` and `window.__INITIAL_STATE__`
+- `extract_social_meta()` - OpenGraph and Twitter Card tags
+- `get_metadata()` - Title, description, canonical URL, robots and other `<meta>` tags
+- `extract_links()` - Links resolved against the base URL, with rel and nofollow
+- `parse_srcset()` - `srcset` candidates with width/density descriptors and a `best()` pick
+- `extract_link_rels()` - Canonical, hreflang alternates, next/prev and amphtml `<link>`s
+- `extract_images()` - Images with lazy-load sources and the best `srcset` candidate
+- `extract_product()` - Site-agnostic product name, price, availability, brand, images and rating
+- `extract_contacts()` - Emails and phone numbers from links and visible text
+- `extract_forms()` - Forms with their fields and default submission data
+- `parse_sitemap()` - Sitemap and sitemap index XML, plain or gzipped
+- `parse_feed()` - RSS and Atom feeds as normalized entries
+- `sanitize()` - Allowlist-based HTML cleaning for re-display
+- `minify()` - Whitespace, comment and quote minification
+- `to_text()` - Block-aware plain text with list markers and table columns
+- `extract_outline()` - Nested h1-h6 outline with anchors and each section's text, for chunking
+- `content_hash()` - SimHash/MinHash fingerprints of visible text for near-duplicate detection
+- `diff()` - Added, removed and changed nodes and attributes between two versions of a page
+- `detect_language()` - BCP-47 page language from declarations and visible text, with confidence
+- `parse_price()` - Amount and ISO currency from price text, handling locale separators
+- `parse_datetime()` - Timezone-aware datetimes from absolute, numeric and relative dates
+- `detect_encoding()` - Encoding detection for raw bytes without parsing
+- `configure_logging()` - Forward parse/selector/extraction events to Python `logging`
+- `selector_cache_info()` - Hits, misses and size of the LRU cache of compiled selectors
 
 */
 
 use pyo3::prelude::*;
 
+mod arrow;
+mod benchmark;
+mod conditional;
+mod containers;
+mod contacts;
+mod dates;
+mod dom_diff;
 mod errors;
+mod forms;
+mod fragment;
+mod explain;
+mod feeds;
+mod fingerprint;
 mod encoding;
+mod language;
+mod limits;
+mod links;
+mod logging;
+mod markup;
+mod parquet;
+mod price;
+mod product;
+mod pseudo;
+mod registry;
+mod template_match;
+mod text;
+mod tokenizer;
+mod render;
+mod sanitizer;
 mod scraper;
+mod sinks;
+mod scripts;
+mod selector;
+mod structured;
 mod universal_extractor;
+mod xml;
+mod xpath;
 mod bs4_api;
 
-use scraper::{WebScraper, Element, parse_html, extract, extract_all};
-use universal_extractor::{extract_data, extract_table_data};
+use scraper::{WebScraper, Element, StreamingParser, parse_fragment, parse_html, extract, extract_all};
+use arrow::ArrowTable;
+use benchmark::benchmark_against;
+use sinks::{write_csv, write_parquet};
+use universal_extractor::{compile_selectors, extract_data, extract_table_data, validate_mappings, CompiledSelectors, ExtractionReport};
 use bs4_api::RusticSoup;
+use template_match::match_template;
+use containers::detect_containers;
+use feeds::{parse_feed, parse_sitemap};
+use fingerprint::content_hash;
+use dom_diff::diff;
+use contacts::extract_contacts;
+use forms::extract_forms;
+use language::detect_language;
+use links::{extract_images, extract_link_rels, extract_links, parse_srcset, Srcset};
+use markup::minify;
+use dates::parse_datetime;
+use price::parse_price;
+use product::extract_product;
+use render::{extract_outline, to_text};
+use sanitizer::sanitize;
+use xml::parse_xml;
+use tokenizer::{duplicate_attributes, tokenize, TokenStream};
+use scripts::extract_script_json;
+use selector::{clear_selector_cache, configure_selector_cache, css_to_xpath, selector_cache_info};
+use pseudo::{register_pseudo_class, unregister_pseudo_class};
+use registry::{extract_with, register_extractor, registered_extractors, unregister_extractor};
+use structured::{extract_json_ld, extract_microdata, extract_social_meta, get_metadata};
+use encoding::{detect_encoding, StreamDecoder};
+use logging::configure_logging;
 
 #[pymodule]
 fn rusticsoup(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add("__version__", "0.1.0")?;
+    // The global allocator this build uses; builds with the `mimalloc` feature report "mimalloc"
+    m.add("ALLOCATOR", if cfg!(feature = "mimalloc") { "mimalloc" } else { "system" })?;
     m.add("__doc__", "Lightning-fast HTML parser and data extractor - BeautifulSoup killer built in Rust")?;
 
     // Exceptions (exposed types)
@@ -63,19 +161,93 @@ fn rusticsoup(m: &Bound<'_, PyModule>) -> PyResult<()> {
         m.add("HTMLParseError", py.get_type_bound::<errors::HTMLParseError>())?;
         m.add("SelectorError", py.get_type_bound::<errors::SelectorError>())?;
         m.add("EncodingError", py.get_type_bound::<errors::EncodingError>())?;
+        m.add("ParseTimeoutError", py.get_type_bound::<errors::ParseTimeoutError>())?;
+        m.add("LimitExceededError", py.get_type_bound::<errors::LimitExceededError>())?;
+        errors::init_detail_attributes(py)?;
         Ok(())
     })?;
     
     // Universal extractors - the main API
     m.add_function(wrap_pyfunction!(extract_data, m)?)?;
     m.add_function(wrap_pyfunction!(extract_table_data, m)?)?;
+    m.add_class::<ExtractionReport>()?;
+    m.add_function(wrap_pyfunction!(validate_mappings, m)?)?;
+    m.add_function(wrap_pyfunction!(compile_selectors, m)?)?;
+    m.add_class::<CompiledSelectors>()?;
+    m.add_function(wrap_pyfunction!(register_extractor, m)?)?;
+    m.add_function(wrap_pyfunction!(unregister_extractor, m)?)?;
+    m.add_function(wrap_pyfunction!(registered_extractors, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_with, m)?)?;
+    m.add_class::<ArrowTable>()?;
+    m.add_function(wrap_pyfunction!(write_csv, m)?)?;
+    m.add_function(wrap_pyfunction!(write_parquet, m)?)?;
+    m.add_function(wrap_pyfunction!(benchmark_against, m)?)?;
+    m.add_function(wrap_pyfunction!(match_template, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_containers, m)?)?;
+
+    // Structured data
+    m.add_function(wrap_pyfunction!(extract_json_ld, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_microdata, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_script_json, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_social_meta, m)?)?;
+    m.add_function(wrap_pyfunction!(get_metadata, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_language, m)?)?;
+
+    // Page primitives
+    m.add_function(wrap_pyfunction!(extract_links, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_link_rels, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_images, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_srcset, m)?)?;
+    m.add_class::<Srcset>()?;
+    m.add_function(wrap_pyfunction!(extract_forms, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_contacts, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_product, m)?)?;
+
+    // Crawl seeding
+    m.add_function(wrap_pyfunction!(parse_sitemap, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_feed, m)?)?;
+
+    // Output
+    m.add_function(wrap_pyfunction!(sanitize, m)?)?;
+    m.add_function(wrap_pyfunction!(minify, m)?)?;
+    m.add_function(wrap_pyfunction!(to_text, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_outline, m)?)?;
+
+    // Value parsing
+    m.add_function(wrap_pyfunction!(parse_price, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_datetime, m)?)?;
+
+    // Deduplication
+    m.add_function(wrap_pyfunction!(content_hash, m)?)?;
+
+    // Change monitoring
+    m.add_function(wrap_pyfunction!(diff, m)?)?;
     
     // Low-level HTML parsing
     m.add_class::<WebScraper>()?;
     m.add_class::<Element>()?;
     m.add_function(wrap_pyfunction!(parse_html, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_fragment, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_xml, m)?)?;
+    m.add_class::<StreamingParser>()?;
     m.add_function(wrap_pyfunction!(extract, m)?)?;
     m.add_function(wrap_pyfunction!(extract_all, m)?)?;
+    m.add_function(wrap_pyfunction!(tokenize, m)?)?;
+    m.add_class::<TokenStream>()?;
+    m.add_function(wrap_pyfunction!(duplicate_attributes, m)?)?;
+    m.add_function(wrap_pyfunction!(css_to_xpath, m)?)?;
+    m.add_function(wrap_pyfunction!(register_pseudo_class, m)?)?;
+    m.add_function(wrap_pyfunction!(unregister_pseudo_class, m)?)?;
+
+    // Encoding detection
+    m.add_function(wrap_pyfunction!(detect_encoding, m)?)?;
+    m.add_class::<StreamDecoder>()?;
+
+    // Observability
+    m.add_function(wrap_pyfunction!(configure_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(selector_cache_info, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_selector_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(configure_selector_cache, m)?)?;
 
     // BS4-like facade (early scaffold)
     m.add_class::<RusticSoup>()?;
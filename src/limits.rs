@@ -0,0 +1,255 @@
+use pyo3::prelude::*;
+use ahash::AHashMap;
+use ego_tree::NodeId;
+use html5ever::tendril::StrTendril;
+use html5ever::tree_builder::{ElementFlags, NextParserState, NodeOrText, QuirksMode, TreeSink};
+use html5ever::{Attribute, QualName};
+use scraper::{Html, HtmlTreeSink};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+/// Validate the Python-facing `timeout` argument (seconds).
+pub fn timeout_arg(timeout: Option<f64>) -> PyResult<Option<Duration>> {
+    match timeout {
+        None => Ok(None),
+        Some(secs) if secs.is_finite() && secs > 0.0 => Ok(Some(Duration::from_secs_f64(secs))),
+        Some(secs) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid timeout: {}. Use a positive number of seconds", secs)
+        )),
+    }
+}
+
+/// Wall-clock budget for work on one document. Without a timeout every check passes.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    budget: Option<(Instant, Duration)>,
+}
+
+impl Deadline {
+    /// Start a budget of `timeout` now
+    pub fn start(timeout: Option<Duration>) -> Self {
+        Deadline { budget: timeout.map(|timeout| (Instant::now(), timeout)) }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.budget.is_some()
+    }
+
+    /// Raise `ParseTimeoutError` if the budget is spent; `stage` says what was interrupted
+    pub fn check(&self, stage: &str) -> PyResult<()> {
+        match self.budget {
+            Some((started, timeout)) if started.elapsed() > timeout => Err(PyErr::new::<crate::errors::ParseTimeoutError, _>(
+                format!("{} exceeded the time budget of {:.3} s", stage, timeout.as_secs_f64())
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Size guards on a single document; `None` leaves that dimension unbounded.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    /// Maximum input length in bytes
+    pub max_size: Option<usize>,
+    /// Maximum element nesting depth
+    pub max_depth: Option<usize>,
+    /// Maximum number of elements, text, comment and other nodes
+    pub max_nodes: Option<usize>,
+}
+
+impl Limits {
+    pub fn is_set(&self) -> bool {
+        self.max_size.is_some() || self.max_depth.is_some() || self.max_nodes.is_some()
+    }
+
+    /// Raise `LimitExceededError` if `len` bytes of input is over `max_size`
+    pub fn check_size(&self, len: usize) -> PyResult<()> {
+        match self.max_size {
+            Some(max) if len > max => Err(limit_error(format!(
+                "Document is {} bytes, over the max_size limit of {}", len, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn limit_error(message: String) -> PyErr {
+    PyErr::new::<crate::errors::LimitExceededError, _>(message)
+}
+
+/// `HtmlTreeSink` that counts nodes and tracks nesting depth as the tree is built. The tree
+/// builder cannot be interrupted from inside the sink, so the parse loop polls `check()`
+/// between input chunks.
+pub struct LimitedSink {
+    inner: HtmlTreeSink,
+    limits: Limits,
+    nodes: Cell<usize>,
+    max_depth_seen: Cell<usize>,
+    depths: RefCell<AHashMap<NodeId, usize>>,
+    /// Parent of the last appended node, if that node was text; more text appended to the same
+    /// parent is merged into it rather than creating a node
+    text_parent: Cell<Option<NodeId>>,
+}
+
+impl LimitedSink {
+    pub fn new(inner: HtmlTreeSink, limits: Limits) -> Self {
+        LimitedSink {
+            inner,
+            limits,
+            nodes: Cell::new(0),
+            max_depth_seen: Cell::new(0),
+            depths: RefCell::new(AHashMap::new()),
+            text_parent: Cell::new(None),
+        }
+    }
+
+    /// Raise `LimitExceededError` if the tree built so far is over a limit
+    pub fn check(&self) -> PyResult<()> {
+        if let Some(max) = self.limits.max_nodes {
+            if self.nodes.get() > max {
+                return Err(limit_error(format!("Document has more than {} nodes (max_nodes)", max)));
+            }
+        }
+        if let Some(max) = self.limits.max_depth {
+            if self.max_depth_seen.get() > max {
+                return Err(limit_error(format!("Document nests deeper than {} levels (max_depth)", max)));
+            }
+        }
+        Ok(())
+    }
+
+    fn depth(&self, node: &NodeId) -> usize {
+        self.depths.borrow().get(node).copied().unwrap_or(0)
+    }
+
+    /// Account for `child` landing under `parent`, or next to a sibling at `depth`
+    fn track(&self, parent: Option<&NodeId>, depth: usize, child: &NodeOrText<NodeId>) {
+        match child {
+            NodeOrText::AppendText(_) => {
+                let parent = parent.copied();
+                if parent.is_none() || self.text_parent.get() != parent {
+                    self.nodes.set(self.nodes.get() + 1);
+                }
+                self.text_parent.set(parent);
+            }
+            NodeOrText::AppendNode(node) => {
+                self.text_parent.set(None);
+                if self.limits.max_depth.is_some() {
+                    self.depths.borrow_mut().insert(*node, depth);
+                    self.max_depth_seen.set(self.max_depth_seen.get().max(depth));
+                }
+            }
+        }
+    }
+
+    fn created(&self, node: NodeId) -> NodeId {
+        self.nodes.set(self.nodes.get() + 1);
+        node
+    }
+}
+
+impl TreeSink for LimitedSink {
+    type Output = Html;
+    type Handle = NodeId;
+    type ElemName<'a> = <HtmlTreeSink as TreeSink>::ElemName<'a>;
+
+    fn finish(self) -> Html {
+        self.inner.finish()
+    }
+
+    fn parse_error(&self, msg: Cow<'static, str>) {
+        self.inner.parse_error(msg)
+    }
+
+    fn get_document(&self) -> NodeId {
+        self.inner.get_document()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a NodeId) -> Self::ElemName<'a> {
+        self.inner.elem_name(target)
+    }
+
+    fn create_element(&self, name: QualName, attrs: Vec<Attribute>, flags: ElementFlags) -> NodeId {
+        self.created(self.inner.create_element(name, attrs, flags))
+    }
+
+    fn create_comment(&self, text: StrTendril) -> NodeId {
+        self.created(self.inner.create_comment(text))
+    }
+
+    fn create_pi(&self, target: StrTendril, data: StrTendril) -> NodeId {
+        self.created(self.inner.create_pi(target, data))
+    }
+
+    fn append(&self, parent: &NodeId, child: NodeOrText<NodeId>) {
+        self.track(Some(parent), self.depth(parent) + 1, &child);
+        self.inner.append(parent, child)
+    }
+
+    fn append_based_on_parent_node(&self, element: &NodeId, prev_element: &NodeId, child: NodeOrText<NodeId>) {
+        // Lands either before `element` or inside `prev_element`; both sit at about the same depth
+        self.track(None, self.depth(prev_element) + 1, &child);
+        self.inner.append_based_on_parent_node(element, prev_element, child)
+    }
+
+    fn append_doctype_to_document(&self, name: StrTendril, public_id: StrTendril, system_id: StrTendril) {
+        self.nodes.set(self.nodes.get() + 1);
+        self.inner.append_doctype_to_document(name, public_id, system_id)
+    }
+
+    fn mark_script_already_started(&self, node: &NodeId) {
+        self.inner.mark_script_already_started(node)
+    }
+
+    fn pop(&self, node: &NodeId) {
+        self.inner.pop(node)
+    }
+
+    fn get_template_contents(&self, target: &NodeId) -> NodeId {
+        self.inner.get_template_contents(target)
+    }
+
+    fn same_node(&self, x: &NodeId, y: &NodeId) -> bool {
+        self.inner.same_node(x, y)
+    }
+
+    fn set_quirks_mode(&self, mode: QuirksMode) {
+        self.inner.set_quirks_mode(mode)
+    }
+
+    fn append_before_sibling(&self, sibling: &NodeId, new_node: NodeOrText<NodeId>) {
+        self.track(None, self.depth(sibling), &new_node);
+        self.inner.append_before_sibling(sibling, new_node)
+    }
+
+    fn add_attrs_if_missing(&self, target: &NodeId, attrs: Vec<Attribute>) {
+        self.inner.add_attrs_if_missing(target, attrs)
+    }
+
+    fn associate_with_form(&self, target: &NodeId, form: &NodeId, nodes: (&NodeId, Option<&NodeId>)) {
+        self.inner.associate_with_form(target, form, nodes)
+    }
+
+    fn remove_from_parent(&self, target: &NodeId) {
+        self.text_parent.set(None);
+        self.inner.remove_from_parent(target)
+    }
+
+    fn reparent_children(&self, node: &NodeId, new_parent: &NodeId) {
+        self.text_parent.set(None);
+        self.inner.reparent_children(node, new_parent)
+    }
+
+    fn is_mathml_annotation_xml_integration_point(&self, handle: &NodeId) -> bool {
+        self.inner.is_mathml_annotation_xml_integration_point(handle)
+    }
+
+    fn set_current_line(&self, line_number: u64) {
+        self.inner.set_current_line(line_number)
+    }
+
+    fn complete_script(&self, node: &NodeId) -> NextParserState {
+        self.inner.complete_script(node)
+    }
+}
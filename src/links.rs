@@ -0,0 +1,396 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use scraper::{ElementRef, Html, Selector};
+use once_cell::sync::Lazy;
+use url::Url;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+
+static BASE_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("base[href]").unwrap());
+static LINK_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("a[href], area[href]").unwrap());
+static IMG_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("img").unwrap());
+static LINK_REL_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("link[rel][href]").unwrap());
+static ANCHOR_REL_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("a[rel][href], area[rel][href]").unwrap());
+static ROBOTS_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[name][content]").unwrap());
+
+/// Accepts one `rel` value or several
+#[derive(FromPyObject)]
+pub enum RelFilter {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The URL relative links resolve against: the page's `<base href>` (itself resolved against
+/// `base_url`), else `base_url`. `None` when neither gives an absolute URL.
+pub fn document_base(document: &Html, base_url: Option<&str>) -> PyResult<Option<Url>> {
+    let base_url = match base_url {
+        Some(url) => Some(Url::parse(url.trim()).map_err(|err| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid base_url '{}': {}", url, err))
+        })?),
+        None => None,
+    };
+    let base_href = document.select(&BASE_SELECTOR).next().and_then(|base| base.value().attr("href"));
+    Ok(match base_href {
+        Some(href) => resolve(base_url.as_ref(), href).or(base_url),
+        None => base_url,
+    })
+}
+
+/// Resolve `href` against `base`; absolute hrefs need no base. `None` if it cannot be resolved.
+pub fn resolve(base: Option<&Url>, href: &str) -> Option<Url> {
+    let href = href.trim();
+    match base {
+        Some(base) => base.join(href).ok(),
+        None => Url::parse(href).ok(),
+    }
+}
+
+/// Attributes holding a single URL
+pub const URL_ATTRIBUTES: [&str; 10] =
+    ["href", "src", "cite", "action", "formaction", "poster", "background", "longdesc", "data", "manifest"];
+
+/// An attribute value resolved against `base` when the attribute holds URLs: one of
+/// `URL_ATTRIBUTES`, a lazy-loader image attribute, or a `srcset` whose candidates are each
+/// resolved. Other attributes, values that cannot be resolved and any value without a `base`
+/// are returned as written.
+pub fn resolve_attr(base: Option<&Url>, attr: &str, value: &str) -> String {
+    let Some(base) = base else { return value.to_string() };
+    let absolute = |href: &str| resolve(Some(base), href).map(String::from).unwrap_or_else(|| href.to_string());
+    let attr = attr.to_ascii_lowercase();
+    if URL_ATTRIBUTES.contains(&attr.as_str()) || LAZY_SRC_ATTRS.contains(&attr.as_str()) {
+        return if value.trim().is_empty() { value.to_string() } else { absolute(value) };
+    }
+    if attr == "srcset" || attr == "imagesrcset" || LAZY_SRCSET_ATTRS.contains(&attr.as_str()) {
+        let candidates: Vec<String> = split_srcset(value)
+            .into_iter()
+            .map(|c| match (c.width, c.density) {
+                (Some(w), _) => format!("{} {}w", absolute(&c.url), w),
+                (None, d) if d != 1.0 => format!("{} {}x", absolute(&c.url), d),
+                (None, _) => absolute(&c.url),
+            })
+            .collect();
+        return candidates.join(", ");
+    }
+    value.to_string()
+}
+
+/// Text of an element with whitespace runs collapsed
+pub fn collapsed_text(elem: ElementRef) -> String {
+    elem.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Every `<a href>` and `<area href>` as a dict of `url` (resolved against `base_url` and the
+/// page's `<base href>`; the raw href when it cannot be resolved), `href`, `text`, `rel` (list
+/// of lowercased tokens) and `nofollow` (from `rel`, or a page-wide `robots` nofollow).
+/// `javascript:` links are skipped. `rel` keeps links carrying any of the given values;
+/// `internal_only` keeps links on the same host as the base URL.
+#[pyfunction]
+#[pyo3(signature = (html, base_url=None, rel=None, internal_only=false))]
+pub fn extract_links(
+    py: Python,
+    html: HtmlInput,
+    base_url: Option<&str>,
+    rel: Option<RelFilter>,
+    internal_only: bool,
+) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let base = document_base(&document, base_url)?;
+    let base_host = match (&base, internal_only) {
+        (Some(base), true) => Some(base.host_str().unwrap_or("").to_ascii_lowercase()),
+        (None, true) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "internal_only needs an absolute base_url or <base href> to compare hosts against",
+            ))
+        }
+        _ => None,
+    };
+    let wanted: Option<Vec<String>> = rel.map(|rel| match rel {
+        RelFilter::One(value) => vec![value.to_ascii_lowercase()],
+        RelFilter::Many(values) => values.iter().map(|v| v.to_ascii_lowercase()).collect(),
+    });
+    let page_nofollow = document.select(&ROBOTS_SELECTOR).any(|meta| {
+        meta.value().attr("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+            && meta.value().attr("content").unwrap_or("").split(',').any(|d| d.trim().eq_ignore_ascii_case("nofollow"))
+    });
+
+    let links = PyList::empty_bound(py);
+    for link in document.select(&LINK_SELECTOR) {
+        let href = link.value().attr("href").unwrap_or("").trim();
+        if href.is_empty() || href.get(..11).is_some_and(|scheme| scheme.eq_ignore_ascii_case("javascript:")) {
+            continue;
+        }
+        let rels: Vec<String> = link
+            .value()
+            .attr("rel")
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|r| r.to_ascii_lowercase())
+            .collect();
+        if let Some(wanted) = &wanted {
+            if !rels.iter().any(|r| wanted.contains(r)) {
+                continue;
+            }
+        }
+        let url = resolve(base.as_ref(), href);
+        if let Some(host) = &base_host {
+            let same_host = url
+                .as_ref()
+                .and_then(|url| url.host_str())
+                .is_some_and(|h| h.eq_ignore_ascii_case(host));
+            if !same_host {
+                continue;
+            }
+        }
+
+        let item = PyDict::new_bound(py);
+        item.set_item("url", url.map(String::from).unwrap_or_else(|| href.to_string()))?;
+        item.set_item("href", href)?;
+        item.set_item("text", collapsed_text(link))?;
+        item.set_item("nofollow", page_nofollow || rels.iter().any(|r| r == "nofollow"))?;
+        item.set_item("rel", rels)?;
+        links.append(item)?;
+    }
+    Ok(links.unbind())
+}
+
+fn has_rel(elem: ElementRef, wanted: &str) -> bool {
+    elem.value().attr("rel").unwrap_or("").split_whitespace().any(|r| r.eq_ignore_ascii_case(wanted))
+}
+
+/// Attributes on `<html>` that mark an AMP document, for web pages, emails and ads
+const AMP_ATTRS: [&str; 6] = ["amp", "\u{26a1}", "amp4email", "\u{26a1}4email", "amp4ads", "\u{26a1}4ads"];
+
+/// Whether the document is AMP: `<html amp>` or `<html ⚡>` (or an email/ads variant)
+pub fn is_amp(document: &Html) -> bool {
+    let root = document.root_element().value();
+    root.name() == "html" && AMP_ATTRS.iter().any(|attr| root.attr(attr).is_some())
+}
+
+/// The page's `<link>` relationships as one dict: `canonical`, `amphtml`, `next` and `prev`
+/// (URLs or `None`) and `alternates`, mapping each `hreflang` (e.g. `"de-AT"`, `"x-default"`)
+/// to its URL, plus `amp`, whether the page is itself an AMP document. An AMP page points to
+/// its regular variant with `canonical`; a regular page to its AMP variant with `amphtml`.
+/// The first declaration wins. Pagination falls back to `<a rel="next">` links when no
+/// `<link>` declares it, and `rel="previous"` counts as `prev`. URLs are resolved like
+/// `extract_links`.
+#[pyfunction]
+#[pyo3(signature = (html, base_url=None))]
+pub fn extract_link_rels(py: Python, html: HtmlInput, base_url: Option<&str>) -> PyResult<Py<PyDict>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let base = document_base(&document, base_url)?;
+    let absolute = |elem: ElementRef| {
+        let href = elem.value().attr("href").unwrap_or("").trim();
+        resolve(base.as_ref(), href).map(String::from).unwrap_or_else(|| href.to_string())
+    };
+    let links: Vec<ElementRef> = document.select(&LINK_REL_SELECTOR).collect();
+    let first = |rels: &[&str]| {
+        links.iter().copied().find(|link| rels.iter().any(|rel| has_rel(*link, rel))).or_else(|| {
+            // Only pagination is commonly marked up on anchors
+            if rels.contains(&"next") || rels.contains(&"prev") {
+                document.select(&ANCHOR_REL_SELECTOR).find(|a| rels.iter().any(|rel| has_rel(*a, rel)))
+            } else {
+                None
+            }
+        })
+    };
+
+    let alternates = PyDict::new_bound(py);
+    for link in links.iter().filter(|link| has_rel(**link, "alternate")) {
+        let Some(lang) = link.value().attr("hreflang").map(str::trim).filter(|l| !l.is_empty()) else { continue };
+        if !alternates.contains(lang)? {
+            alternates.set_item(lang, absolute(*link))?;
+        }
+    }
+
+    let dict = PyDict::new_bound(py);
+    dict.set_item("canonical", first(&["canonical"]).map(absolute))?;
+    dict.set_item("alternates", alternates)?;
+    dict.set_item("next", first(&["next"]).map(absolute))?;
+    dict.set_item("prev", first(&["prev", "previous"]).map(absolute))?;
+    dict.set_item("amphtml", first(&["amphtml"]).map(absolute))?;
+    dict.set_item("amp", is_amp(&document))?;
+    Ok(dict.unbind())
+}
+
+/// Attributes lazy loaders keep the real image URL in, in order of preference
+const LAZY_SRC_ATTRS: [&str; 4] = ["data-src", "data-lazy", "data-lazy-src", "data-original"];
+const LAZY_SRCSET_ATTRS: [&str; 2] = ["data-srcset", "data-lazy-srcset"];
+
+/// Every `<img>` as a dict of `src` (the real image: a lazy-loader attribute such as
+/// `data-src` wins over the placeholder in `src`), `best` (the largest `srcset` candidate,
+/// including `<source>`s of an enclosing `<picture>`, falling back to `src`), `alt`, `width`,
+/// `height` (ints, `None` unless given in pixels), `lazy` and `srcset`, the candidates as
+/// `parse_srcset` gives them. With `width`, `best` is the candidate a browser would load for
+/// that display width instead of the largest. URLs are resolved like `extract_links`. Images
+/// with no usable source are skipped.
+#[pyfunction]
+#[pyo3(signature = (html, base_url=None, width=None))]
+pub fn extract_images(py: Python, html: HtmlInput, base_url: Option<&str>, width: Option<f64>) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let base = document_base(&document, base_url)?;
+    let absolute = |href: &str| resolve(base.as_ref(), href).map(String::from).unwrap_or_else(|| href.to_string());
+
+    let images = PyList::empty_bound(py);
+    for img in document.select(&IMG_SELECTOR) {
+        let value = img.value();
+        let lazy_src = LAZY_SRC_ATTRS.iter().find_map(|attr| non_empty(value.attr(attr)));
+        let src = lazy_src.or_else(|| non_empty(value.attr("src")));
+        let lazy_srcset = LAZY_SRCSET_ATTRS.iter().find_map(|attr| non_empty(value.attr(attr)));
+
+        let mut candidates = Vec::new();
+        if let Some(picture) = img.parent().and_then(ElementRef::wrap).filter(|p| p.value().name() == "picture") {
+            for source in picture.children().filter_map(ElementRef::wrap).filter(|s| s.value().name() == "source") {
+                let srcset = non_empty(source.value().attr("data-srcset")).or_else(|| non_empty(source.value().attr("srcset")));
+                candidates.extend(srcset.map(split_srcset).unwrap_or_default());
+            }
+        }
+        candidates.extend(lazy_srcset.or_else(|| non_empty(value.attr("srcset"))).map(split_srcset).unwrap_or_default());
+        let best = pick(&candidates, width, None).map(|c| c.url.as_str()).or(src);
+        let Some(best) = best else { continue };
+
+        let item = PyDict::new_bound(py);
+        item.set_item("src", src.map(absolute))?;
+        item.set_item("best", absolute(best))?;
+        item.set_item("alt", value.attr("alt"))?;
+        item.set_item("width", pixels(value.attr("width")))?;
+        item.set_item("height", pixels(value.attr("height")))?;
+        item.set_item("lazy", lazy_src.is_some() || lazy_srcset.is_some() || value.attr("loading").is_some_and(|l| l.eq_ignore_ascii_case("lazy")))?;
+        let srcset: Vec<Bound<PyDict>> =
+            candidates.iter().map(|c| candidate_dict(py, c, absolute(&c.url))).collect::<PyResult<_>>()?;
+        item.set_item("srcset", srcset)?;
+        images.append(item)?;
+    }
+    Ok(images.unbind())
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// `width="640"` or `width="640px"`; percentages and other units give `None`
+fn pixels(value: Option<&str>) -> Option<u32> {
+    let value = value?.trim();
+    value.strip_suffix("px").unwrap_or(value).trim().parse().ok()
+}
+
+/// One `srcset` candidate; the size is the `w` descriptor, or the `x` density
+#[derive(Clone, Debug, PartialEq)]
+struct Candidate {
+    url: String,
+    width: Option<u32>,
+    density: f64,
+}
+
+/// Split a `srcset` attribute into candidates. URLs may themselves contain commas, so a
+/// candidate ends at whitespace after the URL rather than at the next comma.
+fn split_srcset(srcset: &str) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let url_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let url = &rest[..url_end];
+        rest = &rest[url_end..];
+        let (url, descriptor) = if url.ends_with(',') {
+            (url.trim_end_matches(','), "")
+        } else {
+            let descriptor_end = rest.find(',').unwrap_or(rest.len());
+            let descriptor = &rest[..descriptor_end];
+            rest = &rest[descriptor_end..];
+            (url, descriptor.trim())
+        };
+        let mut candidate = Candidate { url: url.to_string(), width: None, density: 1.0 };
+        for token in descriptor.split_whitespace() {
+            if let Some(w) = token.strip_suffix('w').and_then(|w| w.parse().ok()) {
+                candidate.width = Some(w);
+            } else if let Some(x) = token.strip_suffix('x').and_then(|x| x.parse().ok()) {
+                candidate.density = x;
+            }
+        }
+        candidates.push(candidate);
+    }
+    candidates
+}
+
+/// The candidate a browser would load. For an image `width` CSS pixels wide, the narrowest
+/// `w` candidate covering `width * density`; for a target `density` alone, the lowest `x`
+/// candidate at or above it. Otherwise, or when none is large enough, the largest: widest if
+/// any has a `w` descriptor, else highest density.
+fn pick(candidates: &[Candidate], width: Option<f64>, density: Option<f64>) -> Option<&Candidate> {
+    let has_widths = candidates.iter().any(|c| c.width.is_some());
+    let fitting = match (width, density) {
+        (Some(width), density) if has_widths => {
+            let needed = width * density.unwrap_or(1.0);
+            candidates
+                .iter()
+                .filter(|c| c.width.is_some_and(|w| f64::from(w) >= needed))
+                .min_by_key(|c| c.width)
+        }
+        (_, Some(density)) if !has_widths => candidates
+            .iter()
+            .filter(|c| c.density >= density)
+            .min_by(|a, b| a.density.total_cmp(&b.density)),
+        _ => None,
+    };
+    let size = |c: &Candidate| (c.width.unwrap_or(0), c.density);
+    fitting.or_else(|| candidates.iter().reduce(|best, c| if size(c) > size(best) { c } else { best }))
+}
+
+fn candidate_dict<'py>(py: Python<'py>, candidate: &Candidate, url: String) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("url", url)?;
+    dict.set_item("width", candidate.width)?;
+    dict.set_item("density", candidate.density)?;
+    Ok(dict)
+}
+
+/// A parsed `srcset` attribute
+#[pyclass]
+pub struct Srcset {
+    candidates: Vec<Candidate>,
+}
+
+#[pymethods]
+impl Srcset {
+    /// Candidates in source order: `url`, `width` (the `w` descriptor, or `None`) and `density`
+    /// (the `x` descriptor, 1.0 when absent)
+    #[getter]
+    fn candidates<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.candidates.iter().map(|c| candidate_dict(py, c, c.url.clone())).collect()
+    }
+
+    /// The URL to load for an image displayed `width` CSS pixels wide on a screen of pixel
+    /// `density`, picked as a browser would; the largest candidate when neither is given or
+    /// none is large enough. `None` for an empty srcset.
+    #[pyo3(signature = (width=None, density=None))]
+    fn best(&self, width: Option<f64>, density: Option<f64>) -> Option<String> {
+        pick(&self.candidates, width, density).map(|c| c.url.clone())
+    }
+
+    fn __len__(&self) -> usize {
+        self.candidates.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Srcset candidates={}>", self.candidates.len())
+    }
+}
+
+/// Parse a `srcset` attribute value into a `Srcset`. Commas inside candidate URLs do not split
+/// them; unknown descriptors are ignored.
+#[pyfunction]
+pub fn parse_srcset(value: &str) -> Srcset {
+    Srcset { candidates: split_srcset(value) }
+}
@@ -0,0 +1,76 @@
+use pyo3::prelude::*;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Python `logging` levels used by RusticSoup's events.
+pub const DEBUG: u32 = 10;
+pub const INFO: u32 = 20;
+pub const WARNING: u32 = 30;
+
+/// Name of the Python logger events are forwarded to.
+const LOGGER_NAME: &str = "rusticsoup";
+
+/// Minimum level forwarded to Python; `DISABLED` until `configure_logging` is called so the
+/// hot paths never touch the interpreter by default.
+const DISABLED: u32 = u32::MAX;
+static LEVEL: AtomicU32 = AtomicU32::new(DISABLED);
+
+/// Whether an event at `level` would be forwarded.
+pub fn enabled(level: u32) -> bool {
+    level >= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Forward an event to the `rusticsoup` Python logger. The message is only built when the
+/// level is enabled.
+pub fn emit(level: u32, message: impl FnOnce() -> String) {
+    if !enabled(level) {
+        return;
+    }
+    Python::with_gil(|py| {
+        let result = py
+            .import_bound("logging")
+            .and_then(|logging| logging.call_method1("getLogger", (LOGGER_NAME,)))
+            .and_then(|logger| logger.call_method1("log", (level, message())));
+        // A failing handler must not turn a successful parse into an error
+        if let Err(err) = result {
+            err.print(py);
+        }
+    });
+}
+
+/// Accepts a level as a Python `logging` constant or its name.
+#[derive(FromPyObject)]
+pub enum LevelArg {
+    Number(u32),
+    Name(String),
+}
+
+impl LevelArg {
+    fn resolve(self) -> PyResult<u32> {
+        match self {
+            LevelArg::Number(level) => Ok(level),
+            LevelArg::Name(name) => match name.to_ascii_uppercase().as_str() {
+                "DEBUG" => Ok(DEBUG),
+                "INFO" => Ok(INFO),
+                "WARNING" | "WARN" => Ok(WARNING),
+                "ERROR" => Ok(40),
+                "CRITICAL" => Ok(50),
+                _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Invalid log level: {}. Use 'DEBUG', 'INFO', 'WARNING', 'ERROR' or 'CRITICAL'", name)
+                )),
+            },
+        }
+    }
+}
+
+/// Forward parse, selector and extraction events at `level` and above to the `rusticsoup`
+/// logger of Python's `logging` module. `None` turns forwarding off again.
+#[pyfunction]
+#[pyo3(signature = (level=Some(LevelArg::Name("DEBUG".to_string()))))]
+pub fn configure_logging(level: Option<LevelArg>) -> PyResult<()> {
+    let level = match level {
+        Some(level) => level.resolve()?,
+        None => DISABLED,
+    };
+    LEVEL.store(level, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,407 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use ego_tree::NodeRef;
+use html5ever::{namespace_url, ns, QualName};
+use scraper::{ElementRef, Html, Node};
+
+use crate::text;
+
+/// Elements that never have content or an end tag
+pub const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text the parser does not entity-decode; it is written back verbatim
+const RAW_TEXT_ELEMENTS: [&str; 7] = ["script", "style", "xmp", "iframe", "noembed", "noframes", "plaintext"];
+
+/// Elements whose whitespace is significant, along with everything inside them
+const PRESERVE_WHITESPACE: [&str; 4] = ["pre", "textarea", "listing", "plaintext"];
+
+/// Elements that start a new line box, so whitespace-only text next to them never renders
+const BLOCK_ELEMENTS: [&str; 53] = [
+    "address", "article", "aside", "base", "blockquote", "body", "caption", "col", "colgroup", "dd", "details",
+    "dialog", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4",
+    "h5", "h6", "head", "header", "hgroup", "hr", "html", "li", "link", "main", "meta", "nav", "ol", "optgroup",
+    "option", "p", "pre", "section", "select", "summary", "table", "tbody", "td", "tfoot", "th", "thead", "title",
+    "tr", "ul",
+];
+
+pub fn is_void(name: &str) -> bool {
+    VOID_ELEMENTS.contains(&name)
+}
+
+/// Escape text content for output between tags
+pub fn escape_text(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Escape an attribute value for output inside double quotes
+pub fn escape_attr(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Minify a document for storage: comments are dropped, whitespace runs collapse to one space
+/// and whitespace-only text between block elements is removed, and attribute quotes are
+/// omitted where HTML allows. `pre`, `textarea`, `script` and `style` content is kept exactly.
+/// Optional end tags are kept, so the output parses back to the same tree.
+#[pyfunction]
+pub fn minify(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut out = String::with_capacity(html.len() / 2);
+    for child in document.tree.root().children() {
+        minify_node(child, false, &mut out);
+    }
+    out
+}
+
+fn minify_node(node: NodeRef<'_, Node>, preserve: bool, out: &mut String) {
+    match node.value() {
+        Node::Doctype(doctype) => {
+            out.push_str("<!DOCTYPE ");
+            out.push_str(&doctype.name);
+            out.push('>');
+        }
+        Node::Text(text) => {
+            let parent = node.parent().and_then(ElementRef::wrap).map(|p| p.value().name());
+            if parent.is_some_and(|p| RAW_TEXT_ELEMENTS.contains(&p)) {
+                out.push_str(text);
+            } else if preserve {
+                escape_text(text, out);
+            } else {
+                minify_text(node, text, out);
+            }
+        }
+        Node::Element(elem) => {
+            let name = elem.name();
+            out.push('<');
+            out.push_str(name);
+            for (attr, value) in elem.attrs() {
+                out.push(' ');
+                out.push_str(attr);
+                write_minified_attr(value, out);
+            }
+            out.push('>');
+            if is_void(name) {
+                return;
+            }
+            let preserve = preserve || PRESERVE_WHITESPACE.contains(&name);
+            // The parser drops one newline right after <pre> and <textarea>; keep a leading one
+            if matches!(name, "pre" | "textarea" | "listing") {
+                if let Some(Node::Text(text)) = node.first_child().map(|c| c.value()) {
+                    if text.starts_with('\n') {
+                        out.push('\n');
+                    }
+                }
+            }
+            for child in node.children() {
+                minify_node(child, preserve, out);
+            }
+            out.push_str("</");
+            out.push_str(name);
+            out.push('>');
+        }
+        _ => {}
+    }
+}
+
+/// Collapse a text node's whitespace, trimming it next to block boundaries
+fn minify_text(node: NodeRef<'_, Node>, text: &str, out: &mut String) {
+    // Nothing in <head> renders, so whitespace between its elements never matters
+    let in_head = node.parent().and_then(ElementRef::wrap).is_some_and(|p| p.value().name() == "head");
+    if in_head && text.chars().all(|c| c.is_ascii_whitespace()) {
+        return;
+    }
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_space {
+                collapsed.push(' ');
+            }
+            in_space = true;
+        } else {
+            collapsed.push(c);
+            in_space = false;
+        }
+    }
+    let mut collapsed = collapsed.as_str();
+    if boundary(node, |n| n.prev_sibling()) {
+        collapsed = collapsed.trim_start_matches(' ');
+    }
+    if boundary(node, |n| n.next_sibling()) {
+        collapsed = collapsed.trim_end_matches(' ');
+    }
+    escape_text(collapsed, out);
+}
+
+/// Whether the nearest rendered neighbour in one direction is a block boundary: a block
+/// element, or the edge of a block parent. Comments are skipped since they are dropped.
+fn boundary<'a>(node: NodeRef<'a, Node>, step: impl Fn(NodeRef<'a, Node>) -> Option<NodeRef<'a, Node>>) -> bool {
+    let mut current = step(node);
+    while let Some(sibling) = current {
+        match sibling.value() {
+            Node::Comment(_) => current = step(sibling),
+            Node::Element(elem) => return BLOCK_ELEMENTS.contains(&elem.name()),
+            _ => return false,
+        }
+    }
+    match node.parent().map(|p| p.value()) {
+        Some(Node::Element(parent)) => BLOCK_ELEMENTS.contains(&parent.name()),
+        _ => true,
+    }
+}
+
+/// `=value` without quotes when HTML allows it, quoted otherwise; nothing for an empty value
+fn write_minified_attr(value: &str, out: &mut String) {
+    if value.is_empty() {
+        return;
+    }
+    out.push('=');
+    let needs_quotes = value.contains('&')
+        || value.chars().any(|c| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'));
+    if needs_quotes {
+        out.push('"');
+        escape_attr(value, out);
+        out.push('"');
+    } else {
+        out.push_str(value);
+    }
+}
+
+/// How attribute values are quoted
+#[derive(Clone, Copy, PartialEq)]
+enum Quote {
+    Double,
+    Single,
+    /// Unquoted where HTML allows it, as `minify` writes them
+    Minimal,
+}
+
+/// Output style for `html()` when any formatting option is given. The default writes what
+/// html5ever's serializer does.
+#[derive(Clone, Copy)]
+pub struct SerializeOptions {
+    /// Spaces per nesting level; each element, comment and non-blank text on its own line
+    indent: Option<usize>,
+    quote: Quote,
+    /// Write every non-ASCII character as a numeric character reference
+    ascii: bool,
+    /// `<br />` rather than `<br>`
+    xhtml: bool,
+}
+
+impl SerializeOptions {
+    /// Build from the Python-facing arguments; `None` when none is given
+    pub fn from_args(
+        indent: Option<usize>,
+        quote: Option<&str>,
+        entities: Option<&str>,
+        void: Option<&str>,
+    ) -> PyResult<Option<Self>> {
+        if indent.is_none() && quote.is_none() && entities.is_none() && void.is_none() {
+            return Ok(None);
+        }
+        let quote = match quote.unwrap_or("double") {
+            "double" => Quote::Double,
+            "single" => Quote::Single,
+            "minimal" => Quote::Minimal,
+            other => return Err(invalid("quote", other, "'double', 'single' or 'minimal'")),
+        };
+        let ascii = match entities.unwrap_or("minimal") {
+            "minimal" => false,
+            "ascii" => true,
+            other => return Err(invalid("entities", other, "'minimal' or 'ascii'")),
+        };
+        let xhtml = match void.unwrap_or("html") {
+            "html" => false,
+            "xhtml" => true,
+            other => return Err(invalid("void", other, "'html' or 'xhtml'")),
+        };
+        Ok(Some(SerializeOptions { indent, quote, ascii, xhtml }))
+    }
+
+    /// `node` and its subtree
+    pub fn write(&self, node: NodeRef<'_, Node>) -> String {
+        let mut out = String::new();
+        self.write_node(node, 0, false, &mut out);
+        out
+    }
+
+    /// The children of `node`, for documents and fragments
+    pub fn write_children(&self, node: NodeRef<'_, Node>) -> String {
+        let mut out = String::new();
+        for child in node.children() {
+            self.write_node(child, 0, false, &mut out);
+        }
+        out
+    }
+
+    /// Start a new indented line, unless pretty-printing is off or inside preserved content
+    fn line(&self, depth: usize, preserve: bool, out: &mut String) {
+        if let (Some(width), false) = (self.indent, preserve) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.extend(std::iter::repeat_n(' ', depth * width));
+        }
+    }
+
+    fn escape(&self, text: &str, quote: Option<char>, out: &mut String) {
+        for c in text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' | '>' if quote.is_none() => out.push_str(if c == '<' { "&lt;" } else { "&gt;" }),
+                '"' if quote == Some('"') => out.push_str("&quot;"),
+                '\'' if quote == Some('\'') => out.push_str("&#39;"),
+                '\u{a0}' => out.push_str("&nbsp;"),
+                c if self.ascii && !c.is_ascii() => out.push_str(&format!("&#{};", c as u32)),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn write_attr(&self, name: &QualName, value: &str, out: &mut String) {
+        out.push(' ');
+        match (&name.ns, &*name.local) {
+            (&ns!(xml), local) => out.push_str(&format!("xml:{}", local)),
+            (&ns!(xmlns), "xmlns") => out.push_str("xmlns"),
+            (&ns!(xmlns), local) => out.push_str(&format!("xmlns:{}", local)),
+            (&ns!(xlink), local) => out.push_str(&format!("xlink:{}", local)),
+            (_, local) => out.push_str(local),
+        }
+        let quote = match self.quote {
+            Quote::Single => '\'',
+            Quote::Minimal if !self.ascii || value.is_ascii() => return write_minified_attr(value, out),
+            _ => '"',
+        };
+        out.push('=');
+        out.push(quote);
+        self.escape(value, Some(quote), out);
+        out.push(quote);
+    }
+
+    fn write_node(&self, node: NodeRef<'_, Node>, depth: usize, preserve: bool, out: &mut String) {
+        match node.value() {
+            Node::Doctype(doctype) => {
+                self.line(depth, preserve, out);
+                out.push_str("<!DOCTYPE ");
+                out.push_str(&doctype.name);
+                out.push('>');
+            }
+            Node::Comment(comment) => {
+                self.line(depth, preserve, out);
+                out.push_str("<!--");
+                out.push_str(comment);
+                out.push_str("-->");
+            }
+            Node::ProcessingInstruction(pi) => {
+                self.line(depth, preserve, out);
+                out.push_str(&format!("<?{} {}>", &*pi.target, &*pi.data));
+            }
+            Node::Text(text) => {
+                let parent = node.parent().and_then(ElementRef::wrap).map(|p| p.value().name());
+                if parent.is_some_and(|p| RAW_TEXT_ELEMENTS.contains(&p)) {
+                    out.push_str(text);
+                } else if preserve || self.indent.is_none() {
+                    self.escape(text, None, out);
+                } else if !text.trim().is_empty() {
+                    self.line(depth, preserve, out);
+                    self.escape(text.trim(), None, out);
+                }
+            }
+            Node::Element(elem) => {
+                let name = elem.name();
+                self.line(depth, preserve, out);
+                out.push('<');
+                out.push_str(name);
+                for (attr, value) in elem.attrs.iter() {
+                    self.write_attr(attr, value, out);
+                }
+                if is_void(name) && elem.name.ns == ns!(html) {
+                    out.push_str(if self.xhtml { " />" } else { ">" });
+                    return;
+                }
+                out.push('>');
+                // Whitespace inside these is content, so it is written as it is
+                let inner_preserve = preserve || PRESERVE_WHITESPACE.contains(&name) || RAW_TEXT_ELEMENTS.contains(&name);
+                let before = out.len();
+                for child in node.children() {
+                    self.write_node(child, depth + 1, inner_preserve, out);
+                }
+                if out.len() > before {
+                    self.line(depth, inner_preserve, out);
+                }
+                out.push_str("</");
+                out.push_str(name);
+                out.push('>');
+            }
+            _ => {}
+        }
+    }
+}
+
+fn invalid(option: &str, value: &str, choices: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Invalid {}: '{}'. Use {}", option, value, choices))
+}
+
+/// A nested dict for `elem`: `tag`, `attrs` and `children`, where children are nested dicts
+/// for elements and plain strings for text. Whitespace-only text is dropped unless
+/// `keep_whitespace`. Elements at `max_depth` (the given element is depth 0) get `text`,
+/// their whitespace-collapsed descendant text, instead of `children`.
+pub fn element_to_dict<'py>(
+    py: Python<'py>,
+    elem: ElementRef<'_>,
+    max_depth: Option<usize>,
+    keep_whitespace: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    element_dict_at(py, elem, 0, max_depth, keep_whitespace)
+}
+
+fn element_dict_at<'py>(
+    py: Python<'py>,
+    elem: ElementRef<'_>,
+    depth: usize,
+    max_depth: Option<usize>,
+    keep_whitespace: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("tag", elem.value().name())?;
+    let attrs = PyDict::new_bound(py);
+    for (name, value) in elem.value().attrs() {
+        attrs.set_item(name, value)?;
+    }
+    dict.set_item("attrs", attrs)?;
+
+    if max_depth.is_some_and(|max| depth >= max) {
+        let text = text::element_text(elem, true);
+        dict.set_item("text", text)?;
+        return Ok(dict);
+    }
+    let children = PyList::empty_bound(py);
+    for child in elem.children() {
+        match child.value() {
+            Node::Element(_) => {
+                children.append(element_dict_at(py, ElementRef::wrap(child).unwrap(), depth + 1, max_depth, keep_whitespace)?)?;
+            }
+            Node::Text(text) if keep_whitespace || !text.trim().is_empty() => children.append(&**text)?,
+            _ => {}
+        }
+    }
+    dict.set_item("children", children)?;
+    Ok(dict)
+}
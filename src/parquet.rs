@@ -0,0 +1,421 @@
+//! A small Parquet writer for extraction results: one row group of uncompressed, PLAIN
+//! encoded pages with every column optional, and the footer in Thrift's compact protocol.
+//! It covers the column types `write_parquet` infers and nothing else.
+
+use pyo3::prelude::*;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::sinks::create_file;
+
+/// Values per data page are cut at the first row boundary past this many bytes
+const PAGE_SIZE: usize = 1 << 20;
+
+// Parquet enums, as numbered in parquet.thrift
+const TYPE_BOOLEAN: i32 = 0;
+const TYPE_INT64: i32 = 2;
+const TYPE_DOUBLE: i32 = 5;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REPETITION_OPTIONAL: i32 = 1;
+const REPETITION_REPEATED: i32 = 2;
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_LIST: i32 = 3;
+const CONVERTED_DECIMAL: i32 = 5;
+const CONVERTED_TIMESTAMP_MICROS: i32 = 10;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+/// The values of one column, `None` for nulls
+pub enum ColumnData {
+    Strings(Vec<Option<String>>),
+    Booleans(Vec<Option<bool>>),
+    Int64(Vec<Option<i64>>),
+    Doubles(Vec<Option<f64>>),
+    /// Unscaled values of a DECIMAL(18, scale)
+    Decimals { unscaled: Vec<Option<i64>>, scale: u32 },
+    /// Microseconds since the epoch; `utc` for instants, false for naive local times
+    Timestamps { micros: Vec<Option<i64>>, utc: bool },
+    /// A list of strings per row
+    Lists(Vec<Option<Vec<Option<String>>>>),
+}
+
+impl ColumnData {
+    fn physical_type(&self) -> i32 {
+        match self {
+            ColumnData::Strings(_) | ColumnData::Lists(_) => TYPE_BYTE_ARRAY,
+            ColumnData::Booleans(_) => TYPE_BOOLEAN,
+            ColumnData::Int64(_) | ColumnData::Decimals { .. } | ColumnData::Timestamps { .. } => TYPE_INT64,
+            ColumnData::Doubles(_) => TYPE_DOUBLE,
+        }
+    }
+
+    /// Repetition levels are only written for lists, where 1 continues the row's list
+    fn max_repetition(&self) -> u8 {
+        match self {
+            ColumnData::Lists(_) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Append row `row`'s levels and values to `page`. A present value's definition level is
+    /// 1; in a list column an empty list is 1, a null item 2 and a present item 3.
+    fn encode_row(&self, row: usize, page: &mut Page) {
+        fn plain<T>(page: &mut Page, value: &Option<T>, encode: impl FnOnce(&mut Vec<u8>, &T)) {
+            match value {
+                Some(value) => {
+                    page.definitions.push(1);
+                    encode(&mut page.values, value);
+                }
+                None => page.definitions.push(0),
+            }
+        }
+        let int64 = |values: &mut Vec<u8>, value: &i64| values.extend_from_slice(&value.to_le_bytes());
+        match self {
+            ColumnData::Strings(values) => plain(page, &values[row], |out, value| push_byte_array(out, value)),
+            ColumnData::Booleans(values) => match values[row] {
+                Some(value) => {
+                    page.definitions.push(1);
+                    page.booleans.push(value);
+                }
+                None => page.definitions.push(0),
+            },
+            ColumnData::Int64(values) => plain(page, &values[row], int64),
+            ColumnData::Decimals { unscaled, .. } => plain(page, &unscaled[row], int64),
+            ColumnData::Timestamps { micros, .. } => plain(page, &micros[row], int64),
+            ColumnData::Doubles(values) => plain(page, &values[row], |out, value| out.extend_from_slice(&value.to_le_bytes())),
+            ColumnData::Lists(lists) => match &lists[row] {
+                None => {
+                    page.repetitions.push(0);
+                    page.definitions.push(0);
+                }
+                Some(items) if items.is_empty() => {
+                    page.repetitions.push(0);
+                    page.definitions.push(1);
+                }
+                Some(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        page.repetitions.push(u8::from(index > 0));
+                        match item {
+                            Some(item) => {
+                                page.definitions.push(3);
+                                push_byte_array(&mut page.values, item);
+                            }
+                            None => page.definitions.push(2),
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    /// The column's schema elements: one leaf, or the three-level LIST group for lists
+    fn write_schema(&self, name: &str, out: &mut Compact) {
+        let leaf = |out: &mut Compact, name: &str| {
+            out.begin_element();
+            out.i32(1, self.physical_type());
+            out.i32(3, REPETITION_OPTIONAL);
+            out.binary(4, name.as_bytes());
+            match self {
+                ColumnData::Strings(_) | ColumnData::Lists(_) => {
+                    out.i32(6, CONVERTED_UTF8);
+                    out.begin_struct(10);
+                    out.empty_struct(1);
+                    out.end_struct();
+                }
+                ColumnData::Decimals { scale, .. } => {
+                    out.i32(6, CONVERTED_DECIMAL);
+                    out.i32(7, *scale as i32);
+                    out.i32(8, 18);
+                    out.begin_struct(10);
+                    out.begin_struct(5);
+                    out.i32(1, *scale as i32);
+                    out.i32(2, 18);
+                    out.end_struct();
+                    out.end_struct();
+                }
+                ColumnData::Timestamps { utc, .. } => {
+                    if *utc {
+                        out.i32(6, CONVERTED_TIMESTAMP_MICROS);
+                    }
+                    out.begin_struct(10);
+                    out.begin_struct(8);
+                    out.bool(1, *utc);
+                    out.begin_struct(2);
+                    out.empty_struct(2);
+                    out.end_struct();
+                    out.end_struct();
+                    out.end_struct();
+                }
+                ColumnData::Booleans(_) | ColumnData::Int64(_) | ColumnData::Doubles(_) => {}
+            }
+            out.end_struct();
+        };
+        if let ColumnData::Lists(_) = self {
+            out.begin_element();
+            out.i32(3, REPETITION_OPTIONAL);
+            out.binary(4, name.as_bytes());
+            out.i32(5, 1);
+            out.i32(6, CONVERTED_LIST);
+            out.begin_struct(10);
+            out.empty_struct(3);
+            out.end_struct();
+            out.end_struct();
+            out.begin_element();
+            out.i32(3, REPETITION_REPEATED);
+            out.binary(4, b"list");
+            out.i32(5, 1);
+            out.end_struct();
+            leaf(out, "element");
+        } else {
+            leaf(out, name);
+        }
+    }
+
+    fn path(&self, name: &str) -> Vec<String> {
+        match self {
+            ColumnData::Lists(_) => vec![name.to_string(), "list".to_string(), "element".to_string()],
+            _ => vec![name.to_string()],
+        }
+    }
+}
+
+fn push_byte_array(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// One data page being filled
+#[derive(Default)]
+struct Page {
+    repetitions: Vec<u8>,
+    definitions: Vec<u8>,
+    values: Vec<u8>,
+    booleans: Vec<bool>,
+}
+
+impl Page {
+    fn size(&self) -> usize {
+        self.values.len() + self.booleans.len() / 8
+    }
+
+    /// The page body: repetition levels (list columns only), definition levels, then values
+    fn body(self, max_repetition: u8) -> (usize, Vec<u8>) {
+        let mut body = Vec::new();
+        if max_repetition > 0 {
+            push_levels(&mut body, &self.repetitions);
+        }
+        push_levels(&mut body, &self.definitions);
+        body.extend_from_slice(&self.values);
+        for chunk in self.booleans.chunks(8) {
+            body.push(chunk.iter().enumerate().fold(0u8, |byte, (bit, &value)| byte | (u8::from(value) << bit)));
+        }
+        (self.definitions.len(), body)
+    }
+}
+
+/// Levels in the RLE/bit-packing hybrid, as RLE runs only, after their 4-byte length. Levels
+/// here are at most 3, so each run's value takes one byte.
+fn push_levels(out: &mut Vec<u8>, levels: &[u8]) {
+    let mut encoded = Vec::new();
+    let mut start = 0;
+    while start < levels.len() {
+        let run = levels[start..].iter().take_while(|&&level| level == levels[start]).count();
+        push_varint(&mut encoded, (run as u64) << 1);
+        encoded.push(levels[start]);
+        start += run;
+    }
+    out.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+    out.extend_from_slice(&encoded);
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Thrift's compact protocol, for the page headers and the footer
+#[derive(Default)]
+struct Compact {
+    out: Vec<u8>,
+    /// The last field id written in each open struct
+    last: Vec<i16>,
+    field: i16,
+}
+
+impl Compact {
+    const BOOLEAN_TRUE: u8 = 1;
+    const BOOLEAN_FALSE: u8 = 2;
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field_header(&mut self, id: i16, kind: u8) {
+        let delta = id - self.field;
+        if (1..=15).contains(&delta) {
+            self.out.push(((delta as u8) << 4) | kind);
+        } else {
+            self.out.push(kind);
+            push_varint(&mut self.out, zigzag(id as i64));
+        }
+        self.field = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field_header(id, Self::I32);
+        push_varint(&mut self.out, zigzag(value as i64));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field_header(id, Self::I64);
+        push_varint(&mut self.out, zigzag(value));
+    }
+
+    fn bool(&mut self, id: i16, value: bool) {
+        self.field_header(id, if value { Self::BOOLEAN_TRUE } else { Self::BOOLEAN_FALSE });
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field_header(id, Self::BINARY);
+        push_varint(&mut self.out, value.len() as u64);
+        self.out.extend_from_slice(value);
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field_header(id, Self::LIST);
+        if len < 15 {
+            self.out.push(((len as u8) << 4) | kind);
+        } else {
+            self.out.push(0xf0 | kind);
+            push_varint(&mut self.out, len as u64);
+        }
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field_header(id, Self::STRUCT);
+        self.begin_element();
+    }
+
+    /// Open a struct that is a list element, which has no field header
+    fn begin_element(&mut self) {
+        self.last.push(self.field);
+        self.field = 0;
+    }
+
+    fn end_struct(&mut self) {
+        self.out.push(0);
+        self.field = self.last.pop().unwrap_or_default();
+    }
+
+    fn empty_struct(&mut self, id: i16) {
+        self.begin_struct(id);
+        self.end_struct();
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Where one column chunk ended up in the file
+struct Chunk {
+    offset: u64,
+    size: u64,
+    values: usize,
+}
+
+/// Write `columns`, each holding `rows` values, to a new Parquet file at `path`
+pub fn write(path: &Path, columns: &[(String, ColumnData)], rows: usize) -> PyResult<()> {
+    let mut out = BufWriter::new(create_file(path)?);
+    out.write_all(b"PAR1")?;
+    let mut offset = 4u64;
+    let mut chunks = Vec::with_capacity(columns.len());
+    for (_, column) in columns {
+        let mut chunk = Chunk { offset, size: 0, values: 0 };
+        let mut row = 0;
+        // Every column chunk has at least one page, even with no rows
+        loop {
+            let mut page = Page::default();
+            while row < rows && page.size() < PAGE_SIZE {
+                column.encode_row(row, &mut page);
+                row += 1;
+            }
+            let (values, body) = page.body(column.max_repetition());
+            let mut header = Compact::default();
+            header.i32(1, PAGE_DATA);
+            header.i32(2, body.len() as i32);
+            header.i32(3, body.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, values as i32);
+            header.i32(2, ENCODING_PLAIN);
+            header.i32(3, ENCODING_RLE);
+            header.i32(4, ENCODING_RLE);
+            header.end_struct();
+            header.out.push(0);
+            out.write_all(&header.out)?;
+            out.write_all(&body)?;
+            chunk.size += (header.out.len() + body.len()) as u64;
+            chunk.values += values;
+            if row >= rows {
+                break;
+            }
+        }
+        offset += chunk.size;
+        chunks.push(chunk);
+    }
+
+    let mut footer = Compact::default();
+    footer.i32(1, 1);
+    let elements: usize = columns.iter().map(|(_, column)| if let ColumnData::Lists(_) = column { 3 } else { 1 }).sum();
+    footer.list(2, Compact::STRUCT, elements + 1);
+    footer.begin_element();
+    footer.binary(4, b"schema");
+    footer.i32(5, columns.len() as i32);
+    footer.end_struct();
+    for (name, column) in columns {
+        column.write_schema(name, &mut footer);
+    }
+    footer.i64(3, rows as i64);
+    footer.list(4, Compact::STRUCT, 1);
+    footer.begin_element();
+    footer.list(1, Compact::STRUCT, columns.len());
+    for ((name, column), chunk) in columns.iter().zip(&chunks) {
+        footer.begin_element();
+        footer.i64(2, chunk.offset as i64);
+        footer.begin_struct(3);
+        footer.i32(1, column.physical_type());
+        footer.list(2, Compact::I32, 2);
+        push_varint(&mut footer.out, zigzag(ENCODING_PLAIN as i64));
+        push_varint(&mut footer.out, zigzag(ENCODING_RLE as i64));
+        let path = column.path(name);
+        footer.list(3, Compact::BINARY, path.len());
+        for part in &path {
+            push_varint(&mut footer.out, part.len() as u64);
+            footer.out.extend_from_slice(part.as_bytes());
+        }
+        footer.i32(4, CODEC_UNCOMPRESSED);
+        footer.i64(5, chunk.values as i64);
+        footer.i64(6, chunk.size as i64);
+        footer.i64(7, chunk.size as i64);
+        footer.i64(9, chunk.offset as i64);
+        footer.end_struct();
+        footer.end_struct();
+    }
+    footer.i64(2, chunks.iter().map(|chunk| chunk.size as i64).sum());
+    footer.i64(3, rows as i64);
+    footer.end_struct();
+    footer.binary(6, format!("rusticsoup version {}", env!("CARGO_PKG_VERSION")).as_bytes());
+    footer.out.push(0);
+
+    out.write_all(&footer.out)?;
+    out.write_all(&(footer.out.len() as u32).to_le_bytes())?;
+    out.write_all(b"PAR1")?;
+    out.flush()?;
+    Ok(())
+}
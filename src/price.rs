@@ -0,0 +1,167 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Currency markers and their ISO 4217 codes, longest first so `US$` wins over `$`
+const SYMBOLS: [(&str, &str); 27] = [
+    ("US$", "USD"), ("CA$", "CAD"), ("AU$", "AUD"), ("NZ$", "NZD"), ("HK$", "HKD"), ("CN¥", "CNY"),
+    ("C$", "CAD"), ("A$", "AUD"), ("R$", "BRL"), ("S$", "SGD"), ("Rs.", "INR"), ("Rs", "INR"),
+    ("zł", "PLN"), ("Kč", "CZK"), ("Fr.", "CHF"), ("€", "EUR"), ("£", "GBP"), ("₹", "INR"), ("₽", "RUB"),
+    ("₩", "KRW"), ("₺", "TRY"), ("₪", "ILS"), ("₫", "VND"), ("฿", "THB"), ("₱", "PHP"), ("$", "USD"),
+    ("¥", "JPY"),
+];
+
+/// Symbols shared by several currencies; a caller's `currency` hint overrides them
+const AMBIGUOUS: [&str; 2] = ["$", "¥"];
+
+/// ISO 4217 codes recognized when written out next to the amount
+const CODES: [&str; 32] = [
+    "USD", "EUR", "GBP", "JPY", "CNY", "CHF", "CAD", "AUD", "NZD", "HKD", "SGD", "SEK", "NOK", "DKK", "PLN", "CZK",
+    "HUF", "RON", "BGN", "RUB", "UAH", "TRY", "INR", "BRL", "MXN", "ZAR", "KRW", "ILS", "THB", "PHP", "VND", "AED",
+];
+
+/// A parsed price: the amount as a plain decimal string (`"1234.56"`) and the currency
+pub struct Price {
+    pub amount: String,
+    pub currency: Option<String>,
+}
+
+/// Parse the first amount in `text` with its currency. `currency` is used when the text names
+/// none or only an ambiguous symbol (`$`, `¥`); `decimal_separator` settles amounts like
+/// `1.234` that read either way. Without it a lone separator followed by exactly three digits
+/// groups thousands, as on most price tags.
+pub fn parse(text: &str, currency: Option<&str>, decimal_separator: Option<char>) -> Option<Price> {
+    let (start, number) = number_token(text)?;
+    let amount = normalize_amount(number, decimal_separator);
+    let negative = strip_currency_end(&text[..start]).ends_with(['-', '−']);
+    let amount = if negative { format!("-{}", amount) } else { amount };
+    Some(Price { amount, currency: detect_currency(text, currency) })
+}
+
+/// `text` without the currency symbols, codes and spaces it ends with, so a sign written
+/// before them (`-$5`, `- € 5`, `-USD 5`) is found
+fn strip_currency_end(mut text: &str) -> &str {
+    loop {
+        text = text.trim_end();
+        let symbol = SYMBOLS.iter().find(|(symbol, _)| text.ends_with(symbol)).map(|(symbol, _)| *symbol);
+        let code = CODES.iter().find(|code| {
+            text.ends_with(*code) && !text[..text.len() - code.len()].ends_with(char::is_alphabetic)
+        });
+        match symbol.or(code.copied()) {
+            Some(marker) => text = &text[..text.len() - marker.len()],
+            None => return text,
+        }
+    }
+}
+
+/// The first digit run, with the separators inside it, and its byte offset. A separator right
+/// before the first digit starts the run (`$.99`), unless it follows a word (`No.5`). Spaces
+/// count as separators only before a three-digit group (`1 234,56`).
+fn number_token(text: &str) -> Option<(usize, &str)> {
+    let digit = text.find(|c: char| c.is_ascii_digit())?;
+    let mut before = text[..digit].chars().rev();
+    let leading_separator = matches!(before.next(), Some('.' | ',')) && !before.next().is_some_and(char::is_alphanumeric);
+    let start = if leading_separator { digit - 1 } else { digit };
+    let bytes: Vec<(usize, char)> = text[start..].char_indices().collect();
+    let mut end = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let (offset, c) = bytes[i];
+        if c.is_ascii_digit() {
+            end = offset + c.len_utf8();
+        } else if matches!(c, '.' | ',' | '\'' | '’') {
+            if !bytes.get(i + 1).is_some_and(|(_, next)| next.is_ascii_digit()) {
+                break;
+            }
+        } else if matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '\u{2009}') {
+            let group = bytes[i + 1..].iter().take_while(|(_, d)| d.is_ascii_digit()).count();
+            if group != 3 {
+                break;
+            }
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    Some((start, &text[start..start + end]))
+}
+
+/// `"1.234,56"` to `"1234.56"`
+fn normalize_amount(number: &str, decimal_separator: Option<char>) -> String {
+    let last_dot = number.rfind('.');
+    let last_comma = number.rfind(',');
+    let decimal = match decimal_separator {
+        Some(sep) => number.rfind(sep),
+        // With both, whichever comes last separates the decimals
+        None if last_dot.is_some() && last_comma.is_some() => last_dot.max(last_comma),
+        None => last_dot.or(last_comma).filter(|&pos| {
+            let sep = &number[pos..pos + 1];
+            let once = number.matches(sep).count() == 1;
+            let fraction = number.len() - pos - 1;
+            let integer = &number[..pos];
+            once && (fraction != 3 || integer.chars().all(|c| c == '0'))
+        }),
+    };
+    let (integer, fraction) = match decimal {
+        Some(pos) => (&number[..pos], &number[pos + 1..]),
+        None => (number, ""),
+    };
+    let mut amount: String = integer.chars().filter(char::is_ascii_digit).collect();
+    if amount.is_empty() {
+        amount.push('0');
+    }
+    if !fraction.is_empty() {
+        amount.push('.');
+        amount.extend(fraction.chars().filter(char::is_ascii_digit));
+    }
+    amount
+}
+
+fn detect_currency(text: &str, hint: Option<&str>) -> Option<String> {
+    let hint = hint.map(|code| code.trim().to_ascii_uppercase());
+    let code = text
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .find(|word| CODES.contains(word))
+        .map(str::to_string);
+    if code.is_some() {
+        return code;
+    }
+    match SYMBOLS.iter().find(|(symbol, _)| text.contains(symbol)) {
+        Some((symbol, _)) if AMBIGUOUS.contains(symbol) && hint.is_some() => hint,
+        Some((_, code)) => Some(code.to_string()),
+        None => hint,
+    }
+}
+
+/// `{"amount": Decimal, "currency": str | None}` for a parsed price
+pub fn to_py(py: Python, price: Price) -> PyResult<PyObject> {
+    let decimal = py.import_bound("decimal")?.getattr("Decimal")?;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("amount", decimal.call1((price.amount,))?)?;
+    dict.set_item("currency", price.currency)?;
+    Ok(dict.into())
+}
+
+/// Parse a price tag such as `"1.234,56 €"`, `"US$ 1,299.00"` or `"CHF 1'250.–"` into
+/// `{"amount": Decimal, "currency": "EUR"}`, or `None` when it holds no amount. The currency is
+/// an ISO 4217 code from a written code or symbol; `currency` fills it in when the text has
+/// none or only an ambiguous `$`/`¥`. When both `.` and `,` appear the last one separates
+/// decimals; a single separator followed by exactly three digits groups thousands unless
+/// `decimal_separator` says otherwise.
+#[pyfunction]
+#[pyo3(signature = (text, currency=None, decimal_separator=None))]
+pub fn parse_price(py: Python, text: &str, currency: Option<&str>, decimal_separator: Option<&str>) -> PyResult<PyObject> {
+    let decimal_separator = match decimal_separator {
+        None => None,
+        Some(".") => Some('.'),
+        Some(",") => Some(','),
+        Some(other) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid decimal_separator: {}. Use '.' or ','", other)
+            ))
+        }
+    };
+    match parse(text, currency, decimal_separator) {
+        Some(price) => to_py(py, price),
+        None => Ok(py.None()),
+    }
+}
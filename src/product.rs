@@ -0,0 +1,334 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use scraper::{Html, Selector};
+use once_cell::sync::Lazy;
+use url::Url;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::links;
+use crate::price;
+use crate::structured;
+
+static META_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[content]").unwrap());
+static H1_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("h1").unwrap());
+static PRICE_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+    Selector::parse("[itemprop=price], [data-price], [class*=price], [id*=price]").unwrap()
+});
+static STOCK_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+    Selector::parse("[class*=stock], [class*=availability], [id*=stock], [id*=availability]").unwrap()
+});
+static IMAGE_SELECTOR: Lazy<Selector> = Lazy::new(|| {
+    Selector::parse("[class*=product] img[src], [id*=product] img[src], img[id*=product][src]").unwrap()
+});
+
+/// schema.org `ItemAvailability` values, and the shelf phrases that mean them
+const AVAILABILITY: [(&str, &[&str]); 7] = [
+    ("OutOfStock", &["out of stock", "sold out", "unavailable", "currently unavailable"]),
+    ("PreOrder", &["pre-order", "preorder", "pre order"]),
+    ("BackOrder", &["backorder", "back order", "back-order"]),
+    ("Discontinued", &["discontinued"]),
+    ("LimitedAvailability", &["only a few left", "limited stock", "low stock"]),
+    ("InStoreOnly", &["in store only", "in-store only"]),
+    ("InStock", &["in stock", "available", "ships today", "add to cart", "add to basket"]),
+];
+
+#[derive(Default)]
+struct Product {
+    name: Option<String>,
+    amount: Option<String>,
+    currency: Option<String>,
+    availability: Option<String>,
+    brand: Option<String>,
+    images: Vec<String>,
+    rating: Option<f64>,
+    review_count: Option<u64>,
+    sku: Option<String>,
+    sources: Vec<&'static str>,
+}
+
+impl Product {
+    /// Fill missing fields from a schema.org Product node (JSON-LD or microdata)
+    fn merge_schema(&mut self, node: &Bound<'_, PyAny>, source: &'static str) -> PyResult<()> {
+        let before = self.filled();
+        fill(&mut self.name, text_of(get(node, "name")?))?;
+        fill(&mut self.sku, text_of(get(node, "sku")?))?;
+        if self.brand.is_none() {
+            let brand = first(get(node, "brand")?.or(get(node, "manufacturer")?))?;
+            self.brand = match brand {
+                Some(brand) if brand.is_instance_of::<PyDict>() => text_of(get(&brand, "name")?)?,
+                brand => text_of(brand)?,
+            };
+        }
+        if self.images.is_empty() {
+            for image in values(get(node, "image")?)? {
+                let url = if image.is_instance_of::<PyDict>() {
+                    text_of(get(&image, "url")?.or(get(&image, "contentUrl")?))?
+                } else {
+                    text_of(Some(image))?
+                };
+                self.images.extend(url);
+            }
+        }
+        for offer in values(get(node, "offers")?)? {
+            self.merge_offer(&offer)?;
+        }
+        if let Some(rating) = get(node, "aggregateRating")? {
+            if self.rating.is_none() {
+                self.rating = text_of(get(&rating, "ratingValue")?)?.and_then(|v| v.replace(',', ".").parse().ok());
+            }
+            if self.review_count.is_none() {
+                let count = get(&rating, "reviewCount")?.or(get(&rating, "ratingCount")?);
+                self.review_count = text_of(count)?.and_then(|v| v.parse().ok());
+            }
+        }
+        if self.filled() > before {
+            self.sources.push(source);
+        }
+        Ok(())
+    }
+
+    /// An `Offer` or `AggregateOffer`: price (`lowPrice` for a range), currency, availability
+    fn merge_offer(&mut self, offer: &Bound<'_, PyAny>) -> PyResult<()> {
+        let specification = first(get(offer, "priceSpecification")?)?;
+        let currency = match text_of(get(offer, "priceCurrency")?)? {
+            Some(currency) => Some(currency),
+            None => match &specification {
+                Some(spec) => text_of(get(spec, "priceCurrency")?)?,
+                None => None,
+            },
+        };
+        if self.amount.is_none() {
+            let mut amount = text_of(get(offer, "price")?.or(get(offer, "lowPrice")?))?;
+            if amount.is_none() {
+                if let Some(spec) = &specification {
+                    amount = text_of(get(spec, "price")?)?;
+                }
+            }
+            // Structured prices use `.` for decimals
+            if let Some(parsed) = amount.and_then(|a| price::parse(&a, currency.as_deref(), Some('.'))) {
+                self.amount = Some(parsed.amount);
+                fill(&mut self.currency, Ok(parsed.currency))?;
+            }
+        }
+        fill(&mut self.currency, Ok(currency))?;
+        if self.availability.is_none() {
+            self.availability = text_of(get(offer, "availability")?)?.as_deref().and_then(availability_of);
+        }
+        Ok(())
+    }
+
+    /// Fill missing fields from OpenGraph and product `<meta>` tags
+    fn merge_meta(&mut self, document: &Html) -> PyResult<()> {
+        let before = self.filled();
+        let mut images = Vec::new();
+        for meta in document.select(&META_SELECTOR) {
+            let value = meta.value();
+            let Some(key) = value.attr("property").or_else(|| value.attr("name")) else { continue };
+            let content = value.attr("content").unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            let content = Some(content.to_string());
+            match key.trim().to_ascii_lowercase().as_str() {
+                "og:title" => fill(&mut self.name, Ok(content))?,
+                "product:price:amount" | "og:price:amount" if self.amount.is_none() => {
+                    self.amount = content.and_then(|c| price::parse(&c, None, Some('.'))).map(|p| p.amount);
+                }
+                "product:price:currency" | "og:price:currency" => fill(&mut self.currency, Ok(content))?,
+                "product:availability" | "og:availability" if self.availability.is_none() => {
+                    self.availability = content.as_deref().and_then(availability_of);
+                }
+                "product:brand" | "og:brand" => fill(&mut self.brand, Ok(content))?,
+                "product:retailer_item_id" => fill(&mut self.sku, Ok(content))?,
+                "og:image" | "og:image:url" | "og:image:secure_url" => images.extend(content),
+                _ => {}
+            }
+        }
+        if self.images.is_empty() {
+            self.images = images;
+        }
+        if self.filled() > before {
+            self.sources.push("meta");
+        }
+        Ok(())
+    }
+
+    /// Last resort: the page's `<h1>`, price-looking elements, stock notices and product images
+    fn merge_heuristics(&mut self, document: &Html) {
+        let before = self.filled();
+        if self.name.is_none() {
+            self.name = document.select(&H1_SELECTOR).map(links::collapsed_text).find(|t| !t.is_empty());
+        }
+        if self.amount.is_none() {
+            let found = document.select(&PRICE_SELECTOR).find_map(|elem| {
+                let value = elem.value();
+                let text = value.attr("content").or_else(|| value.attr("data-price")).map(str::to_string)
+                    .unwrap_or_else(|| links::collapsed_text(elem));
+                // Labels like "Price" with no amount, and long blurbs, are not prices
+                if text.chars().count() > 40 {
+                    return None;
+                }
+                price::parse(&text, self.currency.as_deref(), None)
+            });
+            if let Some(found) = found {
+                self.amount = Some(found.amount);
+                if self.currency.is_none() {
+                    self.currency = found.currency;
+                }
+            }
+        }
+        if self.availability.is_none() {
+            self.availability = document.select(&STOCK_SELECTOR).find_map(|elem| {
+                let href = elem.value().attr("href").and_then(availability_of);
+                href.or_else(|| availability_of(&links::collapsed_text(elem)))
+            });
+        }
+        if self.images.is_empty() {
+            self.images = document
+                .select(&IMAGE_SELECTOR)
+                .filter_map(|img| img.value().attr("src"))
+                .map(str::to_string)
+                .take(10)
+                .collect();
+        }
+        if self.filled() > before {
+            self.sources.push("heuristic");
+        }
+    }
+
+    fn filled(&self) -> usize {
+        [
+            self.name.is_some(),
+            self.amount.is_some(),
+            self.currency.is_some(),
+            self.availability.is_some(),
+            self.brand.is_some(),
+            !self.images.is_empty(),
+            self.rating.is_some(),
+            self.review_count.is_some(),
+            self.sku.is_some(),
+        ]
+        .iter()
+        .filter(|&&f| f)
+        .count()
+    }
+}
+
+fn fill(field: &mut Option<String>, value: PyResult<Option<String>>) -> PyResult<()> {
+    if field.is_none() {
+        *field = value?;
+    }
+    Ok(())
+}
+
+fn get<'py>(node: &Bound<'py, PyAny>, key: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+    match node.downcast::<PyDict>() {
+        Ok(dict) => Ok(dict.get_item(key)?.filter(|value| !value.is_none())),
+        Err(_) => Ok(None),
+    }
+}
+
+/// A value or each item of a list value
+fn values<'py>(value: Option<Bound<'py, PyAny>>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    Ok(match value {
+        None => Vec::new(),
+        Some(value) => match value.downcast::<PyList>() {
+            Ok(list) => list.iter().collect(),
+            Err(_) => vec![value],
+        },
+    })
+}
+
+fn first<'py>(value: Option<Bound<'py, PyAny>>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    Ok(values(value)?.into_iter().next())
+}
+
+/// A scalar's text (the first item of a list; `@value` of a value object), trimmed; `None`
+/// when empty or not a scalar
+fn text_of(value: Option<Bound<'_, PyAny>>) -> PyResult<Option<String>> {
+    let Some(value) = first(value)? else { return Ok(None) };
+    if let Some(inner) = get(&value, "@value")? {
+        return text_of(Some(inner));
+    }
+    if value.is_instance_of::<PyDict>() || value.is_instance_of::<pyo3::types::PyBool>() {
+        return Ok(None);
+    }
+    let text = value.str()?.to_string();
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
+/// A schema.org availability name from a URL (`https://schema.org/InStock`), a bare name or
+/// shelf text
+fn availability_of(value: &str) -> Option<String> {
+    let name = value.trim().rsplit(['/', ':']).next().unwrap_or("").replace([' ', '_', '-'], "");
+    if let Some((known, _)) = AVAILABILITY.iter().find(|(known, _)| known.eq_ignore_ascii_case(&name)) {
+        return Some(known.to_string());
+    }
+    let text = value.to_lowercase();
+    // Spelled-out names in meta tags (`in stock`, `out of stock`) and shelf notices
+    AVAILABILITY
+        .iter()
+        .find(|(_, phrases)| phrases.iter().any(|phrase| text.contains(phrase)))
+        .map(|(known, _)| known.to_string())
+}
+
+/// Extract a product from any product page, combining JSON-LD, microdata, OpenGraph/product
+/// `<meta>` tags and, for whatever is still missing, heuristics (the `<h1>`, price-like and
+/// stock elements, product images). Earlier sources win field by field. Returns `name`,
+/// `price` (`Decimal`), `currency`, `availability` (a schema.org name such as `"InStock"`),
+/// `brand`, `images` (resolved against `base_url` and `<base href>`), `rating`,
+/// `review_count`, `sku` and `sources`, the sources that contributed, in order.
+#[pyfunction]
+#[pyo3(signature = (html, base_url=None))]
+pub fn extract_product(py: Python, html: HtmlInput, base_url: Option<&str>) -> PyResult<Py<PyDict>> {
+    let source = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&source);
+    let base = links::document_base(&document, base_url)?;
+    let wanted = ["Product".to_string(), "ProductGroup".to_string()];
+
+    let mut product = Product::default();
+    for node in structured::json_ld_nodes(py, &document, Some(&wanted))?.iter() {
+        product.merge_schema(&node, "json-ld")?;
+    }
+    for item in structured::microdata_items(py, &document, Some(&wanted))?.iter() {
+        product.merge_schema(&item, "microdata")?;
+    }
+    product.merge_meta(&document)?;
+    product.merge_heuristics(&document);
+    product.sources.dedup();
+
+    let result = PyDict::new_bound(py);
+    result.set_item("name", product.name)?;
+    let amount = match product.amount {
+        Some(amount) => py.import_bound("decimal")?.getattr("Decimal")?.call1((amount,))?.into_py(py),
+        None => py.None(),
+    };
+    result.set_item("price", amount)?;
+    result.set_item("currency", product.currency.map(|c| c.to_ascii_uppercase()))?;
+    result.set_item("availability", product.availability)?;
+    result.set_item("brand", product.brand)?;
+    let images: Vec<String> = product.images.iter().map(|src| absolute(base.as_ref(), src)).collect();
+    result.set_item("images", dedup(images))?;
+    result.set_item("rating", product.rating)?;
+    result.set_item("review_count", product.review_count)?;
+    result.set_item("sku", product.sku)?;
+    result.set_item("sources", product.sources)?;
+    Ok(result.unbind())
+}
+
+fn absolute(base: Option<&Url>, src: &str) -> String {
+    links::resolve(base, src).map(String::from).unwrap_or_else(|| src.to_string())
+}
+
+fn dedup(items: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(items.len());
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
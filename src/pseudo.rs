@@ -0,0 +1,330 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use cssparser::{CowRcStr, ParseError, ParserInput, SourceLocation, ToCss};
+use html5ever::Namespace;
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use scraper::selector::{CssLocalName, CssString};
+use scraper::{ElementRef, Selector};
+use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
+use selectors::bloom::BloomFilter;
+use selectors::matching::{ElementSelectorFlags, MatchingContext, SelectorCaches};
+use selectors::parser::{self, Component, ParseRelative, RelativeSelector, SelectorList, SelectorParseErrorKind};
+use selectors::visitor::SelectorVisitor;
+use selectors::OpaqueElement;
+
+use crate::selector;
+use crate::text;
+
+/// Registered pseudo-class names (lowercase) and their predicates
+static REGISTRY: Lazy<Mutex<AHashMap<String, Py<PyAny>>>> = Lazy::new(|| Mutex::new(AHashMap::new()));
+
+/// `SelectorImpl` whose non-tree-structural pseudo-classes are the registered ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Custom;
+
+impl parser::SelectorImpl for Custom {
+    type AttrValue = CssString;
+    type Identifier = CssLocalName;
+    type LocalName = CssLocalName;
+    type NamespacePrefix = CssLocalName;
+    type NamespaceUrl = Namespace;
+    type BorrowedNamespaceUrl = Namespace;
+    type BorrowedLocalName = CssLocalName;
+
+    type NonTSPseudoClass = PseudoClass;
+    type PseudoElement = PseudoElement;
+
+    type ExtraMatchingData<'a> = ();
+}
+
+/// A registered pseudo-class, by name; its predicate is looked up when matching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PseudoClass(String);
+
+impl parser::NonTSPseudoClass for PseudoClass {
+    type Impl = Custom;
+
+    fn is_active_or_hover(&self) -> bool {
+        false
+    }
+
+    fn is_user_action_state(&self) -> bool {
+        false
+    }
+}
+
+impl ToCss for PseudoClass {
+    fn to_css<W: fmt::Write>(&self, dest: &mut W) -> fmt::Result {
+        dest.write_char(':')?;
+        dest.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PseudoElement {}
+
+impl parser::PseudoElement for PseudoElement {
+    type Impl = Custom;
+}
+
+impl ToCss for PseudoElement {
+    fn to_css<W: fmt::Write>(&self, _dest: &mut W) -> fmt::Result {
+        match *self {}
+    }
+}
+
+struct Parser;
+
+impl<'i> parser::Parser<'i> for Parser {
+    type Impl = Custom;
+    type Error = SelectorParseErrorKind<'i>;
+
+    fn parse_is_and_where(&self) -> bool {
+        true
+    }
+
+    fn parse_has(&self) -> bool {
+        true
+    }
+
+    fn parse_non_ts_pseudo_class(
+        &self,
+        location: SourceLocation,
+        name: CowRcStr<'i>,
+    ) -> Result<PseudoClass, ParseError<'i, SelectorParseErrorKind<'i>>> {
+        let lower = name.to_ascii_lowercase();
+        if REGISTRY.lock().unwrap().contains_key(&lower) {
+            return Ok(PseudoClass(lower));
+        }
+        Err(location.new_custom_error(SelectorParseErrorKind::UnsupportedPseudoClassOrElement(name)))
+    }
+}
+
+/// Parse a selector list with the registered pseudo-classes, for error diagnostics; with
+/// none registered this accepts exactly what scraper does.
+pub fn check<'i>(parser: &mut cssparser::Parser<'i, '_>) -> Result<(), ParseError<'i, SelectorParseErrorKind<'i>>> {
+    SelectorList::parse(&Parser, parser, ParseRelative::No).map(|_| ())
+}
+
+/// Stops at the first registered pseudo-class, including inside `:is()`, `:not()` and `:has()`
+struct FindPseudoClass;
+
+impl SelectorVisitor for FindPseudoClass {
+    type Impl = Custom;
+
+    fn visit_simple_selector(&mut self, component: &Component<Custom>) -> bool {
+        !matches!(component, Component::NonTSPseudoClass(_))
+    }
+
+    fn visit_relative_selector_list(&mut self, list: &[RelativeSelector<Custom>]) -> bool {
+        list.iter().all(|relative| relative.selector.visit(self))
+    }
+}
+
+/// A selector using registered pseudo-classes
+#[derive(Debug, Clone)]
+pub struct CustomSelector {
+    selectors: SelectorList<Custom>,
+}
+
+impl CustomSelector {
+    /// None when the selector uses no registered pseudo-class or is invalid. Checked before
+    /// scraper's parser, whose forgiving `:is()`/`:where()` would drop unknown pseudo-classes.
+    pub fn parse(selector: &str) -> Option<Self> {
+        if REGISTRY.lock().unwrap().is_empty() {
+            return None;
+        }
+        let mut input = ParserInput::new(selector);
+        let mut parser = cssparser::Parser::new(&mut input);
+        let selectors = SelectorList::parse(&Parser, &mut parser, ParseRelative::No).ok()?;
+        let uses_registered = selectors.slice().iter().any(|s| !s.visit(&mut FindPseudoClass));
+        uses_registered.then_some(CustomSelector { selectors })
+    }
+
+    pub fn matches(&self, elem: &ElementRef, caches: &mut SelectorCaches) -> bool {
+        selector::matches_list(&self.selectors, &Node(*elem), caches)
+    }
+}
+
+/// Whether `elem` satisfies the predicate registered as `name`. A predicate that raises
+/// is reported through `sys.unraisablehook` and counts as not matching.
+fn satisfies(name: &str, elem: ElementRef<'_>) -> bool {
+    Python::with_gil(|py| {
+        let Some(predicate) = REGISTRY.lock().unwrap().get(name).map(|p| p.clone_ref(py)) else {
+            return false;
+        };
+        let predicate = predicate.bind(py);
+        let result = if let Ok(needle) = predicate.downcast::<PyString>() {
+            needle.to_str().map(|needle| text::element_text(elem, true).contains(needle))
+        } else if predicate.hasattr("search").unwrap_or(false) {
+            predicate.call_method1("search", (text::element_text(elem, true),)).map(|found| !found.is_none())
+        } else {
+            predicate.call1((crate::scraper::Element::new(elem),)).and_then(|result| result.is_truthy())
+        };
+        result.unwrap_or_else(|err| {
+            err.write_unraisable_bound(py, Some(predicate));
+            false
+        })
+    })
+}
+
+/// `ElementRef` matched against `Custom` selectors
+#[derive(Debug, Clone, Copy)]
+struct Node<'a>(ElementRef<'a>);
+
+impl selectors::Element for Node<'_> {
+    type Impl = Custom;
+
+    fn opaque(&self) -> OpaqueElement {
+        self.0.opaque()
+    }
+
+    fn parent_element(&self) -> Option<Self> {
+        self.0.parent_element().map(Node)
+    }
+
+    fn parent_node_is_shadow_root(&self) -> bool {
+        false
+    }
+
+    fn containing_shadow_host(&self) -> Option<Self> {
+        None
+    }
+
+    fn is_pseudo_element(&self) -> bool {
+        false
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        self.0.prev_sibling_element().map(Node)
+    }
+
+    fn next_sibling_element(&self) -> Option<Self> {
+        self.0.next_sibling_element().map(Node)
+    }
+
+    fn first_element_child(&self) -> Option<Self> {
+        self.0.first_element_child().map(Node)
+    }
+
+    fn is_html_element_in_html_document(&self) -> bool {
+        self.0.is_html_element_in_html_document()
+    }
+
+    fn has_local_name(&self, name: &CssLocalName) -> bool {
+        self.0.has_local_name(name)
+    }
+
+    fn has_namespace(&self, namespace: &Namespace) -> bool {
+        self.0.has_namespace(namespace)
+    }
+
+    fn is_same_type(&self, other: &Self) -> bool {
+        self.0.is_same_type(&other.0)
+    }
+
+    fn attr_matches(
+        &self,
+        ns: &NamespaceConstraint<&Namespace>,
+        local_name: &CssLocalName,
+        operation: &AttrSelectorOperation<&CssString>,
+    ) -> bool {
+        self.0.attr_matches(ns, local_name, operation)
+    }
+
+    fn match_non_ts_pseudo_class(&self, pc: &PseudoClass, _context: &mut MatchingContext<'_, Custom>) -> bool {
+        satisfies(&pc.0, self.0)
+    }
+
+    fn match_pseudo_element(&self, pe: &PseudoElement, _context: &mut MatchingContext<'_, Custom>) -> bool {
+        match *pe {}
+    }
+
+    fn apply_selector_flags(&self, _flags: ElementSelectorFlags) {}
+
+    fn is_link(&self) -> bool {
+        self.0.is_link()
+    }
+
+    fn is_html_slot_element(&self) -> bool {
+        self.0.is_html_slot_element()
+    }
+
+    fn has_id(&self, id: &CssLocalName, case_sensitivity: CaseSensitivity) -> bool {
+        self.0.has_id(id, case_sensitivity)
+    }
+
+    fn has_class(&self, name: &CssLocalName, case_sensitivity: CaseSensitivity) -> bool {
+        self.0.has_class(name, case_sensitivity)
+    }
+
+    fn has_custom_state(&self, _name: &CssLocalName) -> bool {
+        false
+    }
+
+    fn imported_part(&self, _name: &CssLocalName) -> Option<CssLocalName> {
+        None
+    }
+
+    fn is_part(&self, _name: &CssLocalName) -> bool {
+        false
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn is_root(&self) -> bool {
+        self.0.is_root()
+    }
+
+    fn add_element_unique_hashes(&self, _filter: &mut BloomFilter) -> bool {
+        false
+    }
+}
+
+/// Register `:name` for use in any selector: `select()`, `extract_data` field and container
+/// selectors, `WebScraper.explain()` and the rest. `predicate` is a callable taking an
+/// `Element` and returning whether it matches, a str the element's text must contain, or a
+/// compiled regex searched in the element's text. Registering a name again replaces its
+/// predicate; built-in pseudo-classes such as `:first-child` cannot be redefined.
+#[pyfunction]
+pub fn register_pseudo_class(name: &str, predicate: Bound<'_, PyAny>) -> PyResult<()> {
+    let mut chars = name.chars();
+    let is_ident = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !is_ident {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid pseudo-class name: '{}'. Use letters, digits, '-' and '_', without the leading ':'", name)
+        ));
+    }
+    if Selector::parse(&format!(":{}", name)).is_ok() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid pseudo-class name: '{}'. It is a built-in pseudo-class", name)
+        ));
+    }
+    if !(predicate.is_instance_of::<PyString>() || predicate.hasattr("search")? || predicate.is_callable()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "Pseudo-class predicate must be a callable, a str or a compiled regex"
+        ));
+    }
+    REGISTRY.lock().unwrap().insert(name.to_ascii_lowercase(), predicate.unbind());
+    // Forgiving `:is()` may have compiled a selector using the name as matching nothing
+    selector::clear_cache();
+    Ok(())
+}
+
+/// Remove a pseudo-class registered with `register_pseudo_class`; returns whether it existed.
+/// Selectors using it raise `SelectorError` from then on.
+#[pyfunction]
+pub fn unregister_pseudo_class(name: &str) -> bool {
+    let removed = REGISTRY.lock().unwrap().remove(&name.to_ascii_lowercase()).is_some();
+    if removed {
+        selector::clear_cache();
+    }
+    removed
+}
@@ -0,0 +1,86 @@
+//! Named extractors registered at runtime, so teams can ship a library of site parsers and
+//! pick one by name: `register_extractor("shop_detail", rules)`, then
+//! `extract_with("shop_detail", html)`.
+
+use std::sync::Mutex;
+
+use ahash::AHashMap;
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+
+use crate::universal_extractor::{extract_data, CompiledSelectors};
+
+/// Registered extractors by name: `CompiledSelectors` with a container, or callables
+static EXTRACTORS: Lazy<Mutex<AHashMap<String, Py<PyAny>>>> = Lazy::new(|| Mutex::new(AHashMap::new()));
+
+/// Register `extractor` as `name` for `extract_with()`. `extractor` is one of:
+/// `CompiledSelectors` compiled with a `container`, run with `extract_data`; a path to a
+/// rules file, read with `CompiledSelectors.from_file()`; or a callable taking the HTML
+/// (and any keyword arguments given to `extract_with`). Registering a name again replaces
+/// its extractor.
+#[pyfunction]
+pub fn register_extractor(py: Python, name: &str, extractor: Bound<'_, PyAny>) -> PyResult<()> {
+    if name.trim().is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Extractor name must not be empty"));
+    }
+    let extractor = if extractor.is_instance_of::<PyString>() || extractor.hasattr("__fspath__")? {
+        py.get_type_bound::<CompiledSelectors>().call_method1("from_file", (extractor,))?
+    } else {
+        extractor
+    };
+    if let Ok(compiled) = extractor.downcast::<CompiledSelectors>() {
+        if compiled.get().container().is_none() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Extractor '{}' has no container selector. Pass container= to compile_selectors(), or set container in the rules file",
+                name
+            )));
+        }
+    } else if !extractor.is_callable() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "Extractor must be CompiledSelectors, a path to a rules file or a callable",
+        ));
+    }
+    EXTRACTORS.lock().unwrap().insert(name.to_string(), extractor.unbind());
+    Ok(())
+}
+
+/// Remove an extractor registered with `register_extractor`; returns whether it existed
+#[pyfunction]
+pub fn unregister_extractor(name: &str) -> bool {
+    EXTRACTORS.lock().unwrap().remove(name).is_some()
+}
+
+/// Names of the registered extractors, sorted
+#[pyfunction]
+pub fn registered_extractors() -> Vec<String> {
+    let mut names: Vec<String> = EXTRACTORS.lock().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Run the extractor registered as `name` on `html`. Keyword arguments go to
+/// `extract_data` (`report`, `output`, `base_url`, ...) for compiled rules, or to the
+/// callable. Raises `ValueError` for a name that is not registered.
+#[pyfunction]
+#[pyo3(signature = (name, html, **kwargs))]
+pub fn extract_with(py: Python, name: &str, html: Bound<'_, PyAny>, kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<PyObject> {
+    // Cloned out of the lock, so an extractor may register or run others
+    let extractor = EXTRACTORS.lock().unwrap().get(name).map(|extractor| extractor.clone_ref(py));
+    let Some(extractor) = extractor else {
+        let names = registered_extractors();
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(match names.is_empty() {
+            true => format!("Unknown extractor: '{}'. No extractors are registered", name),
+            false => format!("Unknown extractor: '{}'. Registered: {}", name, names.join(", ")),
+        }));
+    };
+    let extractor = extractor.bind(py);
+    match extractor.downcast::<CompiledSelectors>() {
+        Ok(compiled) => {
+            let container = compiled.get().container().unwrap_or_default().to_string();
+            let extract = wrap_pyfunction_bound!(extract_data, py)?;
+            Ok(extract.call((html, container, compiled), kwargs)?.unbind())
+        }
+        Err(_) => Ok(extractor.call((html,), kwargs)?.unbind()),
+    }
+}
@@ -0,0 +1,363 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node};
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::text::{self, Normalization};
+
+/// Never rendered: scripts, styles, metadata and inert templates
+const SKIPPED: [&str; 8] = ["script", "style", "noscript", "template", "head", "iframe", "object", "svg"];
+
+/// Blocks separated from their neighbours by a blank line
+const PARAGRAPHS: [&str; 10] = ["p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "figure"];
+
+/// Blocks that start on a new line
+const LINES: [&str; 26] = [
+    "address", "article", "aside", "body", "caption", "dd", "details", "dialog", "div", "dl", "dt", "fieldset",
+    "figcaption", "footer", "form", "header", "hgroup", "li", "main", "nav", "ol", "section", "summary", "tr",
+    "ul", "option",
+];
+
+/// Accumulates rendered text, merging requested line breaks so nested blocks do not stack
+/// blank lines.
+struct Renderer {
+    out: String,
+    layout: bool,
+    /// Line breaks owed before the next content
+    pending_breaks: usize,
+    /// A space owed before the next content on the same line
+    pending_space: bool,
+    /// Open lists: `Some(n)` for an ordered list at item `n`, `None` for bullets
+    lists: Vec<Option<usize>>,
+    /// Marker to write before the next content, e.g. `"- "` or `"2. "`
+    marker: Option<String>,
+    /// Rendered headings with the byte range of their text in `out`
+    headings: Vec<(NodeId, usize, usize)>,
+}
+
+impl Renderer {
+    fn new(layout: bool) -> Self {
+        Renderer { out: String::new(), layout, pending_breaks: 0, pending_space: false, lists: Vec::new(), marker: None, headings: Vec::new() }
+    }
+
+    /// Ask for at least `n` line breaks before the next content
+    fn block_break(&mut self, n: usize) {
+        let n = if self.layout { n } else { n.min(1) };
+        self.pending_breaks = self.pending_breaks.max(n);
+        self.pending_space = false;
+    }
+
+    /// Start the next content: flush owed breaks, then the list indent and marker
+    fn begin_content(&mut self) {
+        if self.out.is_empty() {
+            self.pending_breaks = 0;
+        }
+        if self.pending_breaks > 0 {
+            let trailing = self.out.len() - self.out.trim_end_matches('\n').len();
+            for _ in trailing..self.pending_breaks {
+                self.out.push('\n');
+            }
+            self.pending_breaks = 0;
+            self.pending_space = false;
+            if self.layout {
+                for _ in 1..self.lists.len() {
+                    self.out.push_str("  ");
+                }
+            }
+        } else if self.pending_space {
+            self.out.push(' ');
+            self.pending_space = false;
+        }
+        if let Some(marker) = self.marker.take() {
+            self.out.push_str(&marker);
+        }
+    }
+
+    /// Write text with whitespace runs collapsed to single spaces
+    fn text(&mut self, text: &str) {
+        let mut words = text.split_ascii_whitespace().peekable();
+        if words.peek().is_none() {
+            if !text.is_empty() && !self.at_line_start() {
+                self.pending_space = true;
+            }
+            return;
+        }
+        if text.starts_with(|c: char| c.is_ascii_whitespace()) && !self.at_line_start() {
+            self.pending_space = true;
+        }
+        self.begin_content();
+        let mut first = true;
+        for word in words {
+            if !first {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+            first = false;
+        }
+        self.pending_space = text.ends_with(|c: char| c.is_ascii_whitespace());
+    }
+
+    /// Write preformatted text line by line, keeping its spacing
+    fn preformatted(&mut self, text: &str) {
+        let mut lines = text.split('\n');
+        if let Some(first) = lines.next() {
+            if !first.is_empty() {
+                self.begin_content();
+                self.out.push_str(first);
+            }
+        }
+        for line in lines {
+            self.pending_breaks += 1;
+            if !line.is_empty() {
+                self.begin_content();
+                self.out.push_str(line);
+            }
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n') || self.pending_breaks > 0
+    }
+
+    fn finish(self) -> String {
+        clean(&self.out)
+    }
+}
+
+/// Rendered text without trailing spaces on lines or blank lines around it
+fn clean(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+fn render_node(node: NodeRef<'_, Node>, r: &mut Renderer, pre: bool) {
+    match node.value() {
+        Node::Text(text) if pre => r.preformatted(text),
+        Node::Text(text) => r.text(text),
+        Node::Element(_) => render_element(ElementRef::wrap(node).unwrap(), r, pre),
+        Node::Document | Node::Fragment => {
+            for child in node.children() {
+                render_node(child, r, pre);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn render_element(elem: ElementRef<'_>, r: &mut Renderer, pre: bool) {
+    let name = elem.value().name();
+    if SKIPPED.contains(&name) || elem.value().attr("hidden").is_some() {
+        return;
+    }
+    match name {
+        "br" => {
+            r.pending_breaks += 1;
+            r.pending_space = false;
+            return;
+        }
+        "table" => {
+            r.block_break(2);
+            render_table(elem, r);
+            r.block_break(2);
+            return;
+        }
+        "hr" => {
+            r.block_break(2);
+            if r.layout {
+                r.begin_content();
+                r.out.push_str("---");
+            }
+            r.block_break(2);
+            return;
+        }
+        "ul" | "ol" => {
+            // Nested lists hang off their item; top-level lists stand apart like paragraphs
+            r.block_break(if r.lists.is_empty() { 2 } else { 1 });
+            let start = elem.value().attr("start").and_then(|s| s.trim().parse().ok()).unwrap_or(1);
+            r.lists.push(if name == "ol" { Some(start) } else { None });
+            render_children(elem, r, pre);
+            r.lists.pop();
+            r.block_break(if r.lists.is_empty() { 2 } else { 1 });
+            return;
+        }
+        "li" => {
+            r.block_break(1);
+            if r.layout {
+                r.marker = Some(match r.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                });
+            }
+            render_children(elem, r, pre);
+            r.marker = None;
+            r.block_break(1);
+            return;
+        }
+        _ => {}
+    }
+
+    let breaks = if PARAGRAPHS.contains(&name) {
+        2
+    } else if LINES.contains(&name) {
+        1
+    } else {
+        0
+    };
+    if breaks > 0 {
+        r.block_break(breaks);
+    }
+    let start = r.out.len();
+    render_children(elem, r, pre || text::PRESERVE_WHITESPACE.contains(&name));
+    if heading_level(name).is_some() {
+        r.headings.push((elem.id(), start, r.out.len()));
+    }
+    if breaks > 0 {
+        r.block_break(breaks);
+    }
+}
+
+fn render_children(elem: ElementRef<'_>, r: &mut Renderer, pre: bool) {
+    for child in elem.children() {
+        render_node(child, r, pre);
+    }
+}
+
+/// Rows on their own lines; with layout, cells padded to column width and joined by ` | `
+fn render_table(table: ElementRef<'_>, r: &mut Renderer) {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for row in table.descendants().filter_map(ElementRef::wrap).filter(|e| e.value().name() == "tr") {
+        // Skip rows of nested tables; they are rendered inside their cell
+        let owner = row.ancestors().filter_map(ElementRef::wrap).find(|a| a.value().name() == "table");
+        if owner.map(|t| t.id()) != Some(table.id()) {
+            continue;
+        }
+        let cells: Vec<String> = row
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            .map(|cell| {
+                let mut inner = Renderer::new(false);
+                render_children(cell, &mut inner, false);
+                inner.finish().split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .collect();
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|c| rows.iter().filter_map(|row| row.get(c)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        r.block_break(1);
+        r.begin_content();
+        if r.layout {
+            let padded: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(c, cell)| format!("{:width$}", cell, width = widths[c]))
+                .collect();
+            r.out.push_str(&padded.join(" | "));
+        } else {
+            r.out.push_str(&row.join(" "));
+        }
+    }
+}
+
+/// Render a page as readable plain text. Block elements start new lines (paragraphs and
+/// headings get a blank line), `<br>` breaks lines, list items get `-` or `1.` markers indented
+/// by nesting, table cells are padded into columns and `pre` keeps its spacing; scripts,
+/// styles, `<head>` and `hidden` elements are skipped. `preserve_layout=False` keeps the line
+/// structure but drops markers, indentation, padding and blank lines.
+#[pyfunction]
+#[pyo3(signature = (html, preserve_layout=true, normalize=None))]
+pub fn to_text(html: HtmlInput, preserve_layout: bool, normalize: Option<&str>) -> PyResult<String> {
+    let normalize = Normalization::from_arg(normalize)?;
+    let source = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&source);
+    Ok(text::normalize(render_document(&document, preserve_layout), normalize))
+}
+
+/// The text `to_text` renders for a parsed document
+pub fn render_document(document: &Html, preserve_layout: bool) -> String {
+    let mut renderer = Renderer::new(preserve_layout);
+    render_node(document.tree.root(), &mut renderer, false);
+    renderer.finish()
+}
+
+/// 1 to 6 for `h1` to `h6`
+fn heading_level(name: &str) -> Option<usize> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(usize::from(level - b'0')),
+        _ => None,
+    }
+}
+
+/// Link target for a heading: its id, a named anchor inside it, or the id of the section it
+/// opens (`<section id="x"><h2>`)
+fn heading_anchor(heading: ElementRef<'_>) -> Option<String> {
+    if let Some(id) = heading.value().id() {
+        return Some(id.to_string());
+    }
+    let inner = heading
+        .descendants()
+        .filter_map(ElementRef::wrap)
+        .filter(|e| e.value().name() == "a")
+        .find_map(|a| a.value().id().or_else(|| a.value().attr("name")));
+    if let Some(inner) = inner {
+        return Some(inner.to_string());
+    }
+    let parent = heading.parent().and_then(ElementRef::wrap)?;
+    let first = parent.children().filter_map(ElementRef::wrap).next()?;
+    match first.id() == heading.id() && matches!(parent.value().name(), "section" | "article" | "div") {
+        true => parent.value().id().map(str::to_string),
+        false => None,
+    }
+}
+
+/// Outline of a page's `h1`-`h6` headings, nested by level: each section is
+/// `{"level", "title", "anchor", "text", "children"}`. `text` is what `to_text` renders
+/// between the heading and the next heading of any level, so a section's subsections are in
+/// `children`, not in its text; text before the first heading belongs to no section.
+/// `anchor` is the heading's id, a named anchor inside it or the id of the section it opens.
+/// A skipped level (`h4` right after `h2`) nests directly under the nearest higher heading.
+#[pyfunction]
+#[pyo3(signature = (html, preserve_layout=true))]
+pub fn extract_outline<'py>(py: Python<'py>, html: HtmlInput, preserve_layout: bool) -> PyResult<Bound<'py, PyList>> {
+    let source = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&source);
+    let mut renderer = Renderer::new(preserve_layout);
+    render_node(document.tree.root(), &mut renderer, false);
+
+    let roots = PyList::empty_bound(py);
+    // Open sections with their level, innermost last
+    let mut open: Vec<(usize, Bound<'py, PyList>)> = Vec::new();
+    for (index, &(id, _, end)) in renderer.headings.iter().enumerate() {
+        let heading = ElementRef::wrap(document.tree.get(id).unwrap()).unwrap();
+        let level = heading_level(heading.value().name()).unwrap_or(6);
+        let next = renderer.headings.get(index + 1).map_or(renderer.out.len(), |&(_, start, _)| start);
+        let children = PyList::empty_bound(py);
+        let section = PyDict::new_bound(py);
+        section.set_item("level", level)?;
+        section.set_item("title", text::element_text(heading, true))?;
+        section.set_item("anchor", heading_anchor(heading))?;
+        section.set_item("text", clean(&renderer.out[end..next]))?;
+        section.set_item("children", &children)?;
+
+        while open.last().is_some_and(|(open_level, _)| *open_level >= level) {
+            open.pop();
+        }
+        match open.last() {
+            Some((_, parent)) => parent.append(section)?,
+            None => roots.append(section)?,
+        }
+        open.push((level, children));
+    }
+    Ok(roots)
+}
@@ -0,0 +1,189 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use ahash::{AHashMap, AHashSet};
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node};
+use std::collections::HashMap;
+
+use crate::links;
+use crate::markup::{escape_attr, escape_text, is_void};
+
+/// Tags kept by the default policy: text formatting, lists, tables, links and images
+const DEFAULT_TAGS: [&str; 58] = [
+    "a", "abbr", "acronym", "b", "bdi", "bdo", "blockquote", "br", "caption", "cite", "code", "col", "colgroup",
+    "dd", "del", "dfn", "div", "dl", "dt", "em", "figcaption", "figure", "h1", "h2", "h3", "h4", "h5", "h6", "hr",
+    "i", "img", "ins", "kbd", "li", "mark", "ol", "p", "pre", "q", "s", "samp", "small", "span", "strike",
+    "strong", "sub", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "time", "tr", "u", "ul", "wbr",
+];
+
+/// Attributes kept by the default policy, per tag
+const DEFAULT_ATTRIBUTES: [(&str, &[&str]); 10] = [
+    ("a", &["href", "title", "rel"]),
+    ("img", &["src", "alt", "title", "width", "height"]),
+    ("abbr", &["title"]),
+    ("acronym", &["title"]),
+    ("blockquote", &["cite"]),
+    ("q", &["cite"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("time", &["datetime"]),
+    ("ol", &["start", "reversed"]),
+];
+
+const DEFAULT_PROTOCOLS: [&str; 3] = ["http", "https", "mailto"];
+
+/// Disallowed tags are unwrapped (their content kept), except these, which go with their content
+const DROP_WITH_CONTENT: [&str; 16] = [
+    "script", "style", "template", "noscript", "iframe", "object", "embed", "applet", "svg", "math",
+    "frame", "frameset", "head", "title", "textarea", "select",
+];
+
+
+const POLICY_KEYS: [&str; 4] = ["tags", "attributes", "protocols", "comments"];
+
+/// Compiled allowlists for one `sanitize` call
+struct Policy {
+    tags: AHashSet<String>,
+    /// Attributes per tag; `*` applies to every allowed tag
+    attributes: AHashMap<String, AHashSet<String>>,
+    protocols: AHashSet<String>,
+    comments: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            tags: DEFAULT_TAGS.iter().map(|t| t.to_string()).collect(),
+            attributes: DEFAULT_ATTRIBUTES
+                .iter()
+                .map(|(tag, attrs)| (tag.to_string(), attrs.iter().map(|a| a.to_string()).collect()))
+                .collect(),
+            protocols: DEFAULT_PROTOCOLS.iter().map(|p| p.to_string()).collect(),
+            comments: false,
+        }
+    }
+}
+
+impl Policy {
+    /// Build from the Python `policy` dict; keys it leaves out keep their defaults
+    fn from_dict(policy: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut compiled = Policy::default();
+        for (key, value) in policy.iter() {
+            let key: String = key.extract()?;
+            match key.as_str() {
+                "tags" => compiled.tags = lowercase_set(value.extract()?),
+                "attributes" => {
+                    let attributes: HashMap<String, Vec<String>> = value.extract()?;
+                    compiled.attributes = attributes
+                        .into_iter()
+                        .map(|(tag, attrs)| (tag.to_ascii_lowercase(), lowercase_set(attrs)))
+                        .collect();
+                }
+                "protocols" => compiled.protocols = lowercase_set(value.extract()?),
+                "comments" => compiled.comments = value.extract()?,
+                other => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Invalid policy key: '{}'. Use one of {}", other, POLICY_KEYS.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(compiled)
+    }
+
+    fn allows_attribute(&self, tag: &str, name: &str) -> bool {
+        let listed = |key: &str| self.attributes.get(key).is_some_and(|attrs| attrs.contains(name));
+        listed(tag) || listed("*")
+    }
+
+    /// Relative URLs are always fine; absolute ones need an allowed scheme. Whitespace and
+    /// control characters are ignored so `java\tscript:` cannot slip through.
+    fn allows_url(&self, url: &str) -> bool {
+        let compact: String = url.chars().filter(|c| !c.is_whitespace() && !c.is_control()).collect();
+        match compact.find([':', '/', '?', '#']) {
+            Some(i) if compact[i..].starts_with(':') => self.protocols.contains(&compact[..i].to_ascii_lowercase()),
+            _ => true,
+        }
+    }
+}
+
+fn lowercase_set(values: Vec<String>) -> AHashSet<String> {
+    values.into_iter().map(|v| v.to_ascii_lowercase()).collect()
+}
+
+/// Clean untrusted HTML for re-display. Only allowlisted tags and attributes survive; other
+/// tags are unwrapped, keeping their text, except script-like ones (`script`, `style`,
+/// `iframe`, ...) which are removed with their content. `on*` event handlers never survive
+/// and URL attributes must use an allowed protocol. `policy` overrides any of `tags` (list),
+/// `attributes` (dict of tag, or `"*"` for all tags, to a list of names), `protocols` (list)
+/// and `comments` (keep comments, default `False`).
+#[pyfunction]
+#[pyo3(signature = (html, policy=None))]
+pub fn sanitize(html: &str, policy: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let policy = match policy {
+        Some(policy) => Policy::from_dict(policy)?,
+        None => Policy::default(),
+    };
+    let fragment = Html::parse_fragment(html);
+    let mut out = String::with_capacity(html.len());
+    for child in fragment.root_element().children() {
+        write_node(child, &policy, &mut out);
+    }
+    Ok(out)
+}
+
+fn write_node(node: NodeRef<'_, Node>, policy: &Policy, out: &mut String) {
+    match node.value() {
+        Node::Text(text) => escape_text(text, out),
+        Node::Comment(comment) if policy.comments => {
+            // A comment cannot safely contain its own terminator
+            out.push_str("<!--");
+            out.push_str(&comment.replace("--", "- -"));
+            out.push_str("-->");
+        }
+        Node::Element(_) => write_element(ElementRef::wrap(node).unwrap(), policy, out),
+        _ => {}
+    }
+}
+
+fn write_element(elem: ElementRef<'_>, policy: &Policy, out: &mut String) {
+    let name = elem.value().name();
+    if !policy.tags.contains(name) {
+        if !DROP_WITH_CONTENT.contains(&name) {
+            for child in elem.children() {
+                write_node(child, policy, out);
+            }
+        }
+        return;
+    }
+
+    out.push('<');
+    out.push_str(name);
+    for (attr, value) in elem.value().attrs() {
+        let attr = attr.to_ascii_lowercase();
+        if attr.starts_with("on") || !policy.allows_attribute(name, &attr) {
+            continue;
+        }
+        if links::URL_ATTRIBUTES.contains(&attr.as_str()) && !policy.allows_url(value) {
+            continue;
+        }
+        if attr == "srcset" && !value.split(',').all(|candidate| policy.allows_url(candidate.trim().split(' ').next().unwrap_or(""))) {
+            continue;
+        }
+        out.push(' ');
+        out.push_str(&attr);
+        out.push_str("=\"");
+        escape_attr(value, out);
+        out.push('"');
+    }
+    out.push('>');
+    if is_void(name) {
+        return;
+    }
+    for child in elem.children() {
+        write_node(child, policy, out);
+    }
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
@@ -1,67 +1,832 @@
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyString};
-use scraper::{Html, Selector, ElementRef};
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
+use scraper::{Html, HtmlTreeSink, Node, ElementRef};
+use ego_tree::NodeId;
+use html5ever::driver::{self, ParseOpts};
+use html5ever::{namespace_url, ns, LocalName, QualName};
+use html5ever::tendril::{StrTendril, TendrilSink};
+use html5ever::tokenizer::TokenizerOpts;
+use html5ever::tree_builder::{QuirksMode, TreeBuilderOpts, TreeSink};
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::collections::HashMap;
-use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
 use ahash::AHashMap;
-use sxd_document::parser;
-use sxd_xpath::{evaluate_xpath, Value, nodeset::Node};
 
-// Cache compiled selectors for performance
-static SELECTOR_CACHE: Lazy<Mutex<AHashMap<String, Selector>>> = 
-    Lazy::new(|| Mutex::new(AHashMap::new()));
+use crate::conditional;
+use crate::errors;
+use crate::explain;
+use crate::fragment;
+use crate::limits::{self, Deadline, LimitedSink, Limits};
+use crate::logging;
+use crate::markup::{self, SerializeOptions};
+use crate::text::{self, Normalization};
+use crate::xml;
+use crate::selector::{self, Query};
+use crate::xpath::{self, XPathItem};
+use crate::encoding::{encode_string, DecodeOptions, Decoded, EncodingInfo, EncodingSource, HtmlInput, StreamDecoder};
 
-#[pyclass(unsendable)]
+/// Input is fed to the parser in chunks of this many bytes when a timeout is set, so the
+/// deadline can be checked between them.
+const PARSE_CHUNK: usize = 16 * 1024;
+
+/// Nodes visited between deadline checks while selecting.
+const SELECT_CHECK_INTERVAL: usize = 1024;
+
+/// Knobs for the HTML tree builder.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// Ask html5ever for detailed parse error messages (token and insertion mode) instead of
+    /// short generic ones. Formatting them costs time on badly broken pages.
+    pub detailed_warnings: bool,
+    /// Time budget for parsing, and for each selection on the parsed document.
+    pub timeout: Option<Duration>,
+    /// Size, depth and node-count guards.
+    pub limits: Limits,
+    /// Parse `<noscript>` content as markup, as a browser with scripting disabled would,
+    /// instead of keeping it as one text node.
+    pub parse_noscript: bool,
+    /// Replace downlevel-hidden conditional comments (`<!--[if IE]>...<![endif]-->`) with the
+    /// markup inside them.
+    pub conditional_comments: bool,
+    /// Which parser builds the document.
+    pub mode: ParseMode,
+}
+
+/// Which parser `parse_document` uses. The tree is the same either way: the lightweight
+/// parser in `fragment` only takes markup it builds exactly as html5ever would.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// The lightweight parser for inputs up to `fragment::AUTO_MAX_LEN` bytes
+    #[default]
+    Auto,
+    /// Always html5ever
+    Document,
+    /// The lightweight parser at any size, falling back to html5ever when it must
+    Fragment,
+}
+
+impl ParseMode {
+    pub fn parse(mode: &str) -> PyResult<Self> {
+        match mode {
+            "auto" => Ok(ParseMode::Auto),
+            "document" => Ok(ParseMode::Document),
+            "fragment" => Ok(ParseMode::Fragment),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid mode: '{}'. Use 'auto', 'document' or 'fragment'", mode)
+            )),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Build from the Python-facing arguments, validating them
+    pub fn new(detailed_warnings: bool, timeout: Option<f64>, limits: Limits) -> PyResult<Self> {
+        Ok(ParseOptions { detailed_warnings, timeout: limits::timeout_arg(timeout)?, limits, parse_noscript: false, conditional_comments: false, mode: ParseMode::Auto })
+    }
+
+    /// Whether `parse_document` should try the lightweight parser on `len` bytes. Depth and
+    /// node limits, and detailed warnings, are html5ever's.
+    fn lightweight(&self, len: usize) -> bool {
+        let wanted = match self.mode {
+            ParseMode::Auto => len <= fragment::AUTO_MAX_LEN,
+            ParseMode::Document => false,
+            ParseMode::Fragment => true,
+        };
+        wanted && !self.detailed_warnings && self.limits.max_depth.is_none() && self.limits.max_nodes.is_none()
+    }
+}
+
+/// Parse a full HTML document according to `options`, raising `ParseTimeoutError` once
+/// `deadline` passes
+pub(crate) fn parse_document(html: &str, options: &ParseOptions, deadline: &Deadline) -> PyResult<Html> {
+    let opts = parse_opts(options);
+    options.limits.check_size(html.len())?;
+    logging::emit(logging::DEBUG, || format!("parse start: {} bytes", html.len()));
+    let started = Instant::now();
+    let lightweight = options.lightweight(html.len()).then(|| fragment::parse_document(html)).flatten();
+    let mut document = if let Some(document) = lightweight {
+        deadline.check("Parsing")?;
+        document
+    } else {
+        let sink = HtmlTreeSink::new(Html::new_document());
+        if options.limits.is_set() {
+            let parser = driver::parse_document(LimitedSink::new(sink, options.limits), opts);
+            parse_in_chunks(parser, html, deadline, |sink| sink.check())?
+        } else if deadline.is_set() {
+            parse_in_chunks(driver::parse_document(sink, opts), html, deadline, |_| Ok(()))?
+        } else {
+            driver::parse_document(sink, opts).one(html)
+        }
+    };
+    inline_templates(&mut document);
+    if options.conditional_comments {
+        conditional::expand(&mut document, None)?;
+    }
+    log_parsed(&document, started);
+    Ok(document)
+}
+
+/// Move the content of each `<template>` out of the separate fragment html5ever builds for it
+/// and directly under the element, so child combinators and XPath reach it like any markup
+pub(crate) fn inline_templates(document: &mut Html) {
+    let fragments: Vec<(NodeId, NodeId)> = document
+        .tree
+        .nodes()
+        .filter(|node| node.value().is_fragment())
+        .filter_map(|node| node.parent().map(|template| (template.id(), node.id())))
+        .collect();
+    for (template, fragment) in fragments {
+        let children: Vec<NodeId> = document.tree.get(fragment).unwrap().children().map(|child| child.id()).collect();
+        let mut template = document.tree.get_mut(template).unwrap();
+        for child in children {
+            template.append_id(child);
+        }
+        document.tree.get_mut(fragment).unwrap().detach();
+    }
+}
+
+/// Copy `source` and its subtree into `tree` as a detached node, returning its id
+pub(crate) fn graft(tree: &mut ego_tree::Tree<Node>, source: ego_tree::NodeRef<Node>) -> NodeId {
+    let id = tree.orphan(source.value().clone()).id();
+    for child in source.children() {
+        let child_id = graft(tree, child);
+        tree.get_mut(id).unwrap().append_id(child_id);
+    }
+    id
+}
+
+/// `document` rebuilt from its root. The tree keeps detached nodes in its arena and
+/// `select()` walks the arena in allocation order, so after an edit it would still find
+/// removed nodes and return inserted ones out of document order.
+pub(crate) fn compact(document: &Html) -> Html {
+    let root = document.tree.root();
+    let mut tree = ego_tree::Tree::new(root.value().clone());
+    for child in root.children() {
+        let id = graft(&mut tree, child);
+        tree.root_mut().append_id(id);
+    }
+    Html { errors: document.errors.clone(), quirks_mode: document.quirks_mode, tree }
+}
+
+/// Parse `html` as the content of a `context` element, e.g. `"tr"` so that bare `<td>` cells
+/// are kept rather than dropped by the tree builder's insertion rules
+pub(crate) fn parse_fragment_in(html: &str, context: &str, options: &ParseOptions, deadline: &Deadline) -> PyResult<Html> {
+    let context_name = context.trim().to_ascii_lowercase();
+    if context_name.is_empty() || !context_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid context: '{}'. Use a tag name such as 'tr' or 'ul'", context)
+        ));
+    }
+    let context_qual = QualName::new(None, ns!(html), LocalName::from(context_name.as_str()));
+    options.limits.check_size(html.len())?;
+    let started = Instant::now();
+    let sink = HtmlTreeSink::new(Html::new_fragment());
+    let mut fragment = if options.limits.is_set() {
+        let parser = driver::parse_fragment(LimitedSink::new(sink, options.limits), parse_opts(options), context_qual, Vec::new());
+        parse_in_chunks(parser, html, deadline, |sink| sink.check())?
+    } else {
+        parse_in_chunks(driver::parse_fragment(sink, parse_opts(options), context_qual, Vec::new()), html, deadline, |_| Ok(()))?
+    };
+    inline_templates(&mut fragment);
+    if options.conditional_comments {
+        conditional::expand(&mut fragment, Some(&context_name))?;
+    }
+    log_parsed(&fragment, started);
+    Ok(fragment)
+}
+
+fn parse_opts(options: &ParseOptions) -> ParseOpts {
+    ParseOpts {
+        tokenizer: TokenizerOpts { exact_errors: options.detailed_warnings, ..Default::default() },
+        tree_builder: TreeBuilderOpts {
+            exact_errors: options.detailed_warnings,
+            scripting_enabled: !options.parse_noscript,
+            ..Default::default()
+        },
+    }
+}
+
+/// Parse an element's serialized markup on its own, for selecting within it. Scripting is
+/// off so `<noscript>` content reads back as it was: markup stays markup and text serialized
+/// with escapes stays text.
+pub(crate) fn reparse(html: &str) -> Html {
+    if let Some(fragment) = fragment::parse_body_fragment(html) {
+        return fragment;
+    }
+    let opts = ParseOpts {
+        tree_builder: TreeBuilderOpts { scripting_enabled: false, ..Default::default() },
+        ..Default::default()
+    };
+    let context = QualName::new(None, ns!(html), LocalName::from("body"));
+    let sink = HtmlTreeSink::new(Html::new_fragment());
+    let mut fragment = driver::parse_fragment(sink, opts, context, Vec::new()).one(html);
+    inline_templates(&mut fragment);
+    fragment
+}
+
+fn log_parsed(document: &Html, started: Instant) {
+    logging::emit(logging::DEBUG, || format!(
+        "parse finished in {:.3} ms: {} nodes, {} warnings",
+        started.elapsed().as_secs_f64() * 1000.0,
+        document.tree.nodes().count(),
+        document.errors.len()
+    ));
+    if logging::enabled(logging::WARNING) {
+        for warning in &document.errors {
+            logging::emit(logging::WARNING, || format!("parse warning: {}", warning));
+        }
+    }
+}
+
+/// Feed `html` to `parser` a chunk at a time, checking `deadline` and the sink's own limits
+/// between chunks.
+fn parse_in_chunks<S: TreeSink>(
+    mut parser: driver::Parser<S>,
+    html: &str,
+    deadline: &Deadline,
+    check: impl Fn(&S) -> PyResult<()>,
+) -> PyResult<S::Output> {
+    let mut start = 0;
+    while start < html.len() {
+        deadline.check("Parsing")?;
+        check(&parser.tokenizer.sink.sink)?;
+        let mut end = (start + PARSE_CHUNK).min(html.len());
+        while !html.is_char_boundary(end) {
+            end += 1;
+        }
+        parser.process(StrTendril::from_slice(&html[start..end]));
+        start = end;
+    }
+    deadline.check("Parsing")?;
+    check(&parser.tokenizer.sink.sink)?;
+    Ok(parser.finish())
+}
+
+/// `document.select(selector)` that raises `ParseTimeoutError` once `deadline` passes.
+/// At most `limit` matches are collected.
+pub(crate) fn select_within<'a>(
+    document: &'a Html,
+    selector: &Query,
+    selector_str: &str,
+    deadline: &Deadline,
+    limit: Option<usize>,
+) -> PyResult<Vec<ElementRef<'a>>> {
+    if !deadline.is_set() {
+        let matches = selector.select(document);
+        return Ok(match limit {
+            Some(limit) => matches.take(limit).collect(),
+            None => matches.collect(),
+        });
+    }
+    let mut matcher = selector.matcher();
+    let mut matches = Vec::new();
+    for (visited, node) in document.tree.nodes().enumerate() {
+        if visited % SELECT_CHECK_INTERVAL == 0 {
+            deadline.check(&format!("Selecting '{}'", selector_str))
+                .map_err(|err| errors::with_detail(err, "selector", selector_str))?;
+        }
+        if let Some(elem) = ElementRef::wrap(node) {
+            if matcher.matches(&elem) {
+                matches.push(elem);
+                if limit == Some(matches.len()) {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Comment texts under `root` in document order. `pattern` keeps those containing a str or
+/// matching a compiled regex (anything with `.search()`).
+pub(crate) fn comments_in(
+    root: ego_tree::NodeRef<Node>,
+    pattern: Option<&Bound<'_, PyAny>>,
+    limit: Option<usize>,
+) -> PyResult<Vec<String>> {
+    let mut found = Vec::new();
+    for node in root.descendants() {
+        if limit == Some(found.len()) {
+            break;
+        }
+        let Node::Comment(comment) = node.value() else { continue };
+        let keep = match pattern {
+            None => true,
+            Some(pattern) => {
+                if let Ok(needle) = pattern.downcast::<PyString>() {
+                    comment.contains(needle.to_str()?)
+                } else if pattern.hasattr("search")? {
+                    !pattern.call_method1("search", (&**comment,))?.is_none()
+                } else {
+                    return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                        "comment pattern must be a str or a compiled regex"
+                    ));
+                }
+            }
+        };
+        if keep {
+            found.push(comment.to_string());
+        }
+    }
+    Ok(found)
+}
+
+/// Where new markup goes relative to each element a mutation targets
+#[derive(Clone, Copy, PartialEq)]
+enum Placement {
+    Before,
+    After,
+    Prepend,
+    Append,
+    Replace,
+}
+
+impl Placement {
+    fn from_arg(position: &str) -> PyResult<Self> {
+        match position {
+            "before" => Ok(Placement::Before),
+            "after" => Ok(Placement::After),
+            "prepend" => Ok(Placement::Prepend),
+            "append" => Ok(Placement::Append),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid position: '{}'. Use 'before', 'after', 'prepend' or 'append'",
+                other
+            ))),
+        }
+    }
+}
+
+/// A parsed tree shared by a `WebScraper` and the Elements selected from it, which may be
+/// read from other threads. Reentrant, so a pseudo-class predicate can read an Element of
+/// the document being selected from.
+type SharedHtml = Arc<ReentrantMutex<Html>>;
+
+fn share(document: Html) -> SharedHtml {
+    Arc::new(ReentrantMutex::new(document))
+}
+
+/// Lock `document`, letting other threads run while it is held elsewhere: the holder may
+/// be calling into Python and need the GIL.
+fn lock(document: &SharedHtml) -> ReentrantMutexGuard<'_, Html> {
+    loop {
+        if let Some(guard) = document.try_lock() {
+            return guard;
+        }
+        Python::with_gil(|py| py.allow_threads(|| drop(document.lock())));
+    }
+}
+
+/// The tree to edit, copied first if Elements selected from it still share it
+fn make_mut(document: &mut SharedHtml) -> &mut Html {
+    if Arc::get_mut(document).is_none() {
+        let copy = lock(document).clone();
+        *document = share(copy);
+    }
+    Arc::get_mut(document).unwrap().get_mut()
+}
+
+#[pyclass(unsendable, module = "rusticsoup")]
+#[derive(Clone)]
 pub struct WebScraper {
-    document: Html,
+    /// Shared with the Elements selected from it; edits copy it first if any are alive
+    document: SharedHtml,
+    encoding: Option<EncodingInfo>,
+    timeout: Option<Duration>,
+    /// Built by `parse_xml`: serialized as XML, and its elements re-parsed as XML
+    xml: bool,
+    /// Built by `parse_fragment`: the context element the snippet was parsed in
+    context: Option<String>,
+    /// Parsed with `parse_noscript=True`, so a pickled copy parses `<noscript>` the same way
+    parse_noscript: bool,
+}
+
+/// A document argument: markup to parse, or a document `parse_html()` already parsed, so a
+/// page can be queried many times without being parsed again.
+#[derive(FromPyObject)]
+pub enum DocumentInput<'py> {
+    #[pyo3(transparent, annotation = "WebScraper")]
+    Parsed(PyRef<'py, WebScraper>),
+    #[pyo3(transparent, annotation = "str | bytes")]
+    Markup(HtmlInput<'py>),
+}
+
+impl WebScraper {
+    /// Parse an already-decoded HTML document
+    pub(crate) fn parse(html: &str, options: &ParseOptions) -> PyResult<Self> {
+        Ok(WebScraper {
+            document: share(parse_document(html, options, &Deadline::start(options.timeout))?),
+            encoding: None,
+            timeout: options.timeout,
+            xml: false,
+            context: None,
+            parse_noscript: options.parse_noscript,
+        })
+    }
+
+    /// Wrap a document built by the XML parser
+    pub(crate) fn from_xml(document: Html) -> Self {
+        WebScraper { document: share(document), encoding: None, timeout: None, xml: true, context: None, parse_noscript: false }
+    }
+
+    /// The parsed tree, for functions that also accept markup to parse
+    pub(crate) fn document(&self) -> ReentrantMutexGuard<'_, Html> {
+        lock(&self.document)
+    }
+
+    fn element(&self, elem: ElementRef) -> Element {
+        Element::handle(&self.document, elem, self.xml)
+    }
+
+    /// Ids of the elements matching `selector`, in document order. With `outermost`, matches
+    /// inside another match are left out, since they go with it.
+    fn matching_ids(&self, selector: &str, outermost: bool) -> PyResult<Vec<NodeId>> {
+        let sel = selector::compile(selector)?;
+        let document = self.document();
+        let matches = select_within(&document, &sel, selector, &Deadline::start(self.timeout), None)?;
+        let ids: ahash::AHashSet<NodeId> = matches.iter().map(|elem| elem.id()).collect();
+        Ok(matches
+            .iter()
+            .filter(|elem| !outermost || !elem.ancestors().any(|ancestor| ids.contains(&ancestor.id())))
+            .map(|elem| elem.id())
+            .collect())
+    }
+
+    /// Parse `markup` into `placement` for every element matching `selector`. HTML is parsed
+    /// as the content of the element it will end up in, so `<tr>` rows survive going into a
+    /// `<tbody>`.
+    fn place(&mut self, selector: &str, markup: &str, placement: Placement) -> PyResult<usize> {
+        let targets = self.matching_ids(selector, placement == Placement::Replace)?;
+        let mut parsed: AHashMap<String, Html> = AHashMap::new();
+        let document = make_mut(&mut self.document);
+        for &target in &targets {
+            let node = document.tree.get(target).unwrap();
+            let container = match placement {
+                Placement::Prepend | Placement::Append => Some(node),
+                _ => node.parent(),
+            };
+            // A fragment's top-level nodes sit in a synthetic <html>; they belong in its context
+            let context = match container.and_then(ElementRef::wrap) {
+                Some(elem) if elem.parent().is_some_and(|parent| parent.value().is_fragment()) => {
+                    self.context.clone().unwrap_or_else(|| "body".to_string())
+                }
+                Some(elem) => elem.value().name().to_string(),
+                None => "body".to_string(),
+            };
+            if !parsed.contains_key(&context) {
+                let fragment = if self.xml {
+                    xml::parse_nodes(markup)?
+                } else {
+                    parse_fragment_in(markup, &context, &ParseOptions::default(), &Deadline::start(None))?
+                };
+                parsed.insert(context.clone(), fragment);
+            }
+            let copies: Vec<NodeId> = parsed[&context]
+                .root_element()
+                .children()
+                .map(|child| graft(&mut document.tree, child))
+                .collect();
+            let mut target = document.tree.get_mut(target).unwrap();
+            match placement {
+                Placement::Before | Placement::Replace => copies.iter().for_each(|&id| { target.insert_id_before(id); }),
+                Placement::After => copies.iter().rev().for_each(|&id| { target.insert_id_after(id); }),
+                Placement::Prepend => copies.iter().rev().for_each(|&id| { target.prepend_id(id); }),
+                Placement::Append => copies.iter().for_each(|&id| { target.append_id(id); }),
+            }
+            if placement == Placement::Replace {
+                target.detach();
+            }
+        }
+        if !targets.is_empty() {
+            let compacted = compact(&self.document());
+            self.document = share(compacted);
+        }
+        Ok(targets.len())
+    }
+
+    /// Elements matching `selector`, in document order
+    pub(crate) fn select_all(&self, selector: &str) -> PyResult<Vec<Element>> {
+        let sel = selector::compile(selector)?;
+        Ok(select_within(&self.document(), &sel, selector, &Deadline::start(self.timeout), None)?
+            .into_iter()
+            .map(|elem| self.element(elem))
+            .collect())
+    }
+
+    /// Decode str/bytes input and parse it, remembering how bytes were decoded
+    pub(crate) fn from_input(html: HtmlInput, options: &DecodeOptions, parse_options: &ParseOptions) -> PyResult<Self> {
+        let (text, encoding) = html.decode_with_info(options)?;
+        Ok(WebScraper { encoding, ..WebScraper::parse(&text, parse_options)? })
+    }
+
+    /// Serialize the whole document; a fragment without the `<html>` element that wraps it
+    pub(crate) fn document_html(&self) -> String {
+        if self.xml {
+            xml::serialize_document(&self.document())
+        } else if self.document().tree.root().value().is_fragment() {
+            self.document().root_element().inner_html()
+        } else {
+            self.document().html()
+        }
+    }
+
+    /// `document_html`, or the document written with `options`
+    pub(crate) fn formatted_html(&self, options: Option<SerializeOptions>) -> PyResult<String> {
+        match options {
+            None => Ok(self.document_html()),
+            Some(_) if self.xml => Err(xml_formatting_error()),
+            Some(options) if self.document().tree.root().value().is_fragment() => {
+                Ok(options.write_children(*self.document().root_element()))
+            }
+            Some(options) => Ok(options.write_children(self.document().tree.root())),
+        }
+    }
+
+    /// Parse a document decoded from bytes elsewhere
+    pub(crate) fn from_decoded(decoded: Decoded, options: &ParseOptions) -> PyResult<Self> {
+        Ok(WebScraper { encoding: Some(decoded.info), ..WebScraper::parse(&decoded.text, options)? })
+    }
+
+    /// What `__reduce__` pickles: the serialized document, how to parse it back, and the
+    /// parse warnings and encoding decision of the original, which reparsing would lose
+    pub(crate) fn pickle_state<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let state = PyDict::new_bound(py);
+        state.set_item("html", self.document_html())?;
+        state.set_item("xml", self.xml)?;
+        state.set_item("context", &self.context)?;
+        state.set_item("parse_noscript", self.parse_noscript)?;
+        state.set_item("timeout", self.timeout.map(|timeout| timeout.as_secs_f64()))?;
+        state.set_item("warnings", self.warnings())?;
+        let encoding = self.encoding.as_ref().map(|info| (info.encoding.as_str(), info.source.as_str(), info.confidence));
+        state.set_item("encoding", encoding)?;
+        Ok(state)
+    }
+
+    /// Rebuild a document from `pickle_state`
+    pub(crate) fn from_pickle_state(state: &Bound<'_, PyDict>) -> PyResult<Self> {
+        fn item<'py, T: FromPyObject<'py>>(state: &Bound<'py, PyDict>, key: &str) -> PyResult<T> {
+            match state.get_item(key)? {
+                Some(value) => value.extract(),
+                None => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Pickled document state has no '{}'", key))),
+            }
+        }
+        let html: String = item(state, "html")?;
+        let xml: bool = item(state, "xml")?;
+        let context: Option<String> = item(state, "context")?;
+        let parse_noscript: bool = item(state, "parse_noscript")?;
+        let options = ParseOptions { parse_noscript, ..ParseOptions::default() };
+        let deadline = Deadline::start(None);
+        let mut document = match (&context, xml) {
+            (_, true) => xml::parse(&html)?,
+            (Some(context), false) => parse_fragment_in(&html, context, &options, &deadline)?,
+            (None, false) => parse_document(&html, &options, &deadline)?,
+        };
+        let warnings: Vec<String> = item(state, "warnings")?;
+        document.errors = warnings.into_iter().map(Cow::Owned).collect();
+        let timeout: Option<f64> = item(state, "timeout")?;
+        let encoding: Option<(String, String, f32)> = item(state, "encoding")?;
+        let encoding = match encoding {
+            Some((encoding, source, confidence)) => {
+                let source = EncodingSource::from_name(&source).ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Unknown encoding source in pickled document: '{}'", source))
+                })?;
+                Some(EncodingInfo { encoding, source, confidence })
+            }
+            None => None,
+        };
+        Ok(WebScraper { document: share(document), encoding, timeout: limits::timeout_arg(timeout)?, xml, context, parse_noscript })
+    }
 }
 
 #[pymethods]
 impl WebScraper {
     #[new]
-    fn new(html: &str) -> Self {
-        WebScraper {
-            document: Html::parse_document(html),
+    #[pyo3(signature = (html, from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None, parse_noscript=false, parse_conditional_comments=false, mode="auto"))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        html: HtmlInput,
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+        fix_mojibake: bool,
+        detailed_warnings: bool,
+        timeout: Option<f64>,
+        max_size: Option<usize>,
+        max_depth: Option<usize>,
+        max_nodes: Option<usize>,
+        parse_noscript: bool,
+        parse_conditional_comments: bool,
+        mode: &str,
+    ) -> PyResult<Self> {
+        let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, fix_mojibake)?;
+        let limits = Limits { max_size, max_depth, max_nodes };
+        let parse_options = ParseOptions {
+            parse_noscript,
+            conditional_comments: parse_conditional_comments,
+            mode: ParseMode::parse(mode)?,
+            ..ParseOptions::new(detailed_warnings, timeout, limits)?
+        };
+        WebScraper::from_input(html, &options, &parse_options)
+    }
+
+    /// Parse errors reported by the tree builder (mismatched tags, stray content, bad nesting).
+    /// A sudden rise in warnings often means a page's structure changed.
+    #[getter]
+    pub fn warnings(&self) -> Vec<String> {
+        self.document().errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    /// The document's `<!DOCTYPE>` as `{"name", "public_id", "system_id"}`, or None
+    #[getter]
+    pub fn doctype<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let document = self.document();
+        let Some(doctype) = document.tree.root().children().find_map(|node| match node.value() {
+            Node::Doctype(doctype) => Some(doctype),
+            _ => None,
+        }) else {
+            return Ok(None);
+        };
+        let dict = PyDict::new_bound(py);
+        dict.set_item("name", doctype.name())?;
+        dict.set_item("public_id", doctype.public_id())?;
+        dict.set_item("system_id", doctype.system_id())?;
+        Ok(Some(dict))
+    }
+
+    /// How the tree builder treated the document: "no-quirks" (standards mode), "quirks" or
+    /// "limited-quirks", as set by its doctype. Quirks mode changes how some markup (notably
+    /// `<table>` inside `<p>`) nests.
+    #[getter]
+    pub fn quirks_mode(&self) -> &'static str {
+        match self.document().quirks_mode {
+            QuirksMode::NoQuirks => "no-quirks",
+            QuirksMode::Quirks => "quirks",
+            QuirksMode::LimitedQuirks => "limited-quirks",
         }
     }
 
-    /// Select all elements matching the CSS selector
-    pub fn select(&self, selector: &str) -> PyResult<Vec<Element>> {
-        let sel = get_or_compile_selector(selector)?;
-        Ok(self.document.select(&sel)
-            .map(|elem| Element::new(elem))
-            .collect())
+    /// Encoding the document was decoded from, or None if it was given as str
+    #[getter]
+    pub fn original_encoding(&self) -> Option<String> {
+        self.encoding.as_ref().map(|info| info.encoding.clone())
+    }
+
+    /// How the encoding was determined: "bom", "override", "header", "meta" or "heuristic"
+    #[getter]
+    pub fn encoding_source(&self) -> Option<&'static str> {
+        self.encoding.as_ref().map(|info| info.source.as_str())
+    }
+
+    /// Confidence in the detected encoding, from 0.0 to 1.0
+    #[getter]
+    pub fn encoding_confidence(&self) -> Option<f32> {
+        self.encoding.as_ref().map(|info| info.confidence)
+    }
+
+    /// Remove every element matching `selector`, with its content, from the document.
+    /// Returns how many were removed. `html()` and later selections see the change; Elements
+    /// selected earlier are copies and keep their old content.
+    pub fn remove(&mut self, selector: &str) -> PyResult<usize> {
+        let targets = self.matching_ids(selector, true)?;
+        let document = make_mut(&mut self.document);
+        for &target in &targets {
+            document.tree.get_mut(target).unwrap().detach();
+        }
+        if !targets.is_empty() {
+            let compacted = compact(&self.document());
+            self.document = share(compacted);
+        }
+        Ok(targets.len())
+    }
+
+    /// Replace every element matching `selector` with `html` (any number of nodes, or "" to
+    /// remove it). Returns how many were replaced.
+    pub fn replace_with(&mut self, selector: &str, html: &str) -> PyResult<usize> {
+        self.place(selector, html, Placement::Replace)
+    }
+
+    /// Insert `html` at `position` ("before", "after", "prepend" or "append") relative to
+    /// every element matching `selector`. Returns how many elements it was inserted at.
+    #[pyo3(signature = (selector, html, position="append"))]
+    pub fn insert(&mut self, selector: &str, html: &str, position: &str) -> PyResult<usize> {
+        let placement = Placement::from_arg(position)?;
+        self.place(selector, html, placement)
+    }
+
+    /// An independent copy of the document, edits so far included: `remove()`,
+    /// `replace_with()` and `insert()` on either one leave the other as it was.
+    #[pyo3(name = "clone")]
+    pub fn clone_document(&self) -> WebScraper {
+        Clone::clone(self)
+    }
+
+    fn __copy__(&self) -> WebScraper {
+        Clone::clone(self)
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> WebScraper {
+        Clone::clone(self)
+    }
+
+    /// Pickle support: the document is stored as its serialized HTML, edits included, and
+    /// parsed again on load, so it can be sent to worker processes (multiprocessing, Celery)
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (&'static str,), Bound<'py, PyDict>)> {
+        Ok((slf.get_type().into_any(), ("",), slf.borrow().pickle_state(slf.py())?))
+    }
+
+    fn __setstate__(&mut self, state: &Bound<'_, PyDict>) -> PyResult<()> {
+        *self = WebScraper::from_pickle_state(state)?;
+        Ok(())
+    }
+
+    /// Select all elements matching the CSS selector. With `explain=True`, returns
+    /// `(elements, plan)`: the plan gives `matches`, `elapsed_ms` and, per compound step,
+    /// how many elements the engine `considered` and `rejected` there.
+    #[pyo3(signature = (selector, explain=false))]
+    pub fn select(&self, py: Python, selector: &str, explain: bool) -> PyResult<PyObject> {
+        if !explain {
+            return Ok(self.select_all(selector)?.into_py(py));
+        }
+        let started = Instant::now();
+        let elements = self.select_all(selector)?;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let plan = explain::plan(py, &self.document(), selector, elements.len(), elapsed_ms)?;
+        Ok((elements, plan).into_py(py))
     }
 
     /// Select first element matching the CSS selector
     pub fn select_one(&self, selector: &str) -> PyResult<Option<Element>> {
-        let sel = get_or_compile_selector(selector)?;
-        Ok(self.document.select(&sel)
+        let sel = selector::compile(selector)?;
+        Ok(select_within(&self.document(), &sel, selector, &Deadline::start(self.timeout), Some(1))?
+            .into_iter()
             .next()
-            .map(|elem| Element::new(elem)))
+            .map(|elem| self.element(elem)))
+    }
+
+    /// Explain a selector's result: matches after each compound step, the step that
+    /// eliminated every candidate (`failed_step`), and near-miss `suggestions`
+    pub fn explain(&self, py: Python, selector: &str) -> PyResult<PyObject> {
+        explain::explain(py, &self.document(), selector)
+    }
+
+    /// IE conditional comments, in order, as `{"condition", "html", "revealed"}`: the markup
+    /// hidden inside `<!--[if IE]>...<![endif]-->` (`revealed` False), or the markup between
+    /// `<![if !IE]>` and `<![endif]>` that other browsers render (`revealed` True). Parse with
+    /// `parse_conditional_comments=True` to make hidden markup part of the tree instead.
+    pub fn conditional_comments<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        conditional::conditional_comments(py, &self.document())
+    }
+
+    /// Text of the document's HTML comments, in order. `pattern` (a str to look for, or a
+    /// compiled regex) keeps only matching ones; `limit` stops after that many.
+    #[pyo3(signature = (pattern=None, limit=None))]
+    pub fn comments(&self, pattern: Option<&Bound<'_, PyAny>>, limit: Option<usize>) -> PyResult<Vec<String>> {
+        comments_in(self.document().tree.root(), pattern, limit)
+    }
+
+    /// Extract all text from the document, optionally Unicode-normalized ("NFC", "NFKC", ...)
+    #[pyo3(signature = (normalize=None))]
+    pub fn text(&self, normalize: Option<&str>) -> PyResult<String> {
+        let text = text::element_text(self.document().root_element(), true);
+        Ok(text::normalize(text, Normalization::from_arg(normalize)?))
     }
 
-    /// Extract all text from the document
-    pub fn text(&self) -> String {
-        self.document.root_element()
-            .text()
-            .collect::<Vec<_>>()
-            .join(" ")
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ")
+    /// Get the HTML of the entire document.
+    /// With `encoding`, returns bytes in that encoding, unmappable characters as `&#NNNN;`.
+    /// `indent` (spaces per level) pretty-prints; `quote` ("double", "single", "minimal"),
+    /// `entities` ("minimal", "ascii") and `void` ("html", "xhtml") set the output style.
+    #[pyo3(signature = (encoding=None, indent=None, quote=None, entities=None, void=None))]
+    pub fn html(
+        &self,
+        py: Python,
+        encoding: Option<&str>,
+        indent: Option<usize>,
+        quote: Option<&str>,
+        entities: Option<&str>,
+        void: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let html = self.formatted_html(SerializeOptions::from_args(indent, quote, entities, void)?)?;
+        match encoding {
+            Some(label) => Ok(PyBytes::new_bound(py, &encode_string(&html, label)?).into()),
+            None => Ok(PyString::new_bound(py, &html).into()),
+        }
     }
 
-    /// Get the HTML of the entire document
-    fn html(&self) -> String {
-        self.document.html()
+    /// The document as nested dicts of `tag`, `attrs` and `children` (text as strings), for
+    /// storing or diffing with JSON tooling. Below `max_depth` levels elements carry `text`
+    /// instead of `children`; whitespace-only text is dropped unless `keep_whitespace`.
+    #[pyo3(signature = (max_depth=None, keep_whitespace=false))]
+    fn to_dict<'py>(&self, py: Python<'py>, max_depth: Option<usize>, keep_whitespace: bool) -> PyResult<Bound<'py, PyDict>> {
+        markup::element_to_dict(py, self.document().root_element(), max_depth, keep_whitespace)
     }
 
     /// Extract all links (href attributes) from the document
     fn links(&self) -> PyResult<Vec<String>> {
-        let sel = get_or_compile_selector("a[href]")?;
-        Ok(self.document.select(&sel)
+        let sel = selector::compile("a[href]")?;
+        Ok(sel.select(&self.document())
             .filter_map(|elem| elem.value().attr("href"))
             .map(|s| s.to_string())
             .collect())
@@ -69,8 +834,8 @@ impl WebScraper {
 
     /// Extract all image sources
     fn images(&self) -> PyResult<Vec<String>> {
-        let sel = get_or_compile_selector("img[src]")?;
-        Ok(self.document.select(&sel)
+        let sel = selector::compile("img[src]")?;
+        Ok(sel.select(&self.document())
             .filter_map(|elem| elem.value().attr("src"))
             .map(|s| s.to_string())
             .collect())
@@ -80,7 +845,7 @@ impl WebScraper {
     /// Supports both text and attribute extraction
     /// Use format "selector@attr" to extract attribute, e.g. "a@href"
     fn extract_grid(&self, py: Python, container_sel: &str, field_selectors: HashMap<String, String>) -> PyResult<PyObject> {
-        let container = get_or_compile_selector(container_sel)?;
+        let container = selector::compile(container_sel)?;
         let py_list = PyList::empty_bound(py);
         
         // Pre-compile selectors and parse attribute specifications
@@ -90,7 +855,7 @@ impl WebScraper {
                 // Format: "selector@attribute"
                 let parts: Vec<&str> = spec.split('@').collect();
                 if parts.len() == 2 {
-                    let selector = get_or_compile_selector(parts[0])?;
+                    let selector = selector::compile(parts[0])?;
                     compiled_specs.insert(field.clone(), (selector, Some(parts[1].to_string())));
                 } else {
                     return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
@@ -99,23 +864,23 @@ impl WebScraper {
                 }
             } else {
                 // Regular text selector
-                let selector = get_or_compile_selector(spec)?;
+                let selector = selector::compile(spec)?;
                 compiled_specs.insert(field.clone(), (selector, None));
             }
         }
         
-        for elem in self.document.select(&container) {
+        for elem in container.select(&self.document()) {
             let item_dict = PyDict::new_bound(py);
-            let elem_html = Html::parse_fragment(&elem.html());
+            let elem_html = reparse(&elem.html());
             
             for (field, (selector, attr_name)) in &compiled_specs {
-                if let Some(found) = elem_html.select(selector).next() {
+                if let Some(found) = selector.select(&elem_html).next() {
                     let value = if let Some(attr) = attr_name {
                         // Extract attribute
                         found.value().attr(attr).unwrap_or("").to_string()
                     } else {
                         // Extract text
-                        found.text().collect::<Vec<_>>().join(" ").trim().to_string()
+                        text::element_text(found, false)
                     };
                     item_dict.set_item(field.as_str(), value)?;
                 } else {
@@ -129,55 +894,16 @@ impl WebScraper {
         Ok(py_list.into())
     }
 
-    /// XPath selection - returns list of matching elements
+    /// XPath 1.0 selection - returns matching elements
     fn xpath(&self, xpath_expr: &str) -> PyResult<Vec<Element>> {
-        let html_str = self.document.html();
-        match parser::parse(&html_str) {
-            Ok(package) => {
-                let doc = package.as_document();
-                match evaluate_xpath(&doc, xpath_expr) {
-                    Ok(Value::Nodeset(nodeset)) => {
-                        let mut elements = Vec::new();
-                        for node in nodeset.iter() {
-                            if let Node::Element(elem) = node {
-                                // Convert sxd element to our Element type
-                                let mut elem_html = format!("<{}", elem.name().local_part());
-                                // Add attributes
-                                for attr in elem.attributes() {
-                                    elem_html.push_str(&format!(" {}=\"{}\"", attr.name().local_part(), attr.value()));
-                                }
-                                elem_html.push('>');
-                                
-                                // Add text content
-                                let text_content = get_element_text(&elem);
-                                elem_html.push_str(&text_content);
-                                elem_html.push_str(&format!("</{}>", elem.name().local_part()));
-                                
-                                let parsed = Html::parse_fragment(&elem_html);
-                                if let Some(element_ref) = parsed.select(&Selector::parse("*").unwrap()).next() {
-                                    elements.push(Element::new(element_ref));
-                                }
-                            }
-                        }
-                        Ok(elements)
-                    }
-                    Ok(Value::String(s)) => {
-                        // Return as text-only element
-                        let elem_html = format!("<span>{}</span>", s);
-                        let parsed = Html::parse_fragment(&elem_html);
-                        if let Some(element_ref) = parsed.select(&Selector::parse("span").unwrap()).next() {
-                            Ok(vec![Element::new(element_ref)])
-                        } else {
-                            Ok(vec![])
-                        }
-                    }
-                    _ => Ok(vec![]),
-                }
-            }
-            Err(_) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Failed to parse HTML for XPath"
-            ))
-        }
+        let compiled = xpath::compile(xpath_expr)?;
+        Ok(xpath::evaluate(self.document().tree.root(), &compiled, xpath_expr)?
+            .into_iter()
+            .filter_map(|item| match item {
+                XPathItem::Element(elem) => Some(self.element(elem)),
+                XPathItem::Text(_) => None,
+            })
+            .collect())
     }
 
     /// XPath selection - returns first matching element
@@ -188,39 +914,11 @@ impl WebScraper {
 
     /// Get all matching XPath results as strings (Scrapy-compatible)
     fn xpath_getall(&self, xpath_expr: &str) -> PyResult<Vec<String>> {
-        let html_str = self.document.html();
-        match parser::parse(&html_str) {
-            Ok(package) => {
-                let doc = package.as_document();
-                match evaluate_xpath(&doc, xpath_expr) {
-                    Ok(Value::Nodeset(nodeset)) => {
-                        let mut results = Vec::new();
-                        for node in nodeset.iter() {
-                            match node {
-                                Node::Element(elem) => {
-                                    results.push(get_element_text(&elem));
-                                }
-                                Node::Text(text) => {
-                                    results.push(text.text().to_string());
-                                }
-                                Node::Attribute(attr) => {
-                                    results.push(attr.value().to_string());
-                                }
-                                _ => {}
-                            }
-                        }
-                        Ok(results)
-                    }
-                    Ok(Value::String(s)) => Ok(vec![s]),
-                    Ok(Value::Number(n)) => Ok(vec![n.to_string()]),
-                    Ok(Value::Boolean(b)) => Ok(vec![b.to_string()]),
-                    _ => Ok(vec![]),
-                }
-            }
-            Err(_) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                "Failed to parse HTML for XPath"
-            ))
-        }
+        let compiled = xpath::compile(xpath_expr)?;
+        Ok(xpath::evaluate(self.document().tree.root(), &compiled, xpath_expr)?
+            .into_iter()
+            .map(XPathItem::into_string)
+            .collect())
     }
 
     /// Get first XPath result as string (Scrapy-compatible)
@@ -230,25 +928,35 @@ impl WebScraper {
     }
 }
 
+/// A selected element. Elements from `select()` and the like are handles on the document
+/// they came from, and build their HTML, text and attributes the first time those are read,
+/// so selecting thousands of nodes to read a few fields does not convert them all.
 #[pyclass]
 #[derive(Clone)]
 pub struct Element {
-    html: String,
-    tag_name: String,
-    attributes: HashMap<String, String>,
-    text_content: String,
+    /// The document and node this element was selected from, `None` for one built whole
+    node: Option<(SharedHtml, NodeId)>,
+    tag_name: LocalName,
+    html: OnceCell<String>,
+    /// In source order
+    attributes: OnceCell<Vec<(String, String)>>,
+    text_content: OnceCell<String>,
+    /// From a `parse_xml` document: `html` is XML
+    xml: bool,
 }
 
 #[pymethods]
 impl Element {
-    /// Get the text content of the element
-    fn text(&self) -> String {
-        self.text_content.clone()
+    /// Get the text content of the element, optionally Unicode-normalized ("NFC", "NFKC", ...)
+    #[pyo3(signature = (normalize=None))]
+    fn text(&self, normalize: Option<&str>) -> PyResult<String> {
+        let text = self.text_content.get_or_init(|| self.with_elem(|elem| text::element_text(elem, true)));
+        Ok(text::normalize(text.clone(), Normalization::from_arg(normalize)?))
     }
 
     /// BeautifulSoup-like .get(attr, default=None)
     fn get(&self, py: Python, name: &str, default: Option<PyObject>) -> PyObject {
-        if let Some(val) = self.attributes.get(name) {
+        if let Some(val) = self.attribute(name) {
             PyString::new_bound(py, val).into()
         } else {
             default.unwrap_or_else(|| py.None())
@@ -257,124 +965,126 @@ impl Element {
 
     /// Get an attribute value
     fn attr(&self, name: &str) -> Option<String> {
-        self.attributes.get(name).cloned()
+        self.attribute(name).cloned()
     }
 
-    /// Get all attributes as a dict
+    /// Get all attributes as a dict, in source order. Names are interned, so the dicts of
+    /// many elements share their keys.
     fn attrs(&self, py: Python) -> PyResult<PyObject> {
         let dict = PyDict::new_bound(py);
-        for (key, value) in &self.attributes {
-            dict.set_item(key, value)?;
+        for (key, value) in self.attributes() {
+            dict.set_item(PyString::intern_bound(py, key), value)?;
         }
         Ok(dict.into())
     }
 
-    /// Get the tag name
-    fn tag(&self) -> String {
-        self.tag_name.clone()
+    /// Get the tag name, as an interned str shared by every element with that name
+    fn tag<'py>(&self, py: Python<'py>) -> Bound<'py, PyString> {
+        PyString::intern_bound(py, &self.tag_name)
+    }
+
+    /// Get the HTML of this element, with the same formatting options as `WebScraper.html`
+    #[pyo3(signature = (indent=None, quote=None, entities=None, void=None))]
+    fn html(&self, indent: Option<usize>, quote: Option<&str>, entities: Option<&str>, void: Option<&str>) -> PyResult<String> {
+        let Some(options) = SerializeOptions::from_args(indent, quote, entities, void)? else {
+            return Ok(self.markup().to_string());
+        };
+        if self.xml {
+            return Err(xml_formatting_error());
+        }
+        let fragment = self.fragment()?;
+        Ok(fragment.root_element().children().find(|node| node.value().is_element()).map_or_else(String::new, |node| options.write(node)))
     }
 
-    /// Get the HTML of this element
-    fn html(&self) -> String {
-        self.html.clone()
+    /// A new document holding a copy of this element and its descendants, with the full
+    /// `WebScraper` API, to edit with `remove()`, `replace_with()` and `insert()` while the
+    /// page it came from stays as it is. Table rows, cells and list items keep their tags.
+    fn clone_subtree(&self) -> PyResult<WebScraper> {
+        if self.xml {
+            return Ok(WebScraper::from_xml(xml::parse(self.markup())?));
+        }
+        let options = ParseOptions::default();
+        let deadline = Deadline::start(None);
+        let (document, context) = match &*self.tag_name {
+            "html" | "head" | "body" => (parse_document(self.markup(), &options, &deadline)?, None),
+            // Template contents accept any element, where a body context would drop `<tr>` or `<td>`
+            _ => (parse_fragment_in(self.markup(), "template", &options, &deadline)?, Some("template".to_string())),
+        };
+        Ok(WebScraper { document: share(document), encoding: None, timeout: None, xml: false, context, parse_noscript: false })
     }
 
     /// Select within this element
     fn select(&self, selector: &str) -> PyResult<Vec<Element>> {
-        let html = Html::parse_fragment(&self.html);
-        let sel = get_or_compile_selector(selector)?;
-        Ok(html.select(&sel)
-            .map(|elem| Element::new(elem))
+        let html = share(self.fragment()?);
+        let sel = selector::compile(selector)?;
+        let fragment = lock(&html);
+        Ok(sel.select(&fragment)
+            .map(|elem| Element::handle(&html, elem, self.xml))
             .collect())
     }
 
     /// Select one within this element
     fn select_one(&self, selector: &str) -> PyResult<Option<Element>> {
-        let html = Html::parse_fragment(&self.html);
-        let sel = get_or_compile_selector(selector)?;
-        Ok(html.select(&sel)
-            .next()
-            .map(|elem| Element::new(elem)))
+        let html = share(self.fragment()?);
+        let sel = selector::compile(selector)?;
+        let fragment = lock(&html);
+        let found = sel.select(&fragment).next();
+        Ok(found.map(|elem| Element::handle(&html, elem, self.xml)))
     }
 
     /// Check if element has a class
     fn has_class(&self, class_name: &str) -> bool {
-        self.attributes.get("class")
+        self.attribute("class")
             .map(|classes| classes.split_whitespace().any(|c| c == class_name))
             .unwrap_or(false)
     }
 
     /// Get element id
     fn id(&self) -> Option<String> {
-        self.attributes.get("id").cloned()
+        self.attribute("id").cloned()
+    }
+
+    /// Text of the HTML comments inside this element, as `WebScraper.comments`
+    #[pyo3(signature = (pattern=None, limit=None))]
+    fn comments(&self, pattern: Option<&Bound<'_, PyAny>>, limit: Option<usize>) -> PyResult<Vec<String>> {
+        comments_in(self.fragment()?.tree.root(), pattern, limit)
+    }
+
+    /// The document inlined in an `<iframe srcdoc>`, parsed like `parse_html`. `None` for
+    /// other elements and for iframes without `srcdoc`.
+    fn srcdoc_document(&self) -> PyResult<Option<WebScraper>> {
+        if self.xml || &*self.tag_name != "iframe" {
+            return Ok(None);
+        }
+        self.attribute("srcdoc")
+            .map(|srcdoc| WebScraper::parse(srcdoc, &ParseOptions::default()))
+            .transpose()
     }
 
     /// XPath selection within this element
     fn xpath(&self, xpath_expr: &str) -> PyResult<Vec<Element>> {
-        match parser::parse(&self.html) {
-            Ok(package) => {
-                let doc = package.as_document();
-                match evaluate_xpath(&doc, xpath_expr) {
-                    Ok(Value::Nodeset(nodeset)) => {
-                        let mut elements = Vec::new();
-                        for node in nodeset.iter() {
-                            if let Node::Element(elem) = node {
-                                let mut elem_html = format!("<{}", elem.name().local_part());
-                                for attr in elem.attributes() {
-                                    elem_html.push_str(&format!(" {}=\"{}\"", attr.name().local_part(), attr.value()));
-                                }
-                                elem_html.push('>');
-                                let text_content = get_element_text(&elem);
-                                elem_html.push_str(&text_content);
-                                elem_html.push_str(&format!("</{}>", elem.name().local_part()));
-                                
-                                let parsed = Html::parse_fragment(&elem_html);
-                                if let Some(element_ref) = parsed.select(&Selector::parse("*").unwrap()).next() {
-                                    elements.push(Element::new(element_ref));
-                                }
-                            }
-                        }
-                        Ok(elements)
-                    }
-                    _ => Ok(vec![]),
-                }
-            }
-            Err(_) => Ok(vec![])
-        }
+        let compiled = xpath::compile(xpath_expr)?;
+        let html = share(self.fragment()?);
+        let fragment = lock(&html);
+        let Some(context) = fragment.root_element().children().find(|node| node.value().is_element()) else { return Ok(vec![]) };
+        Ok(xpath::evaluate(context, &compiled, xpath_expr)?
+            .into_iter()
+            .filter_map(|item| match item {
+                XPathItem::Element(elem) => Some(Element::handle(&html, elem, self.xml)),
+                XPathItem::Text(_) => None,
+            })
+            .collect())
     }
 
     /// XPath get all as strings (Scrapy-compatible)
     fn xpath_getall(&self, xpath_expr: &str) -> PyResult<Vec<String>> {
-        match parser::parse(&self.html) {
-            Ok(package) => {
-                let doc = package.as_document();
-                match evaluate_xpath(&doc, xpath_expr) {
-                    Ok(Value::Nodeset(nodeset)) => {
-                        let mut results = Vec::new();
-                        for node in nodeset.iter() {
-                            match node {
-                                Node::Element(elem) => {
-                                    results.push(get_element_text(&elem));
-                                }
-                                Node::Text(text) => {
-                                    results.push(text.text().to_string());
-                                }
-                                Node::Attribute(attr) => {
-                                    results.push(attr.value().to_string());
-                                }
-                                _ => {}
-                            }
-                        }
-                        Ok(results)
-                    }
-                    Ok(Value::String(s)) => Ok(vec![s]),
-                    Ok(Value::Number(n)) => Ok(vec![n.to_string()]),
-                    Ok(Value::Boolean(b)) => Ok(vec![b.to_string()]),
-                    _ => Ok(vec![]),
-                }
-            }
-            Err(_) => Ok(vec![])
-        }
+        let compiled = xpath::compile(xpath_expr)?;
+        let fragment = self.fragment()?;
+        let Some(context) = fragment.root_element().children().find(|node| node.value().is_element()) else { return Ok(vec![]) };
+        Ok(xpath::evaluate(context, &compiled, xpath_expr)?
+            .into_iter()
+            .map(XPathItem::into_string)
+            .collect())
     }
 
     /// XPath get first as string (Scrapy-compatible)
@@ -383,11 +1093,21 @@ impl Element {
         Ok(results.into_iter().next())
     }
 
+    /// This element as nested dicts, like `WebScraper.to_dict`
+    #[pyo3(signature = (max_depth=None, keep_whitespace=false))]
+    fn to_dict<'py>(&self, py: Python<'py>, max_depth: Option<usize>, keep_whitespace: bool) -> PyResult<Bound<'py, PyDict>> {
+        let fragment = self.fragment()?;
+        match fragment.root_element().children().find_map(ElementRef::wrap) {
+            Some(elem) => markup::element_to_dict(py, elem, max_depth, keep_whitespace),
+            None => Ok(PyDict::new_bound(py)),
+        }
+    }
+
     fn __repr__(&self) -> String {
         format!("<Element '{}' {}>", self.tag_name, 
             if let Some(id) = self.id() {
                 format!("id='{}'", id)
-            } else if let Some(class) = self.attributes.get("class") {
+            } else if let Some(class) = self.attribute("class") {
                 format!("class='{}'", class)
             } else {
                 String::new()
@@ -398,82 +1118,283 @@ impl Element {
 }
 
 impl Element {
-    fn new(elem: ElementRef) -> Self {
-        let mut attributes = HashMap::new();
-        for attr in elem.value().attrs() {
-            attributes.insert(attr.0.to_string(), attr.1.to_string());
+    /// A copy of `elem` built whole, for elements whose document is only borrowed
+    pub(crate) fn new(elem: ElementRef) -> Self {
+        Element {
+            node: None,
+            tag_name: elem.value().name.local.clone(),
+            html: OnceCell::from(elem.html()),
+            attributes: OnceCell::from(Element::attributes_of(elem)),
+            text_content: OnceCell::from(text::element_text(elem, true)),
+            xml: false,
         }
-        
-        let text_content = elem.text()
-            .collect::<Vec<_>>()
-            .join(" ")
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ");
-        
+    }
+
+    /// A handle on `elem` in `document`, with `xml` for `parse_xml` documents
+    fn handle(document: &SharedHtml, elem: ElementRef, xml: bool) -> Self {
         Element {
-            html: elem.html(),
-            tag_name: elem.value().name().to_string(),
-            attributes,
-            text_content,
+            node: Some((Arc::clone(document), elem.id())),
+            tag_name: elem.value().name.local.clone(),
+            html: OnceCell::new(),
+            attributes: OnceCell::new(),
+            text_content: OnceCell::new(),
+            xml,
         }
     }
-}
 
-fn get_or_compile_selector(selector: &str) -> PyResult<Selector> {
-    let mut cache = SELECTOR_CACHE.lock().unwrap();
-    
-    if let Some(sel) = cache.get(selector) {
-        return Ok(sel.clone());
+    fn attributes_of(elem: ElementRef) -> Vec<(String, String)> {
+        elem.value().attrs().map(|(name, value)| (name.to_string(), value.to_string())).collect()
     }
-    
-    match Selector::parse(selector) {
-        Ok(sel) => {
-            cache.insert(selector.to_string(), sel.clone());
-            Ok(sel)
+
+    /// Read the node a handle points at. Elements built whole have every field set already.
+    fn with_elem<T>(&self, read: impl FnOnce(ElementRef) -> T) -> T {
+        let (document, id) = self.node.as_ref().expect("Element built whole has no node");
+        let document = lock(document);
+        read(ElementRef::wrap(document.tree.get(*id).unwrap()).unwrap())
+    }
+
+    fn markup(&self) -> &str {
+        self.html.get_or_init(|| self.with_elem(|elem| if self.xml { xml::serialize(elem) } else { elem.html() }))
+    }
+
+    fn attributes(&self) -> &[(String, String)] {
+        self.attributes.get_or_init(|| self.with_elem(Element::attributes_of))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&String> {
+        self.attributes().iter().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// This element re-parsed on its own, for selecting within it
+    fn fragment(&self) -> PyResult<Html> {
+        if self.xml {
+            return xml::parse_fragment(self.markup());
         }
-        Err(_) => Err(PyErr::new::<crate::errors::SelectorError, _>(
-            format!("Invalid CSS selector: {}", selector)
-        ))
+        Ok(reparse(self.markup()))
     }
 }
 
-fn get_element_text(elem: &sxd_document::dom::Element) -> String {
-    let mut text = String::new();
-    for child in elem.children() {
-        match child {
-            sxd_document::dom::ChildOfElement::Text(text_node) => {
-                text.push_str(text_node.text());
-            }
-            sxd_document::dom::ChildOfElement::Element(child_elem) => {
-                text.push_str(&get_element_text(&child_elem));
+fn xml_formatting_error() -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>("Formatting options apply to HTML; parse_xml documents are written as XML")
+}
+
+
+/// Parse HTML (str or bytes) and return a WebScraper instance. With `parse_noscript=True`,
+/// `<noscript>` content is parsed as markup so fallback images and data in it can be selected.
+/// Snippets of plain markup up to 1 KiB are built by a lightweight parser instead of the
+/// html5ever tree builder (`mode="auto"`); `mode="fragment"` uses it at any size and
+/// `mode="document"` never. The tree is the same either way: markup the lightweight parser
+/// cannot build exactly as html5ever would goes to html5ever.
+#[pyfunction]
+#[pyo3(signature = (html, from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None, parse_noscript=false, parse_conditional_comments=false, mode="auto"))]
+#[allow(clippy::too_many_arguments)]
+pub fn parse_html(
+    html: HtmlInput,
+    from_encoding: Option<&str>,
+    exclude_encodings: Option<Vec<String>>,
+    errors: Option<&str>,
+    content_type: Option<&str>,
+    fix_mojibake: bool,
+    detailed_warnings: bool,
+    timeout: Option<f64>,
+    max_size: Option<usize>,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    parse_noscript: bool,
+    parse_conditional_comments: bool,
+    mode: &str,
+) -> PyResult<WebScraper> {
+    WebScraper::new(html, from_encoding, exclude_encodings, errors, content_type, fix_mojibake, detailed_warnings, timeout, max_size, max_depth, max_nodes, parse_noscript, parse_conditional_comments, mode)
+}
+
+/// A tree builder fed a chunk at a time, with node and depth limits checked as it grows
+enum IncrementalParser {
+    Plain(driver::Parser<HtmlTreeSink>),
+    Limited(driver::Parser<LimitedSink>),
+}
+
+impl IncrementalParser {
+    fn process(&mut self, text: &str) -> PyResult<()> {
+        match self {
+            IncrementalParser::Plain(parser) => parser.process(StrTendril::from_slice(text)),
+            IncrementalParser::Limited(parser) => {
+                parser.process(StrTendril::from_slice(text));
+                parser.tokenizer.sink.sink.check()?;
             }
-            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Html {
+        match self {
+            IncrementalParser::Plain(parser) => parser.finish(),
+            IncrementalParser::Limited(parser) => parser.finish(),
+        }
+    }
+}
+
+/// Builds a document from chunks as they arrive, e.g. from a streaming HTTP response, so
+/// parsing overlaps the download instead of starting after it. Bytes chunks are decoded as by
+/// `StreamDecoder`. `max_size`, `max_depth` and `max_nodes` are checked after every chunk, so
+/// an oversized response can be abandoned early; `timeout` applies to the finished
+/// document's `select()` calls.
+#[pyclass(unsendable)]
+pub struct StreamingParser {
+    parser: Option<IncrementalParser>,
+    decoder: StreamDecoder,
+    bytes_seen: bool,
+    options: ParseOptions,
+    /// Decoded input so far, for `max_size`
+    size: usize,
+    started: Instant,
+}
+
+impl StreamingParser {
+    fn parser(&mut self) -> PyResult<&mut IncrementalParser> {
+        self.parser.as_mut().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("StreamingParser is already closed")
+        })
+    }
+
+    fn process(&mut self, text: &str) -> PyResult<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.size += text.len();
+        self.options.limits.check_size(self.size)?;
+        self.parser()?.process(text)
+    }
+
+    fn process_bytes(&mut self, bytes: &[u8]) -> PyResult<()> {
+        self.bytes_seen = true;
+        let text = self.decoder.feed_bytes(bytes)?;
+        self.process(&text)
+    }
+}
+
+#[pymethods]
+impl StreamingParser {
+    #[new]
+    #[pyo3(signature = (from_encoding=None, exclude_encodings=None, errors=None, content_type=None, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None, parse_noscript=false, parse_conditional_comments=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        from_encoding: Option<&str>,
+        exclude_encodings: Option<Vec<String>>,
+        errors: Option<&str>,
+        content_type: Option<&str>,
+        detailed_warnings: bool,
+        timeout: Option<f64>,
+        max_size: Option<usize>,
+        max_depth: Option<usize>,
+        max_nodes: Option<usize>,
+        parse_noscript: bool,
+        parse_conditional_comments: bool,
+    ) -> PyResult<Self> {
+        let decode_options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, false)?;
+        let limits = Limits { max_size, max_depth, max_nodes };
+        let options = ParseOptions { parse_noscript, conditional_comments: parse_conditional_comments, ..ParseOptions::new(detailed_warnings, timeout, limits)? };
+        let sink = HtmlTreeSink::new(Html::new_document());
+        let parser = if options.limits.is_set() {
+            IncrementalParser::Limited(driver::parse_document(LimitedSink::new(sink, options.limits), parse_opts(&options)))
+        } else {
+            IncrementalParser::Plain(driver::parse_document(sink, parse_opts(&options)))
+        };
+        logging::emit(logging::DEBUG, || "streaming parse start".to_string());
+        Ok(StreamingParser {
+            parser: Some(parser),
+            decoder: StreamDecoder::with_options(decode_options),
+            bytes_seen: false,
+            options,
+            size: 0,
+            started: Instant::now(),
+        })
+    }
+
+    /// Feed the next chunk (str or bytes) to the parser
+    fn feed(&mut self, data: HtmlInput) -> PyResult<()> {
+        self.parser()?;
+        match data {
+            HtmlInput::Str(text) => self.process(text.to_str()?),
+            HtmlInput::Bytes(bytes) => self.process_bytes(bytes.as_bytes()),
+            HtmlInput::Buffer(buffer) => self.process_bytes(buffer.as_bytes()),
         }
     }
-    text.trim().to_string()
+
+    /// Signal the end of input and return the finished document
+    fn close(&mut self) -> PyResult<WebScraper> {
+        self.parser()?;
+        if self.bytes_seen {
+            let rest = self.decoder.finish()?;
+            self.process(&rest)?;
+        }
+        let mut document = self.parser.take().map(IncrementalParser::finish).unwrap_or_else(Html::new_document);
+        inline_templates(&mut document);
+        if self.options.conditional_comments {
+            conditional::expand(&mut document, None)?;
+        }
+        log_parsed(&document, self.started);
+        Ok(WebScraper {
+            document: share(document),
+            encoding: self.decoder.info().cloned(),
+            timeout: self.options.timeout,
+            xml: false,
+            context: None,
+            parse_noscript: self.options.parse_noscript,
+        })
+    }
+
+    /// Number of characters fed so far, after decoding
+    #[getter]
+    fn size(&self) -> usize {
+        self.size
+    }
 }
 
-/// Parse HTML and return a WebScraper instance
+/// Parse an HTML snippet (str or bytes) as the content of a `context` element and return a
+/// WebScraper instance. Snippets such as bare `<td>` cells or `<li>` items need a matching
+/// context (`"tr"`, `"ul"`): parsed as a document or in `<body>`, the tree builder drops them.
 #[pyfunction]
-pub fn parse_html(html: &str) -> WebScraper {
-    WebScraper::new(html)
+#[pyo3(signature = (html, context="body", from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, detailed_warnings=false, timeout=None, max_size=None, max_depth=None, max_nodes=None, parse_noscript=false, parse_conditional_comments=false))]
+#[allow(clippy::too_many_arguments)]
+pub fn parse_fragment(
+    html: HtmlInput,
+    context: &str,
+    from_encoding: Option<&str>,
+    exclude_encodings: Option<Vec<String>>,
+    errors: Option<&str>,
+    content_type: Option<&str>,
+    fix_mojibake: bool,
+    detailed_warnings: bool,
+    timeout: Option<f64>,
+    max_size: Option<usize>,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    parse_noscript: bool,
+    parse_conditional_comments: bool,
+) -> PyResult<WebScraper> {
+    let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, fix_mojibake)?;
+    let limits = Limits { max_size, max_depth, max_nodes };
+    let parse_options = ParseOptions { parse_noscript, conditional_comments: parse_conditional_comments, ..ParseOptions::new(detailed_warnings, timeout, limits)? };
+    let (text, encoding) = html.decode_with_info(&options)?;
+    let document = parse_fragment_in(&text, context, &parse_options, &Deadline::start(parse_options.timeout))?;
+    let context = Some(context.trim().to_ascii_lowercase());
+    Ok(WebScraper { document: share(document), encoding, timeout: parse_options.timeout, xml: false, context, parse_noscript })
 }
 
 /// Parse HTML and extract data in one go
 #[pyfunction]
-pub fn extract(py: Python, html: &str, selectors: HashMap<String, String>) -> PyResult<PyObject> {
+#[pyo3(signature = (html, selectors, normalize=None))]
+pub fn extract(py: Python, html: &str, selectors: HashMap<String, String>, normalize: Option<&str>) -> PyResult<PyObject> {
+    let normalize = Normalization::from_arg(normalize)?;
     let doc = Html::parse_document(html);
     let result = PyDict::new_bound(py);
     
     for (field, selector) in selectors {
-        let sel = get_or_compile_selector(&selector)?;
-        if let Some(elem) = doc.select(&sel).next() {
-            let text = elem.text().collect::<Vec<_>>().join(" ").trim().to_string();
-            result.set_item(field, text)?;
-        } else {
-            result.set_item(field, py.None())?;
-        }
+        let sel = selector::compile(&selector)?;
+        match sel.select(&doc).next() {
+            Some(elem) => result.set_item(field, text::normalize(text::element_text(elem, false), normalize))?,
+            None => result.set_item(field, py.None())?,
+        };
     }
     
     Ok(result.into())
@@ -481,12 +1402,14 @@ pub fn extract(py: Python, html: &str, selectors: HashMap<String, String>) -> Py
 
 /// Extract all matching elements' text
 #[pyfunction]
-pub fn extract_all(html: &str, selector: &str) -> PyResult<Vec<String>> {
+#[pyo3(signature = (html, selector, normalize=None))]
+pub fn extract_all(html: &str, selector: &str, normalize: Option<&str>) -> PyResult<Vec<String>> {
+    let normalize = Normalization::from_arg(normalize)?;
     let doc = Html::parse_document(html);
-    let sel = get_or_compile_selector(selector)?;
+    let sel = selector::compile(selector)?;
     
-    Ok(doc.select(&sel)
-        .map(|elem| elem.text().collect::<Vec<_>>().join(" ").trim().to_string())
+    Ok(sel.select(&doc)
+        .map(|elem| text::normalize(text::element_text(elem, false), normalize))
         .filter(|s| !s.is_empty())
         .collect())
 }
\ No newline at end of file
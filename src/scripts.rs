@@ -0,0 +1,187 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use scraper::{Html, Selector};
+use once_cell::sync::Lazy;
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::logging;
+use crate::structured::clean_json;
+
+static SCRIPT_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("script:not([src])").unwrap());
+
+/// Script types that hold JavaScript or JSON rather than templates or other languages
+const SCRIPT_TYPES: [&str; 5] = ["", "text/javascript", "application/javascript", "module", "application/json"];
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// The JSON values assigned to `name` in `script`, in order: `name = {...}`,
+/// `window.name = [...]`, `window["name"] = {...}` or `name = JSON.parse("...")`. A value ends
+/// at its matching bracket, so the rest of the script may follow it.
+fn assigned_json(script: &str, name: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    for (at, _) in script.match_indices(name) {
+        if script[..at].chars().next_back().is_some_and(is_identifier_char) {
+            continue;
+        }
+        let rest = &script[at + name.len()..];
+        if rest.starts_with(is_identifier_char) {
+            continue;
+        }
+        let rest = rest.trim_start_matches(['"', '\'']).trim_start_matches(']').trim_start();
+        let Some(value) = rest.strip_prefix('=').filter(|v| !v.starts_with('=')) else { continue };
+        let value = value.trim_start();
+        if let Some(literal) = value.strip_prefix("JSON.parse(") {
+            values.extend(string_literal(literal.trim_start()));
+        } else if let Some(json) = balanced(value) {
+            values.push(json.to_string());
+        }
+    }
+    values
+}
+
+/// The object or array literal at the start of `text`, up to its matching bracket. Brackets
+/// inside strings and comments do not count.
+fn balanced(text: &str) -> Option<&str> {
+    if !text.starts_with(['{', '[']) {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(q) => {
+                if b == b'\\' {
+                    i += 1;
+                } else if b == q {
+                    quote = None;
+                }
+            }
+            None => match b {
+                b'"' | b'\'' | b'`' => quote = Some(b),
+                b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                    i += text[i..].find('\n').unwrap_or(text.len() - i);
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    i += text[i + 2..].find("*/").map_or(text.len() - i, |end| end + 3);
+                }
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&text[..=i]);
+                    }
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The value of the JavaScript string literal at the start of `text`, with escapes decoded
+fn string_literal(text: &str) -> Option<String> {
+    let mut chars = text.chars();
+    let quote = chars.next().filter(|q| matches!(q, '"' | '\'' | '`'))?;
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                '0' => out.push('\0'),
+                'x' => out.push(hex_char(&mut chars, 2)?),
+                'u' => {
+                    let unit = hex_char(&mut chars, 4)? as u32;
+                    // A surrogate pair is two `\u` escapes
+                    if (0xD800..0xDC00).contains(&unit) {
+                        let low = chars.as_str().strip_prefix("\\u").and_then(|s| s.get(..4))
+                            .and_then(|h| u32::from_str_radix(h, 16).ok())
+                            .filter(|low| (0xDC00..0xE000).contains(low))?;
+                        chars.nth(5);
+                        out.push(char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))?);
+                    } else {
+                        out.push(char::from_u32(unit)?);
+                    }
+                }
+                '\n' => {}
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn hex_char(chars: &mut std::str::Chars, digits: usize) -> Option<char> {
+    let hex: String = chars.by_ref().take(digits).collect();
+    if hex.len() != digits {
+        return None;
+    }
+    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Application state embedded in inline scripts, as `{pattern: parsed value}`. A pattern
+/// matches a script whose `id` it is and whose whole content is JSON (Next.js'
+/// `<script id="__NEXT_DATA__">`), or a variable or `window` property it names assigned an
+/// object or array literal or `JSON.parse("...")`. The first occurrence of each pattern that
+/// parses is returned; patterns that are not found are left out. Trailing commas and comments
+/// are tolerated, as for `extract_json_ld`; values that still fail to parse (JavaScript
+/// rather than JSON) are skipped and logged.
+#[pyfunction]
+#[pyo3(signature = (html, patterns=None))]
+pub fn extract_script_json(py: Python, html: HtmlInput, patterns: Option<Vec<String>>) -> PyResult<Py<PyDict>> {
+    let patterns = patterns.unwrap_or_else(|| vec!["__INITIAL_STATE__".to_string(), "__NEXT_DATA__".to_string()]);
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let loads = py.import_bound("json")?.getattr("loads")?;
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("strict", false)?;
+
+    let scripts: Vec<(Option<&str>, String)> = document
+        .select(&SCRIPT_SELECTOR)
+        .filter(|script| {
+            let kind = script.value().attr("type").unwrap_or("").trim().to_ascii_lowercase();
+            SCRIPT_TYPES.contains(&kind.as_str())
+        })
+        .map(|script| (script.value().attr("id"), script.text().collect()))
+        .collect();
+
+    let found = PyDict::new_bound(py);
+    for pattern in patterns {
+        if pattern.is_empty() {
+            continue;
+        }
+        for (id, content) in &scripts {
+            let candidates = match *id {
+                Some(id) if id == pattern => vec![content.clone()],
+                _ => assigned_json(content, &pattern),
+            };
+            for json in candidates {
+                match loads.call((clean_json(&json),), Some(&kwargs)) {
+                    Ok(value) => {
+                        found.set_item(&pattern, value)?;
+                        break;
+                    }
+                    Err(err) => logging::emit(logging::WARNING, || {
+                        format!("skipping unparseable script JSON for '{}': {}", pattern, err.value_bound(py))
+                    }),
+                }
+            }
+            if found.contains(&pattern)? {
+                break;
+            }
+        }
+    }
+    Ok(found.unbind())
+}
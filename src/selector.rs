@@ -0,0 +1,679 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use ahash::AHashMap;
+use once_cell::sync::Lazy;
+use cssparser::{BasicParseErrorKind, ParseErrorKind, ParserInput, ToCss, Token};
+use scraper::selector::Simple;
+use scraper::{ElementRef, Html};
+use crate::errors;
+use crate::logging;
+use crate::pseudo::{self, CustomSelector};
+use selectors::matching::{self, MatchingContext, MatchingForInvalidation, MatchingMode, NeedsSelectorFlags, QuirksMode, SelectorCaches};
+use selectors::attr::{AttrSelectorOperator, CaseSensitivity, ParsedAttrSelectorOperation, ParsedCaseSensitivity};
+use selectors::parser::{Combinator, NthType, ParseRelative, SelectorList, SelectorParseErrorKind};
+
+/// Where and why a selector failed to compile.
+#[derive(Clone, Debug)]
+pub struct SelectorDiagnostic {
+    /// Character offset of the failure within the selector (0-based).
+    pub position: usize,
+    /// Human-readable reason.
+    pub reason: String,
+}
+
+impl SelectorDiagnostic {
+    /// The selector with a caret under the failing position.
+    pub fn snippet(&self, selector: &str) -> String {
+        let line = selector.replace(['\n', '\r', '\t'], " ");
+        format!("    {}\n    {}^", line, " ".repeat(self.position))
+    }
+}
+
+/// A compiled selector: a lone tag, id and class compound, other plain CSS, or CSS using
+/// pseudo-classes registered with `register_pseudo_class`.
+#[derive(Clone, Debug)]
+pub enum Query {
+    Simple(SimpleSelector),
+    Css(SelectorList<Simple>),
+    Custom(CustomSelector),
+}
+
+impl Query {
+    fn parse(selector: &str) -> Option<Self> {
+        if let Some(sel) = SimpleSelector::parse(selector) {
+            return Some(Query::Simple(sel));
+        }
+        if let Some(sel) = CustomSelector::parse(selector) {
+            return Some(Query::Custom(sel));
+        }
+        let mut input = ParserInput::new(selector);
+        let mut parser = cssparser::Parser::new(&mut input);
+        SelectorList::parse(&scraper::selector::Parser, &mut parser, ParseRelative::No).ok().map(Query::Css)
+    }
+
+    pub fn matcher(&self) -> Matcher<'_> {
+        Matcher { query: self, caches: SelectorCaches::default() }
+    }
+
+    /// Matching elements of `document` in tree order, like `Html::select`.
+    pub fn select<'a, 'b>(&'b self, document: &'a Html) -> Box<dyn Iterator<Item = ElementRef<'a>> + 'b>
+    where
+        'a: 'b,
+    {
+        let mut matcher = self.matcher();
+        Box::new(document.tree.nodes().filter_map(ElementRef::wrap).filter(move |elem| matcher.matches(elem)))
+    }
+}
+
+/// Tests the elements of one document against a `Query`. The sibling indices `:nth-*`
+/// pseudo-classes compute are kept between calls, so positional selectors cost O(1) per
+/// element on wide nodes instead of a rescan of the siblings before each one.
+pub struct Matcher<'q> {
+    query: &'q Query,
+    caches: SelectorCaches,
+}
+
+impl Matcher<'_> {
+    pub fn matches(&mut self, elem: &ElementRef) -> bool {
+        match self.query {
+            Query::Simple(sel) => sel.matches(elem),
+            Query::Css(list) => matches_list(list, elem, &mut self.caches),
+            Query::Custom(sel) => sel.matches(elem, &mut self.caches),
+        }
+    }
+}
+
+/// A selector of one compound of an optional tag, at most one id and any classes
+/// (`"div.item"`, `"#main"`, `"a"`). Most schemas use nothing else, and comparing names
+/// directly skips the selectors engine's matching context and bloom-filter setup per element.
+#[derive(Clone, Debug)]
+pub struct SimpleSelector {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+impl SimpleSelector {
+    /// None for anything beyond plain identifiers, including escapes and upper-case tags
+    /// (matched case-insensitively on HTML elements only), which the engine handles.
+    fn parse(selector: &str) -> Option<Self> {
+        let selector = selector.trim_matches([' ', '\t', '\n', '\r', '\x0C']);
+        let (tag, mut rest) = selector.split_at(selector.find(['.', '#']).unwrap_or(selector.len()));
+        let mut sel = SimpleSelector { tag: None, id: None, classes: Vec::new() };
+        if !tag.is_empty() {
+            if !is_identifier(tag) || tag.bytes().any(|b| b.is_ascii_uppercase()) {
+                return None;
+            }
+            sel.tag = Some(tag.to_string());
+        }
+        while let Some(sigil) = rest.chars().next() {
+            let body = &rest[1..];
+            let (name, next) = body.split_at(body.find(['.', '#']).unwrap_or(body.len()));
+            if !is_identifier(name) {
+                return None;
+            }
+            if sigil == '#' {
+                if sel.id.is_some() {
+                    return None;
+                }
+                sel.id = Some(name.to_string());
+            } else {
+                sel.classes.push(name.to_string());
+            }
+            rest = next;
+        }
+        (sel.tag.is_some() || sel.id.is_some() || !sel.classes.is_empty()).then_some(sel)
+    }
+
+    fn matches(&self, elem: &ElementRef) -> bool {
+        let elem = elem.value();
+        self.tag.as_deref().is_none_or(|tag| elem.name() == tag)
+            && self.id.as_deref().is_none_or(|id| elem.id() == Some(id))
+            && self.classes.iter().all(|class| elem.has_class(class, CaseSensitivity::CaseSensitive))
+    }
+}
+
+/// A CSS identifier needing no escapes: a letter or underscore, then letters, digits, `-`
+/// and `_`
+fn is_identifier(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    bytes.next().is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Whether `elem` matches any selector of `list`, with `:scope` as the root element
+pub(crate) fn matches_list<E: selectors::Element>(list: &SelectorList<E::Impl>, elem: &E, caches: &mut SelectorCaches) -> bool {
+    let mut context = MatchingContext::new(
+        MatchingMode::Normal,
+        None,
+        caches,
+        QuirksMode::NoQuirks,
+        NeedsSelectorFlags::No,
+        MatchingForInvalidation::No,
+    );
+    list.slice().iter().any(|s| matching::matches_selector(s, 0, None, elem, &mut context))
+}
+
+/// Compiled selectors kept by default; see `configure_selector_cache`.
+const DEFAULT_CACHE_SIZE: usize = 1024;
+
+/// Compiled selectors by source text, shared by every API that takes a selector string.
+/// Once full, the least recently used entry makes room for the next one.
+static CACHE: Lazy<Mutex<SelectorCache>> = Lazy::new(|| Mutex::new(SelectorCache::new(DEFAULT_CACHE_SIZE)));
+
+struct SelectorCache {
+    entries: AHashMap<String, (Query, u64)>,
+    /// Selectors by the tick of their last use, oldest first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    maxsize: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl SelectorCache {
+    fn new(maxsize: usize) -> Self {
+        SelectorCache {
+            entries: AHashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            maxsize,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, selector: &str) -> Option<Query> {
+        let Some((query, used)) = self.entries.get_mut(selector) else {
+            self.misses += 1;
+            return None;
+        };
+        self.tick += 1;
+        let key = self.recency.remove(used).expect("cached selector has a recency entry");
+        *used = self.tick;
+        self.recency.insert(self.tick, key);
+        self.hits += 1;
+        Some(query.clone())
+    }
+
+    fn insert(&mut self, selector: &str, query: Query) {
+        if self.maxsize == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(selector.to_string(), (query, self.tick)) {
+            self.recency.remove(&used);
+        }
+        self.recency.insert(self.tick, selector.to_string());
+        self.shrink();
+    }
+
+    /// Evict least recently used entries down to `maxsize`
+    fn shrink(&mut self) {
+        while self.entries.len() > self.maxsize {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// Compiled form of `selector` from the cache, parsing and caching it on a miss. None when
+/// it is invalid; invalid selectors are not cached.
+fn cached(selector: &str) -> Option<Query> {
+    if let Some(query) = CACHE.lock().unwrap().get(selector) {
+        return Some(query);
+    }
+    let started = Instant::now();
+    let query = Query::parse(selector)?;
+    logging::emit(logging::DEBUG, || format!(
+        "compiled selector '{}' in {:.3} ms",
+        selector,
+        started.elapsed().as_secs_f64() * 1000.0
+    ));
+    CACHE.lock().unwrap().insert(selector, query.clone());
+    Some(query)
+}
+
+/// Forget compiled selectors when the registered pseudo-classes change
+pub(crate) fn clear_cache() {
+    CACHE.lock().unwrap().clear();
+}
+
+/// Compile a CSS selector, raising `SelectorError` with the failing position, the offending
+/// token and a caret-annotated snippet when it is invalid.
+pub fn compile(selector: &str) -> PyResult<Query> {
+    cached(selector).ok_or_else(|| selector_error(selector, None))
+}
+
+/// Like `compile`, naming where the selector came from (e.g. "container selector") in the error.
+pub fn compile_in(selector: &str, context: &str) -> PyResult<Query> {
+    cached(selector).ok_or_else(|| selector_error(selector, Some(context)))
+}
+
+/// Compile the selector of a `field_mappings` entry; the error's `.field` names the field.
+pub fn compile_field(selector: &str, field: &str) -> PyResult<Query> {
+    cached(selector).ok_or_else(|| {
+        let err = selector_error(selector, Some(&format!("field '{}'", field)));
+        errors::with_detail(err, "field", field)
+    })
+}
+
+/// Build the `SelectorError` for a selector known to be invalid, with `.selector` and
+/// `.position` set.
+pub fn selector_error(selector: &str, context: Option<&str>) -> PyErr {
+    let subject = match context {
+        Some(context) => format!("Invalid CSS selector for {}: '{}'", context, selector),
+        None => format!("Invalid CSS selector: '{}'", selector),
+    };
+    let diag = diagnose(selector);
+    let message = match &diag {
+        Some(diag) => format!(
+            "{} - {} at position {}\n{}",
+            subject,
+            diag.reason,
+            diag.position,
+            diag.snippet(selector)
+        ),
+        None => subject,
+    };
+    let err = PyErr::new::<errors::SelectorError, _>(message);
+    let err = errors::with_detail(err, "selector", selector);
+    match diag {
+        Some(diag) => errors::with_detail(err, "position", diag.position),
+        None => err,
+    }
+}
+
+/// Re-parse an invalid selector with the underlying CSS parser to recover the error location,
+/// which scraper's own error type drops. Registered pseudo-classes are accepted.
+pub fn diagnose(selector: &str) -> Option<SelectorDiagnostic> {
+    let mut input = ParserInput::new(selector);
+    let mut parser = cssparser::Parser::new(&mut input);
+    let err = match pseudo::check(&mut parser) {
+        Ok(()) => return None,
+        Err(err) => err,
+    };
+
+    let (reason, token) = describe(&err.kind);
+    let mut position = char_offset(selector, err.location.line, err.location.column);
+    // cssparser reports the location after the offending token; point at the token itself
+    if let Some(token) = &token {
+        let before: String = selector.chars().take(position).collect();
+        if let Some(start) = before.rfind(token.as_str()) {
+            position = before[..start].chars().count();
+        }
+    }
+    let reason = match attribute_hint(selector, position) {
+        Some(hint) => format!("{} ({})", reason, hint),
+        None => reason,
+    };
+    Some(SelectorDiagnostic { position: position.min(selector.chars().count()), reason })
+}
+
+/// How to write an attribute selector, when the failure at `position` is inside one
+fn attribute_hint(selector: &str, position: usize) -> Option<&'static str> {
+    let before: String = selector.chars().take(position).collect();
+    let inside = &before[before.rfind('[')? + 1..];
+    if inside.contains(']') {
+        return None;
+    }
+    let starts_ident = selector.chars().skip(position).find(|c| !c.is_whitespace()).is_some_and(char::is_alphabetic);
+    let hint = match inside.find('=') {
+        // Right after the operator: the value itself is malformed
+        Some(at) if inside[at + 1..].trim().is_empty() => {
+            "quote values that are not plain identifiers, e.g. [href$=\".pdf\"]"
+        }
+        Some(_) if starts_ident => {
+            "only the flags 'i' for ASCII case-insensitive and 's' for case-sensitive matching may follow the value, e.g. [href$=\".pdf\" i]"
+        }
+        None if starts_ident => "a case-sensitivity flag needs an operator and a value, e.g. [type=\"text\" i]",
+        _ => return None,
+    };
+    Some(hint)
+}
+
+/// Convert cssparser's (0-based line, 1-based UTF-16 column) location to a character offset.
+fn char_offset(selector: &str, line: u32, column: u32) -> usize {
+    let mut offset = 0;
+    for (i, text) in selector.split('\n').enumerate() {
+        if i as u32 == line {
+            let mut units = 1;
+            for ch in text.chars() {
+                if units >= column {
+                    break;
+                }
+                units += ch.len_utf16() as u32;
+                offset += 1;
+            }
+            return offset;
+        }
+        offset += text.chars().count() + 1;
+    }
+    offset
+}
+
+fn describe(kind: &ParseErrorKind<'_, SelectorParseErrorKind<'_>>) -> (String, Option<String>) {
+    match kind {
+        ParseErrorKind::Basic(BasicParseErrorKind::UnexpectedToken(token)) => {
+            unexpected("unexpected token", token)
+        }
+        ParseErrorKind::Basic(BasicParseErrorKind::EndOfInput) => {
+            ("unexpected end of selector".to_string(), None)
+        }
+        ParseErrorKind::Basic(other) => (format!("{:?}", other), None),
+        ParseErrorKind::Custom(kind) => match kind {
+            SelectorParseErrorKind::EmptySelector => ("empty selector".to_string(), None),
+            SelectorParseErrorKind::DanglingCombinator => {
+                ("combinator without a selector after it".to_string(), None)
+            }
+            SelectorParseErrorKind::UnsupportedPseudoClassOrElement(name) => (
+                format!("unsupported pseudo-class or pseudo-element ':{}'", name),
+                Some(name.to_string()),
+            ),
+            SelectorParseErrorKind::UnexpectedIdent(name) => {
+                (format!("unexpected identifier '{}'", name), Some(name.to_string()))
+            }
+            SelectorParseErrorKind::ClassNeedsIdent(token) => {
+                unexpected("expected a class name after '.', found", token)
+            }
+            SelectorParseErrorKind::NoIdentForPseudo(token) => {
+                unexpected("expected a pseudo-class name after ':', found", token)
+            }
+            SelectorParseErrorKind::PseudoElementExpectedIdent(token) => {
+                unexpected("expected a pseudo-element name after '::', found", token)
+            }
+            SelectorParseErrorKind::NoQualifiedNameInAttributeSelector(token)
+            | SelectorParseErrorKind::InvalidQualNameInAttr(token) => {
+                unexpected("expected an attribute name, found", token)
+            }
+            SelectorParseErrorKind::UnexpectedTokenInAttributeSelector(token)
+            | SelectorParseErrorKind::ExpectedBarInAttr(token) => {
+                unexpected("unexpected token in attribute selector", token)
+            }
+            SelectorParseErrorKind::BadValueInAttr(token) => {
+                unexpected("invalid attribute value", token)
+            }
+            other => (format!("{:?}", other), None),
+        },
+    }
+}
+
+fn unexpected(reason: &str, token: &Token<'_>) -> (String, Option<String>) {
+    let text = token.to_css_string();
+    (format!("{} '{}'", reason, text), Some(text))
+}
+
+type Component = selectors::parser::Component<scraper::selector::Simple>;
+
+/// An XPath 1.0 string literal; `concat()` when the value holds both quote kinds
+fn xpath_literal(value: &str) -> String {
+    if !value.contains('\'') {
+        format!("'{}'", value)
+    } else if !value.contains('"') {
+        format!("\"{}\"", value)
+    } else {
+        let parts: Vec<String> = value.split('\'').map(|part| format!("'{}'", part)).collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
+}
+
+fn untranslatable(selector: &str, what: &str) -> PyErr {
+    let err = PyErr::new::<errors::SelectorError, _>(format!("Cannot translate CSS selector to XPath: '{}' - {} has no XPath 1.0 equivalent", selector, what));
+    errors::with_detail(err, "selector", selector)
+}
+
+/// XPath predicate for an attribute selector on `@name`
+fn attribute_predicate(name: &str, operator: &AttrSelectorOperator, value: &str, case_sensitivity: ParsedCaseSensitivity) -> String {
+    let insensitive = !matches!(case_sensitivity, ParsedCaseSensitivity::CaseSensitive | ParsedCaseSensitivity::ExplicitCaseSensitive);
+    let (attr, value) = match insensitive {
+        true => (
+            format!("translate(@{}, 'ABCDEFGHIJKLMNOPQRSTUVWXYZ', 'abcdefghijklmnopqrstuvwxyz')", name),
+            value.to_ascii_lowercase(),
+        ),
+        false => (format!("@{}", name), value.to_string()),
+    };
+    let literal = xpath_literal(&value);
+    match operator {
+        AttrSelectorOperator::Equal => format!("{} = {}", attr, literal),
+        AttrSelectorOperator::Includes if value.is_empty() || value.contains(char::is_whitespace) => "false()".to_string(),
+        AttrSelectorOperator::Includes => format!("@{} and contains(concat(' ', normalize-space({}), ' '), {})", name, attr, xpath_literal(&format!(" {} ", value))),
+        AttrSelectorOperator::DashMatch => format!("@{} and ({} = {} or starts-with({}, {}))", name, attr, literal, attr, xpath_literal(&format!("{}-", value))),
+        // An empty value never matches `^=`, `*=` or `$=`
+        _ if value.is_empty() => "false()".to_string(),
+        AttrSelectorOperator::Prefix => format!("@{} and starts-with({}, {})", name, attr, literal),
+        AttrSelectorOperator::Substring => format!("@{} and contains({}, {})", name, attr, literal),
+        AttrSelectorOperator::Suffix => format!(
+            "@{} and substring({}, string-length({}) - {}) = {}",
+            name, attr, attr, value.chars().count() - 1, literal
+        ),
+    }
+}
+
+/// XPath condition for an element with `count` siblings before it (or after it, for the
+/// `last` forms) being at a 1-based position that fits `an+b`
+fn nth_condition(count: &str, a: i32, b: i32) -> String {
+    let k = b - 1;
+    let offset = match k {
+        0 => count.to_string(),
+        k if k > 0 => format!("({} - {})", count, k),
+        k => format!("({} + {})", count, -k),
+    };
+    match a {
+        0 => format!("{} = {}", count, k),
+        1 => format!("{} >= {}", count, k),
+        -1 => format!("{} <= {}", count, k),
+        a if a > 0 && k <= 0 => format!("{} mod {} = 0", offset, a),
+        a if a > 0 => format!("{} mod {} = 0 and {} >= {}", offset, a, count, k),
+        a => format!("{} mod {} = 0 and {} <= {}", offset, -a, count, k),
+    }
+}
+
+/// A compound selector: its element name (None for any) and predicates on the element
+struct Compound {
+    name: Option<String>,
+    predicates: Vec<String>,
+}
+
+impl Compound {
+    fn translate(components: &[&Component], selector: &str) -> PyResult<Self> {
+        let name = components.iter().find_map(|c| match c {
+            Component::LocalName(local) => Some(local.lower_name.0.to_string()),
+            _ => None,
+        });
+        let mut predicates = Vec::new();
+        for component in components {
+            let predicate = match component {
+                Component::LocalName(_) | Component::ExplicitUniversalType | Component::ExplicitAnyNamespace => continue,
+                Component::ID(id) => format!("@id = {}", xpath_literal(&id.0)),
+                Component::Class(class) => format!("@class and contains(concat(' ', normalize-space(@class), ' '), {})", xpath_literal(&format!(" {} ", &*class.0))),
+                Component::AttributeInNoNamespaceExists { local_name_lower, .. } => format!("@{}", &*local_name_lower.0),
+                Component::AttributeInNoNamespace { local_name, operator, value, case_sensitivity } => {
+                    attribute_predicate(&local_name.0, operator, &value.0, *case_sensitivity)
+                }
+                Component::AttributeOther(attr) if attr.namespace.is_none() => match &attr.operation {
+                    ParsedAttrSelectorOperation::Exists => format!("@{}", &*attr.local_name_lower.0),
+                    ParsedAttrSelectorOperation::WithValue { operator, case_sensitivity, value } => {
+                        attribute_predicate(&attr.local_name_lower.0, operator, &value.0, *case_sensitivity)
+                    }
+                },
+                Component::Root => "not(parent::*)".to_string(),
+                Component::Empty => "not(*) and not(string-length())".to_string(),
+                Component::Nth(nth) => {
+                    let of_type = matches!(nth.ty, NthType::OfType | NthType::LastOfType | NthType::OnlyOfType);
+                    let test = match (of_type, &name) {
+                        (false, _) => "*".to_string(),
+                        (true, Some(name)) => name.clone(),
+                        (true, None) => return Err(untranslatable(selector, "an *-of-type pseudo-class without a tag name")),
+                    };
+                    let before = format!("count(preceding-sibling::{})", test);
+                    let after = format!("count(following-sibling::{})", test);
+                    match nth.ty {
+                        NthType::Child | NthType::OfType => nth_condition(&before, nth.a, nth.b),
+                        NthType::LastChild | NthType::LastOfType => nth_condition(&after, nth.a, nth.b),
+                        NthType::OnlyChild | NthType::OnlyOfType => format!("{} = 0 and {} = 0", before, after),
+                    }
+                }
+                Component::Negation(list) => format!("not({})", Self::alternatives(list.slice(), selector)?),
+                Component::Is(list) | Component::Where(list) => Self::alternatives(list.slice(), selector)?,
+                Component::Has(_) => return Err(untranslatable(selector, ":has()")),
+                Component::NthOf(_) => return Err(untranslatable(selector, ":nth-child(... of S)")),
+                _ => return Err(untranslatable(selector, "a namespace or scoping selector")),
+            };
+            predicates.push(predicate);
+        }
+        Ok(Compound { name, predicates })
+    }
+
+    /// `self::name and (...)`, for use inside a predicate
+    fn condition(&self) -> String {
+        let mut parts: Vec<String> = self.name.iter().map(|name| format!("self::{}", name)).collect();
+        parts.extend(self.predicates.iter().cloned());
+        match parts.len() {
+            0 => "true()".to_string(),
+            1 => parts.remove(0),
+            _ => parts.iter().map(|p| format!("({})", p)).collect::<Vec<_>>().join(" and "),
+        }
+    }
+
+    /// Selectors inside `:not()`, `:is()` and `:where()` as `a or b`.
+    /// `and` binds tighter than `or`, so the alternatives need no parentheses.
+    fn alternatives(list: &[selectors::parser::Selector<scraper::selector::Simple>], selector: &str) -> PyResult<String> {
+        list.iter().map(|inner| Self::relative(inner, selector)).collect::<PyResult<Vec<_>>>().map(|conditions| conditions.join(" or "))
+    }
+
+    /// A complex selector as a condition on the current element: its rightmost compound on
+    /// the element itself, and each compound to the left through the axis of the combinator
+    /// between them, e.g. `.ad > p` as `self::p and parent::*[...]`
+    fn relative(inner: &selectors::parser::Selector<scraper::selector::Simple>, selector: &str) -> PyResult<String> {
+        // Match order walks compounds right to left; within a compound it is source order
+        let mut compounds = vec![(Vec::new(), None)];
+        for component in inner.iter_raw_match_order() {
+            match component {
+                Component::Combinator(combinator) => compounds.push((Vec::new(), Some(*combinator))),
+                _ => compounds.last_mut().unwrap().0.push(component),
+            }
+        }
+        let mut condition = String::new();
+        for (components, combinator) in compounds.iter().rev() {
+            let own = Self::translate(components, selector)?.condition();
+            let own = if own.contains(" or ") { format!("({})", own) } else { own };
+            condition = match (condition.is_empty(), own.as_str()) {
+                (true, _) => own,
+                (false, "true()") => condition,
+                (false, _) => format!("{} and {}", own, condition),
+            };
+            let axis = match combinator {
+                None => break,
+                Some(Combinator::Child) => "parent::*",
+                Some(Combinator::Descendant) => "ancestor::*",
+                Some(Combinator::NextSibling) => "preceding-sibling::*[1]",
+                Some(Combinator::LaterSibling) => "preceding-sibling::*",
+                Some(_) => return Err(untranslatable(selector, "a pseudo-element")),
+            };
+            condition = match condition.as_str() {
+                "true()" => axis.to_string(),
+                _ => format!("{}[{}]", axis, condition),
+            };
+        }
+        Ok(condition)
+    }
+
+    fn predicates(&self) -> String {
+        self.predicates.iter().map(|p| format!("[{}]", p)).collect()
+    }
+}
+
+/// Statistics of the compiled-selector cache, like `functools.lru_cache`'s `cache_info()`:
+/// a dict with `hits`, `misses`, `maxsize` and `currsize`.
+#[pyfunction]
+pub fn selector_cache_info(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let cache = CACHE.lock().unwrap();
+    let info = PyDict::new_bound(py);
+    info.set_item("hits", cache.hits)?;
+    info.set_item("misses", cache.misses)?;
+    info.set_item("maxsize", cache.maxsize)?;
+    info.set_item("currsize", cache.entries.len())?;
+    Ok(info)
+}
+
+/// Drop every compiled selector and reset the hit and miss counts.
+#[pyfunction]
+pub fn clear_selector_cache() {
+    let mut cache = CACHE.lock().unwrap();
+    cache.clear();
+    cache.hits = 0;
+    cache.misses = 0;
+}
+
+/// Keep at most `maxsize` compiled selectors, evicting the least recently used ones beyond
+/// that; 0 turns caching off.
+#[pyfunction]
+#[pyo3(signature = (maxsize=DEFAULT_CACHE_SIZE))]
+pub fn configure_selector_cache(maxsize: usize) {
+    let mut cache = CACHE.lock().unwrap();
+    cache.maxsize = maxsize;
+    cache.shrink();
+}
+
+/// Translate a CSS selector into an equivalent XPath 1.0 expression, for exporting rules to
+/// XPath-based tools. Follows cssselect's conventions: the expression starts with
+/// `descendant-or-self::` and selector lists become `|` unions. Invalid selectors raise
+/// `SelectorError` like `select()`; so do `:has()` and other selectors XPath 1.0 cannot express.
+/// Combinators inside `:not()`, `:is()` and `:where()` become `parent::`, `ancestor::` and
+/// `preceding-sibling::` conditions on the element.
+#[pyfunction]
+pub fn css_to_xpath(selector: &str) -> PyResult<String> {
+    compile(selector)?;
+    let mut input = ParserInput::new(selector);
+    let mut parser = cssparser::Parser::new(&mut input);
+    let list = SelectorList::parse(&scraper::selector::Parser, &mut parser, ParseRelative::No)
+        .map_err(|_| selector_error(selector, None))?;
+
+    let mut paths = Vec::new();
+    for complex in list.slice() {
+        let mut path = String::from("descendant-or-self::");
+        let mut components = Vec::new();
+        let mut combinator = None;
+        let mut parts = complex.iter_raw_parse_order_from(0).peekable();
+        while let Some(component) = parts.next() {
+            if let Component::Combinator(next) = component {
+                combinator = Some(*next);
+                continue;
+            }
+            components.push(component);
+            if parts.peek().is_some_and(|c| !c.is_combinator()) {
+                continue;
+            }
+            // Parse order reverses the components within each compound
+            components.reverse();
+            let compound = Compound::translate(&components, selector)?;
+            let name = compound.name.clone().unwrap_or_else(|| "*".to_string());
+            match combinator.take() {
+                None => path.push_str(&name),
+                Some(Combinator::Descendant) => path.push_str(&format!("//{}", name)),
+                Some(Combinator::Child) => path.push_str(&format!("/{}", name)),
+                Some(Combinator::LaterSibling) => path.push_str(&format!("/following-sibling::{}", name)),
+                Some(Combinator::NextSibling) => {
+                    path.push_str("/following-sibling::*[1]");
+                    if let Some(name) = &compound.name {
+                        path.push_str(&format!("[self::{}]", name));
+                    }
+                }
+                Some(_) => return Err(untranslatable(selector, "a pseudo-element")),
+            }
+            path.push_str(&compound.predicates());
+            components.clear();
+        }
+        paths.push(path);
+    }
+    Ok(paths.join(" | "))
+}
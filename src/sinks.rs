@@ -0,0 +1,509 @@
+//! Extraction results written straight to files from Rust, a row at a time, so large jobs
+//! never hold every item in memory.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::parquet::{self, ColumnData};
+
+/// Joins the values of a list field (`@get_all`) in one CSV cell
+pub const LIST_SEPARATOR: &str = "|";
+
+/// Where a `sink=` argument sends items: a path by its extension, or a file-like object
+/// taking JSON lines
+pub enum Sink<'py> {
+    Csv(PathBuf),
+    JsonlFile(PathBuf),
+    JsonlStream(Bound<'py, PyAny>),
+}
+
+impl<'py> Sink<'py> {
+    pub fn from_arg(sink: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(path) = sink.extract::<PathBuf>() {
+            return match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+                Some("csv") => Ok(Sink::Csv(path)),
+                Some("jsonl") => Ok(Sink::JsonlFile(path)),
+                _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Unsupported sink: '{}'. Use a .csv or .jsonl path, or a file-like object",
+                    path.display()
+                ))),
+            };
+        }
+        if sink.hasattr("write")? {
+            return Ok(Sink::JsonlStream(sink.clone()));
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "sink must be a path or a file-like object with write(), got {}",
+            sink.get_type().name()?
+        )))
+    }
+}
+
+/// A CSV file being written: RFC 4180 quoting, CRLF line ends, UTF-8 without a BOM
+pub struct CsvWriter {
+    out: BufWriter<File>,
+    line: String,
+}
+
+impl CsvWriter {
+    /// Create (or truncate) the file at `path` and write the header row
+    pub fn create(path: &Path, header: &[String]) -> PyResult<Self> {
+        let mut writer = CsvWriter { out: BufWriter::new(create_file(path)?), line: String::new() };
+        writer.write_row(header)?;
+        Ok(writer)
+    }
+
+    pub fn write_row(&mut self, cells: impl IntoIterator<Item = impl AsRef<str>>) -> PyResult<()> {
+        self.line.clear();
+        for (index, cell) in cells.into_iter().enumerate() {
+            if index > 0 {
+                self.line.push(',');
+            }
+            push_cell(&mut self.line, cell.as_ref());
+        }
+        self.line.push_str("\r\n");
+        self.out.write_all(self.line.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> PyResult<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// A JSON Lines target: one compact JSON object per line, UTF-8
+pub enum JsonlWriter<'py> {
+    /// A file opened for appending, so several calls can fill one file
+    File(BufWriter<File>),
+    /// A file-like object, given `bytes` lines when it is a binary `io` stream and `str` otherwise
+    Stream { target: Bound<'py, PyAny>, text: bool },
+}
+
+impl<'py> JsonlWriter<'py> {
+    pub fn open_file(path: &Path) -> PyResult<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|err| os_error(path, err))?;
+        Ok(JsonlWriter::File(BufWriter::new(file)))
+    }
+
+    pub fn stream(target: Bound<'py, PyAny>) -> PyResult<Self> {
+        let io = target.py().import_bound("io")?;
+        let text = !(target.is_instance(&io.getattr("RawIOBase")?)? || target.is_instance(&io.getattr("BufferedIOBase")?)?);
+        Ok(JsonlWriter::Stream { target, text })
+    }
+
+    /// Write `line`, which holds one JSON value and no newline
+    pub fn write_line(&mut self, line: &str) -> PyResult<()> {
+        match self {
+            JsonlWriter::File(out) => {
+                out.write_all(line.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+            JsonlWriter::Stream { target, text: true } => {
+                target.call_method1("write", (format!("{}\n", line),))?;
+            }
+            JsonlWriter::Stream { target, text: false } => {
+                let mut bytes = Vec::with_capacity(line.len() + 1);
+                bytes.extend_from_slice(line.as_bytes());
+                bytes.push(b'\n');
+                target.call_method1("write", (PyBytes::new_bound(target.py(), &bytes),))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> PyResult<()> {
+        if let JsonlWriter::File(mut out) = self {
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Append `value` to `out` as compact JSON, the way `json.dumps(value, ensure_ascii=False)`
+/// would, except that `Decimal` amounts are written as numbers, dates and times as ISO 8601
+/// strings, and non-finite floats as `null`
+pub fn write_json(value: &Bound<'_, PyAny>, out: &mut String) -> PyResult<()> {
+    if value.is_none() {
+        out.push_str("null");
+    } else if let Ok(value) = value.downcast::<PyString>() {
+        push_json_string(out, value.to_str()?);
+    } else if let Ok(value) = value.downcast::<PyBool>() {
+        out.push_str(if value.is_true() { "true" } else { "false" });
+    } else if value.is_instance_of::<PyInt>() {
+        out.push_str(value.str()?.to_str()?);
+    } else if let Ok(value) = value.downcast::<PyFloat>() {
+        match value.value() {
+            number if number.is_finite() => out.push_str(value.repr()?.to_str()?),
+            _ => out.push_str("null"),
+        }
+    } else if let Ok(dict) = value.downcast::<PyDict>() {
+        out.push('{');
+        for (index, (key, value)) in dict.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            push_json_string(out, key.str()?.to_str()?);
+            out.push(':');
+            write_json(&value, out)?;
+        }
+        out.push('}');
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        out.push('[');
+        for (index, item) in value.iter()?.enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            write_json(&item?, out)?;
+        }
+        out.push(']');
+    } else if value.hasattr("isoformat")? {
+        push_json_string(out, value.call_method0("isoformat")?.str()?.to_str()?);
+    } else if value.is_instance(&value.py().import_bound("decimal")?.getattr("Decimal")?)? {
+        match value.call_method0("is_finite")?.is_truthy()? {
+            true => out.push_str(value.str()?.to_str()?),
+            false => out.push_str("null"),
+        }
+    } else {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "Object of type {} is not JSON serializable",
+            value.get_type().name()?
+        )));
+    }
+    Ok(())
+}
+
+fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+/// Create (or truncate) `path`, raising the `OSError` subclass `open()` would, with the path
+pub fn create_file(path: &Path) -> PyResult<File> {
+    File::create(path).map_err(|err| os_error(path, err))
+}
+
+/// `err` as the `OSError` subclass Python raises for it, naming `path`
+pub fn os_error(path: &Path, err: std::io::Error) -> PyErr {
+    let message = err.to_string();
+    let message = message.split(" (os error").next().unwrap_or_default().to_owned();
+    PyErr::new::<pyo3::exceptions::PyOSError, _>((err.raw_os_error().unwrap_or_default(), message, path.display().to_string()))
+}
+
+/// Quote a cell holding a delimiter, quote or line break, doubling its quotes
+fn push_cell(line: &mut String, cell: &str) {
+    if cell.contains([',', '"', '\r', '\n']) {
+        line.push('"');
+        line.push_str(&cell.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(cell);
+    }
+}
+
+/// Write result dicts (from `extract_data`, or any dicts) to a CSV file and return the
+/// number of rows written. Nested dicts become dotted columns (`seller.name`), lists are
+/// joined with `|`, `None` is an empty cell and other values are written as `str()`.
+/// `columns` picks the columns and their order; without it the first row's keys are the
+/// header and a later row with another key raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (results, path, columns=None))]
+pub fn write_csv(results: &Bound<'_, PyAny>, path: PathBuf, columns: Option<Vec<String>>) -> PyResult<usize> {
+    let chosen = columns.is_some();
+    let mut writer = match columns {
+        Some(columns) => Some((CsvWriter::create(&path, &columns)?, columns)),
+        None => None,
+    };
+    let mut count = 0;
+    for row in results.iter()? {
+        let row = row?;
+        let row = row
+            .downcast::<PyDict>()
+            .map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!("Row {} is not a dict", count)))?;
+        let mut cells = Vec::new();
+        flatten(row, "", &mut cells)?;
+        if writer.is_none() {
+            let header: Vec<String> = cells.iter().map(|(name, _)| name.clone()).collect();
+            writer = Some((CsvWriter::create(&path, &header)?, header));
+        }
+        let Some((writer, header)) = writer.as_mut() else { unreachable!() };
+        if let Some((name, _)) = cells.iter().find(|(name, _)| !chosen && !header.contains(name)) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Row {} has column '{}', which the first row does not; pass columns= to choose the columns",
+                count, name
+            )));
+        }
+        writer.write_row(header.iter().map(|name| cells.iter().find(|(cell, _)| cell == name).map_or("", |(_, value)| value)))?;
+        count += 1;
+    }
+    match writer {
+        Some((writer, _)) => writer.finish()?,
+        // No rows and no columns: an empty file, as there is no header to write
+        None => drop(create_file(&path)?),
+    }
+    Ok(count)
+}
+
+/// A result dict's cells in key order, nested dicts under dotted names
+fn flatten(row: &Bound<'_, PyDict>, prefix: &str, cells: &mut Vec<(String, String)>) -> PyResult<()> {
+    for (key, value) in row.iter() {
+        let name = format!("{}{}", prefix, key.str()?);
+        match value.downcast::<PyDict>() {
+            Ok(nested) => flatten(nested, &format!("{}.", name), cells)?,
+            Err(_) => cells.push((name, cell(&value)?)),
+        }
+    }
+    Ok(())
+}
+
+fn cell(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if value.is_none() {
+        Ok(String::new())
+    } else if let Ok(value) = value.downcast::<PyString>() {
+        Ok(value.to_str()?.to_owned())
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        let items = value.iter()?.map(|item| cell(&item?)).collect::<PyResult<Vec<_>>>()?;
+        Ok(items.join(LIST_SEPARATOR))
+    } else {
+        Ok(value.str()?.to_str()?.to_owned())
+    }
+}
+
+/// Write result dicts to a Parquet file and return the number of rows written. Nested dicts
+/// become dotted columns, as for `write_csv`; `columns` picks the columns and their order,
+/// otherwise every key seen is a column and rows without it hold null. Each column's type
+/// is inferred from its values: str, bool, int and float as themselves, `Decimal` (typed
+/// `price` amounts) as DECIMAL(18), timezone-aware datetimes as UTC timestamps, naive ones
+/// as local timestamps and lists as lists of strings. A column mixing types is written as
+/// strings, like its CSV cells.
+#[pyfunction]
+#[pyo3(signature = (results, path, columns=None))]
+pub fn write_parquet(py: Python, results: &Bound<'_, PyAny>, path: PathBuf, columns: Option<Vec<String>>) -> PyResult<usize> {
+    let chosen = columns.is_some();
+    let mut names = columns.unwrap_or_default();
+    let mut values: Vec<Vec<Option<Bound<'_, PyAny>>>> = names.iter().map(|_| Vec::new()).collect();
+    let mut rows = 0;
+    for row in results.iter()? {
+        let row = row?;
+        let row = row
+            .downcast::<PyDict>()
+            .map_err(|_| PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!("Row {} is not a dict", rows)))?;
+        let mut cells = Vec::new();
+        flatten_values(row, "", &mut cells)?;
+        for (name, value) in cells {
+            let column = match names.iter().position(|column| *column == name) {
+                Some(column) => column,
+                None if chosen => continue,
+                None => {
+                    names.push(name);
+                    values.push(Vec::new());
+                    values.len() - 1
+                }
+            };
+            values[column].resize_with(rows, || None);
+            values[column].push((!value.is_none()).then_some(value));
+        }
+        rows += 1;
+        values.iter_mut().for_each(|column| column.resize_with(rows, || None));
+    }
+    // A typed field that found nothing is None where other rows have its dict, which would
+    // otherwise add an all-null column next to the dict's own
+    let parents: Vec<bool> = names
+        .iter()
+        .zip(&values)
+        .map(|(name, column)| {
+            !chosen && column.iter().all(Option::is_none) && names.iter().any(|other| other.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with('.')))
+        })
+        .collect();
+    let types = ValueTypes::new(py)?;
+    let columns = names
+        .into_iter()
+        .zip(values)
+        .zip(parents)
+        .filter(|(_, parent)| !parent)
+        .map(|((name, values), _)| Ok((name, infer_column(&types, values)?)))
+        .collect::<PyResult<Vec<_>>>()?;
+    parquet::write(&path, &columns, rows)?;
+    Ok(rows)
+}
+
+/// Like `flatten`, keeping the values as they are
+fn flatten_values<'py>(row: &Bound<'py, PyDict>, prefix: &str, cells: &mut Vec<(String, Bound<'py, PyAny>)>) -> PyResult<()> {
+    for (key, value) in row.iter() {
+        let name = format!("{}{}", prefix, key.str()?);
+        match value.downcast::<PyDict>() {
+            Ok(nested) => flatten_values(nested, &format!("{}.", name), cells)?,
+            Err(_) => cells.push((name, value)),
+        }
+    }
+    Ok(())
+}
+
+/// The Python classes a Parquet column type is inferred from
+struct ValueTypes<'py> {
+    datetime: Bound<'py, PyAny>,
+    decimal: Bound<'py, PyAny>,
+    /// The epoch, aware and naive, and one microsecond, to turn datetimes into integers
+    epoch_utc: Bound<'py, PyAny>,
+    epoch_naive: Bound<'py, PyAny>,
+    microsecond: Bound<'py, PyAny>,
+}
+
+impl<'py> ValueTypes<'py> {
+    fn new(py: Python<'py>) -> PyResult<Self> {
+        let module = py.import_bound("datetime")?;
+        let datetime = module.getattr("datetime")?;
+        let utc = module.getattr("timezone")?.getattr("utc")?;
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("microseconds", 1)?;
+        Ok(ValueTypes {
+            epoch_utc: datetime.call1((1970, 1, 1, 0, 0, 0, 0, utc))?,
+            epoch_naive: datetime.call1((1970, 1, 1))?,
+            microsecond: module.getattr("timedelta")?.call((), Some(&kwargs))?,
+            decimal: py.import_bound("decimal")?.getattr("Decimal")?,
+            datetime,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    Str,
+    Bool,
+    Int,
+    Float,
+    Decimal,
+    AwareDatetime,
+    NaiveDatetime,
+    List,
+    Other,
+}
+
+fn value_kind(types: &ValueTypes<'_>, value: &Bound<'_, PyAny>) -> PyResult<ValueKind> {
+    Ok(if value.is_instance_of::<PyString>() {
+        ValueKind::Str
+    } else if value.is_instance_of::<PyBool>() {
+        ValueKind::Bool
+    } else if value.is_instance_of::<PyInt>() {
+        ValueKind::Int
+    } else if value.is_instance_of::<PyFloat>() {
+        ValueKind::Float
+    } else if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+        ValueKind::List
+    } else if value.is_instance(&types.datetime)? {
+        match value.call_method0("utcoffset")?.is_none() {
+            true => ValueKind::NaiveDatetime,
+            false => ValueKind::AwareDatetime,
+        }
+    } else if value.is_instance(&types.decimal)? {
+        ValueKind::Decimal
+    } else {
+        ValueKind::Other
+    })
+}
+
+/// The column for `values`, typed when all of them share a type that maps onto Parquet
+fn infer_column(types: &ValueTypes<'_>, values: Vec<Option<Bound<'_, PyAny>>>) -> PyResult<ColumnData> {
+    let mut kind = None;
+    for value in values.iter().flatten() {
+        let this = value_kind(types, value)?;
+        if kind.is_some_and(|kind| kind != this) {
+            kind = Some(ValueKind::Other);
+            break;
+        }
+        kind = Some(this);
+    }
+    let typed = match kind.unwrap_or(ValueKind::Str) {
+        ValueKind::Str | ValueKind::Other => None,
+        ValueKind::Bool => Some(ColumnData::Booleans(map_values(&values, |value| value.is_truthy())?)),
+        // Ints past i64 are written as strings
+        ValueKind::Int => values
+            .iter()
+            .map(|value| match value {
+                Some(value) => value.extract::<i64>().ok().map(Some),
+                None => Some(None),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(ColumnData::Int64),
+        ValueKind::Float => Some(ColumnData::Doubles(map_values(&values, |value| value.extract::<f64>())?)),
+        ValueKind::Decimal => decimal_column(&values)?,
+        ValueKind::AwareDatetime | ValueKind::NaiveDatetime => {
+            let utc = kind == Some(ValueKind::AwareDatetime);
+            let epoch = if utc { &types.epoch_utc } else { &types.epoch_naive };
+            let micros = map_values(&values, |value| {
+                value.call_method1("__sub__", (epoch,))?.call_method1("__floordiv__", (&types.microsecond,))?.extract::<i64>()
+            })?;
+            Some(ColumnData::Timestamps { micros, utc })
+        }
+        ValueKind::List => Some(ColumnData::Lists(map_values(&values, |value| {
+            value
+                .iter()?
+                .map(|item| {
+                    let item = item?;
+                    match item.is_none() {
+                        true => Ok(None),
+                        false => cell(&item).map(Some),
+                    }
+                })
+                .collect::<PyResult<Vec<_>>>()
+        })?)),
+    };
+    match typed {
+        Some(column) => Ok(column),
+        None => Ok(ColumnData::Strings(map_values(&values, cell)?)),
+    }
+}
+
+/// Convert each value present, keeping nulls
+fn map_values<T>(values: &[Option<Bound<'_, PyAny>>], mut convert: impl FnMut(&Bound<'_, PyAny>) -> PyResult<T>) -> PyResult<Vec<Option<T>>> {
+    values.iter().map(|value| value.as_ref().map(&mut convert).transpose()).collect()
+}
+
+/// Decimals as unscaled integers at the largest scale among them, or `None` (strings) when
+/// a value needs more than 18 digits. Non-finite values are null.
+fn decimal_column(values: &[Option<Bound<'_, PyAny>>]) -> PyResult<Option<ColumnData>> {
+    const MAX_DIGITS: u32 = 18;
+    let finite = |value: &Bound<'_, PyAny>| value.call_method0("is_finite")?.is_truthy();
+    let mut scale = 0;
+    for value in values.iter().flatten() {
+        if finite(value)? {
+            let exponent = value.call_method0("as_tuple")?.getattr("exponent")?.extract::<i64>()?;
+            scale = scale.max(exponent.saturating_neg().clamp(0, MAX_DIGITS as i64 + 1) as u32);
+        }
+    }
+    if scale > MAX_DIGITS {
+        return Ok(None);
+    }
+    let mut unscaled = Vec::with_capacity(values.len());
+    for value in values {
+        let value = match value {
+            Some(value) if finite(value)? => value,
+            _ => {
+                unscaled.push(None);
+                continue;
+            }
+        };
+        match value.call_method1("scaleb", (scale,))?.call_method0("__int__")?.extract::<i64>() {
+            Ok(value) if value.unsigned_abs() < 10u64.pow(MAX_DIGITS) => unscaled.push(Some(value)),
+            _ => return Ok(None),
+        }
+    }
+    Ok(Some(ColumnData::Decimals { unscaled, scale }))
+}
@@ -0,0 +1,423 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyString};
+use ahash::AHashMap;
+use ego_tree::NodeId;
+use scraper::{ElementRef, Html, Selector};
+use once_cell::sync::Lazy;
+
+use crate::encoding::{charset_from_content_type, lookup_encoding, DecodeOptions, HtmlInput};
+use crate::logging;
+
+static JSON_LD_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("script[type]").unwrap());
+static ITEM_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("[itemscope]:not([itemprop])").unwrap());
+static META_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta[content]").unwrap());
+static TITLE_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("title").unwrap());
+static CANONICAL_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("link[rel][href]").unwrap());
+static ALL_META_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("meta").unwrap());
+static ID_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("[id]").unwrap());
+
+/// Accepts one `@type` or several
+#[derive(FromPyObject)]
+pub enum TypeFilter {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TypeFilter {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            TypeFilter::One(name) => vec![name],
+            TypeFilter::Many(names) => names,
+        }
+    }
+}
+
+/// Find `<script type="application/ld+json">` blocks and parse them into Python objects.
+/// Trailing commas, `//` and `/* */` comments, HTML comment and CDATA wrappers and raw control
+/// characters in strings are tolerated; blocks that still fail to parse are skipped (and
+/// logged). With `type`, the blocks are flattened (top-level arrays and `@graph` entries) and
+/// only nodes whose `@type` matches are returned; `"Product"` also matches
+/// `"https://schema.org/Product"`.
+#[pyfunction]
+#[pyo3(signature = (html, r#type=None))]
+pub fn extract_json_ld(py: Python, html: HtmlInput, r#type: Option<TypeFilter>) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let wanted = r#type.map(TypeFilter::into_vec);
+    Ok(json_ld_nodes(py, &document, wanted.as_deref())?.unbind())
+}
+
+/// The document's JSON-LD blocks, or with `wanted` types the matching flattened nodes
+pub fn json_ld_nodes<'py>(py: Python<'py>, document: &Html, wanted: Option<&[String]>) -> PyResult<Bound<'py, PyList>> {
+    let loads = py.import_bound("json")?.getattr("loads")?;
+    let kwargs = PyDict::new_bound(py);
+    // Real pages put raw newlines and tabs inside JSON strings
+    kwargs.set_item("strict", false)?;
+
+    let blocks = PyList::empty_bound(py);
+    for script in document.select(&JSON_LD_SELECTOR) {
+        let is_json_ld = script.value().attr("type")
+            .map(|t| t.trim().eq_ignore_ascii_case("application/ld+json"))
+            .unwrap_or(false);
+        if !is_json_ld {
+            continue;
+        }
+        let raw: String = script.text().collect();
+        let cleaned = clean_json(&raw);
+        if cleaned.is_empty() {
+            continue;
+        }
+        match loads.call((cleaned,), Some(&kwargs)) {
+            Ok(value) => blocks.append(value)?,
+            Err(err) => logging::emit(logging::WARNING, || {
+                format!("skipping unparseable JSON-LD block: {}", err.value_bound(py))
+            }),
+        }
+    }
+
+    let Some(wanted) = wanted else { return Ok(blocks) };
+    let matches = PyList::empty_bound(py);
+    for block in blocks.iter() {
+        for node in flatten(&block)? {
+            if has_type(&node, wanted)? {
+                matches.append(node)?;
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Materialize the page's top-level microdata items (`itemscope` elements that are not
+/// themselves a property) into dicts shaped like JSON-LD: `@type` and `@id` from `itemtype` and
+/// `itemid`, one key per `itemprop` name, nested items as nested dicts and repeated properties
+/// as lists. `itemref` is followed. With `type`, only items whose `@type` matches are returned.
+#[pyfunction]
+#[pyo3(signature = (html, r#type=None))]
+pub fn extract_microdata(py: Python, html: HtmlInput, r#type: Option<TypeFilter>) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let wanted = r#type.map(TypeFilter::into_vec);
+    Ok(microdata_items(py, &document, wanted.as_deref())?.unbind())
+}
+
+/// The document's top-level microdata items, only those of the `wanted` types if given
+pub fn microdata_items<'py>(py: Python<'py>, document: &Html, wanted: Option<&[String]>) -> PyResult<Bound<'py, PyList>> {
+    let ids: AHashMap<&str, ElementRef> = document
+        .select(&ID_SELECTOR)
+        .filter_map(|elem| elem.value().id().map(|id| (id, elem)))
+        .collect();
+
+    let items = PyList::empty_bound(py);
+    for elem in document.select(&ITEM_SELECTOR) {
+        let item = microdata_item(py, elem, &ids, &mut Vec::new())?;
+        if let Some(wanted) = wanted {
+            if !has_type(item.as_any(), wanted)? {
+                continue;
+            }
+        }
+        items.append(item)?;
+    }
+    Ok(items)
+}
+
+fn microdata_item<'py>(
+    py: Python<'py>,
+    elem: ElementRef,
+    ids: &AHashMap<&str, ElementRef>,
+    stack: &mut Vec<NodeId>,
+) -> PyResult<Bound<'py, PyDict>> {
+    stack.push(elem.id());
+    let item = PyDict::new_bound(py);
+    let types: Vec<&str> = elem.value().attr("itemtype").unwrap_or("").split_whitespace().collect();
+    match types.as_slice() {
+        [] => {}
+        [one] => item.set_item("@type", *one)?,
+        many => item.set_item("@type", many.to_vec())?,
+    }
+    if let Some(id) = elem.value().attr("itemid") {
+        item.set_item("@id", id.trim())?;
+    }
+
+    let mut properties = Vec::new();
+    collect_properties(elem, &mut properties);
+    for id in elem.value().attr("itemref").unwrap_or("").split_whitespace() {
+        if let Some(referenced) = ids.get(id) {
+            if referenced.value().attr("itemprop").is_some() {
+                properties.push(*referenced);
+            }
+            if referenced.value().attr("itemscope").is_none() {
+                collect_properties(*referenced, &mut properties);
+            }
+        }
+    }
+
+    for prop in properties {
+        let value: PyObject = if prop.value().attr("itemscope").is_some() {
+            // An itemref cycle would otherwise recurse forever
+            if stack.contains(&prop.id()) {
+                continue;
+            }
+            microdata_item(py, prop, ids, stack)?.into_any().unbind()
+        } else {
+            property_value(prop).into_py(py)
+        };
+        for name in prop.value().attr("itemprop").unwrap_or("").split_whitespace() {
+            insert_repeated(&item, name, value.clone_ref(py))?;
+        }
+    }
+    stack.pop();
+    Ok(item)
+}
+
+/// Descendants of `elem` carrying `itemprop`, without entering nested items
+fn collect_properties<'a>(elem: ElementRef<'a>, out: &mut Vec<ElementRef<'a>>) {
+    for child in elem.children().filter_map(ElementRef::wrap) {
+        if child.value().attr("itemprop").is_some() {
+            out.push(child);
+        }
+        if child.value().attr("itemscope").is_none() {
+            collect_properties(child, out);
+        }
+    }
+}
+
+/// A non-item property's value: the attribute HTML defines for the element, or its text
+fn property_value(elem: ElementRef) -> String {
+    let value = elem.value();
+    let attr = match value.name() {
+        "meta" => Some("content"),
+        "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => Some("src"),
+        "a" | "area" | "link" => Some("href"),
+        "object" => Some("data"),
+        "data" | "meter" => Some("value"),
+        "time" if value.attr("datetime").is_some() => Some("datetime"),
+        _ => None,
+    };
+    match attr {
+        Some(attr) => value.attr(attr).unwrap_or("").trim().to_string(),
+        None => elem.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// Collect OpenGraph (`og:*`) and Twitter Card (`twitter:*`) `<meta>` tags into a dict.
+/// Keys are lowercased and read from `property` or `name` (sites mix them up); the aliases
+/// `og:image:url` and `twitter:image:src` become `og:image` and `twitter:image`. A property
+/// that appears more than once (several `og:image`s) becomes a list in document order.
+#[pyfunction]
+pub fn extract_social_meta(py: Python, html: HtmlInput) -> PyResult<Py<PyDict>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let result = PyDict::new_bound(py);
+    for meta in document.select(&META_SELECTOR) {
+        let value = meta.value();
+        let Some(key) = value.attr("property").or_else(|| value.attr("name")).and_then(social_key) else {
+            continue;
+        };
+        let content = value.attr("content").unwrap_or("").trim();
+        if content.is_empty() {
+            continue;
+        }
+        insert_repeated(&result, &key, content.into_py(py))?;
+    }
+    Ok(result.unbind())
+}
+
+fn social_key(name: &str) -> Option<String> {
+    let key = name.trim().to_ascii_lowercase();
+    if !(key.starts_with("og:") || key.starts_with("twitter:")) {
+        return None;
+    }
+    Some(match key.as_str() {
+        "og:image:url" => "og:image".to_string(),
+        "twitter:image:src" => "twitter:image".to_string(),
+        _ => key,
+    })
+}
+
+/// Common document metadata in one pass: `title`, `description`, `keywords` (list), `canonical`,
+/// `robots` (list of lowercased directives), `viewport`, `charset` and `meta`, a dict of every
+/// other `<meta>` keyed by its lowercased `name`, `property` or `http-equiv`. `charset` is the
+/// declared one, or for bytes input without a declaration the detected encoding. Missing values
+/// are `None` (empty lists for `keywords` and `robots`).
+#[pyfunction]
+pub fn get_metadata(py: Python, html: HtmlInput) -> PyResult<Py<PyDict>> {
+    let (text, info) = html.decode_with_info(&DecodeOptions::default())?;
+    let document = Html::parse_document(&text);
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let title = document.select(&TITLE_SELECTOR).next().map(|t| collapse(&t.text().collect::<String>()));
+    let canonical = document
+        .select(&CANONICAL_SELECTOR)
+        .find(|link| link.value().attr("rel").unwrap_or("").split_whitespace().any(|r| r.eq_ignore_ascii_case("canonical")))
+        .and_then(|link| link.value().attr("href"))
+        .map(|href| href.trim().to_string());
+
+    let mut description = None;
+    let mut keywords = Vec::new();
+    let mut robots = Vec::new();
+    let mut viewport = None;
+    let mut charset = None;
+    let meta = PyDict::new_bound(py);
+    for elem in document.select(&ALL_META_SELECTOR) {
+        let value = elem.value();
+        if let Some(label) = value.attr("charset") {
+            charset.get_or_insert_with(|| encoding_name(label));
+            continue;
+        }
+        let Some(key) = value.attr("name").or_else(|| value.attr("property")).or_else(|| value.attr("http-equiv")) else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let content = value.attr("content").unwrap_or("").trim();
+        match key.as_str() {
+            "description" => { description.get_or_insert_with(|| content.to_string()); }
+            "keywords" => keywords.extend(content.split(',').map(str::trim).filter(|k| !k.is_empty()).map(str::to_string)),
+            "robots" => robots.extend(content.split(',').map(|d| d.trim().to_ascii_lowercase()).filter(|d| !d.is_empty())),
+            "viewport" => { viewport.get_or_insert_with(|| content.to_string()); }
+            "content-type" => {
+                if let Some(encoding) = charset_from_content_type(content) {
+                    charset.get_or_insert_with(|| encoding.name().to_ascii_lowercase());
+                }
+                insert_repeated(&meta, &key, content.into_py(py))?;
+            }
+            _ => insert_repeated(&meta, &key, content.into_py(py))?,
+        }
+    }
+    let charset = charset.or_else(|| info.map(|info| info.encoding));
+
+    let result = PyDict::new_bound(py);
+    result.set_item("title", title)?;
+    result.set_item("description", description)?;
+    result.set_item("keywords", keywords)?;
+    result.set_item("canonical", canonical)?;
+    result.set_item("robots", robots)?;
+    result.set_item("viewport", viewport)?;
+    result.set_item("charset", charset)?;
+    result.set_item("meta", meta)?;
+    Ok(result.unbind())
+}
+
+/// Canonical lowercase name for a declared charset, or the label itself when unknown
+fn encoding_name(label: &str) -> String {
+    match lookup_encoding(label) {
+        Ok(encoding) => encoding.name().to_ascii_lowercase(),
+        Err(_) => label.trim().to_ascii_lowercase(),
+    }
+}
+
+/// Set `key`, turning the value into a list when the key is seen again
+pub fn insert_repeated(dict: &Bound<'_, PyDict>, key: &str, value: PyObject) -> PyResult<()> {
+    match dict.get_item(key)? {
+        None => dict.set_item(key, value),
+        Some(existing) => match existing.downcast::<PyList>() {
+            Ok(list) => list.append(value),
+            Err(_) => dict.set_item(key, PyList::new_bound(dict.py(), [existing.unbind(), value])),
+        },
+    }
+}
+
+/// Top-level nodes of a JSON-LD block: the block itself, the items of a top-level array, and
+/// the entries of any `@graph`
+fn flatten<'py>(value: &Bound<'py, PyAny>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let mut nodes = Vec::new();
+    if let Ok(list) = value.downcast::<PyList>() {
+        for item in list.iter() {
+            nodes.extend(flatten(&item)?);
+        }
+    } else if let Ok(dict) = value.downcast::<PyDict>() {
+        nodes.push(value.clone());
+        if let Some(graph) = dict.get_item("@graph")? {
+            nodes.extend(flatten(&graph)?);
+        }
+    }
+    Ok(nodes)
+}
+
+fn has_type(node: &Bound<'_, PyAny>, wanted: &[String]) -> PyResult<bool> {
+    let Ok(dict) = node.downcast::<PyDict>() else { return Ok(false) };
+    let Some(types) = dict.get_item("@type")? else { return Ok(false) };
+    let names: Vec<String> = if let Ok(name) = types.downcast::<PyString>() {
+        vec![name.to_string()]
+    } else if let Ok(list) = types.downcast::<PyList>() {
+        list.iter().filter_map(|t| t.extract::<String>().ok()).collect()
+    } else {
+        Vec::new()
+    };
+    Ok(names.iter().any(|name| wanted.iter().any(|w| type_matches(name, w))))
+}
+
+/// `"Product"` matches `"Product"`, `"schema:Product"` and `"https://schema.org/Product"`
+fn type_matches(name: &str, wanted: &str) -> bool {
+    let short = |s: &str| s.rsplit(['/', '#', ':']).next().unwrap_or(s).to_string();
+    name == wanted || short(name) == short(wanted)
+}
+
+/// Strip the junk commonly found around and inside JSON-LD: HTML comment and CDATA wrappers,
+/// JavaScript comments and trailing commas. String contents are left alone.
+pub fn clean_json(raw: &str) -> String {
+    let mut text = raw.trim();
+    for (open, close) in [("<!--", "-->"), ("<![CDATA[", "]]>")] {
+        if let Some(inner) = text.strip_prefix(open) {
+            text = inner.strip_suffix(close).unwrap_or(inner).trim();
+        }
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                out.push(chars[i + 1]);
+                i += 1;
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            '}' | ']' => {
+                // Drop a trailing comma before the closing bracket
+                let trimmed = out.trim_end_matches(char::is_whitespace).len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    let out = out.trim();
+    // Some sites end the block with a stray semicolon
+    out.strip_suffix(';').unwrap_or(out).trim_end().to_string()
+}
@@ -0,0 +1,191 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use scraper::{ElementRef, Html};
+
+use crate::encoding::{DecodeOptions, HtmlInput};
+use crate::scraper::reparse;
+use crate::text;
+
+enum Segment {
+    Literal(String),
+    Placeholder(String),
+}
+
+/// Text or an attribute value from the template: literal parts and `{{name}}` placeholders
+struct Pattern {
+    segments: Vec<Segment>,
+}
+
+impl Pattern {
+    fn parse(source: &str, names: &mut Vec<String>) -> PyResult<Self> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + len].trim();
+            if name.is_empty() || names.iter().any(|n| n == name) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    format!("Invalid template placeholder: '{}'. Use a unique {{{{name}}}}", &rest[start..start + 4 + len])
+                ));
+            }
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+            segments.push(Segment::Placeholder(name.to_string()));
+            names.push(name.to_string());
+            rest = &rest[start + 4 + len..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+        Ok(Pattern { segments })
+    }
+
+    fn has_placeholders(&self) -> bool {
+        self.segments.iter().any(|s| matches!(s, Segment::Placeholder(_)))
+    }
+
+    /// Match `value` and push the placeholders' values. A placeholder takes everything up to
+    /// the first occurrence of the literal after it, or the rest of the value when last.
+    fn bind(&self, value: &str, bindings: &mut Vec<(String, String)>) -> bool {
+        let mut rest = value;
+        let mut index = 0;
+        while index < self.segments.len() {
+            match &self.segments[index] {
+                Segment::Literal(literal) => match rest.strip_prefix(literal.as_str()) {
+                    Some(after) => rest = after,
+                    None => return false,
+                },
+                Segment::Placeholder(name) => {
+                    let (value, after) = match self.segments.get(index + 1) {
+                        // The final literal anchors at the end
+                        Some(Segment::Literal(literal)) if index + 2 == self.segments.len() => {
+                            match rest.strip_suffix(literal.as_str()) {
+                                Some(value) => (value, ""),
+                                None => return false,
+                            }
+                        }
+                        Some(Segment::Literal(literal)) => match rest.find(literal.as_str()) {
+                            Some(at) => (&rest[..at], &rest[at + literal.len()..]),
+                            None => return false,
+                        },
+                        Some(Segment::Placeholder(_)) => ("", rest),
+                        None => (rest, ""),
+                    };
+                    bindings.push((name.clone(), value.trim().to_string()));
+                    rest = after;
+                    if matches!(self.segments.get(index + 1), Some(Segment::Literal(_))) {
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+        }
+        rest.is_empty()
+    }
+}
+
+enum AttrRule {
+    /// Template classes must all be present on the element
+    Classes(Vec<String>),
+    Value(Pattern),
+}
+
+/// A compiled template element
+struct TemplateNode {
+    name: String,
+    attrs: Vec<(String, AttrRule)>,
+    /// Whitespace-collapsed text pattern of an element without element children
+    text: Option<Pattern>,
+    children: Vec<TemplateNode>,
+}
+
+impl TemplateNode {
+    fn compile(elem: ElementRef<'_>, names: &mut Vec<String>) -> PyResult<Self> {
+        let mut attrs = Vec::new();
+        for (name, value) in elem.value().attrs() {
+            let pattern = Pattern::parse(value, names)?;
+            let rule = match name {
+                "class" if !pattern.has_placeholders() => AttrRule::Classes(value.split_whitespace().map(str::to_string).collect()),
+                _ => AttrRule::Value(pattern),
+            };
+            attrs.push((name.to_string(), rule));
+        }
+        let children = elem.children().filter_map(ElementRef::wrap).map(|child| Self::compile(child, names)).collect::<PyResult<Vec<_>>>()?;
+        let text = match children.is_empty() {
+            true => Some(Pattern::parse(&text::element_text(elem, true), names)?),
+            false => None,
+        };
+        Ok(TemplateNode { name: elem.value().name().to_string(), attrs, text, children })
+    }
+
+    /// Match `elem`, pushing bindings; on failure `bindings` is left as it was
+    fn matches(&self, elem: ElementRef<'_>, bindings: &mut Vec<(String, String)>) -> bool {
+        let mark = bindings.len();
+        let matched = self.matches_inner(elem, bindings);
+        if !matched {
+            bindings.truncate(mark);
+        }
+        matched
+    }
+
+    fn matches_inner(&self, elem: ElementRef<'_>, bindings: &mut Vec<(String, String)>) -> bool {
+        if elem.value().name() != self.name {
+            return false;
+        }
+        for (name, rule) in &self.attrs {
+            let matched = match (rule, elem.value().attr(name)) {
+                (AttrRule::Classes(classes), Some(_)) => classes.iter().all(|class| elem.value().has_class(class, scraper::CaseSensitivity::CaseSensitive)),
+                (AttrRule::Value(pattern), Some(value)) => pattern.bind(value, bindings),
+                (_, None) => false,
+            };
+            if !matched {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.text {
+            if !pattern.segments.is_empty() && !pattern.bind(&text::element_text(elem, true), bindings) {
+                return false;
+            }
+        }
+        // Template children match an in-order subsequence of the element's children
+        let mut candidates = elem.children().filter_map(ElementRef::wrap);
+        self.children.iter().all(|child| candidates.any(|candidate| child.matches(candidate, bindings)))
+    }
+}
+
+/// Match an annotated HTML template against a document, for rigidly templated pages where
+/// one structure is easier to write than a selector per field. The template is one element
+/// whose text and attribute values may hold `{{name}}` placeholders, e.g.
+/// `<div class="product"><h2>{{title}}</h2><a href="/p/{{id}}">{{label}}</a></div>`.
+/// An element matches when its tag is the same, it has every template attribute (classes as
+/// a subset, other values equal or fitting the pattern), its text fits the template's for
+/// elements without child elements, and the template's children match its children in order
+/// (extra children are skipped). Returns one `{name: value}` dict per matching element in
+/// document order; values are trimmed and whitespace-collapsed.
+#[pyfunction]
+pub fn match_template<'py>(py: Python<'py>, html: HtmlInput, template: &str) -> PyResult<Bound<'py, PyList>> {
+    let template_doc = reparse(template);
+    let roots: Vec<ElementRef> = template_doc.root_element().children().filter_map(ElementRef::wrap).collect();
+    let [root] = roots.as_slice() else {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            format!("Invalid template: expected one root element, found {}", roots.len())
+        ));
+    };
+    let compiled = TemplateNode::compile(*root, &mut Vec::new())?;
+
+    let document = Html::parse_document(&html.decode(&DecodeOptions::default())?);
+    let results = PyList::empty_bound(py);
+    let mut bindings = Vec::new();
+    for elem in document.root_element().descendants().filter_map(ElementRef::wrap) {
+        bindings.clear();
+        if compiled.matches(elem, &mut bindings) {
+            let item = PyDict::new_bound(py);
+            for (name, value) in &bindings {
+                item.set_item(name, value)?;
+            }
+            results.append(item)?;
+        }
+    }
+    Ok(results)
+}
@@ -0,0 +1,149 @@
+use pyo3::prelude::*;
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+use unicode_normalization::UnicodeNormalization;
+
+/// Unicode normalization forms accepted by the `normalize` option of text extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl Normalization {
+    pub fn parse(form: &str) -> PyResult<Self> {
+        match form.to_ascii_uppercase().as_str() {
+            "NFC" => Ok(Normalization::Nfc),
+            "NFD" => Ok(Normalization::Nfd),
+            "NFKC" => Ok(Normalization::Nfkc),
+            "NFKD" => Ok(Normalization::Nfkd),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid normalization form: {}. Use 'NFC', 'NFD', 'NFKC' or 'NFKD'", form)
+            )),
+        }
+    }
+
+    /// Parses the optional Python-facing `normalize` argument.
+    pub fn from_arg(form: Option<&str>) -> PyResult<Option<Self>> {
+        form.map(Normalization::parse).transpose()
+    }
+
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Normalization::Nfc => text.nfc().collect(),
+            Normalization::Nfd => text.nfd().collect(),
+            Normalization::Nfkc => text.nfkc().collect(),
+            Normalization::Nfkd => text.nfkd().collect(),
+        }
+    }
+}
+
+/// Applies an optional normalization form, passing the text through untouched otherwise.
+pub fn normalize(text: String, form: Option<Normalization>) -> String {
+    match form {
+        // ASCII is invariant under every normalization form
+        Some(form) if !text.is_ascii() => form.apply(&text),
+        _ => text,
+    }
+}
+
+/// Elements whose whitespace is content (code listings, ASCII tables, form values); their
+/// text is kept as written by every text extraction, whatever it does elsewhere
+pub const PRESERVE_WHITESPACE: [&str; 5] = ["pre", "code", "textarea", "listing", "plaintext"];
+
+/// Text assembled in place: runs of text (text nodes, or the whole text of a
+/// whitespace-preserving element) are appended to one buffer with a space between them.
+struct TextBuffer {
+    out: String,
+    /// Whether a run has been written, so the next one needs a separating space
+    started: bool,
+    /// End of the last preserved run; trimming the end of the text stops here
+    protected: usize,
+}
+
+impl TextBuffer {
+    fn separate(&mut self) {
+        if self.started {
+            self.out.push(' ');
+        }
+        self.started = true;
+    }
+
+    /// A text node as written; whitespace before the first run is dropped
+    fn push_run(&mut self, text: &str) {
+        let text = if self.started { text } else { text.trim_start() };
+        if !text.is_empty() || self.started {
+            self.separate();
+            self.out.push_str(text);
+        }
+    }
+
+    /// A text node with whitespace runs collapsed, as `split_whitespace().join(" ")`; nothing
+    /// when it is all whitespace. ASCII text, the common case, takes the byte-level split,
+    /// except for the vertical tab that `split_ascii_whitespace` does not treat as a space.
+    fn push_words(&mut self, text: &str) {
+        if text.is_ascii() && !text.contains('\x0B') {
+            self.push_word_iter(text.split_ascii_whitespace());
+        } else {
+            self.push_word_iter(text.split_whitespace());
+        }
+    }
+
+    fn push_word_iter<'a>(&mut self, words: impl Iterator<Item = &'a str>) {
+        let mut words = words.peekable();
+        if words.peek().is_none() {
+            return;
+        }
+        self.separate();
+        for (index, word) in words.enumerate() {
+            if index > 0 {
+                self.out.push(' ');
+            }
+            self.out.push_str(word);
+        }
+    }
+
+    /// The text of a whitespace-preserving element, exempt from trimming
+    fn push_preserved(&mut self, elem: ElementRef<'_>) {
+        self.separate();
+        for text in elem.text() {
+            self.out.push_str(text);
+        }
+        self.protected = self.out.len();
+    }
+
+    fn collect(&mut self, node: NodeRef<'_, Node>, collapse: bool) {
+        for child in node.children() {
+            match child.value() {
+                Node::Text(text) if collapse => self.push_words(text),
+                Node::Text(text) => self.push_run(text),
+                Node::Element(elem) if PRESERVE_WHITESPACE.contains(&elem.name()) => {
+                    self.push_preserved(ElementRef::wrap(child).unwrap())
+                }
+                Node::Element(_) => self.collect(child, collapse),
+                _ => {}
+            }
+        }
+    }
+
+    /// The text with trailing whitespace dropped, back to the end of the last preserved run
+    fn finish(mut self) -> String {
+        let end = self.out.trim_end().len().max(self.protected);
+        self.out.truncate(end);
+        self.out
+    }
+}
+
+/// Text of `elem` with its text nodes joined by spaces and trimmed. With `collapse`, runs of
+/// whitespace become single spaces too. Text inside `PRESERVE_WHITESPACE` elements is exempt
+/// from both, so indentation and alignment survive.
+pub fn element_text(elem: ElementRef<'_>, collapse: bool) -> String {
+    if PRESERVE_WHITESPACE.contains(&elem.value().name()) {
+        return elem.text().collect();
+    }
+    let mut buffer = TextBuffer { out: String::new(), started: false, protected: 0 };
+    buffer.collect(*elem, collapse);
+    buffer.finish()
+}
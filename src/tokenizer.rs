@@ -0,0 +1,421 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyIterator, PyList, PyString};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+use crate::encoding::{DecodeOptions, HtmlInput, StreamDecoder};
+
+/// Bytes or characters handed to the tokenizer at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// One tokenizer event, converted to a dict when it is yielded
+enum Event {
+    Start { name: String, attrs: Vec<(String, String)>, self_closing: bool, line: u64 },
+    End { name: String, line: u64 },
+    Text { data: String, line: u64 },
+    Comment { data: String, line: u64 },
+    Doctype { name: Option<String>, line: u64 },
+}
+
+/// Collects events, merging runs of character tokens into one text event
+#[derive(Default)]
+struct EventSink {
+    events: RefCell<VecDeque<Event>>,
+    text: RefCell<String>,
+    text_line: Cell<u64>,
+}
+
+impl EventSink {
+    fn flush_text(&self) {
+        let data = std::mem::take(&mut *self.text.borrow_mut());
+        if !data.is_empty() {
+            self.events.borrow_mut().push_back(Event::Text { data, line: self.text_line.get() });
+        }
+    }
+
+    fn push_text(&self, text: &str, line: u64) {
+        let mut buffer = self.text.borrow_mut();
+        if buffer.is_empty() {
+            self.text_line.set(line);
+        }
+        buffer.push_str(text);
+    }
+}
+
+impl TokenSink for EventSink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, line: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::CharacterTokens(text) => self.push_text(&text, line),
+            Token::NullCharacterToken => self.push_text("\u{fffd}", line),
+            Token::ParseError(_) => {}
+            Token::EOFToken => self.flush_text(),
+            Token::TagToken(tag) => {
+                self.flush_text();
+                let name = tag.name.to_string();
+                // Without a tree builder the sink has to switch the tokenizer into the raw
+                // text states the HTML parser would, or `<script>` bodies become markup
+                let result = match (tag.kind, &*name) {
+                    (TagKind::StartTag, "script") => TokenSinkResult::RawData(RawKind::ScriptData),
+                    (TagKind::StartTag, "style" | "xmp" | "iframe" | "noembed" | "noframes" | "noscript") => {
+                        TokenSinkResult::RawData(RawKind::Rawtext)
+                    }
+                    (TagKind::StartTag, "title" | "textarea") => TokenSinkResult::RawData(RawKind::Rcdata),
+                    (TagKind::StartTag, "plaintext") => TokenSinkResult::Plaintext,
+                    _ => TokenSinkResult::Continue,
+                };
+                let event = match tag.kind {
+                    TagKind::StartTag => Event::Start {
+                        name,
+                        attrs: tag.attrs.iter().map(|a| (a.name.local.to_string(), a.value.to_string())).collect(),
+                        self_closing: tag.self_closing,
+                        line,
+                    },
+                    TagKind::EndTag => Event::End { name, line },
+                };
+                self.events.borrow_mut().push_back(event);
+                return result;
+            }
+            Token::CommentToken(data) => {
+                self.flush_text();
+                self.events.borrow_mut().push_back(Event::Comment { data: data.to_string(), line });
+            }
+            Token::DoctypeToken(doctype) => {
+                self.flush_text();
+                let name = doctype.name.map(|n| n.to_string());
+                self.events.borrow_mut().push_back(Event::Doctype { name, line });
+            }
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// Where the markup comes from
+enum Source {
+    Text { text: String, pos: usize },
+    Bytes { data: Py<PyBytes>, pos: usize },
+    /// A file-like object with `read(size)`
+    Reader(PyObject),
+    /// An iterable of `str` or `bytes` chunks
+    Chunks(Py<PyIterator>),
+}
+
+/// Iterator over the events of `tokenize()`
+#[pyclass(unsendable)]
+pub struct TokenStream {
+    source: Source,
+    decoder: StreamDecoder,
+    bytes_seen: bool,
+    tokenizer: Tokenizer<EventSink>,
+    input: BufferQueue,
+    done: bool,
+}
+
+impl TokenStream {
+    /// The next chunk of text, `None` at the end of input
+    fn next_chunk(&mut self, py: Python) -> PyResult<Option<String>> {
+        let chunk = match &mut self.source {
+            Source::Text { text, pos } => {
+                if *pos >= text.len() {
+                    return Ok(None);
+                }
+                let mut end = (*pos + CHUNK_SIZE).min(text.len());
+                while !text.is_char_boundary(end) {
+                    end += 1;
+                }
+                let chunk = text[*pos..end].to_string();
+                *pos = end;
+                return Ok(Some(chunk));
+            }
+            Source::Bytes { data, pos } => {
+                let bytes = data.as_bytes(py);
+                if *pos >= bytes.len() {
+                    return Ok(None);
+                }
+                let end = (*pos + CHUNK_SIZE).min(bytes.len());
+                let chunk = PyBytes::new_bound(py, &bytes[*pos..end]).into_any();
+                *pos = end;
+                chunk
+            }
+            Source::Reader(reader) => {
+                let chunk = reader.bind(py).call_method1("read", (CHUNK_SIZE,))?;
+                if !chunk.is_truthy()? {
+                    return Ok(None);
+                }
+                chunk
+            }
+            Source::Chunks(chunks) => match chunks.bind(py).clone().next() {
+                Some(chunk) => chunk?,
+                None => return Ok(None),
+            },
+        };
+        if let Ok(text) = chunk.downcast::<PyString>() {
+            Ok(Some(text.to_str()?.to_string()))
+        } else if let Ok(bytes) = chunk.downcast::<PyBytes>() {
+            self.bytes_seen = true;
+            Ok(Some(self.decoder.feed_bytes(bytes.as_bytes())?))
+        } else {
+            Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "tokenize() chunks must be str or bytes, not {}",
+                chunk.get_type().name()?
+            )))
+        }
+    }
+
+    fn feed(&self, text: &str) {
+        if !text.is_empty() {
+            self.input.push_back(StrTendril::from_slice(text));
+            let _ = self.tokenizer.feed(&self.input);
+        }
+    }
+
+    /// Tokenize the next chunk, or finish at the end of input
+    fn pump(&mut self, py: Python) -> PyResult<()> {
+        match self.next_chunk(py)? {
+            Some(text) => self.feed(&text),
+            None => {
+                if self.bytes_seen {
+                    let rest = self.decoder.finish()?;
+                    self.feed(&rest);
+                }
+                self.tokenizer.end();
+                self.done = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn event_dict(py: Python, event: Event) -> PyResult<Py<PyDict>> {
+    let dict = PyDict::new_bound(py);
+    let line = match event {
+        Event::Start { name, attrs, self_closing, line } => {
+            dict.set_item("type", "start")?;
+            dict.set_item("name", name)?;
+            let attr_dict = PyDict::new_bound(py);
+            for (key, value) in attrs {
+                attr_dict.set_item(key, value)?;
+            }
+            dict.set_item("attrs", attr_dict)?;
+            dict.set_item("self_closing", self_closing)?;
+            line
+        }
+        Event::End { name, line } => {
+            dict.set_item("type", "end")?;
+            dict.set_item("name", name)?;
+            line
+        }
+        Event::Text { data, line } => {
+            dict.set_item("type", "text")?;
+            dict.set_item("data", data)?;
+            line
+        }
+        Event::Comment { data, line } => {
+            dict.set_item("type", "comment")?;
+            dict.set_item("data", data)?;
+            line
+        }
+        Event::Doctype { name, line } => {
+            dict.set_item("type", "doctype")?;
+            dict.set_item("name", name)?;
+            line
+        }
+    };
+    dict.set_item("line", line)?;
+    Ok(dict.unbind())
+}
+
+#[pymethods]
+impl TokenStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<Py<PyDict>>> {
+        loop {
+            let event = self.tokenizer.sink.events.borrow_mut().pop_front();
+            if let Some(event) = event {
+                return event_dict(py, event).map(Some);
+            }
+            if self.done {
+                return Ok(None);
+            }
+            self.pump(py)?;
+        }
+    }
+}
+
+/// Stream the page's tokens without building a DOM, for inputs too large to parse whole.
+/// `source` is a str, bytes, a file object opened in text or binary mode, or any iterable of
+/// str/bytes chunks (e.g. a streaming HTTP response); it is read 64 KiB at a time as events
+/// are consumed. Bytes are decoded as for `StreamDecoder`. Each event is a dict with `type`
+/// and `line`, plus `name`, `attrs` and `self_closing` for `"start"`, `name` for `"end"` and
+/// `"doctype"`, and `data` for `"text"` (adjacent character data merged, entities decoded)
+/// and `"comment"`. Tags are reported as written: no end tags are implied and none are
+/// dropped. `<script>`, `<style>`, `<title>` and `<textarea>` contents come through as text.
+#[pyfunction]
+#[pyo3(signature = (source, from_encoding=None, exclude_encodings=None, errors=None, content_type=None))]
+pub fn tokenize(
+    source: &Bound<'_, PyAny>,
+    from_encoding: Option<&str>,
+    exclude_encodings: Option<Vec<String>>,
+    errors: Option<&str>,
+    content_type: Option<&str>,
+) -> PyResult<TokenStream> {
+    let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, false)?;
+    let source = if let Ok(text) = source.downcast::<PyString>() {
+        Source::Text { text: text.to_str()?.to_string(), pos: 0 }
+    } else if let Ok(bytes) = source.downcast::<PyBytes>() {
+        Source::Bytes { data: bytes.clone().unbind(), pos: 0 }
+    } else if source.hasattr("read")? {
+        Source::Reader(source.clone().unbind())
+    } else {
+        let chunks = PyIterator::from_bound_object(source).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "tokenize() expects str, bytes, a file object or an iterable of chunks"
+            )
+        })?;
+        Source::Chunks(chunks.unbind())
+    };
+    Ok(TokenStream {
+        source,
+        decoder: StreamDecoder::with_options(options),
+        bytes_seen: false,
+        tokenizer: Tokenizer::new(EventSink::default(), TokenizerOpts::default()),
+        input: BufferQueue::default(),
+        done: false,
+    })
+}
+
+/// Elements whose content is text up to their end tag, not markup
+const RAW_TEXT_ELEMENTS: [&str; 10] =
+    ["script", "style", "xmp", "iframe", "noembed", "noframes", "noscript", "title", "textarea", "plaintext"];
+
+/// An attribute repeated on the same start tag, which the parser drops
+struct Duplicate {
+    tag: String,
+    name: String,
+    value: String,
+    kept: String,
+    line: usize,
+}
+
+fn is_space(b: u8) -> bool {
+    matches!(b, b'\t' | b'\n' | b'\x0c' | b'\r' | b' ')
+}
+
+/// Walk the start tags of `html` the way the tokenizer splits them into attributes, collecting
+/// every attribute whose name already appeared on the same tag. Values are as written.
+fn scan_duplicates(html: &str) -> Vec<Duplicate> {
+    let bytes = html.as_bytes();
+    let lower = html.to_ascii_lowercase();
+    let len = html.len();
+    let mut found = Vec::new();
+    let (mut line, mut counted) = (1, 0);
+    let mut i = 0;
+    while let Some(offset) = html[i..].find('<') {
+        let start = i + offset;
+        if html[start..].starts_with("<!--") {
+            i = html[start + 4..].find("-->").map_or(len, |end| start + 4 + end + 3);
+            continue;
+        }
+        // End tags, doctypes and a stray `<` carry no attributes that reach the tree
+        if !bytes.get(start + 1).is_some_and(u8::is_ascii_alphabetic) {
+            i = start + 1;
+            continue;
+        }
+        line += bytes[counted..start].iter().filter(|&&b| b == b'\n').count();
+        counted = start;
+
+        let mut pos = start + 1;
+        while pos < len && !is_space(bytes[pos]) && !matches!(bytes[pos], b'/' | b'>') {
+            pos += 1;
+        }
+        let tag = lower[start + 1..pos].to_string();
+        let mut attrs: Vec<(String, String)> = Vec::new();
+        loop {
+            while pos < len && (is_space(bytes[pos]) || bytes[pos] == b'/') {
+                pos += 1;
+            }
+            if pos >= len || bytes[pos] == b'>' {
+                pos += 1;
+                break;
+            }
+            // The first character of a name may be anything, `=` included
+            let name_start = pos;
+            pos += html[pos..].chars().next().map_or(1, char::len_utf8);
+            while pos < len && !is_space(bytes[pos]) && !matches!(bytes[pos], b'/' | b'>' | b'=') {
+                pos += 1;
+            }
+            let name = lower[name_start..pos].to_string();
+            while pos < len && is_space(bytes[pos]) {
+                pos += 1;
+            }
+            let mut value = "";
+            if pos < len && bytes[pos] == b'=' {
+                pos += 1;
+                while pos < len && is_space(bytes[pos]) {
+                    pos += 1;
+                }
+                match bytes.get(pos) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let end = html[pos + 1..].find(quote as char).map_or(len, |end| pos + 1 + end);
+                        value = &html[pos + 1..end];
+                        pos = (end + 1).min(len);
+                    }
+                    _ => {
+                        let value_start = pos;
+                        while pos < len && !is_space(bytes[pos]) && bytes[pos] != b'>' {
+                            pos += 1;
+                        }
+                        value = &html[value_start..pos];
+                    }
+                }
+            }
+            match attrs.iter().find(|(seen, _)| *seen == name) {
+                Some((_, kept)) => found.push(Duplicate {
+                    tag: tag.clone(),
+                    name,
+                    value: value.to_string(),
+                    kept: kept.clone(),
+                    line,
+                }),
+                None => attrs.push((name, value.to_string())),
+            }
+        }
+        i = pos.min(len);
+        if tag == "plaintext" {
+            break;
+        }
+        if RAW_TEXT_ELEMENTS.contains(&tag.as_str()) {
+            i = lower[i..].find(&format!("</{}", tag)).map_or(len, |end| i + end);
+        }
+    }
+    found
+}
+
+/// Attributes repeated on the same start tag. The parser keeps the first occurrence and
+/// drops the rest, so they never show up in `attrs()`; this reads them from the source. Each
+/// is a dict with `tag`, `name`, the dropped `value`, the `kept` value and the `line`, values
+/// as written (character references not decoded).
+#[pyfunction]
+pub fn duplicate_attributes(py: Python, html: HtmlInput) -> PyResult<Py<PyList>> {
+    let text = html.decode(&DecodeOptions::default())?;
+    let list = PyList::empty_bound(py);
+    for duplicate in scan_duplicates(&text) {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("tag", duplicate.tag)?;
+        dict.set_item("name", duplicate.name)?;
+        dict.set_item("value", duplicate.value)?;
+        dict.set_item("kept", duplicate.kept)?;
+        dict.set_item("line", duplicate.line)?;
+        list.append(dict)?;
+    }
+    Ok(list.unbind())
+}
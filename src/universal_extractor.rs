@@ -1,99 +1,1417 @@
+use ahash::AHashMap;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList};
-use scraper::{Html, Selector};
+use pyo3::types::{PyDict, PyList, PyString};
+use rayon::prelude::*;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::arrow::{ArrowTable, Column, Table};
+use crate::encoding::DecodeOptions;
+use crate::dates;
+use crate::links;
+use crate::logging;
+use crate::price;
+use crate::limits::{Deadline, Limits};
+use crate::sinks::{self, CsvWriter, JsonlWriter, Sink, LIST_SEPARATOR};
+use crate::scraper::{parse_document, reparse, select_within, DocumentInput, ParseMode, ParseOptions};
+use crate::errors;
+use crate::selector::{self, Query};
+use crate::text::{self, Normalization};
+use crate::xpath::{self, XPathItem};
 
 enum ExtractionType {
     Text,
     Attribute(String),
     GetAll,
+    /// `@html`: the matched element's outer HTML
+    Html,
+}
+
+/// Stands in for `&` in the copy of the source parsed for `preserve_entities`, so the
+/// tokenizer leaves character references as written. A noncharacter never occurs in real pages.
+const ENTITY_SENTINEL: char = '\u{FDD0}';
+
+/// Field specs with this prefix are XPath 1.0 expressions evaluated with the container as
+/// context node, e.g. `"xpath:.//a/@href"`; a trailing `@get_all` returns every result
+const XPATH_PREFIX: &str = "xpath:";
+
+/// A compiled field spec
+enum FieldQuery {
+    Css(Query, ExtractionType),
+    /// Compiled per thread on use (see `xpath::compile_cached`), keeping field plans `Send`
+    XPath { expr: String, all: bool },
+}
+
+impl FieldQuery {
+    /// Compile a field spec; errors carry the field name
+    fn compile(spec: &str, field_path: &str) -> PyResult<Self> {
+        if let Some(expr) = spec.strip_prefix(XPATH_PREFIX) {
+            let (expr, all) = match expr.strip_suffix("@get_all") {
+                Some(expr) => (expr, true),
+                None => (expr, false),
+            };
+            xpath::compile_cached(expr).map_err(|err| errors::with_detail(err, "field", field_path))?;
+            return Ok(FieldQuery::XPath { expr: expr.to_string(), all });
+        }
+        let (selector_str, extraction_type) = parse_selector_spec(spec)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid selector spec: {}", spec)
+            ))?;
+        Ok(FieldQuery::Css(selector::compile_field(&selector_str, field_path)?, extraction_type))
+    }
+
+    /// The values the query yields in one container, as text. `first_only` stops after the
+    /// first match where only that is needed. `text_attr` is read instead of the text of
+    /// matched elements that carry it. URL attributes are resolved against `base`. `@html`
+    /// is read from `raw_html`, the entity-preserving copy of the container, when given.
+    fn values(
+        &self,
+        container: &ElementRef,
+        container_html: &Html,
+        first_only: bool,
+        text_attr: Option<&str>,
+        base: Option<&Url>,
+        raw_html: Option<&Html>,
+    ) -> PyResult<Vec<String>> {
+        Ok(match self {
+            FieldQuery::Css(selector, extraction_type) => {
+                css_values(selector, extraction_type, container_html, first_only, text_attr, base, raw_html)
+            }
+            FieldQuery::XPath { expr, .. } => xpath::evaluate(**container, &*xpath::compile_cached(expr)?, expr)?
+                .into_iter()
+                .take(if first_only { 1 } else { usize::MAX })
+                .map(XPathItem::into_string)
+                .collect(),
+        })
+    }
+
+    /// Whether the field's value is a list of every match rather than the first match
+    fn is_list(&self) -> bool {
+        matches!(self, FieldQuery::Css(_, ExtractionType::GetAll) | FieldQuery::XPath { all: true, .. })
+    }
+}
+
+/// `FieldQuery::values` for a CSS selector, which only needs the reparsed container and so
+/// can run off the Python thread
+fn css_values(
+    selector: &Query,
+    extraction_type: &ExtractionType,
+    container_html: &Html,
+    first_only: bool,
+    text_attr: Option<&str>,
+    base: Option<&Url>,
+    raw_html: Option<&Html>,
+) -> Vec<String> {
+    let limit = if first_only { 1 } else { usize::MAX };
+    let matched = selector.select(container_html).take(limit);
+    match extraction_type {
+        ExtractionType::Attribute(attr) => matched
+            .map(|elem| links::resolve_attr(base, attr, elem.value().attr(attr).unwrap_or_default()))
+            .collect(),
+        ExtractionType::Html => matched
+            .map(|elem| match raw_html {
+                Some(raw_html) => counterpart(elem, raw_html)
+                    .map(|raw| raw.html().replace(ENTITY_SENTINEL, "&"))
+                    .unwrap_or_default(),
+                None => elem.html(),
+            })
+            .collect(),
+        ExtractionType::Text | ExtractionType::GetAll => matched
+            .map(|elem| match text_attr.and_then(|attr| elem.value().attr(attr)) {
+                Some(value) => value.trim().to_string(),
+                None => text::element_text(elem, false),
+            })
+            .collect(),
+    }
+}
+
+/// Types a field's text can be coerced to with a typed spec, `{"selector": ..., "type": ...}`
+enum FieldType {
+    Price,
+    Datetime,
+}
+
+impl FieldType {
+    fn parse(name: &str, field_path: &str) -> PyResult<Self> {
+        match name {
+            "price" => Ok(FieldType::Price),
+            "datetime" => Ok(FieldType::Datetime),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                format!("Invalid type '{}' for field '{}'. Use 'price' or 'datetime'", name, field_path)
+            )),
+        }
+    }
+
+    /// Attribute read in place of the text when present: `<time datetime>` is machine-readable
+    fn source_attr(&self) -> Option<&'static str> {
+        match self {
+            FieldType::Datetime => Some("datetime"),
+            FieldType::Price => None,
+        }
+    }
+
+    /// The coerced value; `None` when the text is empty or does not parse
+    fn coerce(&self, py: Python, value: &str) -> PyResult<PyObject> {
+        match self {
+            FieldType::Price => match price::parse(value, None, None) {
+                Some(price) => price::to_py(py, price),
+                None => Ok(py.None()),
+            },
+            FieldType::Datetime => match dates::parse(value, false) {
+                Some(parsed) => dates::to_py(py, parsed, None, None),
+                None => Ok(py.None()),
+            },
+        }
+    }
+}
+
+/// A dict spec with exactly `selector` and `type` (both str) is a typed field, not nested
+/// mappings: returns the selector spec and type name
+fn typed_spec(spec: &Bound<'_, PyAny>) -> PyResult<Option<(String, String)>> {
+    let Ok(dict) = spec.downcast::<PyDict>() else { return Ok(None) };
+    if dict.len() != 2 {
+        return Ok(None);
+    }
+    let (Some(selector), Some(field_type)) = (dict.get_item("selector")?, dict.get_item("type")?) else {
+        return Ok(None);
+    };
+    match (selector.extract::<String>(), field_type.extract::<String>()) {
+        (Ok(selector), Ok(field_type)) => Ok(Some((selector, field_type))),
+        _ => Ok(None),
+    }
+}
+
+/// A field's selector spec and optional type name, from a plain str or a typed spec
+fn field_spec(spec: &Bound<'_, PyAny>) -> PyResult<Option<(String, Option<String>)>> {
+    if let Some((selector, field_type)) = typed_spec(spec)? {
+        return Ok(Some((selector, Some(field_type))));
+    }
+    Ok(spec.extract::<String>().ok().map(|selector| (selector, None)))
 }
 
 /// Universal HTML data extractor - works with any HTML structure
-/// Just pass HTML + field mappings and get structured data back
+/// Just pass HTML (str or bytes) + field mappings and get structured data back.
+/// With `report=True`, invalid field selectors no longer abort the call; a
+/// `(results, ExtractionReport)` tuple is returned instead. With `dry_run=True` only
+/// per-field match counts and a sample value are returned (see `dry_run_summary`).
+/// With `base_url` or a `<base href>` in the page, URL attributes (`@href`, `@src`,
+/// `@srcset`, ...) are resolved to absolute URLs; XPath results are returned as written.
+/// With `parse_noscript=True`, markup inside `<noscript>` is parsed and can be matched, as is
+/// markup in IE conditional comments with `parse_conditional_comments=True`.
+/// With `preserve_entities=True`, `@html` fields keep character references as written in
+/// the source (`&nbsp;`, `&#160;`) instead of the serializer's re-escaped form.
+/// `html` may also be a document returned by `parse_html()`, which is queried without being
+/// parsed again; parsing options then raise `ValueError`, as they belong to `parse_html()`.
+/// With `output="polars"` a polars DataFrame is returned, built from Arrow columns handed
+/// over through the Arrow C stream interface; nested fields become dotted column names.
+/// `output="arrow"` returns those columns as an `ArrowTable` for any other Arrow consumer.
+/// `output="columns"` returns a dict of one list per field (dotted names for nested fields),
+/// values as in records, which takes far fewer Python objects than a dict per item.
+/// With `sink="out.csv"` items are written to the file as they are extracted, in the order
+/// `columns` gives (dotted names for nested fields), and the number written is returned.
+/// `sink="out.jsonl"` appends one JSON object per item instead, as does a file-like `sink`.
+/// With `categorical=True`, Arrow and polars string columns with few distinct values are
+/// dictionary-encoded (polars `Categorical`); a list of column names encodes those columns.
+/// `mode` picks the parser as for `parse_html()`.
 #[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(signature = (html, container_selector, field_mappings, from_encoding=None, exclude_encodings=None, errors=None, content_type=None, fix_mojibake=false, normalize=None, report=false, timeout=None, max_size=None, max_depth=None, max_nodes=None, dry_run=false, base_url=None, parse_noscript=false, parse_conditional_comments=false, preserve_entities=false, output="records", sink=None, columns=None, mode="auto", categorical=None))]
 pub fn extract_data(
     py: Python,
-    html: &str,
+    html: DocumentInput,
     container_selector: &str,
-    field_mappings: &Bound<'_, PyDict>,
+    field_mappings: FieldMappings,
+    from_encoding: Option<&str>,
+    exclude_encodings: Option<Vec<String>>,
+    errors: Option<&str>,
+    content_type: Option<&str>,
+    fix_mojibake: bool,
+    normalize: Option<&str>,
+    report: bool,
+    timeout: Option<f64>,
+    max_size: Option<usize>,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    dry_run: bool,
+    base_url: Option<&str>,
+    parse_noscript: bool,
+    parse_conditional_comments: bool,
+    preserve_entities: bool,
+    output: &str,
+    sink: Option<Bound<'_, PyAny>>,
+    columns: Option<Vec<String>>,
+    mode: &str,
+    categorical: Option<Categorical>,
 ) -> PyResult<PyObject> {
-    let document = Html::parse_document(html);
-    let py_list = PyList::empty_bound(py);
+    let options = DecodeOptions::new(from_encoding, exclude_encodings, errors, content_type, fix_mojibake)?;
+    let normalize = Normalization::from_arg(normalize)?;
+    let output = Output::parse(output)?;
+    let sink = sink.map(|sink| Sink::from_arg(&sink)).transpose()?;
+    match (&sink, &columns) {
+        (None | Some(Sink::JsonlFile(_) | Sink::JsonlStream(_)), Some(_)) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("columns= orders the columns of a CSV sink; pass a .csv path as sink="));
+        }
+        (Some(_), _) if output != Output::Records => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "sink= writes items to a file, so it does not take output='{}'",
+                output.name()
+            )));
+        }
+        _ => {}
+    }
+    if categorical.is_some() && !matches!(output, Output::Polars | Output::Arrow) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "categorical= dictionary-encodes Arrow columns; pass output='arrow' or output='polars'",
+        ));
+    }
+    let limits = Limits { max_size, max_depth, max_nodes };
+    let parse_options = ParseOptions {
+        parse_noscript,
+        conditional_comments: parse_conditional_comments,
+        mode: ParseMode::parse(mode)?,
+        ..ParseOptions::new(false, timeout, limits)?
+    };
+    let started = Instant::now();
+    // One budget covers parsing, container selection and field extraction
+    let deadline = Deadline::start(parse_options.timeout);
+    let (locked, parsed);
+    let (document, raw_document) = match &html {
+        DocumentInput::Parsed(scraper) => {
+            reject_parse_options(&[
+                ("from_encoding", from_encoding.is_some()),
+                ("exclude_encodings", !options.exclude_encodings.is_empty()),
+                ("errors", errors.is_some()),
+                ("content_type", content_type.is_some()),
+                ("fix_mojibake", fix_mojibake),
+                ("max_size", max_size.is_some()),
+                ("max_depth", max_depth.is_some()),
+                ("max_nodes", max_nodes.is_some()),
+                ("parse_noscript", parse_noscript),
+                ("parse_conditional_comments", parse_conditional_comments),
+                ("preserve_entities", preserve_entities),
+                ("mode", mode != "auto"),
+            ])?;
+            locked = scraper.document();
+            (&*locked, None)
+        }
+        DocumentInput::Markup(markup) => {
+            let source = markup.decode(&options)?;
+            parsed = parse_document(&source, &parse_options, &deadline)?;
+            // Entities never change the tree's shape, so the copy lines up node for node
+            let raw_document = match preserve_entities {
+                true => Some(parse_document(&source.replace('&', &ENTITY_SENTINEL.to_string()), &parse_options, &deadline)?),
+                false => None,
+            };
+            (&parsed, raw_document)
+        }
+    };
+    let base = links::document_base(document, base_url)?;
+    // Skipped fields fail through a report, which is dropped unless `report=True` asked for it
+    let skip_errors = matches!(&field_mappings, FieldMappings::Compiled(compiled) if compiled.get().on_error == OnError::Skip);
+    let mut extraction_report = (report || skip_errors).then(ExtractionReport::default);
 
-    let container_sel = match Selector::parse(container_selector) {
-        Ok(sel) => sel,
-        Err(_) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-            format!("Invalid container selector: {}", container_selector)
-        )),
+    let container_sel = selector::compile_in(container_selector, "container selector")?;
+    let containers = select_within(document, &container_sel, container_selector, &deadline, None)?;
+
+    let planned;
+    let (mappings, fields) = match &field_mappings {
+        FieldMappings::Compiled(compiled) => (compiled.get().mappings.bind(py), &compiled.get().fields),
+        FieldMappings::Dict(mappings) if dry_run => (mappings, &Vec::new()),
+        FieldMappings::Dict(mappings) => {
+            planned = plan_fields(mappings, "")?;
+            (mappings, &planned)
+        }
     };
+    if dry_run {
+        if output != Output::Records || sink.is_some() {
+            let target = match sink {
+                Some(_) => "sink=".to_string(),
+                None => format!("output='{}'", output.name()),
+            };
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "dry_run returns a per-field summary, not items, so it does not take {}",
+                target
+            )));
+        }
+        return dry_run_summary(py, &containers, mappings, normalize, base.as_ref(), &deadline);
+    }
 
-    for container in document.select(&container_sel) {
-        let item_dict = extract_item(py, &container, field_mappings)?;
-        py_list.append(item_dict)?;
+    let mut rows = match sink {
+        Some(Sink::Csv(path)) => Rows::csv(&path, columns, fields)?,
+        Some(Sink::JsonlFile(path)) => Rows::jsonl(JsonlWriter::open_file(&path)?),
+        Some(Sink::JsonlStream(target)) => Rows::jsonl(JsonlWriter::stream(target)?),
+        None => Rows::new(py, output, fields, categorical)?,
+    };
+    let raw_of = |container: &ElementRef| raw_document.as_ref().and_then(|raw_document| counterpart(*container, raw_document));
+    let worker_lookups = match containers.len() >= PARALLEL_MIN_CONTAINERS && rayon::current_num_threads() > 1 {
+        true => lookups(fields),
+        false => None,
+    };
+    match worker_lookups {
+        // Containers are serialized here, then reparsed and queried on rayon workers, since
+        // the document itself cannot leave this thread; items are assembled in order after
+        Some(worker_lookups) => {
+            for (chunk_index, chunk) in containers.chunks(PARALLEL_CHUNK).enumerate() {
+                deadline.check("Extraction")?;
+                let markup: Vec<(String, Option<String>)> = chunk
+                    .iter()
+                    .map(|container| (container.html(), raw_of(container).map(|raw| raw.html())))
+                    .collect();
+                let gathered = py.allow_threads(|| {
+                    markup
+                        .par_iter()
+                        .map(|(html, raw)| {
+                            deadline.check("Extraction")?;
+                            let raw_html = raw.as_deref().map(reparse);
+                            Ok(gather_css(&worker_lookups, &reparse(html), base.as_ref(), raw_html.as_ref()))
+                        })
+                        .collect::<PyResult<Vec<_>>>()
+                })?;
+                for (offset, gathered) in gathered.into_iter().enumerate() {
+                    let container = chunk_index * PARALLEL_CHUNK + offset;
+                    let mut ctx = extraction_report.as_mut().map(|report| ItemReport { report, container });
+                    rows.push(py, gathered, fields, normalize, &mut ctx)?;
+                }
+            }
+        }
+        None => {
+            for (index, container) in containers.iter().enumerate() {
+                deadline.check("Extraction")?;
+                let container_html = reparse(&container.html());
+                let raw_html = raw_of(container).map(|raw| reparse(&raw.html()));
+                let gathered = gather(container, &container_html, fields, base.as_ref(), raw_html.as_ref())?;
+                let mut ctx = extraction_report.as_mut().map(|report| ItemReport { report, container: index });
+                rows.push(py, gathered, fields, normalize, &mut ctx)?;
+            }
+        }
     }
 
-    Ok(py_list.into())
+    logging::emit(logging::INFO, || format!(
+        "extract_data: {} items for '{}' in {:.3} ms",
+        rows.len(),
+        container_selector,
+        started.elapsed().as_secs_f64() * 1000.0
+    ));
+    match extraction_report {
+        Some(mut extraction_report) if report => {
+            extraction_report.containers = rows.len();
+            Ok((rows.into_py(py)?, extraction_report).into_py(py))
+        }
+        _ => rows.into_py(py),
+    }
+}
+
+/// Raise for parsing options passed along with an already parsed document, which would
+/// otherwise be silently ignored
+fn reject_parse_options(given: &[(&str, bool)]) -> PyResult<()> {
+    match given.iter().find(|(_, set)| *set) {
+        Some((name, _)) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "'{}' applies when parsing, but the document is already parsed. Pass it to parse_html(), or pass the HTML itself",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// One field of a dry run, with everything needed to evaluate it per container
+struct DryRunField {
+    path: String,
+    query: FieldQuery,
+    matches: usize,
+    containers_matched: usize,
+    sample: Option<String>,
+}
+
+/// Flatten `field_mappings` (nested dicts get dotted paths) and compile every selector once
+fn collect_fields(field_mappings: &Bound<'_, PyDict>, path: &str, out: &mut Vec<DryRunField>) -> PyResult<()> {
+    for (field_name, selector_spec) in field_mappings.iter() {
+        let field_name_str = field_name.extract::<String>()?;
+        let field_path = if path.is_empty() { field_name_str } else { format!("{}.{}", path, field_name_str) };
+        if let Some((spec_str, field_type)) = field_spec(&selector_spec)? {
+            if let Some(field_type) = field_type {
+                FieldType::parse(&field_type, &field_path)?;
+            }
+            let query = FieldQuery::compile(&spec_str, &field_path)?;
+            out.push(DryRunField { path: field_path, query, matches: 0, containers_matched: 0, sample: None });
+        } else if let Ok(nested_mappings) = selector_spec.downcast::<PyDict>() {
+            collect_fields(nested_mappings, &field_path, out)?;
+        }
+    }
+    Ok(())
 }
 
-fn extract_item(
+/// `{"containers": n, "fields": {path: {"matches", "containers_matched", "sample"}}}` -
+/// how many elements each field selector hits across all containers, in how many containers,
+/// and the first non-empty value, without building the per-container results
+fn dry_run_summary(
     py: Python,
-    container: &scraper::ElementRef,
+    containers: &[scraper::ElementRef],
     field_mappings: &Bound<'_, PyDict>,
+    normalize: Option<Normalization>,
+    base: Option<&Url>,
+    deadline: &Deadline,
+) -> PyResult<PyObject> {
+    let mut fields = Vec::new();
+    collect_fields(field_mappings, "", &mut fields)?;
+
+    for container in containers {
+        deadline.check("Extraction")?;
+        let container_html = reparse(&container.html());
+        for field in fields.iter_mut() {
+            let values = field.query.values(container, &container_html, false, None, base, None)?;
+            let Some(first) = values.first() else { continue };
+            field.matches += values.len();
+            field.containers_matched += 1;
+            if field.sample.is_none() && !first.is_empty() {
+                field.sample = Some(text::normalize(first.clone(), normalize));
+            }
+        }
+    }
+
+    let summary = PyDict::new_bound(py);
+    let field_dict = PyDict::new_bound(py);
+    for field in fields {
+        let entry = PyDict::new_bound(py);
+        entry.set_item("matches", field.matches)?;
+        entry.set_item("containers_matched", field.containers_matched)?;
+        entry.set_item("sample", field.sample)?;
+        field_dict.set_item(field.path, entry)?;
+    }
+    summary.set_item("containers", containers.len())?;
+    summary.set_item("fields", field_dict)?;
+    Ok(summary.into())
+}
+
+/// A field that failed or came back empty during `extract_data(..., report=True)`.
+#[derive(Clone, Debug)]
+struct FieldIssue {
+    container: usize,
+    field: String,
+    selector: String,
+    error: Option<String>,
+}
+
+impl FieldIssue {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("container", self.container)?;
+        dict.set_item("field", &self.field)?;
+        dict.set_item("selector", &self.selector)?;
+        if let Some(error) = &self.error {
+            dict.set_item("error", error)?;
+        }
+        Ok(dict)
+    }
+}
+
+/// Per-field outcome of an `extract_data(..., report=True)` call. Nested fields are named
+/// with dotted paths (`"seller.name"`).
+#[pyclass]
+#[derive(Clone, Debug, Default)]
+pub struct ExtractionReport {
+    /// Number of containers matched
+    #[pyo3(get)]
+    containers: usize,
+    failed: Vec<FieldIssue>,
+    empty: Vec<FieldIssue>,
+}
+
+#[pymethods]
+impl ExtractionReport {
+    /// Fields whose selector could not be used: `{"container", "field", "selector", "error"}`
+    #[getter]
+    fn failed<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.failed.iter().map(|issue| issue.to_dict(py)).collect()
+    }
+
+    /// Fields that matched nothing or yielded an empty value: `{"container", "field", "selector"}`
+    #[getter]
+    fn empty<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.empty.iter().map(|issue| issue.to_dict(py)).collect()
+    }
+
+    /// True when no field failed
+    #[getter]
+    fn ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<ExtractionReport containers={} failed={} empty={}>",
+            self.containers,
+            self.failed.len(),
+            self.empty.len()
+        )
+    }
+}
+
+/// The report being filled in for one container.
+struct ItemReport<'a> {
+    report: &'a mut ExtractionReport,
+    container: usize,
+}
+
+impl ItemReport<'_> {
+    fn issue(&self, field: &str, selector: &str, error: Option<String>) -> FieldIssue {
+        FieldIssue {
+            container: self.container,
+            field: field.to_string(),
+            selector: selector.to_string(),
+            error,
+        }
+    }
+}
+
+/// The element at the same position in `other`, a tree of the same shape
+fn counterpart<'a>(elem: ElementRef, other: &'a Html) -> Option<ElementRef<'a>> {
+    let mut path: Vec<usize> = std::iter::once(*elem).chain(elem.ancestors())
+        .filter(|node| node.parent().is_some())
+        .map(|node| node.prev_siblings().count())
+        .collect();
+    path.reverse();
+    let mut node = other.tree.root();
+    for index in path {
+        node = node.children().nth(index)?;
+    }
+    ElementRef::wrap(node)
+}
+
+/// One entry of `field_mappings`, prepared once per `extract_data` call (or once for good
+/// by `compile_selectors`) rather than per container. Items share the mapping's own key
+/// objects instead of building new key strings.
+enum Field {
+    Value {
+        key: Py<PyAny>,
+        path: String,
+        spec: String,
+        /// Compile errors are raised (or reported) when the first container reaches the field
+        compiled: Result<(FieldQuery, Option<FieldType>), PyErr>,
+    },
+    Nested {
+        key: Py<PyAny>,
+        fields: Vec<Field>,
+    },
+}
+
+/// Prepare `field_mappings`; nested dicts get dotted paths
+fn plan_fields(field_mappings: &Bound<'_, PyDict>, path: &str) -> PyResult<Vec<Field>> {
+    let mut fields = Vec::with_capacity(field_mappings.len());
+    for (key, selector_spec) in field_mappings.iter() {
+        let name = key.extract::<String>()?;
+        let path = if path.is_empty() { name } else { format!("{}.{}", path, name) };
+        if let Some((spec, field_type)) = field_spec(&selector_spec)? {
+            let compiled = FieldQuery::compile(&spec, &path).and_then(|query| {
+                let field_type = field_type.map(|t| FieldType::parse(&t, &path)).transpose()?;
+                Ok((query, field_type))
+            });
+            fields.push(Field::Value { key: key.unbind(), path, spec, compiled });
+        } else if let Ok(nested_mappings) = selector_spec.downcast::<PyDict>() {
+            fields.push(Field::Nested { key: key.unbind(), fields: plan_fields(nested_mappings, &path)? });
+        }
+    }
+    Ok(fields)
+}
+
+/// The first compile error among `fields`, nested ones included
+fn first_error(py: Python, fields: &[Field]) -> Option<PyErr> {
+    fields.iter().find_map(|field| match field {
+        Field::Value { compiled: Err(err), .. } => Some(err.clone_ref(py)),
+        Field::Value { .. } => None,
+        Field::Nested { fields, .. } => first_error(py, fields),
+    })
+}
+
+/// Marks files written by `CompiledSelectors.save()`, with the version of their layout
+const SAVED_FORMAT: &str = "rusticsoup.selectors";
+const SAVED_VERSION: u32 = 1;
+
+/// What `compile_selectors()` does with a field selector that does not compile
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OnError {
+    Raise,
+    /// Keep the field: it comes back empty, and is listed as failed with `report=True`
+    Skip,
+}
+
+impl OnError {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "raise" => Ok(OnError::Raise),
+            "skip" => Ok(OnError::Skip),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid on_error: '{}'. Use 'raise' or 'skip'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            OnError::Raise => "raise",
+            OnError::Skip => "skip",
+        }
+    }
+}
+
+/// Keys of a rules file read by `CompiledSelectors.from_file()`
+const RULES_KEYS: [&str; 3] = ["container", "fields", "on_error"];
+
+/// `field_mappings` compiled by `compile_selectors()`, accepted by `extract_data` in place of
+/// the dict
+#[pyclass(frozen, module = "rusticsoup")]
+pub struct CompiledSelectors {
+    /// The mappings as given, for dry runs
+    mappings: Py<PyDict>,
+    fields: Vec<Field>,
+    /// The container selector the fields were written for, if one was given
+    container: Option<String>,
+    on_error: OnError,
+}
+
+#[pymethods]
+impl CompiledSelectors {
+    fn __repr__(&self, py: Python) -> String {
+        format!("<CompiledSelectors fields={}>", self.mappings.bind(py).len())
+    }
+
+    /// The container selector given to `compile_selectors()` or in the rules file, else None
+    #[getter]
+    pub fn container(&self) -> Option<&str> {
+        self.container.as_deref()
+    }
+
+    /// "raise" or "skip", as given to `compile_selectors()`
+    #[getter]
+    fn on_error(&self) -> &'static str {
+        self.on_error.name()
+    }
+
+    /// Pickle support: the mappings are stored and compiled again on load, since compiled
+    /// selectors only live in this process
+    #[allow(clippy::type_complexity)]
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (Bound<'py, PyDict>, Option<&str>, &'static str))> {
+        let compile = py.import_bound("rusticsoup")?.getattr("compile_selectors")?;
+        Ok((compile, (self.mappings.bind(py).copy()?, self.container.as_deref(), self.on_error.name())))
+    }
+
+    /// Write the mappings to `path` as JSON, for workers to load with `CompiledSelectors.load()`
+    fn save(&self, py: Python, path: PathBuf) -> PyResult<()> {
+        let saved = PyDict::new_bound(py);
+        saved.set_item("format", SAVED_FORMAT)?;
+        saved.set_item("version", SAVED_VERSION)?;
+        if let Some(container) = &self.container {
+            saved.set_item("container", container)?;
+        }
+        saved.set_item("on_error", self.on_error.name())?;
+        saved.set_item("fields", self.mappings.bind(py))?;
+        let mut json = String::new();
+        sinks::write_json(saved.as_any(), &mut json)?;
+        json.push('\n');
+        sinks::create_file(&path)?.write_all(json.as_bytes()).map_err(|err| sinks::os_error(&path, err))
+    }
+
+    /// Read a file written by `save()` and compile its fields, as `compile_selectors()` does.
+    /// Raises `ValueError` for any other file, and for one saved by a newer rusticsoup.
+    #[staticmethod]
+    fn load(py: Python, path: PathBuf) -> PyResult<CompiledSelectors> {
+        let text = std::fs::read_to_string(&path).map_err(|err| sinks::os_error(&path, err))?;
+        let invalid = |problem: &str| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}: {}", path.display(), problem))
+        };
+        let saved = py.import_bound("json")?.getattr("loads")?.call1((text,)).map_err(|_| invalid("not valid JSON"))?;
+        let saved = saved.downcast::<PyDict>().map_err(|_| invalid("not written by CompiledSelectors.save()"))?;
+        let format: Option<String> = saved.get_item("format")?.and_then(|format| format.extract().ok());
+        if format.as_deref() != Some(SAVED_FORMAT) {
+            return Err(invalid("not written by CompiledSelectors.save()"));
+        }
+        let version: u32 = saved.get_item("version")?.and_then(|version| version.extract().ok()).unwrap_or(0);
+        if version == 0 || version > SAVED_VERSION {
+            return Err(invalid(&format!("format version {} is not supported; this rusticsoup reads version {}", version, SAVED_VERSION)));
+        }
+        compile_rules(py, saved, &invalid)
+    }
+
+    /// Read extraction rules maintained by hand from a `.json`, `.yaml` or `.yml` file: a
+    /// `fields` mapping as for `compile_selectors()`, typed specs included, and optionally the
+    /// `container` selector and the `on_error` policy. YAML needs PyYAML installed.
+    #[staticmethod]
+    fn from_file(py: Python, path: PathBuf) -> PyResult<CompiledSelectors> {
+        let text = std::fs::read_to_string(&path).map_err(|err| sinks::os_error(&path, err))?;
+        let invalid = |problem: &str| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}: {}", path.display(), problem))
+        };
+        let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+        let rules = match extension.as_deref() {
+            Some("json") => py.import_bound("json")?.getattr("loads")?.call1((text,)).map_err(|err| invalid(&format!("not valid JSON ({})", err.value_bound(py))))?,
+            Some("yaml" | "yml") => {
+                let yaml = py.import_bound("yaml").map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyImportError, _>("Reading YAML rules needs PyYAML installed (pip install pyyaml)")
+                })?;
+                yaml.getattr("safe_load")?.call1((text,)).map_err(|err| invalid(&format!("not valid YAML ({})", err.value_bound(py))))?
+            }
+            _ => return Err(invalid("unsupported rules file; use a .json, .yaml or .yml file")),
+        };
+        let rules = rules.downcast::<PyDict>().map_err(|_| invalid("rules must be a mapping with 'fields'"))?;
+        for key in rules.keys() {
+            let key = key.str()?.to_string();
+            if !RULES_KEYS.contains(&key.as_str()) {
+                return Err(invalid(&format!("unknown key '{}'. Use {}", key, RULES_KEYS.join(", "))));
+            }
+        }
+        compile_rules(py, rules, &invalid)
+    }
+}
+
+/// Compile the `fields`, `container` and `on_error` of a saved or hand-written rules dict
+fn compile_rules(py: Python, rules: &Bound<'_, PyDict>, invalid: &dyn Fn(&str) -> PyErr) -> PyResult<CompiledSelectors> {
+    let fields = rules.get_item("fields")?.ok_or_else(|| invalid("no fields"))?;
+    let fields = fields.downcast::<PyDict>().map_err(|_| invalid("fields are not a mapping"))?;
+    let container: Option<String> = match rules.get_item("container")? {
+        Some(container) => Some(container.extract().map_err(|_| invalid("container is not a string"))?),
+        None => None,
+    };
+    let on_error: Option<String> = match rules.get_item("on_error")? {
+        Some(on_error) => Some(on_error.extract().map_err(|_| invalid("on_error is not a string"))?),
+        None => None,
+    };
+    compile_selectors(py, fields, container.as_deref(), on_error.as_deref().unwrap_or("raise"))
+}
+
+/// Compile every selector of `field_mappings` (nested dicts and typed specs included) once,
+/// for services that run the same fields many times: `extract_data` then skips all selector
+/// parsing. Raises `SelectorError` (or `ValueError` for a bad spec or type) for the first
+/// field that does not compile. With `on_error="skip"`, fields whose selector does not
+/// compile are kept instead and come back empty, listed as failed with `report=True`.
+/// `container` is checked too and kept as `.container`, to pass to `extract_data`.
+#[pyfunction]
+#[pyo3(signature = (field_mappings, container=None, on_error="raise"))]
+pub fn compile_selectors(py: Python, field_mappings: &Bound<'_, PyDict>, container: Option<&str>, on_error: &str) -> PyResult<CompiledSelectors> {
+    let on_error = OnError::parse(on_error)?;
+    if let Some(container) = container {
+        selector::compile_in(container, "container selector")?;
+    }
+    let fields = plan_fields(field_mappings, "")?;
+    if let Some(err) = first_error(py, &fields).filter(|_| on_error == OnError::Raise) {
+        return Err(err);
+    }
+    Ok(CompiledSelectors { mappings: field_mappings.copy()?.unbind(), fields, container: container.map(str::to_string), on_error })
+}
+
+/// `field_mappings` as a dict, or compiled ahead of time by `compile_selectors()`
+#[derive(FromPyObject)]
+pub enum FieldMappings<'py> {
+    #[pyo3(transparent, annotation = "CompiledSelectors")]
+    Compiled(Bound<'py, CompiledSelectors>),
+    #[pyo3(transparent, annotation = "dict")]
+    Dict(Bound<'py, PyDict>),
+}
+
+impl<'py> FieldMappings<'py> {
+    /// The mappings dict, as given or as compiled
+    pub fn dict(&self) -> Bound<'py, PyDict> {
+        match self {
+            FieldMappings::Compiled(compiled) => compiled.get().mappings.bind(compiled.py()).clone(),
+            FieldMappings::Dict(mappings) => mappings.clone(),
+        }
+    }
+}
+
+/// `extract_data` spreads containers over rayon workers from this many on, given more than
+/// one worker thread; below that the hand-off costs more than it saves.
+const PARALLEL_MIN_CONTAINERS: usize = 512;
+
+/// Containers serialized per round of parallel extraction, bounding the markup held at once
+const PARALLEL_CHUNK: usize = 4096;
+
+/// Values up to this many bytes are shared between items by `Interner`; longer ones (text
+/// blocks, markup) rarely repeat.
+const MAX_INTERNED_LEN: usize = 64;
+
+/// Python strings for the short values of one `extract_data` call, so a value repeated
+/// across items (currency codes, labels, class names) is one shared object.
+#[derive(Default)]
+struct Interner {
+    strings: AHashMap<String, Py<PyString>>,
+}
+
+impl Interner {
+    fn get(&mut self, py: Python, value: String) -> PyObject {
+        if value.len() > MAX_INTERNED_LEN {
+            return value.into_py(py);
+        }
+        if let Some(shared) = self.strings.get(&value) {
+            return shared.clone_ref(py).into_any();
+        }
+        let shared = PyString::new_bound(py, &value).unbind();
+        self.strings.insert(value, shared.clone_ref(py));
+        shared.into_any()
+    }
+}
+
+/// The text of a field as its Python value, and whether it counts as empty
+fn convert(
+    py: Python,
+    value: String,
+    field_type: Option<&FieldType>,
+    normalize: Option<Normalization>,
+    strings: &mut Interner,
+) -> PyResult<(PyObject, bool)> {
+    let value = text::normalize(value, normalize);
+    match field_type {
+        Some(field_type) => {
+            let coerced = field_type.coerce(py, &value)?;
+            let empty = coerced.is_none(py);
+            Ok((coerced, empty))
+        }
+        None => {
+            let empty = value.is_empty();
+            Ok((strings.get(py, value), empty))
+        }
+    }
+}
+
+/// A field's values in one container, gathered before any Python object is built
+enum Gathered {
+    Values(Vec<String>),
+    Nested(Vec<Gathered>),
+    /// The field's spec did not compile
+    Failed,
+}
+
+/// Gather every field of one container on the Python thread
+fn gather(
+    container: &ElementRef,
+    container_html: &Html,
+    fields: &[Field],
+    base: Option<&Url>,
+    raw_html: Option<&Html>,
+) -> PyResult<Vec<Gathered>> {
+    fields
+        .iter()
+        .map(|field| match field {
+            Field::Value { compiled: Ok((query, field_type)), .. } => {
+                let text_attr = field_type.as_ref().and_then(FieldType::source_attr);
+                Ok(Gathered::Values(query.values(container, container_html, !query.is_list(), text_attr, base, raw_html)?))
+            }
+            Field::Value { compiled: Err(_), .. } => Ok(Gathered::Failed),
+            Field::Nested { fields, .. } => Ok(Gathered::Nested(gather(container, container_html, fields, base, raw_html)?)),
+        })
+        .collect()
+}
+
+/// A field as rayon workers see it. Only CSS fields qualify: XPath is evaluated on the
+/// original document, which cannot leave the Python thread.
+enum Lookup<'f> {
+    Css { selector: &'f Query, extraction_type: &'f ExtractionType, text_attr: Option<&'static str> },
+    Nested(Vec<Lookup<'f>>),
+    Failed,
+}
+
+/// `fields` as worker lookups; None when any of them is XPath
+fn lookups<'f>(fields: &'f [Field]) -> Option<Vec<Lookup<'f>>> {
+    fields
+        .iter()
+        .map(|field| match field {
+            Field::Value { compiled: Ok((FieldQuery::Css(selector, extraction_type), field_type)), .. } => Some(Lookup::Css {
+                selector,
+                extraction_type,
+                text_attr: field_type.as_ref().and_then(FieldType::source_attr),
+            }),
+            Field::Value { compiled: Ok(_), .. } => None,
+            Field::Value { compiled: Err(_), .. } => Some(Lookup::Failed),
+            Field::Nested { fields, .. } => lookups(fields).map(Lookup::Nested),
+        })
+        .collect()
+}
+
+/// `gather` for one container on a rayon worker
+fn gather_css(lookups: &[Lookup<'_>], container_html: &Html, base: Option<&Url>, raw_html: Option<&Html>) -> Vec<Gathered> {
+    lookups
+        .iter()
+        .map(|lookup| match lookup {
+            Lookup::Css { selector, extraction_type, text_attr } => {
+                let first_only = !matches!(extraction_type, ExtractionType::GetAll);
+                Gathered::Values(css_values(selector, extraction_type, container_html, first_only, *text_attr, base, raw_html))
+            }
+            Lookup::Nested(lookups) => Gathered::Nested(gather_css(lookups, container_html, base, raw_html)),
+            Lookup::Failed => Gathered::Failed,
+        })
+        .collect()
+}
+
+/// Build one item's dict from its gathered values, reporting failed and empty fields
+fn assemble(
+    py: Python,
+    gathered: Vec<Gathered>,
+    fields: &[Field],
+    normalize: Option<Normalization>,
+    report: &mut Option<ItemReport<'_>>,
+    strings: &mut Interner,
 ) -> PyResult<Py<PyDict>> {
     let item_dict = PyDict::new_bound(py);
-    let container_html = Html::parse_fragment(&container.html());
 
+    for (field, gathered) in fields.iter().zip(gathered) {
+        let (key, path, spec, compiled, values) = match (field, gathered) {
+            (Field::Nested { key, fields }, Gathered::Nested(gathered)) => {
+                item_dict.set_item(key, assemble(py, gathered, fields, normalize, report, strings)?)?;
+                continue;
+            }
+            (Field::Value { key, path, spec, compiled }, Gathered::Values(values)) => (key, path, spec, compiled, values),
+            (Field::Value { key, path, spec, compiled: Err(err) }, _) => {
+                let Some(report) = report.as_mut() else { return Err(err.clone_ref(py)) };
+                let issue = report.issue(path, spec, Some(err.value_bound(py).to_string()));
+                report.report.failed.push(issue);
+                item_dict.set_item(key, "")?;
+                continue;
+            }
+            _ => unreachable!("values are gathered field by field"),
+        };
+        let Ok((query, field_type)) = compiled else { unreachable!("failed fields gather no values") };
+        let (value, empty) = field_value(py, query, field_type.as_ref(), values, normalize, strings)?;
+        item_dict.set_item(key, value)?;
+        if let (true, Some(report)) = (empty, report.as_mut()) {
+            let issue = report.issue(path, spec, None);
+            report.report.empty.push(issue);
+        }
+    }
+
+    Ok(item_dict.into())
+}
+
+/// A field's value in one item from its gathered values: a list of every one for list
+/// fields, else the first. Also whether it is empty.
+fn field_value(
+    py: Python,
+    query: &FieldQuery,
+    field_type: Option<&FieldType>,
+    values: Vec<String>,
+    normalize: Option<Normalization>,
+    strings: &mut Interner,
+) -> PyResult<(PyObject, bool)> {
+    if query.is_list() {
+        let list = PyList::empty_bound(py);
+        for value in values {
+            list.append(convert(py, value, field_type, normalize, strings)?.0)?;
+        }
+        let empty = list.is_empty();
+        return Ok((list.into_any().unbind(), empty));
+    }
+    let value = values.into_iter().next().unwrap_or_default();
+    convert(py, value, field_type, normalize, strings)
+}
+
+/// What `extract_data` returns its items as
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// A list of dicts
+    Records,
+    /// A polars DataFrame with one column per field
+    Polars,
+    /// An `ArrowTable` with one column per field
+    Arrow,
+    /// A dict of one list per field
+    Columns,
+}
+
+impl Output {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "records" => Ok(Output::Records),
+            "polars" => Ok(Output::Polars),
+            "arrow" => Ok(Output::Arrow),
+            "columns" => Ok(Output::Columns),
+            other => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Invalid output: '{}'. Use 'records', 'columns', 'polars' or 'arrow'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Output::Records => "records",
+            Output::Polars => "polars",
+            Output::Arrow => "arrow",
+            Output::Columns => "columns",
+        }
+    }
+}
+
+/// `categorical=`: which Arrow string columns to dictionary-encode
+#[derive(FromPyObject)]
+pub enum Categorical {
+    /// With `true`, every string column while it has few distinct values
+    #[pyo3(transparent, annotation = "bool")]
+    LowCardinality(bool),
+    /// These columns, however many distinct values they have
+    #[pyo3(transparent, annotation = "list[str]")]
+    Columns(Vec<String>),
+}
+
+/// Dictionary-encode the string columns `categorical` picks among `columns`
+fn encode_categorical(categorical: Categorical, names: &[String], columns: &mut [Column]) -> PyResult<()> {
+    match categorical {
+        Categorical::LowCardinality(false) => {}
+        Categorical::LowCardinality(true) => {
+            for column in columns.iter_mut().filter(|column| matches!(column, Column::Strings(_))) {
+                *column = Column::dictionary(true);
+            }
+        }
+        Categorical::Columns(chosen) => {
+            for name in chosen {
+                let index = names.iter().position(|column| *column == name).ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Unknown column '{}'. Columns are: {}",
+                        name,
+                        names.join(", ")
+                    ))
+                })?;
+                if matches!(columns[index], Column::Lists { .. }) {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Column '{}' holds lists; categorical= encodes string columns",
+                        name
+                    )));
+                }
+                columns[index] = Column::dictionary(false);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Items as they are assembled, in the shape `Output` asks for
+enum Rows<'py> {
+    Records { list: Bound<'py, PyList>, strings: Interner },
+    Columns { table: Table, output: Output },
+    /// One Python list per field, under the field's dotted path
+    Lists { names: Vec<String>, lists: Vec<Bound<'py, PyList>>, strings: Interner, rows: usize },
+    /// Rows written to a CSV file as they come: cells are in field order, `order` picks
+    /// the columns written and `lists` marks list fields
+    Csv { writer: CsvWriter, order: Vec<usize>, lists: Vec<bool>, rows: usize },
+    /// Items assembled as for `Records`, then written as JSON lines and dropped
+    Jsonl { writer: JsonlWriter<'py>, strings: Interner, line: String, rows: usize },
+}
+
+impl<'py> Rows<'py> {
+    fn new(py: Python<'py>, output: Output, fields: &[Field], categorical: Option<Categorical>) -> PyResult<Self> {
+        match output {
+            Output::Records => Ok(Rows::Records { list: PyList::empty_bound(py), strings: Interner::default() }),
+            Output::Polars | Output::Arrow => {
+                let (mut names, mut columns) = (Vec::new(), Vec::new());
+                plan_columns(fields, &format!("output='{}'", output.name()), &mut names, &mut columns)?;
+                if let Some(categorical) = categorical {
+                    encode_categorical(categorical, &names, &mut columns)?;
+                }
+                Ok(Rows::Columns { table: Table::new(names, columns), output })
+            }
+            Output::Columns => {
+                let mut names = Vec::new();
+                field_paths(fields, &mut names);
+                let lists = names.iter().map(|_| PyList::empty_bound(py)).collect();
+                Ok(Rows::Lists { names, lists, strings: Interner::default(), rows: 0 })
+            }
+        }
+    }
+
+    fn csv(path: &Path, chosen: Option<Vec<String>>, fields: &[Field]) -> PyResult<Self> {
+        let (mut names, mut columns) = (Vec::new(), Vec::new());
+        plan_columns(fields, "sink=", &mut names, &mut columns)?;
+        let order = match &chosen {
+            Some(chosen) => chosen
+                .iter()
+                .map(|name| {
+                    names.iter().position(|column| column == name).ok_or_else(|| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "Unknown column '{}'. Columns are: {}",
+                            name,
+                            names.join(", ")
+                        ))
+                    })
+                })
+                .collect::<PyResult<Vec<_>>>()?,
+            None => (0..names.len()).collect(),
+        };
+        let header: Vec<String> = order.iter().map(|&index| names[index].clone()).collect();
+        let lists = columns.iter().map(|column| matches!(column, Column::Lists { .. })).collect();
+        Ok(Rows::Csv { writer: CsvWriter::create(path, &header)?, order, lists, rows: 0 })
+    }
+
+    fn jsonl(writer: JsonlWriter<'py>) -> Self {
+        Rows::Jsonl { writer, strings: Interner::default(), line: String::new(), rows: 0 }
+    }
+
+    fn push(
+        &mut self,
+        py: Python,
+        gathered: Vec<Gathered>,
+        fields: &[Field],
+        normalize: Option<Normalization>,
+        report: &mut Option<ItemReport<'_>>,
+    ) -> PyResult<()> {
+        match self {
+            Rows::Records { list, strings } => list.append(assemble(py, gathered, fields, normalize, report, strings)?),
+            Rows::Columns { table, .. } => {
+                let mut columns = table.columns_mut().iter_mut();
+                fill_columns(py, &mut |values| columns.next().unwrap().push(values.iter().map(String::as_str)), gathered, fields, normalize, report)?;
+                table.finish_row();
+                Ok(())
+            }
+            Rows::Lists { lists, strings, rows, .. } => {
+                append_columns(py, &mut lists.iter(), gathered, fields, normalize, report, strings)?;
+                *rows += 1;
+                Ok(())
+            }
+            Rows::Csv { writer, order, lists, rows } => {
+                let mut cells = Vec::with_capacity(lists.len());
+                fill_columns(py, &mut |values| cells.push(values), gathered, fields, normalize, report)?;
+                let cell = |index: usize| match lists[index] {
+                    true => cells[index].join(LIST_SEPARATOR),
+                    false => cells[index].first().cloned().unwrap_or_default(),
+                };
+                writer.write_row(order.iter().map(|&index| cell(index)))?;
+                *rows += 1;
+                Ok(())
+            }
+            Rows::Jsonl { writer, strings, line, rows } => {
+                let item = assemble(py, gathered, fields, normalize, report, strings)?;
+                line.clear();
+                sinks::write_json(item.bind(py), line)?;
+                writer.write_line(line)?;
+                *rows += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Rows::Records { list, .. } => list.len(),
+            Rows::Columns { table, .. } => table.rows(),
+            Rows::Lists { rows, .. } | Rows::Csv { rows, .. } | Rows::Jsonl { rows, .. } => *rows,
+        }
+    }
+
+    fn into_py(self, py: Python) -> PyResult<PyObject> {
+        match self {
+            Rows::Records { list, .. } => Ok(list.into_any().unbind()),
+            Rows::Lists { names, lists, .. } => {
+                let columns = PyDict::new_bound(py);
+                for (name, list) in names.into_iter().zip(lists) {
+                    columns.set_item(name, list)?;
+                }
+                Ok(columns.into_any().unbind())
+            }
+            Rows::Csv { writer, rows, .. } => {
+                writer.finish()?;
+                Ok(rows.into_py(py))
+            }
+            Rows::Jsonl { writer, rows, .. } => {
+                writer.finish()?;
+                Ok(rows.into_py(py))
+            }
+            Rows::Columns { table, output: Output::Arrow } => Ok(Py::new(py, ArrowTable::new(table))?.into_any()),
+            Rows::Columns { table, .. } => {
+                let polars = py.import_bound("polars").map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyImportError, _>("output='polars' needs polars installed (pip install polars)")
+                })?;
+                Ok(polars.getattr("DataFrame")?.call1((ArrowTable::new(table),))?.unbind())
+            }
+        }
+    }
+}
+
+/// The dotted path of every value field, in field order
+fn field_paths(fields: &[Field], paths: &mut Vec<String>) {
+    for field in fields {
+        match field {
+            Field::Nested { fields, .. } => field_paths(fields, paths),
+            Field::Value { path, .. } => paths.push(path.clone()),
+        }
+    }
+}
+
+/// `assemble` for `output="columns"`: append one item's values to `lists`, field by field
+fn append_columns<'a, 'py: 'a>(
+    py: Python<'py>,
+    lists: &mut impl Iterator<Item = &'a Bound<'py, PyList>>,
+    gathered: Vec<Gathered>,
+    fields: &[Field],
+    normalize: Option<Normalization>,
+    report: &mut Option<ItemReport<'_>>,
+    strings: &mut Interner,
+) -> PyResult<()> {
+    for (field, gathered) in fields.iter().zip(gathered) {
+        let (path, spec, compiled, values) = match (field, gathered) {
+            (Field::Nested { fields, .. }, Gathered::Nested(gathered)) => {
+                append_columns(py, lists, gathered, fields, normalize, report, strings)?;
+                continue;
+            }
+            (Field::Value { path, spec, compiled, .. }, Gathered::Values(values)) => (path, spec, compiled, values),
+            (Field::Value { path, spec, compiled: Err(err), .. }, _) => {
+                let Some(report) = report.as_mut() else { return Err(err.clone_ref(py)) };
+                let issue = report.issue(path, spec, Some(err.value_bound(py).to_string()));
+                report.report.failed.push(issue);
+                lists.next().unwrap().append("")?;
+                continue;
+            }
+            _ => unreachable!("values are gathered field by field"),
+        };
+        let Ok((query, field_type)) = compiled else { unreachable!("failed fields gather no values") };
+        let (value, empty) = field_value(py, query, field_type.as_ref(), values, normalize, strings)?;
+        lists.next().unwrap().append(value)?;
+        if let (true, Some(report)) = (empty, report.as_mut()) {
+            let issue = report.issue(path, spec, None);
+            report.report.empty.push(issue);
+        }
+    }
+    Ok(())
+}
+
+/// One column per field, nested fields flattened under their dotted paths. Typed fields
+/// become Python objects (`price` dicts, timezone-aware datetimes) with no single Arrow type
+/// or CSV cell, so `target` (the option asking for columns) rejects them.
+fn plan_columns(fields: &[Field], target: &str, names: &mut Vec<String>, columns: &mut Vec<Column>) -> PyResult<()> {
+    for field in fields {
+        match field {
+            Field::Nested { fields, .. } => plan_columns(fields, target, names, columns)?,
+            Field::Value { path, compiled: Ok((_, Some(_))), .. } => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Field '{}' has a type, which {} does not support. Extract it as text and convert the column afterwards",
+                    path, target
+                )));
+            }
+            Field::Value { path, compiled, .. } => {
+                names.push(path.clone());
+                columns.push(match compiled {
+                    Ok((query, _)) if query.is_list() => Column::lists(),
+                    _ => Column::strings(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `assemble` for columns: hand one item's values to `column`, field by field, reporting
+/// failed and empty fields
+fn fill_columns(
+    py: Python,
+    column: &mut impl FnMut(Vec<String>),
+    gathered: Vec<Gathered>,
+    fields: &[Field],
+    normalize: Option<Normalization>,
+    report: &mut Option<ItemReport<'_>>,
+) -> PyResult<()> {
+    for (field, gathered) in fields.iter().zip(gathered) {
+        match (field, gathered) {
+            (Field::Nested { fields, .. }, Gathered::Nested(gathered)) => {
+                fill_columns(py, column, gathered, fields, normalize, report)?;
+            }
+            (Field::Value { path, spec, compiled, .. }, Gathered::Values(values)) => {
+                let values: Vec<String> = values.into_iter().map(|value| text::normalize(value, normalize)).collect();
+                let empty = match compiled {
+                    Ok((query, _)) if query.is_list() => values.is_empty(),
+                    _ => values.first().is_none_or(|value| value.is_empty()),
+                };
+                column(values);
+                if let (true, Some(report)) = (empty, report.as_mut()) {
+                    let issue = report.issue(path, spec, None);
+                    report.report.empty.push(issue);
+                }
+            }
+            (Field::Value { path, spec, compiled: Err(err), .. }, _) => {
+                let Some(report) = report.as_mut() else { return Err(err.clone_ref(py)) };
+                let issue = report.issue(path, spec, Some(err.value_bound(py).to_string()));
+                report.report.failed.push(issue);
+                column(Vec::new());
+            }
+            _ => unreachable!("values are gathered field by field"),
+        }
+    }
+    Ok(())
+}
+
+/// Check a job definition without any HTML: compiles the container selector and every field
+/// spec (nested dicts included) and returns a list of problems, empty when the mappings are
+/// valid. Each problem is `{"field", "selector", "error", "position"}`; `field` is None for
+/// the container selector and dotted for nested fields.
+#[pyfunction]
+pub fn validate_mappings(py: Python, container_selector: &str, field_mappings: &Bound<'_, PyDict>) -> PyResult<Py<PyList>> {
+    let problems = PyList::empty_bound(py);
+    if let Err(err) = selector::compile_in(container_selector, "container selector") {
+        problems.append(problem(py, None, container_selector, &err)?)?;
+    }
+    validate_fields(py, field_mappings, "", &problems)?;
+    Ok(problems.unbind())
+}
+
+fn validate_fields(py: Python, field_mappings: &Bound<'_, PyDict>, path: &str, problems: &Bound<'_, PyList>) -> PyResult<()> {
     for (field_name, selector_spec) in field_mappings.iter() {
-        let field_name_str = field_name.extract::<String>()?;
+        let field_path = match field_name.extract::<String>() {
+            Ok(name) if path.is_empty() => name,
+            Ok(name) => format!("{}.{}", path, name),
+            Err(_) => {
+                let err = PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                    format!("Field names must be str, got {}", field_name.get_type().name()?)
+                );
+                problems.append(problem(py, Some(&field_name.repr()?.to_string()), "", &err)?)?;
+                continue;
+            }
+        };
 
-        if let Ok(spec_str) = selector_spec.extract::<String>() {
-            let (selector_str, extraction_type) = parse_selector_spec(&spec_str)
-                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                    format!("Invalid selector spec: {}", spec_str)
-                ))?;
-            
-            let selector = Selector::parse(&selector_str)
-                .map_err(|_| PyErr::new::<pyo3::exceptions::PyValueError, _>(
-                    format!("Invalid selector '{}' for field '{}'", selector_str, field_name_str)
-                ))?;
-
-            match extraction_type {
-                ExtractionType::Text => {
-                    let value = container_html.select(&selector).next()
-                        .map(|elem| elem.text().collect::<Vec<_>>().join(" ").trim().to_string())
-                        .unwrap_or_default();
-                    item_dict.set_item(field_name_str, value)?;
-                },
-                ExtractionType::Attribute(attr) => {
-                    let value = container_html.select(&selector).next()
-                        .and_then(|elem| elem.value().attr(&attr).map(ToString::to_string))
-                        .unwrap_or_default();
-                    item_dict.set_item(field_name_str, value)?;
-                },
-                ExtractionType::GetAll => {
-                    let values = PyList::empty_bound(py);
-                    for element in container_html.select(&selector) {
-                        values.append(element.text().collect::<Vec<_>>().join(" ").trim().to_string())?;
-                    }
-                    item_dict.set_item(field_name_str, values)?;
+        if let Some((spec_str, field_type)) = field_spec(&selector_spec)? {
+            if let Some(Err(err)) = field_type.map(|t| FieldType::parse(&t, &field_path)) {
+                problems.append(problem(py, Some(&field_path), &spec_str, &err)?)?;
+            }
+            let checked = match parse_selector_spec(&spec_str) {
+                Some((_, ExtractionType::Attribute(attr))) if attr.is_empty() && !spec_str.starts_with(XPATH_PREFIX) => {
+                    Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        format!("Empty attribute name in selector spec: {}", spec_str)
+                    ))
                 }
+                _ => FieldQuery::compile(&spec_str, &field_path).map(|_| ()),
+            };
+            if let Err(err) = checked {
+                problems.append(problem(py, Some(&field_path), &spec_str, &err)?)?;
             }
         } else if let Ok(nested_mappings) = selector_spec.downcast::<PyDict>() {
-            let nested_item = extract_item(py, container, nested_mappings)?;
-            item_dict.set_item(field_name_str, nested_item)?;
+            validate_fields(py, nested_mappings, &field_path, problems)?;
+        } else {
+            let err = PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                format!("Field spec must be a str or dict, got {}", selector_spec.get_type().name()?)
+            );
+            problems.append(problem(py, Some(&field_path), "", &err)?)?;
         }
     }
+    Ok(())
+}
 
-    Ok(item_dict.into())
+fn problem<'py>(py: Python<'py>, field: Option<&str>, selector: &str, err: &PyErr) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    let value = err.value_bound(py);
+    dict.set_item("field", field)?;
+    dict.set_item("selector", selector)?;
+    dict.set_item("error", value.to_string())?;
+    // Set by SelectorError; other problems have no position
+    let position = value.getattr("position").ok().filter(|position| !position.is_none());
+    dict.set_item("position", position)?;
+    Ok(dict)
 }
 
-/// Parse selector specification (supports @attribute and @get_all syntax)
+/// Parse selector specification (supports @attribute, @get_all and @html syntax)
 fn parse_selector_spec(spec: &str) -> Option<(String, ExtractionType)> {
     if spec.contains('@') {
         let parts: Vec<&str> = spec.splitn(2, '@').collect();
         if parts.len() == 2 {
             if parts[1] == "get_all" {
                 Some((parts[0].to_string(), ExtractionType::GetAll))
+            } else if parts[1] == "html" {
+                Some((parts[0].to_string(), ExtractionType::Html))
             } else {
                 Some((parts[0].to_string(), ExtractionType::Attribute(parts[1].to_string())))
             }
@@ -105,30 +1423,36 @@ fn parse_selector_spec(spec: &str) -> Option<(String, ExtractionType)> {
     }
 }
 
-/// Generic table data extractor - works with any table structure
+/// Generic table data extractor - works with any table structure. `html` may also be a
+/// document returned by `parse_html()`.
 #[pyfunction]
-pub fn extract_table_data(py: Python, html: &str, table_selector: &str) -> PyResult<PyObject> {
-    let document = Html::parse_document(html);
+pub fn extract_table_data(py: Python, html: DocumentInput, table_selector: &str) -> PyResult<PyObject> {
+    let (locked, parsed);
+    let document = match &html {
+        DocumentInput::Parsed(scraper) => {
+            locked = scraper.document();
+            &*locked
+        }
+        DocumentInput::Markup(markup) => {
+            parsed = Html::parse_document(&markup.decode(&DecodeOptions::default())?);
+            &parsed
+        }
+    };
     let py_list = PyList::empty_bound(py);
     
-    let table_sel = match Selector::parse(table_selector) {
-        Ok(sel) => sel,
-        Err(_) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
-            format!("Invalid table selector: {}", table_selector)
-        ))
-    };
+    let table_sel = selector::compile_in(table_selector, "table selector")?;
     
     let row_sel = Selector::parse("tr").unwrap();
     let cell_sel = Selector::parse("td, th").unwrap();
     
-    for table in document.select(&table_sel) {
-        let table_html = Html::parse_fragment(&table.html());
+    for table in table_sel.select(document) {
+        let table_html = reparse(&table.html());
         
         for row in table_html.select(&row_sel) {
             let row_data = PyList::empty_bound(py);
             
             for cell in row.select(&cell_sel) {
-                let cell_text = cell.text().collect::<Vec<_>>().join(" ").trim().to_string();
+                let cell_text = text::element_text(cell, false);
                 row_data.append(cell_text)?;
             }
             
@@ -0,0 +1,205 @@
+use pyo3::prelude::*;
+use ahash::AHashMap;
+use ego_tree::NodeId;
+use html5ever::tendril::{StrTendril, Tendril};
+use html5ever::{namespace_url, ns, Attribute, LocalName, Namespace, Prefix, QualName};
+use scraper::node::{Comment, Element, ProcessingInstruction, Text};
+use scraper::{ElementRef, Html, Node};
+use sxd_document::dom;
+
+use crate::encoding::HtmlInput;
+use crate::feeds;
+use crate::scraper::WebScraper;
+
+fn qual_name(prefix: Option<&str>, namespace: Option<&str>, local: &str) -> QualName {
+    QualName::new(
+        prefix.filter(|p| !p.is_empty()).map(Prefix::from),
+        namespace.map_or(ns!(), Namespace::from),
+        LocalName::from(local),
+    )
+}
+
+/// Copy `children` under `parent` as scraper nodes, keeping names case and
+/// namespaces as written
+fn mirror_children(html: &mut Html, parent: NodeId, children: Vec<dom::ChildOfElement>) {
+    for child in children {
+        let node = match child {
+            dom::ChildOfElement::Element(elem) => {
+                let name = elem.name();
+                let attrs = elem
+                    .attributes()
+                    .into_iter()
+                    .map(|attr| Attribute {
+                        name: qual_name(attr.preferred_prefix(), attr.name().namespace_uri(), attr.name().local_part()),
+                        value: StrTendril::from_slice(attr.value()),
+                    })
+                    .collect();
+                let element = Element::new(qual_name(elem.preferred_prefix(), name.namespace_uri(), name.local_part()), attrs);
+                let id = html.tree.get_mut(parent).unwrap().append(Node::Element(element)).id();
+                mirror_children(html, id, elem.children());
+                continue;
+            }
+            dom::ChildOfElement::Text(text) => {
+                // The parser splits text at entity and character references; keep it whole
+                let mut parent_node = html.tree.get_mut(parent).unwrap();
+                if let Some(mut last) = parent_node.last_child() {
+                    if let Node::Text(previous) = last.value() {
+                        previous.text.push_slice(text.text());
+                        continue;
+                    }
+                }
+                Node::Text(Text { text: Tendril::from_slice(text.text()) })
+            }
+            dom::ChildOfElement::Comment(comment) => Node::Comment(Comment { comment: Tendril::from_slice(comment.text()) }),
+            dom::ChildOfElement::ProcessingInstruction(pi) => Node::ProcessingInstruction(ProcessingInstruction {
+                target: Tendril::from_slice(pi.target()),
+                data: Tendril::from_slice(pi.value().unwrap_or("")),
+            }),
+        };
+        html.tree.get_mut(parent).unwrap().append(node);
+    }
+}
+
+/// Parse an XML document into a scraper tree: no HTML fix-ups, names are case-sensitive and
+/// keep their namespace. Malformed XML raises `HTMLParseError` with `.position`.
+pub fn parse(text: &str) -> PyResult<Html> {
+    let package = feeds::parse_xml(text, "document")?;
+    let mut html = Html::new_document();
+    let root = html.tree.root().id();
+    let children = package.as_document().root().children().into_iter().map(|child| match child {
+        dom::ChildOfRoot::Element(elem) => dom::ChildOfElement::Element(elem),
+        dom::ChildOfRoot::Comment(comment) => dom::ChildOfElement::Comment(comment),
+        dom::ChildOfRoot::ProcessingInstruction(pi) => dom::ChildOfElement::ProcessingInstruction(pi),
+    });
+    mirror_children(&mut html, root, children.collect());
+    Ok(html)
+}
+
+/// Parse serialized XML (an element's `html()`) into a fragment shaped like
+/// `Html::parse_fragment`'s, so element methods can treat both alike
+pub fn parse_fragment(text: &str) -> PyResult<Html> {
+    let document = parse(text)?;
+    let mut fragment = Html::new_fragment();
+    let container = Element::new(QualName::new(None, ns!(html), LocalName::from("html")), Vec::new());
+    let container = fragment.tree.root_mut().append(Node::Element(container)).id();
+    copy_subtree(&mut fragment, container, document.root_element());
+    Ok(fragment)
+}
+
+/// Parse a run of XML nodes, which need not have a single root, for inserting into a
+/// `parse_xml` document. The nodes are the children of the result's root element.
+pub fn parse_nodes(text: &str) -> PyResult<Html> {
+    parse(&format!("<fragment>{}</fragment>", text))
+}
+
+fn copy_subtree(html: &mut Html, parent: NodeId, elem: ElementRef) {
+    let id = html.tree.get_mut(parent).unwrap().append(Node::Element(elem.value().clone())).id();
+    for child in elem.children() {
+        match ElementRef::wrap(child) {
+            Some(inner) => copy_subtree(html, id, inner),
+            None => {
+                html.tree.get_mut(id).unwrap().append(child.value().clone());
+            }
+        }
+    }
+}
+
+fn escape(text: &str, attr: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' if !attr => out.push_str("&gt;"),
+            '"' if attr => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn qualified(name: &QualName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local),
+        None => name.local.to_string(),
+    }
+}
+
+/// Write `elem` as XML, declaring each namespace where it first differs from what is in
+/// `scope` (prefix, or "" for the default namespace, to URI)
+fn write_element(out: &mut String, elem: ElementRef, scope: &AHashMap<String, String>) {
+    let value = elem.value();
+    let mut scope = scope.clone();
+    let mut declarations = Vec::new();
+    let names = std::iter::once((&value.name, true)).chain(value.attrs.iter().map(|(name, _)| (name, false)));
+    for (name, is_element) in names {
+        // Unprefixed attributes are in no namespace and need no declaration
+        if name.ns == ns!(xml) || (!is_element && name.prefix.is_none()) {
+            continue;
+        }
+        let prefix = name.prefix.as_ref().map_or(String::new(), |p| p.to_string());
+        if scope.get(&prefix).map_or(!name.ns.is_empty(), |uri| *uri != *name.ns) {
+            declarations.push((prefix.clone(), name.ns.to_string()));
+            scope.insert(prefix, name.ns.to_string());
+        }
+    }
+
+    out.push('<');
+    out.push_str(&qualified(&value.name));
+    for (prefix, uri) in &declarations {
+        let attr = if prefix.is_empty() { "xmlns".to_string() } else { format!("xmlns:{}", prefix) };
+        out.push_str(&format!(" {}=\"{}\"", attr, escape(uri, true)));
+    }
+    for (name, attr_value) in value.attrs.iter() {
+        out.push_str(&format!(" {}=\"{}\"", qualified(name), escape(attr_value, true)));
+    }
+    if elem.children().next().is_none() {
+        out.push_str("/>");
+        return;
+    }
+    out.push('>');
+    for child in elem.children() {
+        write_node(out, child, &scope);
+    }
+    out.push_str("</");
+    out.push_str(&qualified(&value.name));
+    out.push('>');
+}
+
+fn write_node(out: &mut String, node: ego_tree::NodeRef<Node>, scope: &AHashMap<String, String>) {
+    match node.value() {
+        Node::Element(_) => write_element(out, ElementRef::wrap(node).unwrap(), scope),
+        Node::Text(text) => out.push_str(&escape(text, false)),
+        Node::Comment(comment) => out.push_str(&format!("<!--{}-->", &**comment)),
+        Node::ProcessingInstruction(pi) => out.push_str(&format!("<?{} {}?>", &*pi.target, &*pi.data)),
+        _ => {}
+    }
+}
+
+/// An element as standalone XML, with the namespace declarations it needs
+pub fn serialize(elem: ElementRef) -> String {
+    let mut out = String::new();
+    write_element(&mut out, elem, &AHashMap::new());
+    out
+}
+
+/// The whole document as XML
+pub fn serialize_document(html: &Html) -> String {
+    let mut out = String::new();
+    for child in html.tree.root().children() {
+        write_node(&mut out, child, &AHashMap::new());
+    }
+    out
+}
+
+/// Parse XML (str, or bytes that may be gzipped; see `parse_sitemap`) into a WebScraper
+/// with the same selection API as `parse_html`. Names keep their case and CSS selectors
+/// match them case-sensitively by local name, ignoring namespace prefixes; `tag()` and the
+/// keys of `attrs()` are local names too. Nothing is fixed up: malformed XML raises
+/// `HTMLParseError`.
+#[pyfunction]
+#[pyo3(signature = (data, max_size=None))]
+pub fn parse_xml(data: HtmlInput, max_size: Option<usize>) -> PyResult<WebScraper> {
+    let text = feeds::xml_text(&data, max_size)?;
+    Ok(WebScraper::from_xml(parse(&text)?))
+}
@@ -0,0 +1,168 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use pyo3::prelude::*;
+use ahash::AHashMap;
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Node};
+use sxd_document::{dom, Package};
+use sxd_xpath::{nodeset, Context, Factory, Value, XPath};
+
+use crate::errors;
+
+/// One XPath result: an element of the original tree, or the string value of an attribute,
+/// text node, comment or non-nodeset result.
+pub enum XPathItem<'a> {
+    Element(ElementRef<'a>),
+    Text(String),
+}
+
+impl XPathItem<'_> {
+    /// Text for string-valued results: an element's trimmed descendant text, other values as-is
+    pub fn into_string(self) -> String {
+        match self {
+            XPathItem::Element(elem) => elem.text().collect::<String>().trim().to_string(),
+            XPathItem::Text(text) => text,
+        }
+    }
+}
+
+/// Compile an XPath 1.0 expression, raising `SelectorError` (with `.selector`) when invalid
+pub fn compile(expr: &str) -> PyResult<XPath> {
+    match Factory::new().build(expr) {
+        Ok(Some(xpath)) => Ok(xpath),
+        Ok(None) => Err(xpath_error(expr, "empty expression".to_string())),
+        Err(err) => Err(xpath_error(expr, err.to_string())),
+    }
+}
+
+/// Distinct expressions a thread keeps compiled before starting over
+const THREAD_CACHE_SIZE: usize = 256;
+
+thread_local! {
+    /// Expressions compiled on this thread, by source. `XPath` cannot be sent between threads,
+    /// so values shared between threads hold the source and compile it here.
+    static COMPILED: RefCell<AHashMap<String, Rc<XPath>>> = RefCell::new(AHashMap::new());
+}
+
+/// `compile`, reusing this thread's earlier compilation of the same expression
+pub fn compile_cached(expr: &str) -> PyResult<Rc<XPath>> {
+    if let Some(xpath) = COMPILED.with_borrow(|compiled| compiled.get(expr).cloned()) {
+        return Ok(xpath);
+    }
+    let xpath = Rc::new(compile(expr)?);
+    COMPILED.with_borrow_mut(|compiled| {
+        if compiled.len() >= THREAD_CACHE_SIZE {
+            compiled.clear();
+        }
+        compiled.insert(expr.to_string(), xpath.clone());
+    });
+    Ok(xpath)
+}
+
+fn xpath_error(expr: &str, reason: String) -> PyErr {
+    let err = PyErr::new::<errors::SelectorError, _>(format!("Invalid XPath expression '{}': {}", expr, reason));
+    errors::with_detail(err, "selector", expr)
+}
+
+/// Whether a node is mirrored into the XPath tree. Doctypes have no XPath equivalent.
+fn mirrored(node: &NodeRef<'_, Node>) -> bool {
+    !matches!(node.value(), Node::Doctype(_))
+}
+
+/// Evaluate `xpath` with `context` (the document node or an element) as the context node.
+///
+/// sxd-xpath works on its own DOM, so the subtree under `context` is mirrored into one and
+/// element results are mapped back to the original tree by their child-index path. Only the
+/// subtree is mirrored: `//` and `/` are relative to `context`, not the whole page.
+pub fn evaluate<'a>(context: NodeRef<'a, Node>, xpath: &XPath, expr: &str) -> PyResult<Vec<XPathItem<'a>>> {
+    let package = Package::new();
+    let document = package.as_document();
+    let root = document.root();
+    let context_node: nodeset::Node = match ElementRef::wrap(context) {
+        Some(elem) => {
+            let mirror = mirror_element(document, elem);
+            root.append_child(mirror);
+            mirror.into()
+        }
+        None => {
+            for child in context.children().filter(mirrored) {
+                match child.value() {
+                    Node::Element(_) => root.append_child(mirror_element(document, ElementRef::wrap(child).unwrap())),
+                    Node::Comment(comment) => root.append_child(document.create_comment(comment)),
+                    Node::ProcessingInstruction(pi) => {
+                        root.append_child(document.create_processing_instruction(&pi.target, Some(&pi.data)))
+                    }
+                    // Text is not allowed directly under the document
+                    _ => root.append_child(document.create_comment("")),
+                }
+            }
+            root.into()
+        }
+    };
+
+    let value = xpath
+        .evaluate(&Context::new(), context_node)
+        .map_err(|err| xpath_error(expr, err.to_string()))?;
+    Ok(match value {
+        Value::Nodeset(nodes) => nodes
+            .document_order()
+            .into_iter()
+            .filter_map(|node| match node {
+                nodeset::Node::Element(_) => {
+                    locate(context, context_node, node).and_then(ElementRef::wrap).map(XPathItem::Element)
+                }
+                nodeset::Node::Root(_) => None,
+                other => Some(XPathItem::Text(other.string_value())),
+            })
+            .collect(),
+        Value::String(s) => vec![XPathItem::Text(s)],
+        Value::Number(n) => vec![XPathItem::Text(format_number(n))],
+        Value::Boolean(b) => vec![XPathItem::Text(b.to_string())],
+    })
+}
+
+/// XPath's `string()` of a number: integers without a fractional part
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.is_finite() && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+fn mirror_element<'d>(document: dom::Document<'d>, elem: ElementRef) -> dom::Element<'d> {
+    let mirror = document.create_element(elem.value().name());
+    for (name, value) in elem.value().attrs() {
+        mirror.set_attribute_value(name, value);
+    }
+    for child in elem.children().filter(mirrored) {
+        match child.value() {
+            Node::Element(_) => mirror.append_child(mirror_element(document, ElementRef::wrap(child).unwrap())),
+            Node::Text(text) => mirror.append_child(document.create_text(text)),
+            Node::Comment(comment) => mirror.append_child(document.create_comment(comment)),
+            Node::ProcessingInstruction(pi) => {
+                mirror.append_child(document.create_processing_instruction(&pi.target, Some(&pi.data)))
+            }
+            // Fragments and nested documents do not occur below an element
+            _ => mirror.append_child(document.create_comment("")),
+        }
+    }
+    mirror
+}
+
+/// Find the original node for a mirrored one by replaying its child-index path from the context
+fn locate<'a>(context: NodeRef<'a, Node>, mirror_context: nodeset::Node, target: nodeset::Node) -> Option<NodeRef<'a, Node>> {
+    let mut path = Vec::new();
+    let mut current = target;
+    while current != mirror_context {
+        let parent = current.parent()?;
+        path.push(parent.children().iter().position(|child| *child == current)?);
+        current = parent;
+    }
+    let mut node = context;
+    for index in path.into_iter().rev() {
+        node = node.children().filter(mirrored).nth(index)?;
+    }
+    Some(node)
+}
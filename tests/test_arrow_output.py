@@ -0,0 +1,72 @@
+import ctypes
+
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Product {i} {'long name ' * i}</h2><i>tag{i}</i><i>café</i>"
+    f"<span class='seller'>Shop {i % 3}</span></div>"
+    for i in range(25)
+)
+FIELDS = {"name": "h2", "tags": "i@get_all", "seller": {"name": "span.seller"}}
+
+
+def rows():
+    return [
+        {"name": item["name"], "tags": item["tags"], "seller.name": item["seller"]["name"]}
+        for item in rusticsoup.extract_data(HTML, "div.p", FIELDS)
+    ]
+
+
+def test_arrow_table():
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow")
+    assert isinstance(table, rusticsoup.ArrowTable)
+    assert len(table) == 25
+    assert table.column_names == ["name", "tags", "seller.name"]
+    assert repr(table) == "<ArrowTable rows=25 columns=3>"
+
+
+def test_each_call_hands_out_a_new_stream():
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow")
+    is_valid = ctypes.pythonapi.PyCapsule_IsValid
+    is_valid.argtypes = [ctypes.py_object, ctypes.c_char_p]
+    first, second = table.__arrow_c_stream__(), table.__arrow_c_stream__()
+    assert first is not second
+    assert is_valid(first, b"arrow_array_stream") and is_valid(second, b"arrow_array_stream")
+    # Unconsumed streams are released with their capsules
+    del first, second, table
+
+
+def test_pyarrow_reads_table_repeatedly():
+    pa = pytest.importorskip("pyarrow")
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow")
+    first = pa.table(table)
+    assert first.schema.field("tags").type == pa.large_list(pa.string_view())
+    assert first.to_pylist() == rows()
+    del table
+    assert pa.RecordBatchReader.from_stream(first).read_all().to_pylist() == rows()
+
+
+def test_duckdb_scans_table():
+    duckdb = pytest.importorskip("duckdb")
+    items = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow")
+    assert duckdb.sql('SELECT "seller.name", count(*) FROM items GROUP BY 1 ORDER BY 1').fetchall() == [
+        ("Shop 0", 9),
+        ("Shop 1", 8),
+        ("Shop 2", 8),
+    ]
+
+
+def test_polars_reads_arrow_table():
+    pl = pytest.importorskip("polars")
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow")
+    assert pl.DataFrame(table).to_dicts() == rows()
+    assert pl.DataFrame(table).equals(rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars"))
+
+
+def test_typed_fields_and_dry_run_are_rejected():
+    with pytest.raises(ValueError, match="output='arrow'"):
+        rusticsoup.extract_data(HTML, "div.p", {"price": {"selector": "b", "type": "price"}}, output="arrow")
+    with pytest.raises(ValueError, match="dry_run"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow", dry_run=True)
@@ -0,0 +1,31 @@
+import rusticsoup
+
+
+def test_serialization_keeps_source_attribute_order():
+    page = rusticsoup.parse_html('<a z="1" href="/x" a="2" data-k="v">t</a>')
+    link = page.select_one("a")
+    assert list(link.attrs()) == ["z", "href", "a", "data-k"]
+    assert link.html() == '<a z="1" href="/x" a="2" data-k="v">t</a>'
+    assert '<a z="1" href="/x" a="2" data-k="v">' in page.html()
+    assert list(link.to_dict()["attrs"]) == ["z", "href", "a", "data-k"]
+
+
+def test_first_duplicate_wins_in_tree():
+    link = rusticsoup.parse_html('<a href="/1" href="/2">x</a>').select_one("a")
+    assert link.attr("href") == "/1"
+
+
+def test_duplicate_attributes_reports_dropped_values():
+    html = """<!-- <a href=x href=y> -->
+<a href="/1" class=a HREF='/2' class = b>x</a>
+<script>var s = "<b id=1 id=2>";</script>
+<p title="a>b" title=c>"""
+    assert rusticsoup.duplicate_attributes(html) == [
+        {"tag": "a", "name": "href", "value": "/2", "kept": "/1", "line": 2},
+        {"tag": "a", "name": "class", "value": "b", "kept": "a", "line": 2},
+        {"tag": "p", "name": "title", "value": "c", "kept": "a>b", "line": 4},
+    ]
+
+
+def test_no_duplicates():
+    assert rusticsoup.duplicate_attributes(b"<div id=a class=b><img src=x alt=''></div>") == []
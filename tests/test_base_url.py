@@ -0,0 +1,55 @@
+import pytest
+import rusticsoup
+
+HTML = """
+<html><head><base href="/shop/"></head><body>
+<div class="p">
+  <a href="item/1?ref=list">One</a>
+  <img src="img/1.jpg" srcset="img/1.jpg 1x, //cdn.example.com/1@2x.jpg 2x" data-src="img/1-full.jpg">
+  <a href="mailto:sales@example.com" title="item/1">Mail</a>
+</div>
+</body></html>
+"""
+
+FIELDS = {
+    "link": "a@href",
+    "image": "img@src",
+    "lazy": "img@data-src",
+    "srcset": "img@srcset",
+    "title": "a[title]@title",
+    "mail": "a[title]@href",
+}
+
+
+def test_attributes_resolve_against_base_href():
+    [item] = rusticsoup.extract_data(HTML, "div.p", FIELDS, base_url="https://example.com/catalog/page")
+    assert item["link"] == "https://example.com/shop/item/1?ref=list"
+    assert item["image"] == "https://example.com/shop/img/1.jpg"
+    assert item["lazy"] == "https://example.com/shop/img/1-full.jpg"
+    assert item["srcset"] == "https://example.com/shop/img/1.jpg, https://cdn.example.com/1@2x.jpg 2x"
+    # Non-URL attributes and absolute URLs are left alone
+    assert item["title"] == "item/1"
+    assert item["mail"] == "mailto:sales@example.com"
+
+
+def test_absolute_base_href_without_base_url():
+    html = HTML.replace('href="/shop/"', 'href="https://shop.example.org/a/"')
+    [item] = rusticsoup.extract_data(html, "div.p", {"link": "a@href"})
+    assert item["link"] == "https://shop.example.org/a/item/1?ref=list"
+
+
+def test_relative_urls_kept_without_any_base():
+    [item] = rusticsoup.extract_data(HTML, "div.p", {"link": "a@href", "image": "img@src"})
+    assert item == {"link": "item/1?ref=list", "image": "img/1.jpg"}
+
+
+def test_dry_run_samples_are_resolved():
+    summary = rusticsoup.extract_data(
+        HTML, "div.p", {"link": "a@href"}, dry_run=True, base_url="https://example.com/"
+    )
+    assert summary["fields"]["link"]["sample"] == "https://example.com/shop/item/1?ref=list"
+
+
+def test_invalid_base_url():
+    with pytest.raises(ValueError, match="Invalid base_url"):
+        rusticsoup.extract_data(HTML, "div.p", {"link": "a@href"}, base_url="not a url")
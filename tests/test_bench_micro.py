@@ -73,3 +73,36 @@ def test_select_many_medium(benchmark):
             doc.select("div.card .price")
 
     benchmark(run)
+
+
+@pytest.mark.benchmark(group="select")
+def test_select_positional_wide_table(benchmark):
+    if rusticsoup is None:
+        pytest.skip("rusticsoup not importable")
+
+    html = "<table>" + "".join(f"<tr><td>{i}</td><td>x</td></tr>" for i in range(10000)) + "</table>"
+    # A timeout walks the tree itself; sibling indices must still be reused between rows
+    doc = rusticsoup.parse_html(html, timeout=60)
+
+    def run():
+        doc.select("tr:nth-child(2n+1) > td:nth-of-type(2)")
+        doc.select("tr:nth-last-child(-n+3)")
+
+    benchmark(run)
+
+
+@pytest.mark.benchmark(group="extract_data")
+def test_extract_data_many_fields_large(benchmark):
+    if rusticsoup is None:
+        pytest.skip("rusticsoup not importable")
+
+    doc = rusticsoup.parse_html(medium_html(2000))
+    fields = {"title": "h2", "url": "a.buy@href", "img": "img@src", "price": "span.price", "missing": "em"}
+
+    def run():
+        return rusticsoup.extract_data(doc, "div.card", fields)
+
+    rows = benchmark(run)
+    assert len(rows) == 2000
+    # Every item reuses the mapping's key objects rather than new key strings
+    assert all(a is b for a, b in zip(rows[0], fields))
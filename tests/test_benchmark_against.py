@@ -0,0 +1,104 @@
+import pytest
+
+import rusticsoup
+
+PAGES = [
+    """
+    <div class="product"><h2>Widget</h2><span class="price">$9.99</span>
+      <a href="/w">more</a><li>red</li><li>blue</li></div>
+    <div class="product"><h2>Gadget</h2><span class="price">$19.99</span></div>
+    """
+] * 3
+
+FIELDS = {
+    "title": "h2",
+    "price": ".price",
+    "link": "a@href",
+    "colors": "li@get_all",
+}
+
+
+def test_reports_rusticsoup_first():
+    report = rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=[], repeat=2)
+    assert report["pages"] == 3
+    assert report["repeat"] == 2
+    [entry] = report["results"]
+    assert entry["name"] == "rusticsoup"
+    assert entry["items"] == 6
+    assert entry["relative"] == 1.0
+    assert entry["same_output"] is True
+    assert entry["error"] is None
+    assert entry["ms_per_page"] == pytest.approx(entry["seconds"] * 1000 / 3)
+
+
+def test_callable_competitor():
+    seen = []
+
+    def reference(html, container_selector, field_mappings):
+        seen.append((container_selector, field_mappings))
+        return rusticsoup.extract_data(html, container_selector, field_mappings)
+
+    def lossy(html, container_selector, field_mappings):
+        return []
+
+    report = rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=[reference, lossy], repeat=1)
+    _, same, different = report["results"]
+    assert same["name"] == "reference"
+    assert same["same_output"] is True
+    assert same["items"] == 6
+    assert same["relative"] > 0
+    assert different["name"] == "lossy"
+    assert different["same_output"] is False
+    assert different["items"] == 0
+    assert seen[0] == ("div.product", FIELDS)
+
+
+def test_compiled_selectors_are_passed_to_competitors_as_a_dict():
+    compiled = rusticsoup.compile_selectors(FIELDS)
+    received = []
+
+    def competitor(html, container_selector, field_mappings):
+        received.append(field_mappings)
+        return rusticsoup.extract_data(html, container_selector, field_mappings)
+
+    report = rusticsoup.benchmark_against(PAGES, "div.product", compiled, competitors=[competitor], repeat=1)
+    assert report["results"][1]["same_output"] is True
+    assert received[0] == FIELDS
+
+
+def test_failing_competitor_is_reported():
+    def broken(html, container_selector, field_mappings):
+        raise RuntimeError("boom")
+
+    report = rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=[broken])
+    entry = report["results"][1]
+    assert entry["error"] == "RuntimeError: boom"
+    assert entry["seconds"] is None
+    assert entry["same_output"] is None
+
+
+def test_unknown_competitor():
+    with pytest.raises(ValueError, match="Unknown competitor: 'soup'"):
+        rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=["soup"])
+
+
+def test_competitor_must_be_name_or_callable():
+    with pytest.raises(TypeError, match="neither a name nor a callable"):
+        rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=[42])
+
+
+def test_repeat_must_be_positive():
+    with pytest.raises(ValueError, match="repeat"):
+        rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, repeat=0)
+
+
+@pytest.mark.parametrize("name,module", [("bs4+html.parser", "bs4"), ("bs4+lxml", "lxml"), ("selectolax", "selectolax"), ("lxml", "cssselect")])
+def test_builtin_competitor_matches_extract_data(name, module):
+    pytest.importorskip(module)
+    if name.startswith("bs4"):
+        pytest.importorskip("bs4")
+    report = rusticsoup.benchmark_against(PAGES, "div.product", FIELDS, competitors=[name], repeat=1)
+    entry = report["results"][1]
+    assert entry["error"] is None
+    assert entry["same_output"] is True
+    assert entry["items"] == 6
@@ -0,0 +1,84 @@
+import gzip
+import logging
+import mmap
+
+import pytest
+
+import rusticsoup
+
+HTML = "<div class='item'><a href='/café'>Café crème</a><span>$4</span></div>".encode()
+
+
+@pytest.mark.parametrize(
+    "wrap",
+    [memoryview, bytearray, lambda data: memoryview(bytearray(data)), lambda data: memoryview(data)[:]],
+)
+def test_buffers_parse_like_bytes(wrap):
+    page = rusticsoup.parse_html(wrap(HTML))
+    assert page.html() == rusticsoup.parse_html(HTML).html()
+    assert page.original_encoding == "utf-8"
+    assert rusticsoup.extract_data(wrap(HTML), "div.item", {"name": "a", "url": "a@href"}) == [
+        {"name": "Café crème", "url": "/café"}
+    ]
+
+
+def test_mmap_input(tmp_path):
+    path = tmp_path / "page.html"
+    path.write_bytes(b"<html><head><meta charset='windows-1252'></head><body><p>caf\xe9</p></body></html>")
+    with open(path, "rb") as f, mmap.mmap(f.fileno(), 0, access=mmap.ACCESS_READ) as mapped:
+        page = rusticsoup.parse_html(mapped)
+        assert page.select_one("p").text() == "café"
+        assert (page.original_encoding, page.encoding_source) == ("windows-1252", "meta")
+    # The document does not depend on the mapping once parsed
+    assert page.select_one("p").text() == "café"
+
+
+def test_strided_and_sliced_buffers():
+    assert rusticsoup.parse_html(memoryview(b"<p>xyz</p>")[3:6]).html() == rusticsoup.parse_html(b"xyz").html()
+    assert rusticsoup.parse_html(memoryview(b"<<pp>>")[::2]).select("p")
+
+
+def test_writable_buffer_changes_after_parse_do_not_leak_in():
+    data = bytearray(b"<p>before</p>")
+    page = rusticsoup.parse_html(data)
+    data[3:9] = b"after!"
+    assert page.select_one("p").text() == "before"
+
+
+def test_read_only_views_of_writable_memory_are_copied():
+    data = bytearray(b"<p>before</p>")
+
+    class Rewrite(logging.Handler):
+        def emit(self, record):
+            data[3:9] = b"after!"
+
+    logger = logging.getLogger("rusticsoup")
+    logger.setLevel(logging.DEBUG)
+    handler = Rewrite()
+    logger.addHandler(handler)
+    try:
+        rusticsoup.configure_logging("DEBUG")
+        page = rusticsoup.parse_html(memoryview(data).toreadonly())
+    finally:
+        rusticsoup.configure_logging(None)
+        logger.removeHandler(handler)
+    assert data == b"<p>after!</p>"
+    assert page.select_one("p").text() == "before"
+
+
+def test_buffer_input_across_apis():
+    assert rusticsoup.RusticSoup(memoryview(HTML)).select_one("span").text() == "$4"
+    assert rusticsoup.parse_fragment(memoryview(b"<td>1</td>"), context="tr").select("td")
+    parser = rusticsoup.StreamingParser()
+    parser.feed(memoryview(HTML[:20]))
+    parser.feed(bytearray(HTML[20:]))
+    assert parser.close().select_one("a").text() == "Café crème"
+    sitemap = b'<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><url><loc>https://a.example/</loc></url></urlset>'
+    assert rusticsoup.parse_sitemap(memoryview(gzip.compress(sitemap)))["urls"][0]["loc"] == "https://a.example/"
+
+
+def test_non_byte_buffers_are_rejected():
+    import array
+
+    with pytest.raises(TypeError):
+        rusticsoup.parse_html(array.array("i", [1, 2, 3]))
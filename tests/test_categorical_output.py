@@ -0,0 +1,56 @@
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Product {i}</h2><b>{['USD', 'EUR', 'GBP'][i % 3]}</b><i>tag{i % 2}</i></div>"
+    for i in range(200)
+)
+FIELDS = {"name": "h2", "currency": "b", "tags": "i@get_all"}
+
+
+def test_low_cardinality_string_columns_are_encoded():
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow", categorical=True)
+    assert table.categorical_columns == ["currency"]
+    assert len(table) == 200
+
+
+def test_named_columns_are_encoded_whatever_their_cardinality():
+    table = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow", categorical=["name"])
+    assert table.categorical_columns == ["name"]
+
+
+def test_small_tables_keep_plain_columns():
+    html = "<div class='p'><h2>A</h2><b>USD</b><i>x</i></div><div class='p'><h2>B</h2><b>EUR</b></div>"
+    table = rusticsoup.extract_data(html, "div.p", FIELDS, output="arrow", categorical=True)
+    assert table.categorical_columns == []
+
+
+def test_default_encodes_nothing():
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow").categorical_columns == []
+
+
+@pytest.mark.parametrize("options, message", [
+    ({"output": "arrow", "categorical": ["price"]}, "Unknown column 'price'"),
+    ({"output": "arrow", "categorical": ["tags"]}, "Column 'tags' holds lists"),
+    ({"categorical": True}, "pass output='arrow' or output='polars'"),
+    ({"output": "columns", "categorical": ["name"]}, "pass output='arrow' or output='polars'"),
+])
+def test_invalid_categorical_raises(options, message):
+    with pytest.raises(ValueError, match=message):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, **options)
+
+
+def test_pyarrow_reads_dictionary_columns():
+    pa = pytest.importorskip("pyarrow")
+    table = pa.table(rusticsoup.extract_data(HTML, "div.p", FIELDS, output="arrow", categorical=True))
+    assert table.schema.field("currency").type == pa.dictionary(pa.int32(), pa.string())
+    assert table.column("currency").to_pylist()[:4] == ["USD", "EUR", "GBP", "USD"]
+    assert table.schema.field("name").type == pa.string_view()
+
+
+def test_polars_reads_categorical_columns():
+    pl = pytest.importorskip("polars")
+    frame = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars", categorical=True)
+    assert frame.schema["currency"] == pl.Categorical
+    assert frame.group_by("currency").len().sort("currency").rows() == [("EUR", 67), ("GBP", 66), ("USD", 67)]
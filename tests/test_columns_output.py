@@ -0,0 +1,63 @@
+from decimal import Decimal
+
+import pytest
+
+import rusticsoup
+
+HTML = """
+<div class="product"><h2>Widget</h2><span class="price">$9.99</span>
+  <a class="seller" href="/s/1">Acme</a><li class="tag">new</li><li class="tag">sale</li></div>
+<div class="product"><h2>Gadget</h2></div>
+"""
+
+FIELDS = {
+    "title": "h2",
+    "price": {"selector": "span.price", "type": "price"},
+    "tags": "li.tag@get_all",
+    "seller": {"name": "a.seller", "url": "a.seller@href"},
+}
+
+
+def test_one_list_per_field():
+    columns = rusticsoup.extract_data(HTML, "div.product", FIELDS, output="columns")
+    assert columns == {
+        "title": ["Widget", "Gadget"],
+        "price": [{"amount": Decimal("9.99"), "currency": "USD"}, None],
+        "tags": [["new", "sale"], []],
+        "seller.name": ["Acme", ""],
+        "seller.url": ["/s/1", ""],
+    }
+    assert list(columns) == ["title", "price", "tags", "seller.name", "seller.url"]
+
+
+def test_matches_records():
+    records = rusticsoup.extract_data(HTML, "div.product", {"title": "h2", "tags": "li.tag@get_all"})
+    columns = rusticsoup.extract_data(HTML, "div.product", {"title": "h2", "tags": "li.tag@get_all"}, output="columns")
+    assert columns == {key: [record[key] for record in records] for key in records[0]}
+
+
+def test_no_containers_gives_empty_lists():
+    columns = rusticsoup.extract_data(HTML, "div.missing", FIELDS, output="columns")
+    assert columns == {"title": [], "price": [], "tags": [], "seller.name": [], "seller.url": []}
+
+
+def test_compiled_selectors():
+    compiled = rusticsoup.compile_selectors({"title": "h2"})
+    assert rusticsoup.extract_data(HTML, "div.product", compiled, output="columns") == {"title": ["Widget", "Gadget"]}
+
+
+def test_report_keeps_rows_aligned():
+    columns, report = rusticsoup.extract_data(HTML, "div.product", {"title": "h2", "bad": "p[["}, output="columns", report=True)
+    assert columns == {"title": ["Widget", "Gadget"], "bad": ["", ""]}
+    assert len(report.failed) == 2
+
+
+def test_large_extraction_runs_in_parallel_path():
+    html = "".join(f'<div class="p"><h2>T{i}</h2></div>' for i in range(2000))
+    columns = rusticsoup.extract_data(html, "div.p", {"title": "h2"}, output="columns")
+    assert columns["title"] == [f"T{i}" for i in range(2000)]
+
+
+def test_columns_output_rejects_sink(tmp_path):
+    with pytest.raises(ValueError):
+        rusticsoup.extract_data(HTML, "div.product", {"title": "h2"}, output="columns", sink=str(tmp_path / "out.csv"))
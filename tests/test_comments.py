@@ -0,0 +1,44 @@
+import re
+
+import pytest
+
+import rusticsoup
+
+HTML = """<!-- build 4711 -->
+<html><body>
+  <div id="product"><!-- flags: {"beta": true} --><p>Widget</p></div>
+  <!--[if IE]><p>Old browser</p><![endif]-->
+  <template><!-- in template --></template>
+</body></html>
+"""
+
+
+def test_comments_in_document_order():
+    page = rusticsoup.parse_html(HTML)
+    assert page.comments() == [
+        " build 4711 ",
+        ' flags: {"beta": true} ',
+        "[if IE]><p>Old browser</p><![endif]",
+        " in template ",
+    ]
+
+
+def test_comment_pattern_and_limit():
+    page = rusticsoup.parse_html(HTML)
+    assert page.comments("flags") == [' flags: {"beta": true} ']
+    assert page.comments(re.compile(r"build \d+")) == [" build 4711 "]
+    assert page.comments(limit=2) == [" build 4711 ", ' flags: {"beta": true} ']
+    with pytest.raises(TypeError):
+        page.comments(42)
+
+
+def test_element_comments():
+    product = rusticsoup.parse_html(HTML).select_one("#product")
+    assert product.comments() == [' flags: {"beta": true} ']
+
+
+def test_facade_find_comments():
+    soup = rusticsoup.RusticSoup(HTML)
+    assert soup.find_comment(re.compile("endif")) == "[if IE]><p>Old browser</p><![endif]"
+    assert soup.find_comment("missing") is None
+    assert len(soup.find_all_comments()) == 4
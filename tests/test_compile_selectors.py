@@ -0,0 +1,154 @@
+import threading
+
+import pytest
+
+import rusticsoup
+
+HTML = """<div class="p"><h2>Widget</h2><a href="/w">more</a><time datetime="2024-03-01">Mar 1</time></div>
+<div class="p"><h2>Gadget</h2><a href="/g">more</a></div>"""
+
+MAPPINGS = {
+    "name": "h2",
+    "url": "a@href",
+    "heading": "xpath:string(.//h2)",
+    "meta": {"released": {"selector": "time", "type": "datetime"}},
+}
+
+
+def test_compiled_mappings_extract_like_the_dict():
+    compiled = rusticsoup.compile_selectors(MAPPINGS)
+    assert repr(compiled) == "<CompiledSelectors fields=4>"
+    rows = rusticsoup.extract_data(HTML, "div.p", compiled)
+    assert rows == rusticsoup.extract_data(HTML, "div.p", MAPPINGS)
+    assert rows[1] == {"name": "Gadget", "url": "/g", "heading": "Gadget", "meta": {"released": None}}
+
+
+def test_compiled_mappings_with_documents_reports_and_dry_runs():
+    compiled = rusticsoup.compile_selectors(MAPPINGS)
+    page = rusticsoup.parse_html(HTML)
+    rows, report = rusticsoup.extract_data(page, "div.p", compiled, report=True)
+    assert report.ok and [issue["field"] for issue in report.empty] == ["meta.released"]
+    assert rusticsoup.extract_data(page, "div.p", compiled, dry_run=True) == rusticsoup.extract_data(
+        page, "div.p", MAPPINGS, dry_run=True
+    )
+
+
+def test_compiled_mappings_are_shared_between_threads():
+    compiled = rusticsoup.compile_selectors(MAPPINGS)
+    results = []
+    threads = [
+        threading.Thread(target=lambda: results.append(rusticsoup.extract_data(HTML, "div.p", compiled)))
+        for _ in range(4)
+    ]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+    assert results == [rusticsoup.extract_data(HTML, "div.p", MAPPINGS)] * 4
+
+
+def test_compiled_mappings_keep_their_fields():
+    mappings = {"name": "h2"}
+    compiled = rusticsoup.compile_selectors(mappings)
+    mappings["url"] = "a@href"
+    assert rusticsoup.extract_data(HTML, "div.p", compiled)[0] == {"name": "Widget"}
+
+
+@pytest.mark.parametrize("mappings, field", [
+    ({"title": "h2 ["}, "title"),
+    ({"meta": {"link": "xpath:.//a[@"}}, "meta.link"),
+])
+def test_invalid_selectors_raise_when_compiling(mappings, field):
+    with pytest.raises(rusticsoup.SelectorError) as info:
+        rusticsoup.compile_selectors(mappings)
+    assert info.value.field == field
+
+
+def test_invalid_type_raises_when_compiling():
+    with pytest.raises(ValueError, match="Invalid type 'nope'"):
+        rusticsoup.compile_selectors({"price": {"selector": "span", "type": "nope"}})
+
+
+def test_saved_mappings_load_compiled(tmp_path):
+    path = tmp_path / "fields.json"
+    rusticsoup.compile_selectors(MAPPINGS).save(path)
+    loaded = rusticsoup.CompiledSelectors.load(str(path))
+    assert repr(loaded) == "<CompiledSelectors fields=4>"
+    assert rusticsoup.extract_data(HTML, "div.p", loaded) == rusticsoup.extract_data(HTML, "div.p", MAPPINGS)
+
+
+@pytest.mark.parametrize("content, message", [
+    ("{not json", "not valid JSON"),
+    ('{"name": "h2"}', "not written by CompiledSelectors.save"),
+    ('{"format": "rusticsoup.selectors", "version": 99, "fields": {}}', "format version 99 is not supported"),
+])
+def test_loading_other_files_raises(tmp_path, content, message):
+    path = tmp_path / "fields.json"
+    path.write_text(content)
+    with pytest.raises(ValueError, match=message):
+        rusticsoup.CompiledSelectors.load(path)
+
+
+def test_loading_revalidates_selectors(tmp_path):
+    path = tmp_path / "fields.json"
+    path.write_text('{"format": "rusticsoup.selectors", "version": 1, "fields": {"title": "h2 ["}}')
+    with pytest.raises(rusticsoup.SelectorError):
+        rusticsoup.CompiledSelectors.load(path)
+
+
+RULES_YAML = """
+# Maintained by the catalog team
+container: div.p
+fields:
+  name: h2
+  url: a@href
+  meta:
+    released:
+      selector: time
+      type: datetime
+"""
+
+
+def test_rules_from_yaml_file(tmp_path):
+    pytest.importorskip("yaml")
+    path = tmp_path / "rules.yaml"
+    path.write_text(RULES_YAML)
+    rules = rusticsoup.CompiledSelectors.from_file(path)
+    assert rules.container == "div.p" and rules.on_error == "raise"
+    assert rusticsoup.extract_data(HTML, rules.container, rules) == rusticsoup.extract_data(
+        HTML, "div.p", {k: v for k, v in MAPPINGS.items() if k != "heading"}
+    )
+
+
+def test_rules_from_json_file_skip_bad_selectors(tmp_path):
+    path = tmp_path / "rules.json"
+    path.write_text('{"container": "div.p", "on_error": "skip", "fields": {"name": "h2", "broken": "h2 ["}}')
+    rules = rusticsoup.CompiledSelectors.from_file(str(path))
+    assert rusticsoup.extract_data(HTML, rules.container, rules)[0] == {"name": "Widget", "broken": ""}
+    rows, report = rusticsoup.extract_data(HTML, rules.container, rules, report=True)
+    assert [issue["field"] for issue in report.failed] == ["broken", "broken"]
+
+
+def test_container_and_policy_survive_save_and_pickle(tmp_path):
+    import pickle
+
+    compiled = rusticsoup.compile_selectors({"name": "h2"}, container="div.p", on_error="skip")
+    path = tmp_path / "fields.json"
+    compiled.save(path)
+    for copy_ in (rusticsoup.CompiledSelectors.load(path), pickle.loads(pickle.dumps(compiled))):
+        assert (copy_.container, copy_.on_error) == ("div.p", "skip")
+
+
+@pytest.mark.parametrize("name, content, error, message", [
+    ("rules.toml", "", ValueError, "use a .json, .yaml or .yml file"),
+    ("rules.json", '{"fields": {}, "selector": "div"}', ValueError, "unknown key 'selector'"),
+    ("rules.json", '{"container": "div"}', ValueError, "no fields"),
+    ("rules.json", '{"fields": {}, "on_error": "ignore"}', ValueError, "Invalid on_error: 'ignore'"),
+    ("rules.json", '{"fields": {"name": "h2 ["}}', rusticsoup.SelectorError, "field 'name'"),
+    ("rules.json", '{"container": "div[", "fields": {}}', rusticsoup.SelectorError, "container selector"),
+])
+def test_invalid_rules_files_raise(tmp_path, name, content, error, message):
+    path = tmp_path / name
+    path.write_text(content)
+    with pytest.raises(error, match=message):
+        rusticsoup.CompiledSelectors.from_file(path)
@@ -0,0 +1,45 @@
+import rusticsoup
+
+HTML = """<html><head>
+<!--[if lt IE 9]><script src="html5shiv.js"></script><![endif]-->
+</head><body>
+<![if !IE]><p class="modern">Modern</p><![endif]>
+<!--[if !IE]><!--><p class="also">Also modern</p><!--<![endif]-->
+<!--[if IE]><div class="legacy"><a href="/old">Old site</a></div><![endif]-->
+<table><!--[if IE]><tr><td>cell</td></tr><![endif]--></table>
+</body></html>"""
+
+
+def test_conditional_comments_listed():
+    found = rusticsoup.parse_html(HTML).conditional_comments()
+    assert found == [
+        {"condition": "lt IE 9", "html": '<script src="html5shiv.js"></script>', "revealed": False},
+        {"condition": "!IE", "html": '<p class="modern">Modern</p>', "revealed": True},
+        {"condition": "!IE", "html": '<p class="also">Also modern</p>', "revealed": True},
+        {"condition": "IE", "html": '<div class="legacy"><a href="/old">Old site</a></div>', "revealed": False},
+        {"condition": "IE", "html": "<tr><td>cell</td></tr>", "revealed": False},
+    ]
+
+
+def test_hidden_markup_not_in_tree_by_default():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select(".legacy") == []
+    assert [p.text() for p in page.select("p")] == ["Modern", "Also modern"]
+
+
+def test_parse_conditional_comments():
+    page = rusticsoup.parse_html(HTML, parse_conditional_comments=True)
+    assert page.select_one(".legacy a").attr("href") == "/old"
+    assert page.select_one("head script").attr("src") == "html5shiv.js"
+    assert page.select_one("table > tbody > tr > td").text() == "cell"
+    assert [c["revealed"] for c in page.conditional_comments()] == [True, True]
+
+
+def test_option_on_other_entry_points():
+    rows = rusticsoup.extract_data(HTML, "div.legacy", {"url": "a@href"}, parse_conditional_comments=True)
+    assert rows == [{"url": "/old"}]
+    row = rusticsoup.parse_fragment("<!--[if IE]><td>x</td><![endif]-->", context="tr", parse_conditional_comments=True)
+    assert row.html() == "<td>x</td>"
+    parser = rusticsoup.StreamingParser(parse_conditional_comments=True)
+    parser.feed(HTML)
+    assert len(parser.close().select(".legacy")) == 1
@@ -0,0 +1,32 @@
+import pytest
+
+import rusticsoup
+
+PAGE = """<html><body>
+<p>Write to <a href="mailto:Sales@Example.COM?subject=Hi">our sales team</a> or support@example.com.</p>
+<p>Call <a href="tel:+1-415-555-0123">+1 (415) 555-0123</a> or our London office on 020 7946 0958.</p>
+<p>Published 2024-05-07, 1,234,567 visitors. Logo: logo@2x.png. SKU AB1234567.</p>
+<a href="/cdn-cgi/l/email-protection"><span class="__cf_email__" data-cfemail="422f232b2e02273a232f322e276c212d2f">[email&#160;protected]</span></a>
+<script>var hidden = "secret@example.com";</script>
+</body></html>"""
+
+
+def test_extract_contacts():
+    contacts = rusticsoup.extract_contacts(PAGE)
+    assert contacts["emails"] == ["Sales@example.com", "mail@example.com", "support@example.com"]
+    assert contacts["phones"] == [
+        {"number": "+14155550123", "raw": "+1-415-555-0123"},
+        {"number": "02079460958", "raw": "020 7946 0958"},
+    ]
+
+
+def test_default_region():
+    contacts = rusticsoup.extract_contacts(PAGE, default_region="gb")
+    assert [p["number"] for p in contacts["phones"]] == ["+14155550123", "+442079460958"]
+    us = rusticsoup.extract_contacts("<p>Call (415) 555-0199 or 011 49 30 123456</p>", default_region="US")
+    assert [p["number"] for p in us["phones"]] == ["+14155550199", "+4930123456"]
+
+
+def test_unknown_region():
+    with pytest.raises(ValueError):
+        rusticsoup.extract_contacts(PAGE, default_region="XX")
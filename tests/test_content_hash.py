@@ -0,0 +1,48 @@
+import pytest
+
+import rusticsoup
+
+ARTICLE = """<html><head><title>t</title><script>var tracking = 1;</script></head><body>
+<h1>Widgets are great</h1>
+<p>The quick brown fox jumps over the lazy dog while widgets hum quietly in the factory.
+Engineers measured every widget twice before shipping it to the warehouse downtown.</p>
+<p>Customers praised the durability, the price and the friendly support team.</p>
+</body></html>"""
+
+
+def bit_distance(a, b):
+    return bin(a ^ b).count("1")
+
+
+def test_simhash_is_stable_and_ignores_markup():
+    first = rusticsoup.content_hash(ARTICLE)
+    assert isinstance(first, int) and 0 <= first < 2**64
+    assert rusticsoup.content_hash(ARTICLE.encode("utf-8")) == first
+    restyled = ARTICLE.replace("<p>", '<p class="lead">').replace("tracking = 1", "tracking = 2")
+    assert rusticsoup.content_hash(restyled) == first
+    assert rusticsoup.content_hash(ARTICLE.upper().replace("<HTML>", "<html>")) == first
+
+
+def test_simhash_near_duplicates_are_close():
+    near = ARTICLE.replace("friendly", "helpful")
+    other = "<p>Completely unrelated text about sailing boats, tides and harbours in winter.</p>"
+    original = rusticsoup.content_hash(ARTICLE)
+    assert bit_distance(original, rusticsoup.content_hash(near)) < 16
+    assert bit_distance(original, rusticsoup.content_hash(other)) > bit_distance(
+        original, rusticsoup.content_hash(near)
+    )
+
+
+def test_minhash_estimates_similarity():
+    original = rusticsoup.content_hash(ARTICLE, algorithm="minhash")
+    assert len(original) == 64
+    near = rusticsoup.content_hash(ARTICLE.replace("friendly", "helpful"), algorithm="minhash")
+    other = rusticsoup.content_hash("<p>Sailing boats, tides and harbours.</p>", algorithm="minhash")
+    same = sum(a == b for a, b in zip(original, near)) / 64
+    assert same > 0.6
+    assert sum(a == b for a, b in zip(original, other)) < 5
+
+
+def test_unknown_algorithm():
+    with pytest.raises(ValueError):
+        rusticsoup.content_hash("<p>x</p>", algorithm="md5")
@@ -0,0 +1,44 @@
+import pytest
+
+import rusticsoup
+
+HTML = """<html><body>
+<div id="main" class="box wide">
+  <p class="lead">1</p><p lang="en-US" data-k="Hello World">2</p><span>3</span><p>4</p>
+  <ul><li>a</li><li class="odd">b</li><li>c</li><li>d</li><li>e</li><li>f</li></ul>
+  <i></i>
+</div>
+<div class="wide"><a href="https://example.com/a.pdf" title="it's &quot;q&quot;">z</a></div>
+</body></html>"""
+
+SELECTORS = [
+    "p", "div p", ".wide > p", "#main > p.lead + p", "p ~ span", "div.box.wide",
+    "li:nth-child(2n+1)", "li:nth-child(3n-1)", "li:nth-child(-n+2)", "li:nth-last-child(2)",
+    "li:first-child", "li:last-child", "span:only-of-type", "p:nth-of-type(2)", "p:last-of-type",
+    "[lang|=en]", "[data-k~=World]", "a[href^=https]", 'a[href$=".pdf"]', "a[href*=example]",
+    '[data-k="hello world" i]', "p:not(.lead)", "p:not(.lead, [lang])", "div:not(#main) a",
+    ":is(span, i)", "i:empty", ":root", "p, span", "*", "a[title=\"it's \\\"q\\\"\"]",
+    "p:not(div > p)", "li:not(li + li)", "li:not(.odd ~ li)", "a:not(#main a)", "li:not(ul > li:nth-child(odd))",
+    "p:is(#main > p + p, span ~ p)", "li:where(.odd + li, * > .odd)", ":not(div *)",
+]
+
+
+@pytest.mark.parametrize("selector", SELECTORS)
+def test_xpath_selects_the_same_elements(selector):
+    page = rusticsoup.parse_html(HTML)
+    xpath = rusticsoup.css_to_xpath(selector)
+    assert [e.html() for e in page.xpath(xpath)] == [e.html() for e in page.select(selector)]
+
+
+def test_output_follows_cssselect_conventions():
+    assert rusticsoup.css_to_xpath("div > p") == "descendant-or-self::div/p"
+    assert rusticsoup.css_to_xpath("div p, a") == "descendant-or-self::div//p | descendant-or-self::a"
+    assert rusticsoup.css_to_xpath("#x") == "descendant-or-self::*[@id = 'x']"
+    assert rusticsoup.css_to_xpath("h2 + p") == "descendant-or-self::h2/following-sibling::*[1][self::p]"
+
+
+def test_invalid_and_untranslatable_selectors():
+    with pytest.raises(rusticsoup.SelectorError, match="position"):
+        rusticsoup.css_to_xpath("p >")
+    with pytest.raises(rusticsoup.SelectorError, match=":has"):
+        rusticsoup.css_to_xpath("div:has(> a)")
@@ -0,0 +1,89 @@
+import csv
+
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Widget {i}, \"deluxe\"</h2><a href='/p/{i}'>more</a><i>red</i><i>size {i}</i>"
+    f"<p class='desc'>line one\nline two</p><span class='seller'>Shop {i % 2}</span></div>"
+    for i in range(4)
+)
+FIELDS = {"name": "h2", "url": "a@href", "tags": "i@get_all", "desc": "p.desc", "seller": {"name": "span.seller", "rating": "em"}}
+
+
+def read(path):
+    with open(path, newline="", encoding="utf-8") as f:
+        return list(csv.reader(f))
+
+
+def test_sink_writes_rows_and_returns_count(tmp_path):
+    path = tmp_path / "out.csv"
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=str(path)) == 4
+    rows = read(path)
+    assert rows[0] == ["name", "url", "tags", "desc", "seller.name", "seller.rating"]
+    assert rows[1] == ['Widget 0, "deluxe"', "/p/0", "red|size 0", "line one\nline two", "Shop 0", ""]
+    assert len(rows) == 5
+    assert path.read_bytes().startswith(b'name,url,tags,desc,seller.name,seller.rating\r\n"Widget 0, ""deluxe"""')
+
+
+def test_sink_column_order(tmp_path):
+    path = tmp_path / "out.csv"
+    rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=path, columns=["seller.name", "name"])
+    assert read(path)[:2] == [["seller.name", "name"], ["Shop 0", 'Widget 0, "deluxe"']]
+    with pytest.raises(ValueError, match="Unknown column 'price'"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=path, columns=["price"])
+
+
+def test_sink_with_report_and_compiled_fields(tmp_path):
+    path = tmp_path / "out.csv"
+    count, report = rusticsoup.extract_data(HTML, "div.p", {"bad": "a[", "name": "h2"}, sink=path, report=True)
+    assert count == report.containers == 4 and len(report.failed) == 4
+    assert read(path)[1] == ["", 'Widget 0, "deluxe"']
+    compiled = rusticsoup.compile_selectors(FIELDS)
+    assert rusticsoup.extract_data(HTML, "div.p", compiled, sink=path) == 4
+
+
+def test_sink_errors(tmp_path):
+    with pytest.raises(ValueError, match="Unsupported sink"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=tmp_path / "out.txt")
+    with pytest.raises(ValueError, match="CSV sink"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, columns=["name"])
+    with pytest.raises(ValueError, match="output='polars'"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=tmp_path / "out.csv", output="polars")
+    with pytest.raises(ValueError, match="has a type"):
+        rusticsoup.extract_data(HTML, "div.p", {"p": {"selector": "h2", "type": "price"}}, sink=tmp_path / "out.csv")
+    with pytest.raises(FileNotFoundError):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=tmp_path / "missing" / "out.csv")
+
+
+def read_sink(tmp_path):
+    path = tmp_path / "sink.csv"
+    rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=path)
+    return read(path)
+
+
+def test_write_csv_from_results(tmp_path):
+    path = tmp_path / "results.csv"
+    results = rusticsoup.extract_data(HTML, "div.p", FIELDS)
+    assert rusticsoup.write_csv(results, path) == 4
+    assert read(path) == read_sink(tmp_path)
+
+
+def test_write_csv_values_and_columns(tmp_path):
+    path = tmp_path / "rows.csv"
+    rows = [{"a": 1, "b": None, "c": {"d": ["x", "y"]}}, {"a": True, "b": "é"}]
+    assert rusticsoup.write_csv(iter(rows), path) == 2
+    assert read(path) == [["a", "b", "c.d"], ["1", "", "x|y"], ["True", "é", ""]]
+    rusticsoup.write_csv(rows, path, columns=["c.d", "a", "missing"])
+    assert read(path) == [["c.d", "a", "missing"], ["x|y", "1", ""], ["", "True", ""]]
+
+
+def test_write_csv_edge_cases(tmp_path):
+    path = tmp_path / "rows.csv"
+    assert rusticsoup.write_csv([], path) == 0 and path.read_text() == ""
+    assert rusticsoup.write_csv([], path, columns=["a"]) == 0 and read(path) == [["a"]]
+    with pytest.raises(ValueError, match="Row 1 has column 'b'"):
+        rusticsoup.write_csv([{"a": 1}, {"b": 2}], path)
+    with pytest.raises(TypeError, match="Row 0 is not a dict"):
+        rusticsoup.write_csv([["a"]], path)
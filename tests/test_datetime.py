@@ -0,0 +1,87 @@
+from datetime import datetime, timedelta, timezone
+from zoneinfo import ZoneInfo
+
+import pytest
+
+import rusticsoup
+
+UTC = timezone.utc
+REF = datetime(2024, 5, 10, 12, 0, tzinfo=UTC)
+
+
+@pytest.mark.parametrize(
+    "text, expected",
+    [
+        ("2024-05-07", datetime(2024, 5, 7, tzinfo=UTC)),
+        ("2024-05-07T09:30:00Z", datetime(2024, 5, 7, 9, 30, tzinfo=UTC)),
+        ("2024-05-07 09:30:15.25+02:00", datetime(2024, 5, 7, 9, 30, 15, 250000, tzinfo=timezone(timedelta(hours=2)))),
+        ("Tue, 07 May 2024 09:30:00 GMT", datetime(2024, 5, 7, 9, 30, tzinfo=UTC)),
+        ("May 7th, 2024 at 5:30 PM EST", datetime(2024, 5, 7, 17, 30, tzinfo=timezone(timedelta(hours=-5)))),
+        ("Posted on 7 May 2024", datetime(2024, 5, 7, tzinfo=UTC)),
+        ("07.05.2024 14:00", datetime(2024, 5, 7, 14, 0, tzinfo=UTC)),
+        ("05/07/2024", datetime(2024, 5, 7, tzinfo=UTC)),
+        ("25/12/2023", datetime(2023, 12, 25, tzinfo=UTC)),
+        ("12am Jan 1 2024", datetime(2024, 1, 1, 0, 0, tzinfo=UTC)),
+        ("1715074200", datetime(2024, 5, 7, 9, 30, tzinfo=UTC)),
+    ],
+)
+def test_absolute(text, expected):
+    assert rusticsoup.parse_datetime(text) == expected
+    assert rusticsoup.parse_datetime(text).tzinfo is not None
+
+
+@pytest.mark.parametrize(
+    "text, expected",
+    [
+        ("2 days ago", REF - timedelta(days=2)),
+        ("an hour ago", REF - timedelta(hours=1)),
+        ("about 3 hrs ago", REF - timedelta(hours=3)),
+        ("5m ago", REF - timedelta(minutes=5)),
+        ("in 10 minutes", REF + timedelta(minutes=10)),
+        ("yesterday", REF - timedelta(days=1)),
+        ("just now", REF),
+        ("last week", REF - timedelta(weeks=1)),
+        ("3 months ago", datetime(2024, 2, 10, 12, 0, tzinfo=UTC)),
+    ],
+)
+def test_relative(text, expected):
+    assert rusticsoup.parse_datetime(text, reference=REF) == expected
+
+
+def test_month_arithmetic_clamps_day():
+    ref = datetime(2024, 3, 31, tzinfo=UTC)
+    assert rusticsoup.parse_datetime("1 month ago", reference=ref) == datetime(2024, 2, 29, tzinfo=UTC)
+
+
+def test_dayfirst_and_default_tz():
+    assert rusticsoup.parse_datetime("05/07/2024", dayfirst=True).month == 7
+    berlin = ZoneInfo("Europe/Berlin")
+    parsed = rusticsoup.parse_datetime("2024-05-07 09:30", default_tz=berlin)
+    assert parsed == datetime(2024, 5, 7, 9, 30, tzinfo=berlin)
+    naive_ref = datetime(2024, 5, 10, 12, 0)
+    assert rusticsoup.parse_datetime("1 day ago", reference=naive_ref).tzinfo is UTC
+
+
+def test_unparseable():
+    for text in ["", "Sold out", "2024-13-01", "Feb 30, 2024", "25:00 May 1 2024"]:
+        assert rusticsoup.parse_datetime(text) is None
+
+
+def test_year_zero_is_unparseable():
+    for text in ["0000-05-07", "0000/05/07", "May 7, 0000"]:
+        assert rusticsoup.parse_datetime(text) is None
+    html = '<article><time datetime="0000-01-01">soon</time></article>'
+    items = rusticsoup.extract_data(html, "article", {"date": {"selector": "time", "type": "datetime"}})
+    assert items == [{"date": None}]
+
+
+def test_datetime_type_in_extract_data():
+    html = """<article><time datetime="2024-05-07T09:30:00Z">3 days ago</time></article>
+    <article><span class="date">May 8, 2024</span></article>
+    <article><span class="date">whenever</span></article>"""
+    items = rusticsoup.extract_data(html, "article", {"date": {"selector": "time, .date", "type": "datetime"}})
+    assert [item["date"] for item in items] == [
+        datetime(2024, 5, 7, 9, 30, tzinfo=UTC),
+        datetime(2024, 5, 8, tzinfo=UTC),
+        None,
+    ]
@@ -0,0 +1,39 @@
+import rusticsoup
+
+CARDS = "".join(
+    f'<div class="card"><a class="title" href="/p/{i}">Item {i}</a>'
+    f'<img src="/i/{i}.jpg"><span class="price">${i}</span></div>'
+    for i in range(6)
+)
+HTML = f"""<html><body>
+<nav><ul><li><a href="/">Home</a></li><li><a href="/a">A</a></li><li><a href="/b">B</a></li></ul></nav>
+<main id="results">{CARDS}</main>
+<footer><p>a</p><p>b</p><p>c</p></footer>
+</body></html>"""
+
+
+def test_best_candidate_is_the_item_grid():
+    best = rusticsoup.detect_containers(HTML)[0]
+    assert best["selector"] == "#results > div.card"
+    assert best["count"] == 6
+    assert best["fields"] == {
+        "title": "a.title",
+        "title_href": "a.title@href",
+        "img_src": "img@src",
+        "price": "span.price",
+    }
+
+
+def test_suggestions_feed_extract_data():
+    best = rusticsoup.detect_containers(HTML)[0]
+    rows = rusticsoup.extract_data(HTML, best["selector"], best["fields"])
+    assert len(rows) == 6
+    assert rows[2] == {"title": "Item 2", "title_href": "/p/2", "img_src": "/i/2.jpg", "price": "$2"}
+
+
+def test_candidates_ranked_and_limited():
+    candidates = rusticsoup.detect_containers(HTML)
+    assert [c["selector"] for c in candidates] == ["#results > div.card", "html > body > nav > ul > li"]
+    assert candidates[0]["score"] > candidates[1]["score"]
+    assert len(rusticsoup.detect_containers(HTML, limit=1)) == 1
+    assert rusticsoup.detect_containers(HTML, min_items=7) == []
@@ -0,0 +1,65 @@
+import rusticsoup
+
+BEFORE = """<html><body>
+  <h1>Spring   sale</h1>
+  <ul><li>one</li><li>two</li><li>three</li></ul>
+  <p class="price" data-sku="42">$5</p>
+  <!-- build 1 -->
+</body></html>"""
+
+AFTER = """<html><body>
+  <h1>Spring sale</h1>
+  <ul><li>one</li><li>three</li><li>four</li></ul>
+  <p class="price sale">$4</p>
+  <div id="banner">Free shipping</div>
+  <!-- build 2 -->
+</body></html>"""
+
+
+def test_identical_documents_have_no_changes():
+    assert rusticsoup.diff(BEFORE, BEFORE) == []
+    # Whitespace runs and comments do not count by default
+    assert rusticsoup.diff(BEFORE, BEFORE.replace("Spring   sale", "Spring sale").replace("build 1", "x")) == []
+
+
+def test_added_and_removed_elements():
+    changes = rusticsoup.diff(BEFORE, AFTER)
+    removed = [c for c in changes if c["op"] == "removed"]
+    added = [c for c in changes if c["op"] == "added"]
+    assert removed == [{"op": "removed", "path": "html > body > ul > li:nth-of-type(2)", "node": "element", "html": "<li>two</li>"}]
+    assert [(c["path"], c["html"]) for c in added] == [
+        ("html > body > ul > li:nth-of-type(3)", "<li>four</li>"),
+        ("html > body > div", '<div id="banner">Free shipping</div>'),
+    ]
+
+
+def test_text_and_attribute_changes():
+    changed = [c for c in rusticsoup.diff(BEFORE, AFTER) if c["op"] == "changed"]
+    assert changed == [
+        {"op": "changed", "path": "html > body > p", "node": "attribute", "attribute": "class", "old": "price", "new": "price sale"},
+        {"op": "changed", "path": "html > body > p", "node": "attribute", "attribute": "data-sku", "old": "42", "new": None},
+        {"op": "changed", "path": "html > body > p", "node": "text", "attribute": None, "old": "$5", "new": "$4"},
+    ]
+
+
+def test_paths_select_the_changed_element():
+    page = rusticsoup.parse_html(BEFORE)
+    for change in rusticsoup.diff(BEFORE, AFTER):
+        if change["op"] != "added":
+            assert page.select_one(change["path"]) is not None
+
+
+def test_elements_pair_by_id_and_renamed_tags_are_replaced():
+    changes = rusticsoup.diff('<div id="a">x</div><div id="b">y</div>', '<div id="b">z</div>')
+    assert [(c["op"], c["path"]) for c in changes] == [
+        ("removed", "html > body > div:nth-of-type(1)"),
+        ("changed", "html > body > div:nth-of-type(2)"),
+    ]
+    changes = rusticsoup.diff("<p>x</p>", "<section>x</section>")
+    assert [(c["op"], c["node"]) for c in changes] == [("removed", "element"), ("added", "element")]
+
+
+def test_whitespace_significant_when_not_normalized():
+    assert rusticsoup.diff("<p>a  b</p>", "<p>a b</p>") == []
+    changes = rusticsoup.diff("<p>a  b</p>", "<p>a b</p>", normalize_whitespace=False)
+    assert [(c["old"], c["new"]) for c in changes] == [("a  b", "a b")]
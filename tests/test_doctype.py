@@ -0,0 +1,30 @@
+import rusticsoup
+
+
+def test_html5_doctype_is_standards_mode():
+    page = rusticsoup.parse_html("<!DOCTYPE html><p>x</p>")
+    assert page.doctype == {"name": "html", "public_id": "", "system_id": ""}
+    assert page.quirks_mode == "no-quirks"
+
+
+def test_missing_doctype_is_quirks_mode():
+    page = rusticsoup.parse_html("<p>x</p>")
+    assert page.doctype is None
+    assert page.quirks_mode == "quirks"
+
+
+def test_legacy_doctypes():
+    transitional = '<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN"'
+    limited = rusticsoup.parse_html(transitional + ' "http://www.w3.org/TR/html4/loose.dtd"><p>')
+    assert limited.doctype["public_id"] == "-//W3C//DTD HTML 4.01 Transitional//EN"
+    assert limited.quirks_mode == "limited-quirks"
+    assert rusticsoup.parse_html(transitional + "><p>").quirks_mode == "quirks"
+
+
+def test_facade_and_streaming_parser():
+    soup = rusticsoup.RusticSoup("<!doctype HTML><title>t</title>")
+    assert soup.doctype["name"] == "html"
+    assert soup.quirks_mode == "no-quirks"
+    parser = rusticsoup.StreamingParser(max_nodes=100)
+    parser.feed("<p>no doctype</p>")
+    assert parser.close().quirks_mode == "quirks"
@@ -0,0 +1,48 @@
+import pytest
+
+import rusticsoup
+
+HTML = """<html><head><base href="https://shop.example/"></head><body>
+<div class="product"><h2>Widget</h2><a href="/w">more</a></div>
+<div class="product"><h2>Gadget</h2><a href="/g">more</a></div>
+<table id="specs"><tr><th>Size</th><td>M</td></tr><tr><th>Color</th><td>red</td></tr></table>
+</body></html>"""
+
+FIELDS = {"name": "h2", "url": "a@href"}
+
+
+def test_extract_data_accepts_parsed_document():
+    page = rusticsoup.parse_html(HTML)
+    expected = rusticsoup.extract_data(HTML, "div.product", FIELDS)
+    assert rusticsoup.extract_data(page, "div.product", FIELDS) == expected
+    assert expected[0] == {"name": "Widget", "url": "https://shop.example/w"}
+    # The handle is unchanged and can be queried again
+    assert rusticsoup.extract_data(page, "h2", {"t": "h2"}) == [{"t": "Widget"}, {"t": "Gadget"}]
+
+
+def test_extraction_options_apply_to_parsed_document():
+    page = rusticsoup.parse_html(HTML)
+    results, report = rusticsoup.extract_data(page, "div.product", FIELDS, report=True, timeout=5.0)
+    assert len(results) == report.containers == 2
+    dry = rusticsoup.extract_data(page, "div.product", FIELDS, dry_run=True)
+    assert dry == rusticsoup.extract_data(HTML, "div.product", FIELDS, dry_run=True)
+
+
+def test_extract_table_data_accepts_parsed_document():
+    page = rusticsoup.parse_html(HTML)
+    rows = [["Size", "M"], ["Color", "red"]]
+    assert rusticsoup.extract_table_data(page, "#specs") == rows
+    assert rusticsoup.extract_table_data(HTML, "#specs") == rows
+
+
+@pytest.mark.parametrize("option", [
+    {"from_encoding": "utf-8"},
+    {"max_nodes": 10},
+    {"parse_noscript": True},
+    {"preserve_entities": True},
+])
+def test_parsing_options_rejected_with_parsed_document(option):
+    page = rusticsoup.parse_html(HTML)
+    name = next(iter(option))
+    with pytest.raises(ValueError, match=f"'{name}' applies when parsing"):
+        rusticsoup.extract_data(page, "div.product", FIELDS, **option)
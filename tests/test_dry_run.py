@@ -0,0 +1,22 @@
+import rusticsoup
+
+HTML = """
+<div class="p"><h2>One</h2><a href="/1">x</a><li>a</li><li>b</li></div>
+<div class="p"><h2>Two</h2><li>c</li></div>
+<div class="p"><h2></h2></div>
+"""
+
+
+def test_dry_run_counts_and_samples():
+    summary = rusticsoup.extract_data(
+        HTML,
+        "div.p",
+        {"title": "h2", "link": "a@href", "tags": "li@get_all", "meta": {"missing": "span"}},
+        dry_run=True,
+    )
+    assert summary["containers"] == 3
+    fields = summary["fields"]
+    assert fields["title"] == {"matches": 3, "containers_matched": 3, "sample": "One"}
+    assert fields["link"] == {"matches": 1, "containers_matched": 1, "sample": "/1"}
+    assert fields["tags"] == {"matches": 3, "containers_matched": 2, "sample": "a"}
+    assert fields["meta.missing"] == {"matches": 0, "containers_matched": 0, "sample": None}
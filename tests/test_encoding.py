@@ -0,0 +1,163 @@
+import pytest
+import rusticsoup
+
+
+def test_meta_charset_prescan():
+    html = "<html><head><meta charset='shift_jis'></head><body><p>日本語</p></body></html>"
+    soup = rusticsoup.RusticSoup.from_bytes(html.encode("shift_jis"))
+    assert soup.select_one("p").text() == "日本語"
+
+
+def test_meta_http_equiv_prescan():
+    html = (
+        '<meta http-equiv="Content-Type" content="text/html; charset=koi8-r">'
+        "<p>Привет</p>"
+    )
+    soup = rusticsoup.RusticSoup.from_bytes(html.encode("koi8-r"))
+    assert soup.select_one("p").text() == "Привет"
+
+
+def test_utf16_bom():
+    html = "<p>Grüße</p>"
+    for codec in ("utf-16-le", "utf-16-be"):
+        data = "﻿".encode(codec) + html.encode(codec)
+        soup = rusticsoup.RusticSoup.from_bytes(data)
+        assert soup.select_one("p").text() == "Grüße"
+
+
+def test_utf32_bom():
+    html = "<p>Grüße 🦀</p>"
+    for codec in ("utf-32-le", "utf-32-be"):
+        data = "﻿".encode(codec) + html.encode(codec)
+        soup = rusticsoup.RusticSoup.from_bytes(data)
+        assert soup.select_one("p").text() == "Grüße 🦀"
+
+
+def test_statistical_detection_without_declaration():
+    text = "Москва — столица России, крупнейший по численности населения город страны."
+    html = f"<html><body><p>{text}</p></body></html>"
+    soup = rusticsoup.RusticSoup.from_bytes(html.encode("windows-1251"))
+    assert soup.select_one("p").text() == text
+
+
+def test_from_encoding_override():
+    data = "<p>café</p>".encode("latin-1")
+    doc = rusticsoup.parse_html(data, from_encoding="iso-8859-15")
+    assert doc.select_one("p").text() == "café"
+    items = rusticsoup.extract_data(data, "p", {"t": "p"}, from_encoding="latin-1")
+    assert items == [{"t": "café"}]
+
+
+def test_exclude_encodings_skips_meta_declaration():
+    data = "<meta charset='koi8-r'><p>Grüße</p>".encode("utf-8")
+    soup = rusticsoup.RusticSoup(data, exclude_encodings=["koi8-r"])
+    assert soup.select_one("p").text() == "Grüße"
+
+
+def test_unknown_encoding_label():
+    with pytest.raises(rusticsoup.EncodingError):
+        rusticsoup.parse_html(b"<p>x</p>", from_encoding="no-such-codec")
+
+
+def test_original_encoding_reported():
+    doc = rusticsoup.parse_html("<meta charset='shift_jis'><p>日本</p>".encode("shift_jis"))
+    assert doc.original_encoding == "shift_jis"
+    assert doc.encoding_source == "meta"
+    assert 0.0 < doc.encoding_confidence <= 1.0
+
+    soup = rusticsoup.RusticSoup(b"\xef\xbb\xbf<p>x</p>")
+    assert soup.original_encoding == "utf-8"
+    assert soup.encoding_source == "bom"
+
+    assert rusticsoup.parse_html("<p>x</p>").original_encoding is None
+
+
+def test_errors_modes():
+    data = b"<meta charset='utf-8'><p>caf\xc3\xa9 \xff ok</p>"
+    assert rusticsoup.parse_html(data).select_one("p").text() == "café � ok"
+    assert rusticsoup.parse_html(data, errors="ignore").select_one("p").text() == "café ok"
+    with pytest.raises(rusticsoup.EncodingError, match="offset"):
+        rusticsoup.parse_html(data, errors="strict")
+    with pytest.raises(ValueError):
+        rusticsoup.parse_html(data, errors="bogus")
+
+
+def test_transcode_output():
+    doc = rusticsoup.parse_html("<p>café € 日本</p>")
+    out = doc.html(encoding="windows-1252")
+    assert isinstance(out, bytes)
+    assert "café €".encode("windows-1252") in out
+    assert b"&#26085;&#26412;" in out
+
+    soup = rusticsoup.RusticSoup("<p>日本</p>")
+    assert "日本".encode("shift_jis") in soup.encode("shift_jis")
+    assert isinstance(doc.html(), str)
+
+
+def test_content_type_hint_precedence():
+    data = "<meta charset='koi8-r'><p>café</p>".encode("latin-1")
+    doc = rusticsoup.parse_html(data, content_type="text/html; charset=ISO-8859-1")
+    assert doc.select_one("p").text() == "café"
+    assert doc.encoding_source == "header"
+
+    # An explicit override beats the header
+    doc = rusticsoup.parse_html(data, content_type="text/html; charset=utf-8", from_encoding="latin-1")
+    assert doc.encoding_source == "override"
+
+
+def test_fix_mojibake():
+    html = "<p>CafÃ© â€œquotedâ€\x9d Â£5 donâ€™t</p>"
+    doc = rusticsoup.parse_html(html, fix_mojibake=True)
+    assert doc.select_one("p").text() == "Café “quoted” £5 don’t"
+    assert rusticsoup.parse_html(html).select_one("p").text().startswith("CafÃ©")
+
+    clean = "<p>Café à la crème, Ærø</p>"
+    assert rusticsoup.parse_html(clean, fix_mojibake=True).select_one("p").text() == "Café à la crème, Ærø"
+
+    data = "<p>CafÃ©</p>".encode("utf-8")
+    items = rusticsoup.extract_data(data, "p", {"t": "p"}, fix_mojibake=True)
+    assert items == [{"t": "Café"}]
+
+
+def test_detect_encoding():
+    info = rusticsoup.detect_encoding("<meta charset='euc-kr'><p>한국어</p>".encode("euc-kr"))
+    assert info["encoding"] == "euc-kr"
+    assert info["source"] == "meta"
+    assert 0.0 < info["confidence"] <= 1.0
+
+    assert rusticsoup.detect_encoding("é".encode("utf-16"))["source"] == "bom"
+    assert rusticsoup.detect_encoding(b"plain", content_type="text/html; charset=latin1")["encoding"] == "windows-1252"
+
+
+def test_meta_prescan_keeps_first_charset():
+    # The prescan takes charset= only while no charset was found on the element
+    pragma = '<meta http-equiv="content-type" content="text/html; charset=koi8-r" charset="{}"><p>Привет</p>'
+    for label in ["utf-8", "bogus"]:
+        info = rusticsoup.detect_encoding(pragma.format(label).encode("koi8-r"))
+        assert (info["encoding"], info["source"]) == ("koi8-r", "meta")
+
+
+def test_stream_decoder_chunk_boundaries():
+    html = "<html><body>" + "<p>日本語のテキスト</p>" * 200 + "</body></html>"
+    for codec, kwargs in (("utf-8", {}), ("shift_jis", {"content_type": "text/html; charset=shift_jis"})):
+        data = html.encode(codec)
+        decoder = rusticsoup.StreamDecoder(**kwargs)
+        text = "".join(decoder.feed(data[i : i + 7]) for i in range(0, len(data), 7))
+        text += decoder.close()
+        assert text == html
+        assert decoder.original_encoding == codec
+
+
+def test_stream_decoder_utf32_bom():
+    data = "﻿<p>🦀</p>".encode("utf-32-le")
+    decoder = rusticsoup.StreamDecoder()
+    text = "".join(decoder.feed(data[i : i + 3]) for i in range(0, len(data), 3)) + decoder.close()
+    assert text == "<p>🦀</p>"
+    assert decoder.encoding_source == "bom"
+
+
+def test_strict_error_reports_position():
+    with pytest.raises(rusticsoup.EncodingError) as exc:
+        rusticsoup.parse_html(b"<p>ab\xff</p>", from_encoding="utf-8", errors="strict")
+    assert exc.value.position == 5
+    assert exc.value.selector is None
@@ -0,0 +1,35 @@
+import rusticsoup
+
+HTML = """
+<div class="item">
+  <p title="a &amp; b">Price&nbsp;&#36;5 &lt;ok&gt; &copy; 2024</p>
+  <script>if (a && b) {}</script>
+</div>
+<div class="item"><p>R&amp;D</p></div>
+"""
+
+
+def test_html_field_reserializes_by_default():
+    items = rusticsoup.extract_data(HTML, "div.item", {"p": "p@html"})
+    assert items[0]["p"] == '<p title="a &amp; b">Price&nbsp;$5 &lt;ok&gt; © 2024</p>'
+    assert items[1]["p"] == "<p>R&amp;D</p>"
+
+
+def test_preserve_entities_keeps_source_references():
+    items = rusticsoup.extract_data(HTML, "div.item", {"p": "p@html"}, preserve_entities=True)
+    assert items[0]["p"] == '<p title="a &amp; b">Price&nbsp;&#36;5 &lt;ok&gt; &copy; 2024</p>'
+    assert items[1]["p"] == "<p>R&amp;D</p>"
+
+
+def test_preserve_entities_leaves_other_fields_decoded():
+    fields = {"text": "p", "title": "p@title", "nested": {"script": "script@html"}}
+    item = rusticsoup.extract_data(HTML, "div.item", fields, preserve_entities=True)[0]
+    assert item["text"] == "Price\xa0$5 <ok> © 2024"
+    assert item["title"] == "a & b"
+    assert item["nested"]["script"] == "<script>if (a && b) {}</script>"
+
+
+def test_containers_matched_on_decoded_values():
+    html = '<a href="/x?a=1&amp;b=2"><b>&#x41;</b></a>'
+    items = rusticsoup.extract_data(html, 'a[href="/x?a=1&b=2"]', {"b": "b@html"}, preserve_entities=True)
+    assert items == [{"b": "<b>&#x41;</b>"}]
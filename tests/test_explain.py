@@ -0,0 +1,48 @@
+import pytest
+
+import rusticsoup
+
+HTML = """
+<div class="products">
+  <ul class="item-list">
+    <li class="product_card featured"><span class="price">1</span></li>
+    <li class="product_card"><span class="price">2</span></li>
+  </ul>
+</div>
+"""
+
+
+def test_explain_reports_failing_step_and_near_miss():
+    doc = rusticsoup.parse_html(HTML)
+    report = doc.explain("div.products > ul.items li span.price")
+    assert report["matches"] == 0
+    assert report["failed_step"] == 1
+    assert [s["matches"] for s in report["steps"]] == [1, 0]
+    assert report["steps"][1]["compound"] == "ul.items"
+    top = report["suggestions"][0]
+    assert top["selector"] == "div.products > ul.item-list li span.price"
+    assert top["matches"] == 2
+    assert "item-list" in top["reason"]
+
+
+def test_explain_suggests_dropping_overconstraining_class():
+    doc = rusticsoup.parse_html(HTML)
+    report = doc.explain("li.product_card.sale")
+    assert {"selector": "li.product_card", "matches": 2, "reason": "matches without '.sale'"} in report["suggestions"]
+
+
+def test_explain_matching_selector():
+    doc = rusticsoup.parse_html(HTML)
+    report = doc.explain("li.product_card span")
+    assert report["matches"] == 2
+    assert report["failed_step"] is None
+    assert report["suggestions"] == []
+
+
+def test_explain_selector_list_and_errors():
+    doc = rusticsoup.parse_html(HTML)
+    report = doc.explain("section, li.featured")
+    assert report["matches"] == 1
+    assert [alt["matches"] for alt in report["alternatives"]] == [0, 1]
+    with pytest.raises(rusticsoup.SelectorError):
+        doc.explain("li..x")
@@ -0,0 +1,71 @@
+from contextlib import contextmanager
+
+import pytest
+
+import rusticsoup
+
+HTML = """<div class="p"><h2>Widget</h2><a href="/w">more</a></div>
+<div class="p"><h2>Gadget</h2><a href="/g">more</a></div>"""
+
+
+@contextmanager
+def registered(**extractors):
+    """Register `name=extractor` pairs for the duration of a test."""
+    for name, extractor in extractors.items():
+        rusticsoup.register_extractor(name, extractor)
+    try:
+        yield
+    finally:
+        for name in extractors:
+            rusticsoup.unregister_extractor(name)
+
+
+def test_compiled_rules_run_with_their_container():
+    shop = rusticsoup.compile_selectors({"name": "h2", "url": "a@href"}, container="div.p")
+    with registered(shop_detail=shop):
+        assert rusticsoup.registered_extractors() == ["shop_detail"]
+        assert rusticsoup.extract_with("shop_detail", HTML) == rusticsoup.extract_data(HTML, "div.p", shop)
+        columns = rusticsoup.extract_with("shop_detail", HTML, base_url="https://shop.test/", output="columns")
+        assert columns["url"] == ["https://shop.test/w", "https://shop.test/g"]
+    assert rusticsoup.registered_extractors() == []
+
+
+def test_callables_get_html_and_keyword_arguments():
+    def titles(html, limit=None):
+        return [h2.text() for h2 in rusticsoup.parse_html(html).select("h2")][:limit]
+
+    with registered(titles=titles):
+        assert rusticsoup.extract_with("titles", HTML) == ["Widget", "Gadget"]
+        assert rusticsoup.extract_with("titles", HTML, limit=1) == ["Widget"]
+
+
+def test_rules_file_paths_are_loaded(tmp_path):
+    path = tmp_path / "shop.json"
+    path.write_text('{"container": "div.p", "fields": {"name": "h2"}}')
+    with registered(by_path=path, by_str=str(path)):
+        assert rusticsoup.extract_with("by_path", HTML) == [{"name": "Widget"}, {"name": "Gadget"}]
+        assert rusticsoup.extract_with("by_str", HTML) == [{"name": "Widget"}, {"name": "Gadget"}]
+
+
+def test_registering_again_replaces():
+    with registered(site=lambda html: "first"):
+        rusticsoup.register_extractor("site", lambda html: "second")
+        assert rusticsoup.extract_with("site", HTML) == "second"
+
+
+def test_unknown_extractor_lists_registered_names():
+    with registered(alpha=len, beta=len):
+        with pytest.raises(ValueError, match="Unknown extractor: 'gamma'. Registered: alpha, beta"):
+            rusticsoup.extract_with("gamma", HTML)
+    assert rusticsoup.unregister_extractor("alpha") is False
+
+
+@pytest.mark.parametrize("name, extractor, error, message", [
+    ("shop", rusticsoup.compile_selectors({"name": "h2"}), ValueError, "has no container selector"),
+    ("shop", 42, TypeError, "must be CompiledSelectors, a path to a rules file or a callable"),
+    ("", len, ValueError, "must not be empty"),
+])
+def test_invalid_registrations_raise(name, extractor, error, message):
+    with pytest.raises(error, match=message):
+        rusticsoup.register_extractor(name, extractor)
+    assert rusticsoup.registered_extractors() == []
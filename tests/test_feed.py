@@ -0,0 +1,89 @@
+import pytest
+
+import rusticsoup
+
+RSS = """<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/"
+     xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:atom="http://www.w3.org/2005/Atom">
+<channel>
+  <title>Widgets&nbsp;Weekly</title>
+  <atom:link href="https://example.com/feed" rel="self"/>
+  <link>https://example.com/</link>
+  <item>
+    <title>AT&T buys widgets</title>
+    <link>https://example.com/a</link>
+    <guid isPermaLink="false">a-1</guid>
+    <pubDate>Tue, 07 May 2024 09:30:00 GMT</pubDate>
+    <dc:creator>Ann</dc:creator>
+    <description>Short &eacute;t&eacute;</description>
+    <content:encoded><![CDATA[<p>Full &nbsp; text</p>]]></content:encoded>
+  </item>
+  <item><title>Odd date</title><pubDate>sometime</pubDate></item>
+</channel>
+</rss>"""
+
+ATOM = """<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom feed</title>
+  <link rel="self" href="https://example.com/atom"/>
+  <link href="https://example.com/"/>
+  <entry>
+    <title type="html">First</title>
+    <link rel="alternate" href="https://example.com/1"/>
+    <id>urn:1</id>
+    <published>2024-05-07T09:30:00Z</published>
+    <updated>2024-05-08T00:00:00Z</updated>
+    <author><name>Bo</name></author>
+    <summary>Sum</summary>
+    <content type="html">Body</content>
+  </entry>
+</feed>"""
+
+
+def test_rss():
+    feed = rusticsoup.parse_feed(RSS.encode("utf-8"))
+    assert feed["type"] == "rss"
+    assert feed["title"] == "Widgets Weekly"
+    assert feed["link"] == "https://example.com/"
+    first, second = feed["entries"]
+    assert first == {
+        "title": "AT&T buys widgets",
+        "link": "https://example.com/a",
+        "id": "a-1",
+        "published": "2024-05-07T09:30:00+00:00",
+        "updated": None,
+        "author": "Ann",
+        "summary": "Short été",
+        "content": "<p>Full &nbsp; text</p>",
+    }
+    assert second["published"] == "sometime"
+
+
+def test_atom():
+    feed = rusticsoup.parse_feed(ATOM)
+    assert feed["type"] == "atom"
+    assert feed["link"] == "https://example.com/"
+    entry = feed["entries"][0]
+    assert entry["link"] == "https://example.com/1"
+    assert entry["published"] == "2024-05-07T09:30:00Z"
+    assert entry["updated"] == "2024-05-08T00:00:00Z"
+    assert entry["author"] == "Bo"
+    assert (entry["summary"], entry["content"]) == ("Sum", "Body")
+
+
+def test_rss_1_0():
+    rdf = """<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/"
+         xmlns:dc="http://purl.org/dc/elements/1.1/">
+      <channel rdf:about="https://example.com/"><title>RDF</title><link>https://example.com/</link></channel>
+      <item rdf:about="https://example.com/x"><title>X</title><link>https://example.com/x</link>
+        <dc:date>2024-01-01</dc:date></item>
+    </rdf:RDF>"""
+    feed = rusticsoup.parse_feed(rdf)
+    entry = feed["entries"][0]
+    assert feed["title"] == "RDF"
+    assert entry["id"] == "https://example.com/x"
+    assert entry["published"] == "2024-01-01"
+
+
+def test_not_a_feed():
+    with pytest.raises(rusticsoup.HTMLParseError):
+        rusticsoup.parse_feed("<html></html>")
@@ -0,0 +1,63 @@
+import rusticsoup
+
+HTML = """
+<form id="login" action="/session" method="post">
+  <input type="hidden" name="csrf" value="t0k3n">
+  <input name="user" required>
+  <input type="password" name="pass">
+  <input type="checkbox" name="remember" checked>
+  <input type="checkbox" name="newsletter" value="yes">
+  <input type="radio" name="plan" value="free">
+  <input type="radio" name="plan" value="pro" checked>
+  <select name="lang">
+    <option value="en">English</option>
+    <option>Deutsch</option>
+  </select>
+  <select name="tags" multiple>
+    <option value="a" selected>A</option><option value="b" selected>B</option><option value="c">C</option>
+  </select>
+  <textarea name="bio">
+Hello</textarea>
+  <input name="old" value="x" disabled>
+  <button type="submit" name="go" value="1">Sign in</button>
+</form>
+<input name="outside" value="linked" form="login">
+<form><input name="q"></form>
+"""
+
+
+def test_form_attributes():
+    login, search = rusticsoup.extract_forms(HTML, base_url="https://example.com/account/")
+    assert login["action"] == "https://example.com/session"
+    assert login["method"] == "POST"
+    assert login["enctype"] == "application/x-www-form-urlencoded"
+    assert login["id"] == "login"
+    # An empty action submits to the page itself
+    assert search["action"] == "https://example.com/account/"
+    assert search["method"] == "GET"
+
+
+def test_default_submission_data():
+    login = rusticsoup.extract_forms(HTML)[0]
+    assert login["data"] == {
+        "csrf": "t0k3n",
+        "user": "",
+        "pass": "",
+        "remember": "on",
+        "plan": "pro",
+        "lang": "en",
+        "tags": ["a", "b"],
+        "bio": "Hello",
+        "outside": "linked",
+    }
+
+
+def test_field_descriptions():
+    fields = {f["name"]: f for f in rusticsoup.extract_forms(HTML)[0]["fields"]}
+    assert fields["user"]["type"] == "text" and fields["user"]["required"] is True
+    assert fields["newsletter"]["checked"] is False
+    assert fields["old"]["disabled"] is True
+    assert fields["go"]["type"] == "submit"
+    assert [o["text"] for o in fields["lang"]["options"]] == ["English", "Deutsch"]
+    assert fields["lang"]["options"][1]["value"] == "Deutsch"
+    assert fields["tags"]["type"] == "select-multiple" and fields["tags"]["value"] == ["a", "b"]
@@ -0,0 +1,42 @@
+import pytest
+import rusticsoup
+
+
+@pytest.mark.parametrize("context, snippet, selector, count", [
+    ("tr", "<td>a</td><td>b</td>", "td", 2),
+    ("table", "<tr><td>1</td></tr><tr><td>2</td></tr>", "tr", 2),
+    ("tbody", "<tr><td>1</td></tr>", "td", 1),
+    ("ul", "<li>x<li>y", "li", 2),
+    ("select", "<option>a<option selected>b", "option[selected]", 1),
+    ("TR", "<td>a</td>", "td", 1),
+])
+def test_context_keeps_content(context, snippet, selector, count):
+    assert len(rusticsoup.parse_fragment(snippet, context=context).select(selector)) == count
+
+
+def test_body_context_drops_table_parts():
+    fragment = rusticsoup.parse_fragment("<td>a</td><td>b</td>")
+    assert fragment.select("td") == []
+    assert fragment.text() == "ab"
+
+
+def test_html_is_the_fragment_itself():
+    fragment = rusticsoup.parse_fragment("<td>a</td><td class=x>b</td>", context="tr")
+    assert fragment.html() == '<td>a</td><td class="x">b</td>'
+    assert fragment.select_one("td.x").text() == "b"
+
+
+def test_bytes_input():
+    fragment = rusticsoup.parse_fragment("<li>café</li>".encode("latin-1"), context="ol", from_encoding="latin-1")
+    assert fragment.select_one("li").text() == "café"
+
+
+@pytest.mark.parametrize("context", ["", "td>", "a b"])
+def test_invalid_context(context):
+    with pytest.raises(ValueError, match="Invalid context"):
+        rusticsoup.parse_fragment("<td>a</td>", context=context)
+
+
+def test_limits():
+    with pytest.raises(rusticsoup.LimitExceededError):
+        rusticsoup.parse_fragment("<td>a</td>" * 100, context="tr", max_nodes=10)
@@ -0,0 +1,49 @@
+import rusticsoup
+
+BASE = "https://example.com/p/"
+
+HTML = """
+<img src="a.jpg" alt="Plain" width="640" height="50%">
+<img src="data:image/gif;base64,R0lGODlhAQABAAAAACw=" data-src="/real.jpg" alt="Lazy">
+<img src="small.jpg" srcset="small.jpg 320w, https://cdn.example.com/img,v=2/large.jpg 1280w, medium.jpg 800w">
+<img src="x1.png" srcset="x1.png, x2.png 2x" loading="lazy">
+<picture>
+  <source srcset="wide.webp 1600w" type="image/webp">
+  <img src="fallback.jpg" alt="Pic">
+</picture>
+<img alt="no source">
+"""
+
+
+def test_images():
+    images = rusticsoup.extract_images(HTML, base_url=BASE)
+    assert len(images) == 5
+    plain, lazy, srcset, density, picture = images
+
+    assert plain == {
+        "src": BASE + "a.jpg", "best": BASE + "a.jpg", "alt": "Plain",
+        "width": 640, "height": None, "lazy": False, "srcset": [],
+    }
+    assert lazy["src"] == "https://example.com/real.jpg"
+    assert lazy["lazy"] is True
+    # Commas inside a candidate URL do not split it
+    assert srcset["best"] == "https://cdn.example.com/img,v=2/large.jpg"
+    assert density["best"] == BASE + "x2.png"
+    assert density["lazy"] is True
+    assert picture["best"] == BASE + "wide.webp"
+    assert picture["src"] == BASE + "fallback.jpg"
+
+
+def test_lazy_srcset_without_base():
+    images = rusticsoup.extract_images('<img data-srcset="/a.jpg 1x, /b.jpg 3x">')
+    assert images[0]["src"] is None
+    assert images[0]["best"] == "/b.jpg"
+
+
+def test_srcset_candidates_and_width():
+    images = rusticsoup.extract_images(HTML, base_url=BASE, width=500)
+    srcset = images[2]
+    assert srcset["srcset"][0] == {"url": BASE + "small.jpg", "width": 320, "density": 1.0}
+    assert srcset["best"] == BASE + "medium.jpg"
+    # Density-only srcsets ignore the display width
+    assert images[3]["best"] == BASE + "x2.png"
@@ -0,0 +1,57 @@
+import rusticsoup
+
+HTML = """
+<html><head>
+<script type="application/ld+json">
+{
+  "@context": "https://schema.org",
+  "@type": "Product",
+  "name": "Widget",
+  "description": "Line one
+line two",
+  "offers": {"@type": "Offer", "price": "9.99", "url": "https://example.com/w"},
+}
+</script>
+<script type="application/ld+json">
+<!--
+// Added by the SEO plugin
+{"@context": "https://schema.org", "@graph": [
+  {"@type": "Organization", "name": "Acme"}, /* logo pending */
+  {"@type": ["WebPage", "ItemPage"], "name": "Widget page"},
+]}
+-->
+</script>
+<script type="application/ld+json">{ not json at all </script>
+<script type="text/javascript">var x = {"@type": "Product"};</script>
+</head><body></body></html>
+"""
+
+
+def test_blocks_are_parsed_leniently():
+    blocks = rusticsoup.extract_json_ld(HTML)
+    assert len(blocks) == 2
+    product = blocks[0]
+    assert product["name"] == "Widget"
+    assert product["description"] == "Line one\nline two"
+    assert product["offers"]["price"] == "9.99"
+    assert [node["name"] for node in blocks[1]["@graph"]] == ["Acme", "Widget page"]
+
+
+def test_filter_by_type():
+    assert [n["name"] for n in rusticsoup.extract_json_ld(HTML, type="Product")] == ["Widget"]
+    # @graph entries and list-valued @type are searched
+    assert [n["name"] for n in rusticsoup.extract_json_ld(HTML, type="ItemPage")] == ["Widget page"]
+    names = [n["name"] for n in rusticsoup.extract_json_ld(HTML, type=["Organization", "Product"])]
+    assert names == ["Widget", "Acme"]
+    assert rusticsoup.extract_json_ld(HTML, type="Recipe") == []
+
+
+def test_type_matches_full_iri():
+    html = '<script type="application/ld+json">{"@type": "http://schema.org/Product", "name": "X"}</script>'
+    assert rusticsoup.extract_json_ld(html, type="Product")[0]["name"] == "X"
+
+
+def test_bytes_input_and_strings_with_comment_markers():
+    html = '<script type="application/ld+json">{"url": "https://a.com//b", "note": "/* keep */"}</script>'
+    block = rusticsoup.extract_json_ld(html.encode("utf-8"))[0]
+    assert block == {"url": "https://a.com//b", "note": "/* keep */"}
@@ -0,0 +1,90 @@
+import datetime
+import decimal
+import io
+import json
+
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Widget {i} \"deluxe\" é</h2><b>$1,299.0{i}</b><time>2024-03-0{i + 1}</time>"
+    f"<i>red</i><i>size\t{i}</i></div>"
+    for i in range(3)
+)
+FIELDS = {
+    "name": "h2",
+    "price": {"selector": "b", "type": "price"},
+    "date": {"selector": "time", "type": "datetime"},
+    "tags": "i@get_all",
+    "seller": {"name": "em"},
+}
+
+
+def test_jsonl_file_appends_items(tmp_path):
+    path = tmp_path / "items.jsonl"
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=path) == 3
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=str(path)) == 3
+    lines = path.read_text(encoding="utf-8").splitlines()
+    assert len(lines) == 6
+    assert lines[0].startswith('{"name":"Widget 0 \\"deluxe\\" é","price":{"amount":1299.00,"currency":"USD"}')
+    item = json.loads(lines[0], parse_float=decimal.Decimal)
+    assert item == {
+        "name": 'Widget 0 "deluxe" é',
+        "price": {"amount": decimal.Decimal("1299.00"), "currency": "USD"},
+        "date": "2024-03-01T00:00:00+00:00",
+        "tags": ["red", "size\t0"],
+        "seller": {"name": ""},
+    }
+
+
+def test_items_match_records(tmp_path):
+    path = tmp_path / "items.jsonl"
+    fields = {"name": "h2", "tags": "i@get_all", "seller": {"name": "em"}}
+    rusticsoup.extract_data(HTML, "div.p", fields, sink=path)
+    records = [json.loads(line) for line in path.read_text(encoding="utf-8").splitlines()]
+    assert records == rusticsoup.extract_data(HTML, "div.p", fields)
+
+
+def test_file_like_sinks():
+    text, binary = io.StringIO(), io.BytesIO()
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=text) == 3
+    assert rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=binary) == 3
+    assert binary.getvalue().decode("utf-8") == text.getvalue()
+    assert text.getvalue().count("\n") == 3
+
+    class Collector:
+        def __init__(self):
+            self.lines = []
+
+        def write(self, line):
+            self.lines.append(line)
+
+    collector = Collector()
+    rusticsoup.extract_data(HTML, "div.p", {"name": "h2"}, sink=collector)
+    assert collector.lines == ['{"name":"Widget 0 \\"deluxe\\" é"}\n', '{"name":"Widget 1 \\"deluxe\\" é"}\n', '{"name":"Widget 2 \\"deluxe\\" é"}\n']
+
+
+def test_report_with_jsonl_sink():
+    out = io.StringIO()
+    count, report = rusticsoup.extract_data(HTML, "div.p", {"bad": "a[", "name": "h2"}, sink=out, report=True)
+    assert count == report.containers == 3 and len(report.failed) == 3
+    assert json.loads(out.getvalue().splitlines()[0]) == {"bad": "", "name": 'Widget 0 "deluxe" é'}
+
+
+def test_sink_argument_errors(tmp_path):
+    with pytest.raises(ValueError, match="Use a .csv or .jsonl path"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=tmp_path / "items.json")
+    with pytest.raises(ValueError, match="CSV sink"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=io.StringIO(), columns=["name"])
+    with pytest.raises(TypeError, match="file-like"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=3)
+    with pytest.raises(ValueError, match="dry_run"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, sink=io.StringIO(), dry_run=True)
+
+
+def test_date_values_are_iso_strings():
+    out = io.StringIO()
+    rusticsoup.extract_data("<p><time>2024-03-01 10:30</time></p>", "p", {"at": {"selector": "time", "type": "datetime"}}, sink=out)
+    at = json.loads(out.getvalue())["at"]
+    assert datetime.datetime.fromisoformat(at) == datetime.datetime(2024, 3, 1, 10, 30, tzinfo=datetime.timezone.utc)
@@ -0,0 +1,45 @@
+import rusticsoup
+
+ENGLISH = "<p>The committee said that it is ready to vote on the proposal for the new library, and this is good news for the town.</p>"
+GERMAN = "<p>Der Ausschuss sagte, dass er nicht bereit ist, über den Vorschlag für die neue Bibliothek abzustimmen, und das ist eine Überraschung.</p>"
+
+
+def test_declared_and_detected_agree():
+    result = rusticsoup.detect_language(f'<html lang="en_us"><body>{ENGLISH}</body></html>')
+    assert result["language"] == "en-US"
+    assert result["declared"] == "en-US"
+    assert result["detected"] == "en"
+    assert result["confidence"] > 0.9
+
+
+def test_text_only():
+    result = rusticsoup.detect_language(GERMAN)
+    assert (result["language"], result["declared"]) == ("de", None)
+    assert result["confidence"] > 0.5
+
+
+def test_text_overrides_wrong_declaration():
+    result = rusticsoup.detect_language(f'<html lang="en"><body>{GERMAN}{GERMAN}</body></html>')
+    assert result["language"] == "de"
+    assert result["confidence"] < rusticsoup.detect_language(GERMAN + GERMAN)["confidence"]
+
+
+def test_meta_declarations():
+    page = '<html><head><meta http-equiv="Content-Language" content="fr-ca, en"></head><body></body></html>'
+    assert rusticsoup.detect_language(page) == {
+        "language": "fr-CA", "confidence": 0.7, "declared": "fr-CA", "detected": None,
+    }
+    og = '<html><head><meta property="og:locale" content="pt_BR"></head></html>'
+    assert rusticsoup.detect_language(og)["language"] == "pt-BR"
+
+
+def test_scripts():
+    assert rusticsoup.detect_language("<p>これは日本語の文章です。東京は大きい都市です。</p>")["language"] == "ja"
+    assert rusticsoup.detect_language("<p>这是一个中文句子，用来测试语言检测功能是否正常。</p>")["language"] == "zh"
+    assert rusticsoup.detect_language("<p>Это предложение написано на русском языке для проверки.</p>")["language"] == "ru"
+    assert rusticsoup.detect_language("<p>한국어 문장으로 언어 감지 기능을 시험합니다.</p>")["language"] == "ko"
+
+
+def test_nothing_to_go_on():
+    result = rusticsoup.detect_language("<script>var x = 1;</script>")
+    assert result == {"language": None, "confidence": 0.0, "declared": None, "detected": None}
@@ -0,0 +1,62 @@
+from concurrent.futures import ThreadPoolExecutor
+
+import rusticsoup
+
+HTML = """
+<ul>
+  <li class="item" id="a" data-sku="1">First <b>one</b></li>
+  <li class="item" id="b" data-sku="2">Second</li>
+</ul>
+"""
+
+
+def test_fields_read_on_access_match_the_document():
+    page = rusticsoup.parse_html(HTML)
+    first, second = page.select("li.item")
+    assert first.attr("data-sku") == "1"
+    assert first.text() == "First one"
+    assert first.html() == '<li class="item" id="a" data-sku="1">First <b>one</b></li>'
+    assert first.attrs() == {"class": "item", "id": "a", "data-sku": "1"}
+    assert second.tag() == "li"
+    assert second.has_class("item") and second.id() == "b"
+    assert repr(second) == "<Element 'li' id='b'>"
+
+
+def test_elements_outlive_their_document():
+    items = rusticsoup.parse_html(HTML).select("li")
+    assert [item.text() for item in items] == ["First one", "Second"]
+
+
+def test_edits_after_selecting_leave_elements_unchanged():
+    page = rusticsoup.parse_html(HTML)
+    first = page.select_one("li#a")
+    page.replace_with("li#a", "<li id=a>Replaced</li>")
+    page.remove("b")
+    assert first.text() == "First one"
+    assert first.select_one("b").text() == "one"
+    assert page.select_one("li#a").text() == "Replaced"
+
+
+def test_nested_selection_and_xpath_return_handles():
+    page = rusticsoup.parse_html(HTML)
+    first = page.select_one("li")
+    assert [b.html() for b in first.select("b")] == ["<b>one</b>"]
+    assert [elem.text() for elem in page.xpath("//li[@data-sku='2']")] == ["Second"]
+
+
+def test_xml_elements_serialize_as_xml():
+    doc = rusticsoup.parse_xml("<feed><Entry id='1'><Title>Hi</Title></Entry></feed>")
+    entry = doc.select_one("Entry")
+    assert entry.html() == '<Entry id="1"><Title>Hi</Title></Entry>'
+    assert entry.select_one("Title").text() == "Hi"
+
+
+
+def test_elements_can_be_read_from_a_worker_thread():
+    items = rusticsoup.parse_html(HTML).select("li")
+    with ThreadPoolExecutor(max_workers=2) as pool:
+        read = list(pool.map(lambda item: (item.text(), item.attrs()), items))
+    assert read == [
+        ("First one", {"class": "item", "id": "a", "data-sku": "1"}),
+        ("Second", {"class": "item", "id": "b", "data-sku": "2"}),
+    ]
@@ -0,0 +1,52 @@
+import pytest
+
+import rusticsoup
+
+NESTED = "<div>" * 20000 + "x" + "</div>" * 20000
+
+
+def test_parse_timeout_raises():
+    with pytest.raises(rusticsoup.ParseTimeoutError, match="Parsing exceeded"):
+        rusticsoup.parse_html(NESTED, timeout=1e-6)
+
+
+def test_extract_data_timeout_is_rusticsoup_error():
+    with pytest.raises(rusticsoup.RusticSoupError):
+        rusticsoup.extract_data(NESTED, "div", {"t": "span"}, timeout=1e-6)
+
+
+def test_generous_timeout_parses_normally():
+    doc = rusticsoup.parse_html("<ul><li>a</li><li>b</li></ul>", timeout=5)
+    assert [li.text() for li in doc.select("li")] == ["a", "b"]
+    assert doc.select_one("li").text() == "a"
+    soup = rusticsoup.RusticSoup(b"<p>x</p>", timeout=5)
+    assert soup.select_one("p").text() == "x"
+
+
+@pytest.mark.parametrize("timeout", [0, -1, float("nan")])
+def test_invalid_timeout(timeout):
+    with pytest.raises(ValueError):
+        rusticsoup.parse_html("<p>x</p>", timeout=timeout)
+
+
+def test_max_size():
+    with pytest.raises(rusticsoup.LimitExceededError, match="max_size"):
+        rusticsoup.parse_html("<p>" + "x" * 100 + "</p>", max_size=50)
+    with pytest.raises(rusticsoup.LimitExceededError):
+        rusticsoup.RusticSoup.from_bytes(b"<p>" + b"x" * 100, max_size=50)
+
+
+def test_max_depth():
+    deep = "<div>" * 600 + "x"
+    with pytest.raises(rusticsoup.LimitExceededError, match="max_depth"):
+        rusticsoup.parse_html(deep, max_depth=100)
+    # html > body > 600 divs
+    assert len(rusticsoup.parse_html(deep, max_depth=602).select("div")) == 600
+
+
+def test_max_nodes():
+    page = "<ul>" + "<li>item</li>" * 5000 + "</ul>"
+    with pytest.raises(rusticsoup.LimitExceededError, match="max_nodes"):
+        rusticsoup.extract_data(page, "li", {"t": "li"}, max_nodes=1000)
+    assert len(rusticsoup.extract_all(page, "li")) == 5000
+    assert len(rusticsoup.parse_html(page, max_nodes=20000).select("li")) == 5000
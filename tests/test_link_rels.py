@@ -0,0 +1,47 @@
+import pytest
+
+import rusticsoup
+
+PAGE = """<html><head>
+<link rel="canonical" href="/shoes">
+<link rel="alternate" hreflang="de-AT" href="https://example.at/schuhe">
+<link rel="alternate" hreflang="x-default" href="/shoes">
+<link rel="alternate" hreflang="de-AT" href="/ignored">
+<link rel="alternate" type="application/rss+xml" href="/feed">
+<link rel="Next" href="?page=3">
+<link rel="amphtml" href="/amp/shoes">
+</head><body><a rel="prev" href="?page=1">Previous</a></body></html>"""
+
+
+def test_link_rels():
+    rels = rusticsoup.extract_link_rels(PAGE, base_url="https://example.com/shoes?page=2")
+    assert rels == {
+        "canonical": "https://example.com/shoes",
+        "alternates": {"de-AT": "https://example.at/schuhe", "x-default": "https://example.com/shoes"},
+        "next": "https://example.com/shoes?page=3",
+        "prev": "https://example.com/shoes?page=1",
+        "amphtml": "https://example.com/amp/shoes",
+        "amp": False,
+    }
+
+
+def test_missing_rels_and_unresolvable_urls():
+    rels = rusticsoup.extract_link_rels('<link rel="canonical" href="/x">')
+    assert rels == {"canonical": "/x", "alternates": {}, "next": None, "prev": None, "amphtml": None, "amp": False}
+
+
+def test_invalid_base_url():
+    with pytest.raises(ValueError):
+        rusticsoup.extract_link_rels(PAGE, base_url="not a url")
+
+
+@pytest.mark.parametrize("attr", ["amp", "\u26a1", "AMP", "amp4email"])
+def test_amp_documents(attr):
+    html = f'<!doctype html><html {attr} lang="en"><head><link rel="canonical" href="/shoes"></head></html>'
+    rels = rusticsoup.extract_link_rels(html, base_url="https://example.com/amp/shoes")
+    assert rels["amp"] is True
+    assert rels["canonical"] == "https://example.com/shoes"
+
+
+def test_amp_attribute_elsewhere_is_ignored():
+    assert rusticsoup.extract_link_rels('<html><body amp><div amp></div></body></html>')["amp"] is False
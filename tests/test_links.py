@@ -0,0 +1,50 @@
+import pytest
+
+import rusticsoup
+
+HTML = """
+<html><head><base href="/shop/"></head><body>
+<a href="item/1">First
+   item</a>
+<a href="https://other.com/x" rel="nofollow noopener">Elsewhere</a>
+<a href="../about" rel="Next">About</a>
+<a href="javascript:void(0)">JS</a>
+<a href="">Empty</a>
+<map><area href="/map" alt="map"></map>
+</body></html>
+"""
+
+
+def test_links_are_resolved_against_base_href():
+    links = rusticsoup.extract_links(HTML, base_url="https://example.com/index.html")
+    assert [l["url"] for l in links] == [
+        "https://example.com/shop/item/1",
+        "https://other.com/x",
+        "https://example.com/about",
+        "https://example.com/map",
+    ]
+    first = links[0]
+    assert first["href"] == "item/1"
+    assert first["text"] == "First item"
+    assert first["rel"] == [] and first["nofollow"] is False
+    assert links[1]["rel"] == ["nofollow", "noopener"] and links[1]["nofollow"] is True
+
+
+def test_rel_filter_and_internal_only():
+    base = "https://example.com/"
+    assert [l["text"] for l in rusticsoup.extract_links(HTML, base_url=base, rel="next")] == ["About"]
+    internal = rusticsoup.extract_links(HTML, base_url=base, internal_only=True)
+    assert all(l["url"].startswith("https://example.com/") for l in internal)
+    assert len(internal) == 3
+
+
+def test_without_base_relative_urls_stay_raw():
+    links = rusticsoup.extract_links('<a href="/a">a</a><a href="https://b.com">b</a>')
+    assert [l["url"] for l in links] == ["/a", "https://b.com/"]
+    with pytest.raises(ValueError):
+        rusticsoup.extract_links('<a href="/a">a</a>', internal_only=True)
+
+
+def test_page_level_nofollow():
+    html = '<meta name="robots" content="index, nofollow"><a href="/a">a</a>'
+    assert rusticsoup.extract_links(html, base_url="https://e.com")[0]["nofollow"] is True
@@ -0,0 +1,51 @@
+import logging
+
+import rusticsoup
+
+
+class _Collect(logging.Handler):
+    def __init__(self):
+        super().__init__(logging.DEBUG)
+        self.records = []
+
+    def emit(self, record):
+        self.records.append(record)
+
+
+def _capture():
+    logger = logging.getLogger("rusticsoup")
+    logger.setLevel(logging.DEBUG)
+    handler = _Collect()
+    logger.addHandler(handler)
+    return logger, handler
+
+
+def test_events_forwarded_when_enabled():
+    logger, handler = _capture()
+    try:
+        rusticsoup.configure_logging("DEBUG")
+        rusticsoup.extract_data("<div class='x'><p>a</p></p></div>", "div.x", {"t": "p"})
+        messages = [r.getMessage() for r in handler.records]
+        assert any(m.startswith("parse start") for m in messages)
+        assert any(m.startswith("parse finished") for m in messages)
+        assert any(m.startswith("extract_data: 1 items") for m in messages)
+        assert any(r.levelno == logging.WARNING for r in handler.records)
+    finally:
+        rusticsoup.configure_logging(None)
+        logger.removeHandler(handler)
+
+
+def test_level_threshold_and_disable():
+    logger, handler = _capture()
+    try:
+        rusticsoup.configure_logging(logging.INFO)
+        rusticsoup.parse_html("<p>x</p>").select("p.logged-threshold")
+        assert all(r.levelno >= logging.INFO for r in handler.records)
+
+        rusticsoup.configure_logging(None)
+        handler.records.clear()
+        rusticsoup.extract_data("<p>x</p>", "p", {"t": "p"})
+        assert handler.records == []
+    finally:
+        rusticsoup.configure_logging(None)
+        logger.removeHandler(handler)
@@ -0,0 +1,56 @@
+import pytest
+
+import rusticsoup
+
+HTML = """
+<div class="product featured">
+  <span class="badge">New</span>
+  <h2> Widget
+     Pro </h2>
+  <p class="price">Price: $5 USD</p>
+  <a href="/p/42?ref=home">More</a>
+</div>
+<div class="product">
+  <h2>Gadget</h2>
+  <p class="price">Price: $7 USD</p>
+  <a href="/p/7?ref=list">More</a>
+</div>
+<div class="product"><h2>No link</h2><p class="price">Price: $1 USD</p></div>
+<section class="product"><h2>Wrong tag</h2></section>
+"""
+
+TEMPLATE = """
+<div class="product">
+  <h2>{{title}}</h2>
+  <p class="price">Price: {{price}} USD</p>
+  <a href="/p/{{id}}?ref={{ref}}">More</a>
+</div>
+"""
+
+
+def test_binds_placeholders_per_matching_element():
+    assert rusticsoup.match_template(HTML, TEMPLATE) == [
+        {"title": "Widget Pro", "price": "$5", "id": "42", "ref": "home"},
+        {"title": "Gadget", "price": "$7", "id": "7", "ref": "list"},
+    ]
+
+
+def test_literal_text_and_attributes_must_match():
+    assert rusticsoup.match_template(HTML, '<div class="product"><a href="/p/{{id}}?ref=home">More</a></div>') == [{"id": "42"}]
+    assert rusticsoup.match_template(HTML, '<div class="product"><a>Less</a></div>') == []
+    assert rusticsoup.match_template(HTML, '<div class="featured product"><h2>{{t}}</h2></div>') == [{"t": "Widget Pro"}]
+
+
+def test_children_match_in_order():
+    # Extra children are skipped, but the template's order must hold
+    assert len(rusticsoup.match_template(HTML, '<div><h2>{{t}}</h2><a>More</a></div>')) == 2
+    assert rusticsoup.match_template(HTML, '<div><a>More</a><h2>{{t}}</h2></div>') == []
+
+
+def test_invalid_templates():
+    with pytest.raises(ValueError, match="one root element"):
+        rusticsoup.match_template(HTML, "<h2>{{a}}</h2><p>{{b}}</p>")
+    with pytest.raises(ValueError, match="placeholder"):
+        rusticsoup.match_template(HTML, "<p>{{a}} and {{a}}</p>")
+    with pytest.raises(ValueError, match="placeholder"):
+        rusticsoup.match_template(HTML, "<p>{{ }}</p>")
@@ -0,0 +1,44 @@
+import rusticsoup
+
+HTML = """
+<html><head>
+<meta charset="UTF-8">
+<title>  Widget
+  Deluxe | Acme </title>
+<meta name="description" content="The best widget">
+<meta name="keywords" content="widget, gadget, ,tools">
+<meta name="robots" content="NoIndex, nofollow">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<link rel="stylesheet" href="/s.css">
+<link rel="Canonical" href=" https://example.com/widget ">
+<meta name="author" content="Ann">
+<meta property="og:title" content="Widget">
+<meta http-equiv="X-UA-Compatible" content="IE=edge">
+</head></html>
+"""
+
+
+def test_metadata_fields():
+    meta = rusticsoup.get_metadata(HTML)
+    assert meta["title"] == "Widget Deluxe | Acme"
+    assert meta["description"] == "The best widget"
+    assert meta["keywords"] == ["widget", "gadget", "tools"]
+    assert meta["robots"] == ["noindex", "nofollow"]
+    assert meta["viewport"] == "width=device-width, initial-scale=1"
+    assert meta["canonical"] == "https://example.com/widget"
+    assert meta["charset"] == "utf-8"
+    assert meta["meta"] == {"author": "Ann", "og:title": "Widget", "x-ua-compatible": "IE=edge"}
+
+
+def test_missing_metadata():
+    meta = rusticsoup.get_metadata("<p>nothing here</p>")
+    assert meta["title"] is None
+    assert meta["canonical"] is None
+    assert meta["keywords"] == [] and meta["robots"] == []
+    assert meta["charset"] is None
+
+
+def test_charset_from_http_equiv_and_detection():
+    html = '<meta http-equiv="Content-Type" content="text/html; charset=iso-8859-1"><title>x</title>'
+    assert rusticsoup.get_metadata(html)["charset"] == "windows-1252"
+    assert rusticsoup.get_metadata("<title>café</title>".encode("utf-8"))["charset"] == "utf-8"
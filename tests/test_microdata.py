@@ -0,0 +1,56 @@
+import rusticsoup
+
+HTML = """
+<div itemscope itemtype="https://schema.org/Product" itemid="urn:sku:42" itemref="reviews">
+  <h1 itemprop="name">Widget
+     Deluxe</h1>
+  <img itemprop="image" src="/w.png">
+  <a itemprop="url" href="/widget">link</a>
+  <span itemprop="color">red</span> <span itemprop="color">blue</span>
+  <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+    <meta itemprop="priceCurrency" content="USD">
+    <data itemprop="price" value="9.99">$9.99</data>
+    <time itemprop="priceValidUntil" datetime="2030-01-01">New Year</time>
+  </div>
+</div>
+<section id="reviews">
+  <div itemprop="review" itemscope itemtype="https://schema.org/Review">
+    <span itemprop="author">Ann</span>
+  </div>
+</section>
+<div itemscope itemtype="https://schema.org/Organization">
+  <span itemprop="name legalName">Acme</span>
+</div>
+"""
+
+
+def test_items_are_nested_dicts():
+    items = rusticsoup.extract_microdata(HTML)
+    assert len(items) == 2
+    product = items[0]
+    assert product["@type"] == "https://schema.org/Product"
+    assert product["@id"] == "urn:sku:42"
+    assert product["name"] == "Widget Deluxe"
+    assert product["image"] == "/w.png"
+    assert product["url"] == "/widget"
+    assert product["color"] == ["red", "blue"]
+    assert product["offers"] == {
+        "@type": "https://schema.org/Offer",
+        "priceCurrency": "USD",
+        "price": "9.99",
+        "priceValidUntil": "2030-01-01",
+    }
+    # Pulled in through itemref
+    assert product["review"]["author"] == "Ann"
+
+
+def test_multiple_property_names_and_type_filter():
+    orgs = rusticsoup.extract_microdata(HTML, type="Organization")
+    assert orgs == [{"@type": "https://schema.org/Organization", "name": "Acme", "legalName": "Acme"}]
+    assert len(rusticsoup.extract_microdata(HTML, type=["Product", "Organization"])) == 2
+
+
+def test_itemref_cycle_terminates():
+    html = '<div id="a" itemscope itemref="b"><div id="b" itemprop="self" itemscope itemref="a"></div></div>'
+    items = rusticsoup.extract_microdata(html)
+    assert len(items) == 1
@@ -0,0 +1,49 @@
+import rusticsoup
+
+HTML = """<!DOCTYPE html>
+<html>
+  <head>
+    <title> Page </title>
+    <!-- analytics -->
+    <style>  p  { color: red }  </style>
+  </head>
+  <body>
+    <div class="box   wide" id="main" hidden="">
+      <p>Hello    <b>big</b>   <i>world</i>  </p>
+      <pre>
+  keep   this
+    indented</pre>
+      <textarea>  raw   text </textarea>
+      <script>if (a < b && c) { x = "  y  "; }</script>
+      <a href="/x?a=1&amp;b=2" title='say "hi"'>link</a>
+    </div>
+  </body>
+</html>
+"""
+
+
+def test_minify_output():
+    # Attribute order follows the source
+    out = rusticsoup.minify(HTML)
+    assert out == (
+        "<!DOCTYPE html><html><head><title>Page</title>"
+        "<style>  p  { color: red }  </style></head>"
+        "<body><div class=\"box   wide\" id=main hidden>"
+        "<p>Hello <b>big</b> <i>world</i></p>"
+        "<pre>  keep   this\n    indented</pre>"
+        "<textarea>  raw   text </textarea> "
+        "<script>if (a < b && c) { x = \"  y  \"; }</script> "
+        "<a href=\"/x?a=1&amp;b=2\" title=\"say &quot;hi&quot;\">link</a>"
+        "</div></body></html>"
+    )
+
+
+def test_minified_output_parses_to_same_text():
+    doc = rusticsoup.parse_html(HTML)
+    mini = rusticsoup.parse_html(rusticsoup.minify(HTML))
+    assert mini.select_one("pre").text() == doc.select_one("pre").text()
+    # A second leading newline in <pre> survives the parser dropping the first
+    assert "<pre>\n\nx</pre>" in rusticsoup.minify("<pre>\n\nx</pre>")
+    assert mini.select_one("a").attr("href") == "/x?a=1&b=2"
+    assert mini.select_one("a").attr("title") == 'say "hi"'
+    assert len(rusticsoup.minify(HTML)) < len(HTML)
@@ -0,0 +1,113 @@
+import pytest
+
+import rusticsoup
+
+HTML = """<html><head><script>track()</script></head><body>
+<div id="main"><script>ads()</script><p>Keep me</p></div>
+<table><tbody><tr><td>1</td></tr></tbody></table>
+</body></html>"""
+
+
+def test_remove_scripts_and_store():
+    page = rusticsoup.parse_html(HTML)
+    assert page.remove("script") == 2
+    assert "script" not in page.html()
+    assert page.select_one("#main p").text() == "Keep me"
+    assert page.remove("script") == 0
+
+
+def test_remove_counts_outermost_matches():
+    page = rusticsoup.parse_html("<div class=x><div class=x>inner</div></div>")
+    assert page.remove(".x") == 1
+    assert page.select(".x") == []
+
+
+def test_replace_with():
+    page = rusticsoup.parse_html(HTML)
+    assert page.replace_with("#main p", "<h2>New</h2> and text") == 1
+    assert page.select_one("#main h2").text() == "New"
+    assert "and text" in page.select_one("#main").text()
+    assert page.replace_with("td", "") == 1
+    assert page.select("td") == []
+
+
+def test_insert_positions():
+    page = rusticsoup.parse_html("<ul><li>b</li></ul>")
+    page.insert("ul", "<li>c</li>")
+    page.insert("ul", "<li>a</li>", position="prepend")
+    page.insert("ul", "<h3>List</h3>", position="before")
+    page.insert("ul", "<!-- end -->", position="after")
+    assert [li.text() for li in page.select("ul > li")] == ["a", "b", "c"]
+    assert "<h3>List</h3><ul>" in page.html()
+    assert page.comments() == [" end "]
+
+
+def test_markup_parsed_in_target_context():
+    page = rusticsoup.parse_html(HTML)
+    page.insert("tbody", "<tr><td>2</td></tr>")
+    assert [td.text() for td in page.select("tbody > tr > td")] == ["1", "2"]
+    row = rusticsoup.parse_fragment("<td>1</td>", context="tr")
+    row.insert("td", "<td>2</td>", position="after")
+    assert row.html() == "<td>1</td><td>2</td>"
+
+
+def test_earlier_elements_are_unchanged_copies():
+    page = rusticsoup.parse_html(HTML)
+    main = page.select_one("#main")
+    page.remove("#main script")
+    assert "ads()" in main.html()
+
+
+def test_xml_mutation():
+    doc = rusticsoup.parse_xml("<urlset><url><loc>a</loc></url></urlset>")
+    doc.insert("urlset", "<url><loc>b</loc></url>")
+    assert [loc.text() for loc in doc.select("loc")] == ["a", "b"]
+    assert doc.html() == "<urlset><url><loc>a</loc></url><url><loc>b</loc></url></urlset>"
+
+
+def test_invalid_position():
+    with pytest.raises(ValueError, match="Invalid position"):
+        rusticsoup.parse_html(HTML).insert("p", "<b>x</b>", position="inside")
+
+
+def test_clone_is_independent():
+    page = rusticsoup.parse_html(HTML)
+    page.remove("head script")
+    snapshot = page.clone()
+    assert snapshot.html() == page.html()
+    snapshot.remove("#main script")
+    snapshot.insert("#main", "<p>Added</p>")
+    assert [p.text() for p in snapshot.select("#main p")] == ["Keep me", "Added"]
+    assert len(page.select("script")) == 1
+    assert [p.text() for p in page.select("#main p")] == ["Keep me"]
+
+
+def test_copy_module_clones():
+    import copy
+
+    page = rusticsoup.parse_fragment("<td>1</td>", context="tr")
+    for duplicate in (copy.copy(page), copy.deepcopy(page)):
+        duplicate.replace_with("td", "<td>2</td>")
+        assert duplicate.html() == "<td>2</td>"
+    assert page.html() == "<td>1</td>"
+
+
+def test_clone_subtree_keeps_table_tags():
+    page = rusticsoup.parse_html(HTML)
+    row = page.select_one("tr").clone_subtree()
+    assert row.html() == "<tr><td>1</td></tr>"
+    row.insert("tr", "<td>2</td>")
+    assert [td.text() for td in row.select("tr > td")] == ["1", "2"]
+    assert len(page.select("td")) == 1
+
+
+def test_clone_subtree_of_body_and_xml():
+    page = rusticsoup.parse_html(HTML)
+    body = page.select_one("body").clone_subtree()
+    body.remove("script")
+    assert body.select_one("#main p").text() == "Keep me"
+    assert len(page.select("script")) == 2
+
+    doc = rusticsoup.parse_xml('<feed xmlns:m="urn:m"><item id="1"><m:price>3</m:price></item></feed>')
+    item = doc.select_one("item").clone_subtree()
+    assert item.html() == '<item id="1"><m:price xmlns:m="urn:m">3</m:price></item>'
@@ -0,0 +1,44 @@
+import rusticsoup
+
+HTML = """
+<html><head>
+  <noscript><link rel="stylesheet" href="/nojs.css"></noscript>
+</head><body>
+  <div class="product">
+    <img class="lazy" data-src="/full.jpg">
+    <noscript><img src="/full.jpg" alt="Widget"></noscript>
+  </div>
+</body></html>
+"""
+
+
+def test_noscript_is_text_by_default():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select("noscript img") == []
+    assert page.select_one("div noscript").text() == '<img src="/full.jpg" alt="Widget">'
+
+
+def test_parse_noscript_exposes_markup():
+    page = rusticsoup.parse_html(HTML, parse_noscript=True)
+    assert [img.attr("src") for img in page.select("noscript img")] == ["/full.jpg"]
+    assert page.xpath_getall("//noscript/link/@href") == ["/nojs.css"]
+
+
+def test_element_select_inside_noscript():
+    product = rusticsoup.parse_html(HTML, parse_noscript=True).select_one("div.product")
+    assert product.select_one("noscript > img").attr("alt") == "Widget"
+
+
+def test_extract_data_with_noscript():
+    fields = {"image": "noscript img@src"}
+    assert rusticsoup.extract_data(HTML, "div.product", fields, parse_noscript=True) == [{"image": "/full.jpg"}]
+    assert rusticsoup.extract_data(HTML, "div.product", fields) == [{"image": ""}]
+
+
+def test_fragment_and_streaming_parser():
+    fragment = rusticsoup.parse_fragment("<noscript><img src='/a.png'></noscript>", parse_noscript=True)
+    assert fragment.select_one("noscript img").attr("src") == "/a.png"
+    parser = rusticsoup.StreamingParser(parse_noscript=True)
+    parser.feed(HTML[:120])
+    parser.feed(HTML[120:])
+    assert len(parser.close().select("noscript img")) == 1
@@ -0,0 +1,60 @@
+import rusticsoup
+
+HTML = """<html><body>
+<p>Preamble</p>
+<h1 id="top">Guide</h1>
+<p>Welcome   text.</p>
+<section id="install">
+  <h2>Install</h2>
+  <p>Run pip.</p>
+  <ul><li>wheel</li><li>sdist</li></ul>
+  <h3><a name="windows"></a>Windows</h3>
+  <p>Use wheels.</p>
+</section>
+<h2>Usage <small>v2</small></h2>
+<p>Call it.</p>
+<h4>Deep</h4>
+<p>Deep text.</p>
+<h1>Second</h1>
+<script>ignored()</script>
+<p>End.</p>
+</body></html>"""
+
+
+def titles(sections):
+    return [(s["title"], titles(s["children"])) for s in sections]
+
+
+def test_sections_nest_by_level():
+    outline = rusticsoup.extract_outline(HTML)
+    assert titles(outline) == [
+        ("Guide", [("Install", [("Windows", [])]), ("Usage v2", [("Deep", [])])]),
+        ("Second", []),
+    ]
+    assert [s["level"] for s in outline[0]["children"]] == [2, 2]
+    assert outline[0]["children"][1]["children"][0]["level"] == 4
+
+
+def test_section_text_stops_at_next_heading():
+    guide = rusticsoup.extract_outline(HTML)[0]
+    assert guide["text"] == "Welcome text."
+    assert guide["children"][0]["text"] == "Run pip.\n\n- wheel\n- sdist"
+    assert rusticsoup.extract_outline(HTML)[1]["text"] == "End."
+
+
+def test_anchors():
+    guide = rusticsoup.extract_outline(HTML)[0]
+    install, usage = guide["children"]
+    assert guide["anchor"] == "top"
+    assert install["anchor"] == "install"
+    assert install["children"][0]["anchor"] == "windows"
+    assert usage["anchor"] is None
+
+
+def test_plain_text_without_layout():
+    install = rusticsoup.extract_outline(HTML, preserve_layout=False)[0]["children"][0]
+    assert install["text"] == "Run pip.\nwheel\nsdist"
+
+
+def test_no_headings():
+    assert rusticsoup.extract_outline("<p>Just text</p>") == []
@@ -0,0 +1,47 @@
+import rusticsoup
+
+# Enough containers for extract_data to spread them over worker threads where it has more
+# than one; an XPath field keeps the whole call on the calling thread.
+COUNT = 5000
+HTML = "<html><body>" + "".join(
+    f"<div class='p'><h2>Item&nbsp;{i}</h2><a href='/p/{i}'>go</a>{'<em>sale</em>' if i % 3 else ''}"
+    f"<time datetime='2024-05-{i % 28 + 1:02d}'>then</time></div>"
+    for i in range(COUNT)
+) + "</body></html>"
+
+FIELDS = {
+    "name": "h2",
+    "url": "a@href",
+    "markup": "h2@html",
+    "sale": "em",
+    "links": "a@get_all",
+    "meta": {"when": {"selector": "time", "type": "datetime"}},
+    "broken": "p[",
+}
+
+
+def sequential(**kwargs):
+    rows, report = rusticsoup.extract_data(
+        HTML, "div.p", dict(FIELDS, pin="xpath:string(.//h2)"), report=True, **kwargs
+    )
+    for row in rows:
+        del row["pin"]
+    return rows, report
+
+
+def test_large_pages_match_sequential_extraction():
+    kwargs = {"base_url": "https://shop.example/", "preserve_entities": True}
+    rows, report = rusticsoup.extract_data(HTML, "div.p", FIELDS, report=True, **kwargs)
+    expected, expected_report = sequential(**kwargs)
+    assert len(rows) == COUNT
+    assert rows == expected
+    assert rows[1]["url"] == "https://shop.example/p/1"
+    assert rows[1]["markup"] == "<h2>Item&nbsp;1</h2>"
+    assert report.empty == expected_report.empty
+    assert report.failed == [issue for issue in expected_report.failed if issue["field"] != "pin"]
+    assert report.failed[-1]["container"] == COUNT - 1
+
+
+def test_large_pages_keep_container_order():
+    rows = rusticsoup.extract_data(HTML, "div.p", {"name": "h2"})
+    assert [row["name"] for row in rows] == [f"Item\xa0{i}" for i in range(COUNT)]
@@ -0,0 +1,103 @@
+import datetime
+import decimal
+
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Widget {i} é</h2><b>$1,299.0{i}</b><time>2024-03-0{i + 1} 10:30</time>"
+    f"<i>red</i><i>size {i}</i></div>"
+    for i in range(3)
+)
+FIELDS = {
+    "name": "h2",
+    "price": {"selector": "b", "type": "price"},
+    "date": {"selector": "time", "type": "datetime"},
+    "tags": "i@get_all",
+    "seller": {"name": "em"},
+}
+
+
+def results():
+    return rusticsoup.extract_data(HTML, "div.p", FIELDS) + [
+        {"name": "Gadget", "price": None, "date": None, "tags": [], "seller": {"name": "Shop"}}
+    ]
+
+
+def test_file_layout(tmp_path):
+    path = tmp_path / "items.parquet"
+    assert rusticsoup.write_parquet(results(), path) == 4
+    data = path.read_bytes()
+    assert data[:4] == b"PAR1" and data[-4:] == b"PAR1"
+    assert rusticsoup.write_parquet(iter([]), str(path), columns=["name"]) == 0
+    assert path.read_bytes()[:4] == b"PAR1"
+
+
+def test_pyarrow_reads_inferred_types(tmp_path):
+    pa = pytest.importorskip("pyarrow")
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "items.parquet"
+    rusticsoup.write_parquet(results(), path)
+    table = pq.read_table(path)
+    assert table.column_names == ["name", "price.amount", "price.currency", "date", "tags", "seller.name"]
+    assert table.schema.field("name").type == pa.string()
+    assert table.schema.field("price.amount").type == pa.decimal128(18, 2)
+    assert table.schema.field("date").type == pa.timestamp("us", tz="UTC")
+    assert table.schema.field("tags").type.value_type == pa.string()
+    rows = table.to_pylist()
+    assert rows[0] == {
+        "name": "Widget 0 é",
+        "price.amount": decimal.Decimal("1299.00"),
+        "price.currency": "USD",
+        "date": datetime.datetime(2024, 3, 1, 10, 30, tzinfo=datetime.timezone.utc),
+        "tags": ["red", "size 0"],
+        "seller.name": "",
+    }
+    assert rows[3] == {
+        "name": "Gadget",
+        "price.amount": None,
+        "price.currency": None,
+        "date": None,
+        "tags": [],
+        "seller.name": "Shop",
+    }
+
+
+def test_pyarrow_reads_plain_types_and_columns(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "rows.parquet"
+    rows = [
+        {"n": 1, "f": 1.5, "b": True, "mixed": 1, "naive": datetime.datetime(2020, 1, 1, 12)},
+        {"n": None, "f": 2.0, "b": False, "mixed": "x", "extra": "only here"},
+    ]
+    assert rusticsoup.write_parquet(rows, path) == 2
+    assert pq.read_table(path).to_pylist() == [
+        {"n": 1, "f": 1.5, "b": True, "mixed": "1", "naive": datetime.datetime(2020, 1, 1, 12), "extra": None},
+        {"n": None, "f": 2.0, "b": False, "mixed": "x", "naive": None, "extra": "only here"},
+    ]
+    rusticsoup.write_parquet(rows, path, columns=["extra", "n"])
+    assert pq.read_table(path).column_names == ["extra", "n"]
+
+
+def test_many_pages(tmp_path):
+    pq = pytest.importorskip("pyarrow.parquet")
+    path = tmp_path / "big.parquet"
+    rows = [{"text": "x" * 100 + str(i), "tags": ["a", str(i)]} for i in range(30_000)]
+    rusticsoup.write_parquet(rows, path)
+    assert pq.read_table(path).to_pylist() == rows
+
+
+def test_polars_reads_file(tmp_path):
+    pl = pytest.importorskip("polars")
+    path = tmp_path / "items.parquet"
+    rusticsoup.write_parquet(results(), path)
+    df = pl.read_parquet(path)
+    assert df.height == 4 and df["price.currency"].to_list() == ["USD", "USD", "USD", None]
+
+
+def test_errors(tmp_path):
+    with pytest.raises(TypeError, match="Row 0 is not a dict"):
+        rusticsoup.write_parquet([["a"]], tmp_path / "rows.parquet")
+    with pytest.raises(FileNotFoundError):
+        rusticsoup.write_parquet([{"a": "b"}], tmp_path / "missing" / "rows.parquet")
@@ -0,0 +1,15 @@
+import rusticsoup
+
+
+def test_parse_warnings():
+    broken = "<!DOCTYPE html><html><body><div><p>one</span></div></table></body></html>"
+    doc = rusticsoup.parse_html(broken)
+    assert len(doc.warnings) > 0
+    assert rusticsoup.parse_html("<!DOCTYPE html><html><head></head><body><p>ok</p></body></html>").warnings == []
+    assert rusticsoup.RusticSoup(broken).warnings == doc.warnings
+
+
+def test_detailed_warnings():
+    broken = "<!DOCTYPE html><body></p><frameset>"
+    assert not any("frameset" in w for w in rusticsoup.parse_html(broken).warnings)
+    assert any("frameset" in w for w in rusticsoup.parse_html(broken, detailed_warnings=True).warnings)
@@ -0,0 +1,67 @@
+import pytest
+
+import rusticsoup
+
+SNIPPETS = [
+    '<div class="a b" id=main>x &amp; y &lt;z&gt; &nbsp;&#x41;</div>',
+    "  <p>one<p>two  ",
+    "<ul><li>a<li>b<ul><li>c</ul></ul>",
+    "<dl><dt>a<dd>b<dt>c<dd>d</dl>",
+    '<a href="/x?a=1&amp;b=2">link</a><br>text<img src=x alt="y"/>',
+    "<span>a</span>b<!-- c -->d",
+    "plain text",
+    "<b><i>x</b></i>",
+    "<table><tr><td>cell</td></tr></table>",
+    "<!-- lead --><div>x</div>",
+    "<!DOCTYPE html><div>x</div>",
+    "<div>unclosed",
+    "<div>Tom & Jerry &copy;</div>",
+    "<DIV CLASS=X>upper</DIV>",
+    "<div/>",
+    "<p>a<div>b</div>",
+    "<script>var a = '<div>';</script><div>x</div>",
+]
+
+
+def shape(doc):
+    return doc.html(), doc.warnings, [(e.tag(), e.attrs()) for e in doc.select("*")]
+
+
+@pytest.mark.parametrize("html", SNIPPETS)
+def test_modes_build_the_same_document(html):
+    expected = shape(rusticsoup.parse_html(html, mode="document"))
+    assert shape(rusticsoup.parse_html(html)) == expected
+    assert shape(rusticsoup.parse_html(html, mode="fragment")) == expected
+
+
+def test_fragment_mode_at_any_size():
+    html = "".join(f'<div class="card"><h2>T{i}</h2><a href="/p/{i}">go</a></div>' for i in range(200))
+    assert len(html) > 1024
+    fragment = rusticsoup.parse_html(html, mode="fragment")
+    assert shape(fragment) == shape(rusticsoup.parse_html(html, mode="document"))
+    assert len(fragment.select("div.card")) == 200
+
+
+@pytest.mark.parametrize("mode", ["auto", "document", "fragment"])
+def test_extract_data_modes(mode):
+    html = '<div class="p"><h2>A &amp; B</h2><a href="/a">x</a></div><div class="p"><h2>C</h2></div>'
+    items = rusticsoup.extract_data(html, "div.p", {"title": "h2", "url": "a@href"}, mode=mode)
+    assert items == [{"title": "A & B", "url": "/a"}, {"title": "C", "url": ""}]
+
+
+def test_invalid_mode():
+    with pytest.raises(ValueError, match="Invalid mode: 'fast'"):
+        rusticsoup.parse_html("<div></div>", mode="fast")
+    with pytest.raises(ValueError, match="Invalid mode"):
+        rusticsoup.extract_data("<div></div>", "div", {"x": "p"}, mode="fast")
+
+
+def test_mode_rejected_for_parsed_documents():
+    doc = rusticsoup.parse_html("<div><p>x</p></div>")
+    with pytest.raises(ValueError, match="'mode'"):
+        rusticsoup.extract_data(doc, "div", {"x": "p"}, mode="fragment")
+
+
+def test_limits_still_apply():
+    with pytest.raises(rusticsoup.LimitExceededError):
+        rusticsoup.parse_html("<div><div><div>x</div></div></div>", mode="fragment", max_depth=2)
@@ -0,0 +1,102 @@
+import copy
+import os
+import pickle
+import subprocess
+import sys
+
+import pytest
+
+import rusticsoup
+
+HTML = """<html><head><title>Shop</title></head><body>
+<div class="p"><h2>Widget</h2><a href="/w">more</a></div>
+<div class="p"><h2>Gadget</h2><a href="/g">more</a></div>
+</body></html>"""
+
+
+def roundtrip(obj):
+    return pickle.loads(pickle.dumps(obj))
+
+
+def test_document_roundtrip_keeps_markup_and_edits():
+    page = rusticsoup.parse_html(HTML)
+    page.remove("div.p:last-child")
+    copy_ = roundtrip(page)
+    assert type(copy_) is rusticsoup.WebScraper
+    assert copy_.html() == page.html()
+    assert [el.text() for el in copy_.select("h2")] == ["Widget"]
+    # The copy is independent of the original
+    copy_.remove("h2")
+    assert page.select_one("h2").text() == "Widget"
+
+
+def test_document_roundtrip_keeps_warnings_and_encoding():
+    page = rusticsoup.parse_html(b"<meta charset='iso-8859-1'><p>caf\xe9<p>cr\xe8me</b>")
+    copy_ = roundtrip(page)
+    assert copy_.warnings == page.warnings and page.warnings
+    assert copy_.original_encoding == page.original_encoding
+    assert copy_.encoding_source == page.encoding_source == "meta"
+    assert copy_.encoding_confidence == page.encoding_confidence
+    assert copy_.select("p")[1].text() == "crème"
+
+
+def test_str_input_has_no_encoding_after_roundtrip():
+    copy_ = roundtrip(rusticsoup.parse_html("<p>x</p>"))
+    assert copy_.original_encoding is None and copy_.encoding_source is None
+
+
+@pytest.mark.parametrize(
+    "page, selector",
+    [
+        (
+            lambda: rusticsoup.parse_html("<body><noscript><img src=a.png></noscript>", parse_noscript=True),
+            "noscript > img",
+        ),
+        (lambda: rusticsoup.parse_fragment("<td>a</td><td>b</td>", context="tr"), "td"),
+        (lambda: rusticsoup.parse_xml('<feed xmlns="urn:x"><Entry id="1"/></feed>'), "Entry"),
+        (lambda: rusticsoup.parse_html("<template><tr><td>t</td></tr></template>"), "template > tr"),
+        (
+            lambda: rusticsoup.parse_html("<p>a</p><!--[if IE]><p class=ie>x</p><![endif]-->", parse_conditional_comments=True),
+            "p.ie",
+        ),
+    ],
+)
+def test_documents_parse_back_the_way_they_were_parsed(page, selector):
+    page = page()
+    copy_ = roundtrip(page)
+    assert copy_.html() == page.html()
+    assert len(copy_.select(selector)) == len(page.select(selector)) > 0
+
+
+def test_rusticsoup_roundtrip():
+    soup = rusticsoup.RusticSoup(HTML)
+    copy_ = roundtrip(soup)
+    assert type(copy_) is rusticsoup.RusticSoup
+    assert copy_.decode() == soup.decode()
+    assert copy_.select_one("a").get("href") == "/w"
+
+
+def test_compiled_selectors_roundtrip():
+    compiled = rusticsoup.compile_selectors({"name": "h2", "link": {"url": "a@href"}})
+    copy_ = roundtrip(compiled)
+    assert repr(copy_) == "<CompiledSelectors fields=2>"
+    assert rusticsoup.extract_data(HTML, "div.p", copy_) == rusticsoup.extract_data(HTML, "div.p", compiled)
+
+
+def test_deepcopy_still_copies_directly():
+    page = rusticsoup.parse_html(HTML)
+    assert copy.deepcopy(page).html() == page.html()
+
+
+def test_documents_cross_process_boundaries():
+    page = rusticsoup.parse_html(HTML)
+    page.remove("a")
+    worker = "import pickle, sys; print(pickle.loads(sys.stdin.buffer.read()).html(), end='')"
+    result = subprocess.run(
+        [sys.executable, "-c", worker],
+        input=pickle.dumps(page),
+        capture_output=True,
+        env={**os.environ, "PYTHONPATH": os.pathsep.join(sys.path)},
+        check=True,
+    )
+    assert result.stdout.decode() == page.html()
@@ -0,0 +1,76 @@
+import sys
+
+import pytest
+
+import rusticsoup
+
+HTML = "".join(
+    f"<div class='p'><h2>Product {i} {'long name ' * i}</h2><a href='/p/{i}'>more</a>"
+    f"<i>tag{i}</i><i>café</i><span class='seller'>Shop {i % 3}</span></div>"
+    for i in range(40)
+)
+FIELDS = {"name": "h2", "url": "a@href", "tags": "i@get_all", "seller": {"name": "span.seller", "rating": "em"}}
+
+
+def flatten(record, prefix=""):
+    row = {}
+    for key, value in record.items():
+        if isinstance(value, dict):
+            row.update(flatten(value, f"{prefix}{key}."))
+        else:
+            row[f"{prefix}{key}"] = value
+    return row
+
+
+def test_dataframe_matches_records():
+    pl = pytest.importorskip("polars")
+    df = rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars")
+    assert isinstance(df, pl.DataFrame)
+    assert df.columns == ["name", "url", "tags", "seller.name", "seller.rating"]
+    assert df.schema["name"] == pl.String
+    assert df.schema["tags"] == pl.List(pl.String)
+    records = rusticsoup.extract_data(HTML, "div.p", FIELDS)
+    assert df.to_dicts() == [flatten(record) for record in records]
+
+
+def test_empty_and_compiled_inputs():
+    pytest.importorskip("polars")
+    df = rusticsoup.extract_data("<p>no items</p>", "div.p", FIELDS, output="polars")
+    assert df.height == 0 and df.columns == ["name", "url", "tags", "seller.name", "seller.rating"]
+    compiled = rusticsoup.compile_selectors(FIELDS)
+    doc = rusticsoup.parse_html(HTML)
+    assert rusticsoup.extract_data(doc, "div.p", compiled, output="polars").equals(
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars")
+    )
+
+
+def test_report_with_polars_output():
+    pytest.importorskip("polars")
+    df, report = rusticsoup.extract_data(HTML, "div.p", {"bad": "a[", "name": "h2"}, output="polars", report=True)
+    assert df["bad"].to_list() == [""] * 40
+    assert report.containers == 40 and len(report.failed) == 40
+
+
+def test_invalid_output():
+    with pytest.raises(ValueError, match="Invalid output"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, output="pandas")
+
+
+def test_unsupported_polars_options():
+    with pytest.raises(ValueError, match="has a type"):
+        rusticsoup.extract_data(HTML, "div.p", {"price": {"selector": "b", "type": "price"}}, output="polars")
+    with pytest.raises(ValueError, match="dry_run"):
+        rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars", dry_run=True)
+
+
+def test_missing_polars_raises_import_error():
+    saved = sys.modules.get("polars")
+    sys.modules["polars"] = None
+    try:
+        with pytest.raises(ImportError, match="pip install polars"):
+            rusticsoup.extract_data(HTML, "div.p", FIELDS, output="polars")
+    finally:
+        if saved is None:
+            del sys.modules["polars"]
+        else:
+            sys.modules["polars"] = saved
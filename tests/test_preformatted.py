@@ -0,0 +1,40 @@
+import rusticsoup
+
+HTML = """<html><body>
+<div class="snippet">
+  <h3>  Example   code </h3>
+  <pre><code>def f(x):
+    if x:
+        return <span class="kw">None</span>
+</code></pre>
+  <p>Run  <code>f( 1 )</code>  twice.</p>
+  <textarea>  a  |  b
+  1  |  2</textarea>
+</div>
+</body></html>"""
+
+CODE = "def f(x):\n    if x:\n        return None\n"
+
+
+def test_element_text_keeps_preformatted_whitespace():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select_one("pre").text() == CODE
+    assert page.select_one("textarea").text() == "  a  |  b\n  1  |  2"
+    assert page.select_one("h3").text() == "Example code"
+
+
+def test_mixed_content_collapses_around_code():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select_one("p").text() == "Run f( 1 ) twice."
+    assert CODE in page.text()
+
+
+def test_extract_data_keeps_preformatted_whitespace():
+    rows = rusticsoup.extract_data(HTML, "div.snippet", {"title": "h3", "code": "pre", "table": "textarea"})
+    assert rows == [{"title": "Example   code", "code": CODE, "table": "  a  |  b\n  1  |  2"}]
+
+
+def test_extract_helpers_and_to_text():
+    assert rusticsoup.extract(HTML, {"code": "pre"}) == {"code": CODE}
+    assert rusticsoup.extract_all(HTML, "code")[0] == CODE
+    assert "        return None" in rusticsoup.to_text(HTML)
@@ -0,0 +1,74 @@
+from decimal import Decimal
+
+import pytest
+
+import rusticsoup
+
+
+@pytest.mark.parametrize(
+    "text, amount, currency",
+    [
+        ("1.234,56 €", "1234.56", "EUR"),
+        ("$1,234.56", "1234.56", "USD"),
+        ("US$ 1,299.00", "1299.00", "USD"),
+        ("12,99€", "12.99", "EUR"),
+        ("£1,000", "1000", "GBP"),
+        ("1 234,56 zł", "1234.56", "PLN"),
+        ("CHF 1'250.–", "1250", "CHF"),
+        ("Price: 49.90 EUR incl. VAT", "49.90", "EUR"),
+        ("R$ 10,50", "10.50", "BRL"),
+        ("¥1234", "1234", "JPY"),
+        ("0.500 kg", "0.500", None),
+        ("-5,00 €", "-5.00", "EUR"),
+        ("-$5.00", "-5.00", "USD"),
+        ("- € 5", "-5", "EUR"),
+        ("-USD 12", "-12", "USD"),
+        ("1.234 €", "1234", "EUR"),
+        ("$.99", "0.99", "USD"),
+        ("€,50", "0.50", "EUR"),
+        ("No.5 for $10", "5", "USD"),
+    ],
+)
+def test_parse_price(text, amount, currency):
+    assert rusticsoup.parse_price(text) == {"amount": Decimal(amount), "currency": currency}
+
+
+def test_hints():
+    assert rusticsoup.parse_price("1.234", decimal_separator=".")["amount"] == Decimal("1.234")
+    assert rusticsoup.parse_price("$5", currency="cad")["currency"] == "CAD"
+    assert rusticsoup.parse_price("€5", currency="USD")["currency"] == "EUR"
+    assert rusticsoup.parse_price("5", currency="SEK")["currency"] == "SEK"
+
+
+def test_no_amount():
+    assert rusticsoup.parse_price("Sold out") is None
+    with pytest.raises(ValueError):
+        rusticsoup.parse_price("5", decimal_separator=";")
+
+
+def test_price_type_in_extract_data():
+    html = """<div class="p"><h2>A</h2><span class="price">1.299,00 €</span></div>
+    <div class="p"><h2>B</h2><span class="price">Call us</span></div>"""
+    items = rusticsoup.extract_data(html, "div.p", {
+        "name": "h2",
+        "price": {"selector": "span.price", "type": "price"},
+    })
+    assert items == [
+        {"name": "A", "price": {"amount": Decimal("1299.00"), "currency": "EUR"}},
+        {"name": "B", "price": None},
+    ]
+    _, report = rusticsoup.extract_data(html, "div.p", {"price": {"selector": "span.price", "type": "price"}}, report=True)
+    assert [issue["container"] for issue in report.empty] == [1]
+
+
+def test_unknown_type():
+    with pytest.raises(ValueError):
+        rusticsoup.extract_data("<p>1</p>", "p", {"x": {"selector": "p", "type": "money"}})
+    problems = rusticsoup.validate_mappings("p", {"x": {"selector": "p", "type": "money"}})
+    assert problems[0]["field"] == "x"
+
+
+def test_nested_mappings_unaffected():
+    html = '<div><b class="type">t</b><i class="selector">s</i></div>'
+    items = rusticsoup.extract_data(html, "div", {"meta": {"type": "b.type", "selector": "i.selector", "x": "b"}})
+    assert items == [{"meta": {"type": "t", "selector": "s", "x": "t"}}]
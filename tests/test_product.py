@@ -0,0 +1,76 @@
+from decimal import Decimal
+
+import rusticsoup
+
+JSON_LD_PAGE = """<html><head>
+<script type="application/ld+json">
+{"@context": "https://schema.org", "@graph": [
+  {"@type": "BreadcrumbList", "name": "crumbs"},
+  {"@type": "Product", "name": "Trail Runner 2", "sku": "TR2-42",
+   "brand": {"@type": "Brand", "name": "Acme"},
+   "image": ["/img/tr2.jpg", {"@type": "ImageObject", "url": "/img/tr2-side.jpg"}],
+   "offers": {"@type": "Offer", "price": "129.90", "priceCurrency": "EUR",
+              "availability": "https://schema.org/InStock"},
+   "aggregateRating": {"ratingValue": "4.6", "reviewCount": 87}}
+]}
+</script>
+<meta property="og:title" content="Trail Runner 2 | Acme Store">
+</head><body><h1>Trail Runner 2</h1></body></html>"""
+
+
+def test_json_ld_product():
+    product = rusticsoup.extract_product(JSON_LD_PAGE, base_url="https://shop.example/p/tr2")
+    assert product == {
+        "name": "Trail Runner 2",
+        "price": Decimal("129.90"),
+        "currency": "EUR",
+        "availability": "InStock",
+        "brand": "Acme",
+        "images": ["https://shop.example/img/tr2.jpg", "https://shop.example/img/tr2-side.jpg"],
+        "rating": 4.6,
+        "review_count": 87,
+        "sku": "TR2-42",
+        "sources": ["json-ld"],
+    }
+
+
+def test_microdata_and_meta():
+    html = """<html><head>
+    <meta property="og:image" content="https://cdn.example/kettle.jpg">
+    <meta property="product:price:currency" content="GBP">
+    </head><body>
+    <div itemscope itemtype="https://schema.org/Product">
+      <h1 itemprop="name">Kettle</h1>
+      <div itemprop="offers" itemscope itemtype="https://schema.org/AggregateOffer">
+        <meta itemprop="lowPrice" content="24.99">
+        <link itemprop="availability" href="https://schema.org/OutOfStock">
+      </div>
+    </div></body></html>"""
+    product = rusticsoup.extract_product(html)
+    assert product["name"] == "Kettle"
+    assert product["price"] == Decimal("24.99")
+    assert product["currency"] == "GBP"
+    assert product["availability"] == "OutOfStock"
+    assert product["images"] == ["https://cdn.example/kettle.jpg"]
+    assert product["sources"] == ["microdata", "meta"]
+
+
+def test_heuristics_only():
+    html = """<html><body>
+    <h1> Desk Lamp </h1>
+    <div class="product-gallery"><img src="/lamp.png"></div>
+    <span class="price-label">Price</span><span class="product-price">1.299,00 €</span>
+    <p class="stock-status">Only a few left!</p>
+    </body></html>"""
+    product = rusticsoup.extract_product(html)
+    assert product["name"] == "Desk Lamp"
+    assert (product["price"], product["currency"]) == (Decimal("1299.00"), "EUR")
+    assert product["availability"] == "LimitedAvailability"
+    assert product["images"] == ["/lamp.png"]
+    assert product["sources"] == ["heuristic"]
+
+
+def test_nothing_found():
+    product = rusticsoup.extract_product("<p>hello</p>")
+    assert product["name"] is None and product["price"] is None
+    assert product["images"] == [] and product["sources"] == []
@@ -0,0 +1,102 @@
+import re
+import sys
+from contextlib import contextmanager
+
+import pytest
+
+import rusticsoup
+
+HTML = """<html><body>
+<ul>
+  <li class="item">$10.00</li>
+  <li class="item">free</li>
+  <li class="item">EUR 5,50</li>
+</ul>
+<a href="https://example.com/">out</a><a href="/in">in</a>
+</body></html>"""
+
+
+@contextmanager
+def registered(**predicates):
+    """Register `name=predicate` pairs (`_` for `-` in names) for the duration of a test."""
+    names = [name.replace("_", "-") for name in predicates]
+    for name, predicate in zip(names, predicates.values()):
+        rusticsoup.register_pseudo_class(name, predicate)
+    try:
+        yield
+    finally:
+        for name in names:
+            rusticsoup.unregister_pseudo_class(name)
+
+
+def texts(elements):
+    return [e.text() for e in elements]
+
+
+def test_regex_predicate_matches_element_text():
+    page = rusticsoup.parse_html(HTML)
+    with registered(price_like=re.compile(r"\d+[.,]\d{2}")):
+        assert texts(page.select("li:price-like")) == ["$10.00", "EUR 5,50"]
+        assert texts(page.select("li.item:not(:price-like)")) == ["free"]
+
+
+def test_str_and_callable_predicates():
+    page = rusticsoup.parse_html(HTML)
+    with registered(free="free", external=lambda e: (e.attr("href") or "").startswith("http")):
+        assert texts(page.select("li:free")) == ["free"]
+        assert texts(page.select(":is(li:free, a:external)")) == ["free", "out"]
+
+
+def test_registering_recompiles_cached_selectors():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select(":is(li:late)") == []
+    with registered(late="free"):
+        assert texts(page.select(":is(li:late)")) == ["free"]
+
+
+def test_usable_in_extract_data_and_element_select():
+    ul = rusticsoup.parse_html(HTML).select_one("ul")
+    with registered(price_like=re.compile(r"\d+[.,]\d{2}")):
+        result = rusticsoup.extract_data(HTML, "ul", {"prices": "li:price-like@get_all"})
+        assert result == [{"prices": ["$10.00", "EUR 5,50"]}]
+        assert ul.select_one("li:PRICE-LIKE").text() == "$10.00"
+
+
+def test_unregistered_pseudo_class_is_a_selector_error():
+    page = rusticsoup.parse_html(HTML)
+    rusticsoup.register_pseudo_class("gone", "x")
+    page.select("li:gone")
+    assert rusticsoup.unregister_pseudo_class("gone") is True
+    assert rusticsoup.unregister_pseudo_class("gone") is False
+    with pytest.raises(rusticsoup.SelectorError):
+        page.select("li:gone")
+
+
+def test_errors_point_past_registered_names():
+    with registered(price_like="$"), pytest.raises(rusticsoup.SelectorError) as info:
+        rusticsoup.parse_html(HTML).select("li:price-like >")
+    assert info.value.position == 15
+
+
+def test_raising_predicate_does_not_match():
+    page = rusticsoup.parse_html(HTML)
+    reported = []
+    hook, sys.unraisablehook = sys.unraisablehook, reported.append
+    try:
+        with registered(boom=lambda e: 1 / 0):
+            assert page.select("li:boom") == []
+    finally:
+        sys.unraisablehook = hook
+    assert len(reported) == 3
+    assert isinstance(reported[0].exc_value, ZeroDivisionError)
+
+
+@pytest.mark.parametrize("name", ["first-child", "1x", ":x", ""])
+def test_invalid_names(name):
+    with pytest.raises(ValueError, match="Invalid pseudo-class name"):
+        rusticsoup.register_pseudo_class(name, "x")
+
+
+def test_invalid_predicate():
+    with pytest.raises(TypeError):
+        rusticsoup.register_pseudo_class("number", 5)
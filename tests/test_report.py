@@ -0,0 +1,39 @@
+import pytest
+
+import rusticsoup
+
+HTML = """
+<div class="p"><h2>One</h2><span class="price">$1</span></div>
+<div class="p"><h2>Two</h2></div>
+"""
+
+
+def test_report_lists_empty_and_failed_fields():
+    results, report = rusticsoup.extract_data(
+        HTML,
+        "div.p",
+        {"title": "h2", "price": "span.price", "broken": "span..x", "meta": {"link": "a@href"}},
+        report=True,
+    )
+    assert [r["title"] for r in results] == ["One", "Two"]
+    assert results[0]["broken"] == ""
+    assert report.containers == 2
+    assert not report.ok
+
+    failed = {(f["container"], f["field"]) for f in report.failed}
+    assert failed == {(0, "broken"), (1, "broken")}
+    assert "position" in report.failed[0]["error"]
+
+    empty = {(e["container"], e["field"]) for e in report.empty}
+    assert empty == {(0, "meta.link"), (1, "price"), (1, "meta.link")}
+
+
+def test_without_report_invalid_field_still_raises():
+    with pytest.raises(rusticsoup.SelectorError):
+        rusticsoup.extract_data(HTML, "div.p", {"broken": "span..x"})
+
+
+def test_clean_report():
+    results, report = rusticsoup.extract_data(HTML, "div.p", {"title": "h2"}, report=True)
+    assert len(results) == 2
+    assert report.ok and report.failed == [] and report.empty == []
@@ -0,0 +1,47 @@
+import pytest
+
+import rusticsoup
+
+
+def test_default_policy_strips_dangerous_markup():
+    dirty = (
+        '<p onclick="steal()">Hi <b>there</b><script>alert(1)</script></p>'
+        '<a href="java\tscript:alert(1)" title="x">bad</a>'
+        '<a href="/ok" target="_blank">ok</a>'
+        '<img src="data:image/png;base64,AAA" alt="pixel">'
+        '<custom-tag>kept text</custom-tag><iframe src="https://evil"></iframe><!-- note -->'
+    )
+    clean = rusticsoup.sanitize(dirty)
+    assert clean == (
+        '<p>Hi <b>there</b></p>'
+        '<a title="x">bad</a>'
+        '<a href="/ok">ok</a>'
+        '<img alt="pixel">'
+        'kept text'
+    )
+
+
+def test_text_and_attributes_are_escaped():
+    clean = rusticsoup.sanitize('<a href="https://e.com/?a=1&amp;b=&quot;2&quot;">1 &lt; 2 &amp; 3</a>')
+    assert clean == '<a href="https://e.com/?a=1&amp;b=&quot;2&quot;">1 &lt; 2 &amp; 3</a>'
+
+
+def test_custom_policy():
+    policy = {
+        "tags": ["p", "span"],
+        "attributes": {"*": ["class"], "span": ["data-id"]},
+        "comments": True,
+    }
+    clean = rusticsoup.sanitize('<p class="x" id="y"><span data-id="1">a</span><b>b</b></p><!-- c -->', policy)
+    assert clean == '<p class="x"><span data-id="1">a</span>b</p><!-- c -->'
+
+
+def test_protocol_allowlist():
+    policy = {"protocols": ["https"]}
+    assert rusticsoup.sanitize('<a href="http://e.com">x</a>', policy) == "<a>x</a>"
+    assert rusticsoup.sanitize('<a href="HTTPS://e.com">x</a>', policy) == '<a href="HTTPS://e.com">x</a>'
+
+
+def test_unknown_policy_key():
+    with pytest.raises(ValueError):
+        rusticsoup.sanitize("<p>x</p>", {"tag": ["p"]})
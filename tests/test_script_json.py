@@ -0,0 +1,50 @@
+import rusticsoup
+
+HTML = r"""
+<html><head>
+<script src="/app.js"></script>
+<script>
+  window.__INITIAL_STATE__ = {"cart": {"items": [{"sku": "A-1", "note": "braces } and ] in \"strings\""}]},
+                              "user": null,};
+  window.dataLayer = window.dataLayer || [];
+  if (window.__INITIAL_STATE__ == null) { boot(); }
+</script>
+<script id="__NEXT_DATA__" type="application/json">{"props": {"pageProps": {"id": 7}}, "page": "/p/[id]"}</script>
+<script type="text/template">window.__TEMPLATE__ = {"ignored": true}</script>
+<script>
+  window["__APOLLO_STATE__"] = JSON.parse("{\"Product:1\":{\"name\":\"Café \\\"Noir\\\"\"}}");
+  var __PRELOADED__ = [1, 2, /* three */ 3];
+</script>
+</head></html>
+"""
+
+
+def test_default_patterns():
+    state = rusticsoup.extract_script_json(HTML)
+    assert state == {
+        "__INITIAL_STATE__": {
+            "cart": {"items": [{"sku": "A-1", "note": 'braces } and ] in "strings"'}]},
+            "user": None,
+        },
+        "__NEXT_DATA__": {"props": {"pageProps": {"id": 7}}, "page": "/p/[id]"},
+    }
+
+
+def test_custom_patterns():
+    state = rusticsoup.extract_script_json(
+        HTML, patterns=["__APOLLO_STATE__", "__PRELOADED__", "__TEMPLATE__", "__MISSING__"]
+    )
+    assert state == {
+        "__APOLLO_STATE__": {"Product:1": {"name": 'Café "Noir"'}},
+        "__PRELOADED__": [1, 2, 3],
+    }
+
+
+def test_javascript_values_are_skipped():
+    html = "<script>window.__STATE__ = {user: undefined}; window.__STATE__ = {\"ok\": 1};</script>"
+    assert rusticsoup.extract_script_json(html, patterns=["__STATE__"]) == {"__STATE__": {"ok": 1}}
+
+
+def test_identifier_boundaries():
+    html = '<script>var X__STATE__ = {"a": 1}; var __STATE__2 = {"b": 2};</script>'
+    assert rusticsoup.extract_script_json(html, patterns=["__STATE__"]) == {}
@@ -0,0 +1,51 @@
+import rusticsoup
+
+HTML = (
+    "<html><body>"
+    + "".join(f'<div class="card"><p><span class="price">{i}</span></p></div>' for i in range(5))
+    + "<ul><li>a</li><li>b</li></ul></body></html>"
+)
+# html, head, body, 5 x (div, p, span), ul, 2 x li
+ELEMENTS = 21
+
+
+def test_select_without_explain_returns_elements():
+    doc = rusticsoup.parse_html(HTML)
+    assert len(doc.select("span.price")) == 5
+
+
+def test_plan_counts_right_to_left_matching():
+    doc = rusticsoup.parse_html(HTML)
+    elements, plan = doc.select("div.card span.price", explain=True)
+    assert [e.text() for e in elements] == ["0", "1", "2", "3", "4"]
+    assert plan["selector"] == "div.card span.price"
+    assert plan["matches"] == 5
+    assert plan["elapsed_ms"] >= 0
+    last, first = plan["steps"][1], plan["steps"][0]
+    # Every element is tested against the last compound
+    assert last == {"compound": "span.price", "combinator": " ", "considered": ELEMENTS, "rejected": ELEMENTS - 5}
+    # Each span walks up through <p> before reaching its card
+    assert first == {"compound": "div.card", "combinator": None, "considered": 10, "rejected": 5}
+
+
+def test_child_and_sibling_steps():
+    doc = rusticsoup.parse_html(HTML)
+    _, plan = doc.select("body > div + div", explain=True)
+    assert [s["combinator"] for s in plan["steps"]] == [None, ">", "+"]
+    assert [(s["considered"], s["rejected"]) for s in plan["steps"]] == [(4, 0), (4, 0), (ELEMENTS, 16)]
+    assert plan["matches"] == 4
+
+
+def test_selector_list_plans_each_alternative():
+    doc = rusticsoup.parse_html(HTML)
+    elements, plan = doc.select("span.price, li", explain=True)
+    assert len(elements) == plan["matches"] == 7
+    assert [a["selector"] for a in plan["alternatives"]] == ["span.price", "li"]
+    assert plan["alternatives"][1]["steps"][0]["rejected"] == ELEMENTS - 2
+
+
+def test_rusticsoup_facade():
+    soup = rusticsoup.RusticSoup(HTML)
+    elements, plan = soup.select("li", explain=True)
+    assert len(elements) == plan["matches"] == 2
+    assert len(soup.find_all("li")) == 2
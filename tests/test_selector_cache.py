@@ -0,0 +1,74 @@
+from contextlib import contextmanager
+
+import rusticsoup
+
+HTML = "<div class='item'><a href='/a'>A</a></div><div class='item'><a href='/b'>B</a></div>"
+
+
+@contextmanager
+def cache_size(maxsize):
+    """Start from an empty cache of `maxsize` entries, restoring the default afterwards."""
+    rusticsoup.configure_selector_cache(maxsize)
+    rusticsoup.clear_selector_cache()
+    try:
+        yield
+    finally:
+        rusticsoup.configure_selector_cache()
+
+
+def test_shared_across_select_and_extract_data():
+    page = rusticsoup.parse_html(HTML)
+    with cache_size(16):
+        page.select("div.item")
+        rusticsoup.extract_data(HTML, "div.item", {"link": "a@href"})
+        page.select_one("a")
+        info = rusticsoup.selector_cache_info()
+        # Each distinct selector is compiled once, whichever API used it first
+        assert (info["misses"], info["currsize"], info["maxsize"]) == (2, 2, 16)
+        assert info["hits"] >= 2
+
+
+def test_least_recently_used_is_evicted():
+    page = rusticsoup.parse_html(HTML)
+    with cache_size(2):
+        page.select("div")
+        page.select("a")
+        page.select("div")
+        page.select("span")
+        page.select("div")
+        assert rusticsoup.selector_cache_info()["hits"] == 2
+        page.select("a")
+        info = rusticsoup.selector_cache_info()
+        assert (info["hits"], info["misses"], info["currsize"]) == (2, 4, 2)
+
+
+def test_shrinking_and_disabling():
+    page = rusticsoup.parse_html(HTML)
+    with cache_size(8):
+        for selector in ["div", "a", "a[href]", "div.item"]:
+            page.select(selector)
+        rusticsoup.configure_selector_cache(1)
+        assert rusticsoup.selector_cache_info()["currsize"] == 1
+        page.select("div.item")
+        assert rusticsoup.selector_cache_info()["hits"] == 1
+
+        rusticsoup.configure_selector_cache(0)
+        assert rusticsoup.selector_cache_info()["currsize"] == 0
+        assert [e.text() for e in page.select("div.item a")] == ["A", "B"]
+        assert rusticsoup.selector_cache_info()["currsize"] == 0
+
+
+def test_invalid_selectors_are_not_cached():
+    page = rusticsoup.parse_html(HTML)
+    with cache_size(8):
+        for _ in range(2):
+            try:
+                page.select("div[")
+            except rusticsoup.SelectorError:
+                pass
+        assert rusticsoup.selector_cache_info()["currsize"] == 0
+
+
+def test_default_size():
+    rusticsoup.configure_selector_cache()
+    assert rusticsoup.selector_cache_info()["maxsize"] == 1024
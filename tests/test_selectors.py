@@ -0,0 +1,133 @@
+import pytest
+
+import rusticsoup
+
+
+def test_selector_error_points_at_failure():
+    doc = rusticsoup.parse_html("<div class='item'>x</div>")
+    with pytest.raises(rusticsoup.SelectorError, match="position 3") as exc:
+        doc.select("div..item")
+    message = str(exc.value)
+    assert "div..item\n       ^" in message
+    assert "'.'" in message
+
+
+def test_selector_error_is_rusticsoup_error():
+    with pytest.raises(rusticsoup.RusticSoupError):
+        rusticsoup.parse_html("<p>x</p>").select_one("p:nosuchpseudo")
+
+
+def test_extract_data_names_failing_field():
+    html = "<div class='p'><span>1</span></div>"
+    with pytest.raises(rusticsoup.SelectorError, match="field 'price'"):
+        rusticsoup.extract_data(html, "div.p", {"title": "span", "price": "span[data-x="})
+
+
+def test_extract_data_bad_container():
+    with pytest.raises(rusticsoup.SelectorError, match="container selector"):
+        rusticsoup.extract_data("<p>x</p>", "div >", {"t": "p"})
+
+
+def test_selector_error_attributes():
+    html = "<div class='p'><span>1</span></div>"
+    with pytest.raises(rusticsoup.SelectorError) as exc:
+        rusticsoup.extract_data(html, "div.p", {"price": "span..amount"})
+    err = exc.value
+    assert err.selector == "span..amount"
+    assert err.field == "price"
+    assert err.position == 4
+    assert err.page_index is None
+
+
+def test_detail_attributes_default_to_none():
+    err = rusticsoup.HTMLParseError("boom")
+    assert (err.selector, err.field, err.page_index, err.position) == (None, None, None, None)
+
+
+CASE_HTML = """<div class="docs">
+<a href="/A.PDF">1</a><a href="/b.pdf">2</a><a href="/c.Txt" type="TEXT">3</a>
+</div>"""
+
+
+@pytest.mark.parametrize("selector, expected", [
+    ('a[href$=".pdf" i]', ["1", "2"]),
+    ('a[href$=".Pdf" I]', ["1", "2"]),
+    ('a[href$=".pdf"i]', ["1", "2"]),
+    ('a[href$=".pdf" s]', ["2"]),
+    ('a[href^="/a" i], a[href*="TXT" i]', ["1", "3"]),
+    ('a:not([href$=".PDF" i])', ["3"]),
+    ('a[type="text" i]', ["3"]),
+])
+def test_case_insensitive_attribute_flag(selector, expected):
+    page = rusticsoup.parse_html(CASE_HTML)
+    assert [e.text() for e in page.select(selector)] == expected
+    assert [e.text() for e in page.select_one("div").select(selector)] == expected
+    assert [e.text() for e in page.xpath(rusticsoup.css_to_xpath(selector))] == expected
+    assert rusticsoup.extract_data(CASE_HTML, "div.docs", {"x": selector + "@get_all"}) == [{"x": expected}]
+    assert rusticsoup.extract_all(CASE_HTML, selector) == expected
+
+
+def test_case_insensitive_flag_in_xml():
+    doc = rusticsoup.parse_xml('<r><f href="a.PDF"/><f href="b.pdf"/></r>')
+    assert len(doc.select('f[href$=".pdf" i]')) == 2
+    assert len(doc.select('f[href$=".pdf"]')) == 1
+
+
+@pytest.mark.parametrize("selector, hint", [
+    ("a[href i]", "needs an operator and a value"),
+    ('a[href="x" q]', "only the flags 'i' for ASCII case-insensitive and 's'"),
+    ("a[href$=.PDF i]", "quote values that are not plain identifiers"),
+])
+def test_attribute_flag_errors_explain_syntax(selector, hint):
+    with pytest.raises(rusticsoup.SelectorError, match=hint):
+        rusticsoup.parse_html(CASE_HTML).select(selector)
+
+
+NOT_HTML = """<main>
+<div class="ad"><p>ad</p></div>
+<div hidden><p>hidden</p></div>
+<section><div><p>kept</p><p>second</p></div></section>
+<div class="x"><p>x</p></div>
+</main>"""
+
+
+@pytest.mark.parametrize("selector, expected", [
+    ("div:not(div.ad, [hidden]) > p", ["kept", "second", "x"]),
+    ("p:not(.ad p, [hidden] > *)", ["kept", "second", "x"]),
+    ("p:not(section > div > p)", ["ad", "hidden", "x"]),
+    ("p:not(p + p)", ["ad", "hidden", "kept", "x"]),
+    ("div:not(:is(.ad, [hidden]) ~ div) p", ["ad", "kept", "second"]),
+])
+def test_not_with_selector_lists_and_combinators(selector, expected):
+    page = rusticsoup.parse_html(NOT_HTML)
+    assert [e.text() for e in page.select(selector)] == expected
+    assert [e.text() for e in rusticsoup.RusticSoup(NOT_HTML).find_all(selector)] == expected
+    assert rusticsoup.extract_data(NOT_HTML, "main", {"x": selector + "@get_all"}) == [{"x": expected}]
+
+
+def test_not_with_complex_arguments_as_container_selector():
+    items = rusticsoup.extract_data(NOT_HTML, "div:not(.ad, [hidden], section *)", {"text": "p"})
+    assert items == [{"text": "x"}]
+
+
+ROWS_HTML = "<table>" + "".join(f"<tr><td>{i}</td><td>x</td></tr>" for i in range(300)) + "</table>"
+
+
+@pytest.mark.parametrize("selector", [
+    "tr:nth-child(3n+1) > td:first-child",
+    "tr:nth-last-child(-n+2) td:nth-of-type(1)",
+    "tr:nth-of-type(150) ~ tr:nth-last-of-type(148) td:last-child",
+    "tr:only-child, td:nth-child(2):not(tr:nth-child(odd) > td)",
+])
+def test_positional_selectors_agree_across_select_paths(selector):
+    expected = [e.html() for e in rusticsoup.parse_html(ROWS_HTML).select(selector)]
+    assert expected
+    with_timeout = rusticsoup.parse_html(ROWS_HTML, timeout=60)
+    assert [e.html() for e in with_timeout.select(selector)] == expected
+    assert with_timeout.select(selector, explain=True)[1]["matches"] == len(expected)
+    rusticsoup.register_pseudo_class("any-row", lambda e: True)
+    try:
+        custom = selector.replace("tr:", "tr:any-row:", 1)
+        assert [e.html() for e in with_timeout.select(custom)] == expected
+    finally:
+        rusticsoup.unregister_pseudo_class("any-row")
@@ -0,0 +1,55 @@
+import pytest
+
+import rusticsoup
+
+HTML = (
+    '<!DOCTYPE html><html><head><title>T</title></head><body>'
+    '<div id="m" title="say &quot;hi&quot; it\'s"><p>Hi <b>café</b></p><br><img src="a.png" alt="">'
+    "<pre>  keep\n  this</pre><script>if (a < b) go()</script></div></body></html>"
+)
+
+
+def test_defaults_match_plain_html():
+    page = rusticsoup.parse_html(HTML)
+    assert page.html(quote="double", entities="minimal", void="html") == page.html()
+
+
+def test_quote_styles():
+    page = rusticsoup.parse_html(HTML)
+    assert "<div id='m' title='say \"hi\" it&#39;s'>" in page.html(quote="single")
+    assert "<div id=m title=\"say &quot;hi&quot; it's\">" in page.html(quote="minimal")
+    assert '<img src=a.png alt>' in page.html(quote="minimal")
+
+
+def test_entities_and_void_style():
+    page = rusticsoup.parse_html(HTML)
+    out = page.html(entities="ascii", void="xhtml")
+    assert "caf&#233;" in out
+    assert '<br /><img src="a.png" alt="" />' in out
+    assert out.isascii()
+
+
+def test_indent_keeps_preformatted_content():
+    page = rusticsoup.parse_html(HTML)
+    out = page.html(indent=2)
+    assert "\n    <title>\n      T\n    </title>" in out
+    assert "\n      <pre>  keep\n  this</pre>" in out
+    assert "<script>if (a < b) go()</script>" in out
+    assert rusticsoup.parse_html(out).select_one("pre").text() == "  keep\n  this"
+
+
+def test_element_and_facade_outputs():
+    paragraph = rusticsoup.parse_html(HTML).select_one("p")
+    assert paragraph.html(indent=1) == "<p>\n Hi\n <b>\n  café\n </b>\n</p>"
+    soup = rusticsoup.RusticSoup(HTML)
+    assert soup.decode() == rusticsoup.parse_html(HTML).html()
+    assert b"<br />" in soup.encode(void="xhtml")
+    assert soup.encode("ascii", entities="ascii").count(b"&#233;") == 1
+
+
+def test_invalid_options():
+    page = rusticsoup.parse_html(HTML)
+    with pytest.raises(ValueError, match="Invalid quote"):
+        page.html(quote="backtick")
+    with pytest.raises(ValueError, match="parse_xml"):
+        rusticsoup.parse_xml("<a/>").html(indent=2)
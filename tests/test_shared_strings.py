@@ -0,0 +1,27 @@
+import rusticsoup
+
+ITEMS = "".join(
+    f"<div class='item'><span class='cur'>EUR</span><b>{i}</b><p>{'long text ' * 10}</p></div>"
+    for i in range(3)
+)
+
+
+def test_repeated_short_values_are_shared():
+    rows = rusticsoup.extract_data(ITEMS, "div.item", {"cur": "span.cur", "n": "b", "all": "span@get_all"})
+    assert [row["cur"] for row in rows] == ["EUR"] * 3
+    assert rows[0]["cur"] is rows[1]["cur"] is rows[2]["cur"] is rows[2]["all"][0]
+    assert [row["n"] for row in rows] == ["0", "1", "2"]
+
+
+def test_long_values_are_not_shared():
+    rows = rusticsoup.extract_data(ITEMS, "div.item", {"p": "p"})
+    assert rows[0]["p"] == rows[1]["p"]
+    assert rows[0]["p"] is not rows[1]["p"]
+
+
+def test_tag_and_attribute_names_are_interned():
+    a, b = rusticsoup.parse_html("<a href='/x' rel='next'>1</a><a href='/y'>2</a>").select("a")
+    assert a.tag() is b.tag()
+    keys_a, keys_b = list(a.attrs()), list(b.attrs())
+    assert keys_a == ["href", "rel"]
+    assert keys_a[0] is keys_b[0]
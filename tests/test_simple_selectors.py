@@ -0,0 +1,56 @@
+import pytest
+
+import rusticsoup
+
+HTML = """
+<div id="main" class="item  Big">
+  <p class="item">para</p>
+  <svg><foreignObject class="item"></foreignObject></svg>
+  <a id="main" href="/x">link</a>
+  <div class="big item"><span class="a_b">x</span></div>
+  <x-el class="item-1">custom</x-el>
+  <p class="1st">escaped</p>
+</div>
+"""
+
+
+@pytest.mark.parametrize(
+    "selector",
+    [
+        "div.item",
+        "#main",
+        "a",
+        ".item",
+        "p.item",
+        "div#main.item.Big",
+        ".big",
+        ".Big",
+        ".item.big",
+        "div.item#main",
+        "x-el.item-1",
+        "span.a_b",
+        "foreignobject",
+        "DIV",
+        "foreignObject",
+        " p ",
+        ".\\31st",
+        "#main#main",
+    ],
+)
+def test_simple_selectors_match_like_the_full_engine(selector):
+    doc = rusticsoup.parse_html(HTML)
+    expected = [e.html() for e in doc.select(f":is({selector.strip()})")]
+    assert [e.html() for e in doc.select(selector)] == expected
+
+
+def test_simple_container_and_fields():
+    html = "".join(f'<div class="card"><h2>T{i}</h2><a class="buy" href="/b/{i}">b</a></div>' for i in range(3))
+    items = rusticsoup.extract_data(html, "div.card", {"title": "h2", "url": "a.buy@href"})
+    assert items == [{"title": f"T{i}", "url": f"/b/{i}"} for i in range(3)]
+
+
+@pytest.mark.parametrize("selector", ["div.", "#", ".", "div..item"])
+def test_malformed_simple_selectors_still_raise(selector):
+    doc = rusticsoup.parse_html(HTML)
+    with pytest.raises(rusticsoup.SelectorError):
+        doc.select(selector)
@@ -0,0 +1,54 @@
+import gzip
+
+import pytest
+
+import rusticsoup
+
+URLSET = """<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc> https://example.com/?a=1&amp;b=2 </loc>
+    <lastmod>2024-05-01</lastmod>
+    <changefreq>Daily</changefreq>
+    <priority>0.8</priority>
+    <image:image><image:loc>https://example.com/i.png</image:loc></image:image>
+  </url>
+  <url><loc>https://example.com/about</loc></url>
+</urlset>
+"""
+
+INDEX = """<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/s1.xml.gz</loc><lastmod>2024-05-02T10:00:00+00:00</lastmod></sitemap>
+</sitemapindex>"""
+
+
+def test_urlset():
+    sitemap = rusticsoup.parse_sitemap(URLSET)
+    assert sitemap["type"] == "urlset"
+    assert sitemap["urls"] == [
+        {"loc": "https://example.com/?a=1&b=2", "lastmod": "2024-05-01", "changefreq": "daily", "priority": 0.8},
+        {"loc": "https://example.com/about", "lastmod": None, "changefreq": None, "priority": None},
+    ]
+
+
+def test_sitemap_index_gzipped():
+    sitemap = rusticsoup.parse_sitemap(gzip.compress(INDEX.encode("utf-8")))
+    assert sitemap == {
+        "type": "sitemapindex",
+        "urls": [{"loc": "https://example.com/s1.xml.gz", "lastmod": "2024-05-02T10:00:00+00:00"}],
+    }
+
+
+def test_max_size_applies_after_decompression():
+    data = gzip.compress(URLSET.encode("utf-8"))
+    with pytest.raises(rusticsoup.LimitExceededError):
+        rusticsoup.parse_sitemap(data, max_size=len(data) + 10)
+
+
+def test_invalid_sitemaps():
+    with pytest.raises(rusticsoup.HTMLParseError) as exc:
+        rusticsoup.parse_sitemap("<urlset><url></urlset>")
+    assert exc.value.position is not None
+    with pytest.raises(rusticsoup.HTMLParseError):
+        rusticsoup.parse_sitemap("<rss></rss>")
@@ -6,3 +6,8 @@ def test_api_surface_exists():
     import rusticsoup
     assert hasattr(rusticsoup, "extract_data")
     assert hasattr(rusticsoup, "parse_html")
+
+
+def test_allocator_reports_the_default_build():
+    import rusticsoup
+    assert rusticsoup.ALLOCATOR == "system"
@@ -0,0 +1,31 @@
+import rusticsoup
+
+HTML = """
+<html><head>
+<meta property="og:title" content=" Widget Deluxe ">
+<meta property="OG:Description" content="The best widget">
+<meta property="og:image:url" content="https://example.com/1.png">
+<meta property="og:image" content="https://example.com/2.png">
+<meta property="og:url" content="">
+<meta name="twitter:card" content="summary_large_image">
+<meta name="twitter:image:src" content="https://example.com/t.png">
+<meta name="og:site_name" content="Example">
+<meta name="description" content="not social">
+</head></html>
+"""
+
+
+def test_social_meta_keys_are_normalized():
+    meta = rusticsoup.extract_social_meta(HTML)
+    assert meta == {
+        "og:title": "Widget Deluxe",
+        "og:description": "The best widget",
+        "og:image": ["https://example.com/1.png", "https://example.com/2.png"],
+        "twitter:card": "summary_large_image",
+        "twitter:image": "https://example.com/t.png",
+        "og:site_name": "Example",
+    }
+
+
+def test_no_social_meta():
+    assert rusticsoup.extract_social_meta(b"<p>plain</p>") == {}
@@ -0,0 +1,29 @@
+import rusticsoup
+
+HTML = """
+<div class="widget">
+  <iframe srcdoc="<p class=&quot;price&quot;>$9.99</p><a href='/buy'>Buy &amp;amp; save</a>"></iframe>
+  <iframe src="/embed"></iframe>
+</div>
+"""
+
+
+def test_srcdoc_document_is_parsed():
+    frame = rusticsoup.parse_html(HTML).select_one("iframe[srcdoc]")
+    doc = frame.srcdoc_document()
+    assert doc.select_one("p.price").text() == "$9.99"
+    assert doc.select_one("a").text() == "Buy & save"
+    assert doc.xpath_get("//a/@href") == "/buy"
+
+
+def test_srcdoc_document_missing():
+    page = rusticsoup.parse_html(HTML)
+    assert page.select_one("iframe[src]").srcdoc_document() is None
+    assert page.select_one("div").srcdoc_document() is None
+
+
+def test_srcdoc_from_element_select():
+    widget = rusticsoup.parse_html(HTML).select_one("div.widget")
+    frames = [f.srcdoc_document() for f in widget.select("iframe")]
+    assert frames[0].select_one("p").text() == "$9.99"
+    assert frames[1] is None
@@ -0,0 +1,35 @@
+import rusticsoup
+
+WIDTHS = "small.jpg 320w, https://cdn.example.com/img,v=2/large.jpg 1280w, medium.jpg 800w"
+
+
+def test_parse_srcset():
+    srcset = rusticsoup.parse_srcset(WIDTHS)
+    assert len(srcset) == 3
+    assert srcset.candidates == [
+        {"url": "small.jpg", "width": 320, "density": 1.0},
+        {"url": "https://cdn.example.com/img,v=2/large.jpg", "width": 1280, "density": 1.0},
+        {"url": "medium.jpg", "width": 800, "density": 1.0},
+    ]
+
+
+def test_best_by_width():
+    srcset = rusticsoup.parse_srcset(WIDTHS)
+    assert srcset.best() == "https://cdn.example.com/img,v=2/large.jpg"
+    assert srcset.best(width=300) == "small.jpg"
+    assert srcset.best(width=400) == "medium.jpg"
+    assert srcset.best(width=500, density=2) == "https://cdn.example.com/img,v=2/large.jpg"
+    assert srcset.best(width=4000) == "https://cdn.example.com/img,v=2/large.jpg"
+
+
+def test_best_by_density():
+    srcset = rusticsoup.parse_srcset("a.png, b.png 2x, c.png 3x")
+    assert [c["density"] for c in srcset.candidates] == [1.0, 2.0, 3.0]
+    assert srcset.best() == "c.png"
+    assert srcset.best(density=1.5) == "b.png"
+    assert srcset.best(width=100) == "c.png"
+
+
+def test_empty():
+    srcset = rusticsoup.parse_srcset("  ")
+    assert len(srcset) == 0 and srcset.best() is None
@@ -0,0 +1,53 @@
+import pytest
+import rusticsoup
+
+HTML = '<html><head><meta charset="windows-1252"></head><body>' + "".join(
+    f'<div class="item"><h2>Caf\xe9 {i}</h2><a href="/p/{i}">link</a></div>' for i in range(200)
+) + "</body></html>"
+
+
+def chunks(data, size):
+    return [data[i:i + size] for i in range(0, len(data), size)]
+
+
+@pytest.mark.parametrize("size", [1, 7, 4096])
+def test_chunks_build_the_same_document(size):
+    parser = rusticsoup.StreamingParser()
+    for chunk in chunks(HTML.encode("cp1252"), size):
+        parser.feed(chunk)
+    page = parser.close()
+    assert page.original_encoding == "windows-1252"
+    assert len(page.select("div.item")) == 200
+    assert page.select("div.item h2")[-1].text() == "Caf\xe9 199"
+    assert page.html() == rusticsoup.parse_html(HTML).html()
+
+
+def test_str_chunks():
+    parser = rusticsoup.StreamingParser()
+    for chunk in chunks(HTML, 100):
+        parser.feed(chunk)
+    assert parser.size == len(HTML.encode("utf-8"))
+    page = parser.close()
+    assert page.original_encoding is None
+    assert page.select("a")[0].attr("href") == "/p/0"
+
+
+def test_limits_raise_while_feeding():
+    parser = rusticsoup.StreamingParser(max_nodes=50)
+    with pytest.raises(rusticsoup.LimitExceededError):
+        for chunk in chunks(HTML, 256):
+            parser.feed(chunk)
+    parser = rusticsoup.StreamingParser(max_size=1000)
+    parser.feed(HTML[:900])
+    with pytest.raises(rusticsoup.LimitExceededError):
+        parser.feed(HTML[900:1200])
+
+
+def test_closed_parser():
+    parser = rusticsoup.StreamingParser()
+    parser.feed("<p>x")
+    assert parser.close().select_one("p").text() == "x"
+    with pytest.raises(ValueError, match="already closed"):
+        parser.feed("<p>y")
+    with pytest.raises(ValueError, match="already closed"):
+        parser.close()
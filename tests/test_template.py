@@ -0,0 +1,38 @@
+import rusticsoup
+
+HTML = """
+<div id="listing">
+  <template id="cards">
+    <div class="card"><h2>First</h2><a href="/1">go</a></div>
+    <div class="card"><h2>Second</h2><a href="/2">go</a></div>
+  </template>
+</div>
+"""
+
+
+def test_child_combinator_reaches_template_content():
+    page = rusticsoup.parse_html(HTML)
+    assert [e.text() for e in page.select("template#cards > .card > h2")] == ["First", "Second"]
+
+
+def test_xpath_reaches_template_content():
+    page = rusticsoup.parse_html(HTML)
+    assert page.xpath_getall("//template/div/a/@href") == ["/1", "/2"]
+
+
+def test_extract_data_from_template():
+    rows = rusticsoup.extract_data(HTML, "template > .card", {"title": "h2", "url": "a@href"})
+    assert rows == [{"title": "First", "url": "/1"}, {"title": "Second", "url": "/2"}]
+
+
+def test_element_select_within_template():
+    template = rusticsoup.parse_html(HTML).select_one("template")
+    assert len(template.select("template > .card")) == 2
+    assert template.html().count('class="card"') == 2
+
+
+def test_nested_templates_and_fragments():
+    page = rusticsoup.parse_html("<template><ul><li><template><b>x</b></template></li></ul></template>")
+    assert page.select_one("template > ul > li > template > b").text() == "x"
+    row = rusticsoup.parse_fragment("<template><td>cell</td></template>")
+    assert row.select_one("template > td").text() == "cell"
@@ -0,0 +1,32 @@
+import pytest
+
+import rusticsoup
+
+
+def test_normalize_text_extraction():
+    html = "<div class='item'><span>１２３</span><b>Café</b></div>"
+    items = rusticsoup.extract_data(html, "div.item", {"num": "span", "name": "b"}, normalize="NFKC")
+    assert items == [{"num": "123", "name": "Café"}]
+
+    doc = rusticsoup.parse_html(html)
+    assert doc.select_one("b").text(normalize="NFC") == "Café"
+    assert doc.select_one("b").text() == "Café"
+    assert rusticsoup.extract_all(html, "span", normalize="NFKC") == ["123"]
+
+
+def test_normalize_rejects_unknown_form():
+    with pytest.raises(ValueError):
+        rusticsoup.parse_html("<p>x</p>").text(normalize="NFX")
+
+
+@pytest.mark.parametrize("html, collapsed, joined", [
+    ("<div>  a \n\t b  <b> c </b>\n</div>", "a b c", "a \n\t b    c"),
+    ("<div>a\x0bb\xa0c　d</div>", "a b c d", "a\x0bb\xa0c　d"),
+    ("<div> <b> </b> x</div>", "x", "x"),
+    ("<div>\n<pre>  keep  </pre>\n</div>", "  keep  ", "  keep  "),
+    ("<div>a <pre>x </pre> </div>", "a x ", "a  x "),
+    ("<div> \n </div>", "", ""),
+])
+def test_text_joining_and_collapsing(html, collapsed, joined):
+    assert rusticsoup.parse_html(html).select_one("div").text() == collapsed
+    assert rusticsoup.extract_data(html, "body", {"t": "div"}) == [{"t": joined}]
@@ -0,0 +1,35 @@
+import json
+
+import rusticsoup
+
+HTML = """<html><head><title>T</title></head>
+<body><div id="main" class="a b"><p>Hello <b>world</b></p>
+<ul><li>one</li><li>two</li></ul></div></body></html>"""
+
+
+def test_document_to_dict():
+    tree = rusticsoup.parse_html(HTML).to_dict()
+    assert tree["tag"] == "html"
+    body = tree["children"][1]
+    div = body["children"][0]
+    assert div["attrs"] == {"id": "main", "class": "a b"}
+    assert div["children"][0] == {
+        "tag": "p", "attrs": {},
+        "children": ["Hello ", {"tag": "b", "attrs": {}, "children": ["world"]}],
+    }
+    # Plain dicts, lists and strings: ready for json.dumps
+    json.dumps(tree)
+
+
+def test_max_depth_collapses_to_text():
+    tree = rusticsoup.parse_html(HTML).to_dict(max_depth=2)
+    div = tree["children"][1]["children"][0]
+    assert "children" not in div
+    assert div["text"] == "Hello world one two"
+
+
+def test_whitespace_and_element_to_dict():
+    doc = rusticsoup.parse_html(HTML)
+    ul = doc.select_one("ul").to_dict()
+    assert [li["children"] for li in ul["children"]] == [["one"], ["two"]]
+    assert doc.to_dict(keep_whitespace=True)["children"][1] == "\n"
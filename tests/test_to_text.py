@@ -0,0 +1,66 @@
+import rusticsoup
+
+HTML = """<html><head><title>Ignored</title><style>p { color: red }</style></head><body>
+<h1>Title</h1>
+<p>Some <b>bold</b>
+   text.<br>Next line</p>
+<ul>
+  <li>One</li>
+  <li>Two
+    <ol start="3"><li>a</li><li>b</li></ol>
+  </li>
+</ul>
+<table>
+  <tr><th>Name</th><th>Price</th></tr>
+  <tr><td>Widget</td><td>$9</td></tr>
+</table>
+<pre>  x = 1
+  y = 2</pre>
+<div hidden>secret</div><script>track()</script>
+<div>end</div>
+</body></html>"""
+
+
+def test_layout_rendering():
+    assert rusticsoup.to_text(HTML) == "\n".join([
+        "Title",
+        "",
+        "Some bold text.",
+        "Next line",
+        "",
+        "- One",
+        "- Two",
+        "  3. a",
+        "  4. b",
+        "",
+        "Name   | Price",
+        "Widget | $9",
+        "",
+        "  x = 1",
+        "  y = 2",
+        "",
+        "end",
+    ])
+
+
+def test_plain_lines_without_layout():
+    assert rusticsoup.to_text(HTML, preserve_layout=False) == "\n".join([
+        "Title",
+        "Some bold text.",
+        "Next line",
+        "One",
+        "Two",
+        "a",
+        "b",
+        "Name Price",
+        "Widget $9",
+        "  x = 1",
+        "  y = 2",
+        "end",
+    ])
+
+
+def test_inline_spacing_and_normalize():
+    assert rusticsoup.to_text("<p>a<span>b</span> <i>c</i></p>") == "ab c"
+    assert rusticsoup.to_text("<p>ﬁne</p>", normalize="NFKC") == "fine"
+    assert rusticsoup.to_text(b"<p>caf\xc3\xa9</p>") == "café"
@@ -0,0 +1,67 @@
+import io
+
+import pytest
+import rusticsoup
+
+HTML = """<!DOCTYPE html>
+<html><head><title>A &amp; B</title>
+<script>if (a < b) { s = "</div>"; }</script></head>
+<body><p class="x" data-id=7>Hi &lt;you&gt;<br/>there<!-- note --></p>
+</body></html>"""
+
+
+def events(source, **kwargs):
+    return [(e["type"], e.get("name", e.get("data"))) for e in rusticsoup.tokenize(source, **kwargs)]
+
+
+def test_events():
+    stream = list(rusticsoup.tokenize(HTML))
+    assert stream[0] == {"type": "doctype", "name": "html", "line": 1}
+    p = next(e for e in stream if e.get("name") == "p")
+    assert p == {"type": "start", "name": "p", "attrs": {"class": "x", "data-id": "7"}, "self_closing": False, "line": 4}
+    assert events(HTML)[4:] == [
+        ("start", "title"), ("text", "A & B"), ("end", "title"), ("text", "\n"),
+        ("start", "script"), ("text", 'if (a < b) { s = "</div>"; }'), ("end", "script"),
+        ("end", "head"), ("text", "\n"),
+        ("start", "body"), ("start", "p"), ("text", "Hi <you>"), ("start", "br"), ("text", "there"),
+        ("comment", " note "), ("end", "p"), ("text", "\n"), ("end", "body"), ("end", "html"),
+    ]
+
+
+def test_tags_are_reported_as_written():
+    assert events("<p>one<p>two</b>") == [
+        ("start", "p"), ("text", "one"), ("start", "p"), ("text", "two"), ("end", "b"),
+    ]
+
+
+@pytest.mark.parametrize("source", [
+    HTML.encode("utf-8"),
+    io.BytesIO(HTML.encode("utf-8")),
+    io.StringIO(HTML),
+    [HTML[i:i + 7] for i in range(0, len(HTML), 7)],
+])
+def test_sources(source):
+    assert events(source) == events(HTML)
+
+
+def test_chunks_split_characters_and_entities():
+    chunks = (c for c in [b"<p>caf", b"\xc3", b"\xa9 &am", b"p; x</p>"])
+    assert events(chunks) == [("start", "p"), ("text", "café & x"), ("end", "p")]
+
+
+def test_bytes_decoding_options():
+    assert events("<p>café</p>".encode("latin-1"), from_encoding="latin-1")[1] == ("text", "café")
+
+
+def test_lazy_reading():
+    reader = io.StringIO("<p>" + "x" * 200_000 + "</p><i>")
+    stream = rusticsoup.tokenize(reader)
+    assert next(stream)["name"] == "p"
+    assert reader.tell() < 200_000
+
+
+def test_invalid_sources():
+    with pytest.raises(TypeError):
+        rusticsoup.tokenize(42)
+    with pytest.raises(TypeError):
+        list(rusticsoup.tokenize([b"<p>", 3]))
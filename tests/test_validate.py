@@ -0,0 +1,31 @@
+import rusticsoup
+
+
+def test_valid_mappings_have_no_problems():
+    assert rusticsoup.validate_mappings("div.product", {
+        "title": "h2",
+        "link": "a@href",
+        "tags": "li.tag@get_all",
+        "seller": {"name": "a.seller"},
+    }) == []
+
+
+def test_reports_every_problem():
+    problems = rusticsoup.validate_mappings("div >", {
+        "title": "h2",
+        "price": "span..amount",
+        "link": "a@",
+        "count": 3,
+        "seller": {"name": "a[href="},
+    })
+    by_field = {p["field"]: p for p in problems}
+    assert set(by_field) == {None, "price", "link", "count", "seller.name"}
+
+    assert by_field[None]["selector"] == "div >"
+    assert by_field["price"]["position"] == 4
+    assert "SelectorError" not in by_field["price"]["error"]
+    assert "span..amount" in by_field["price"]["error"]
+    assert "attribute" in by_field["link"]["error"]
+    assert by_field["link"]["position"] is None
+    assert "str or dict" in by_field["count"]["error"]
+    assert by_field["seller.name"]["selector"] == "a[href="
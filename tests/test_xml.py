@@ -0,0 +1,70 @@
+import gzip
+
+import pytest
+import rusticsoup
+
+SITEMAP = """<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc>https://example.com/a?x=1&amp;y=2</loc>
+    <lastMod>2024-05-01</lastMod>
+    <image:image><image:loc>https://example.com/a.jpg</image:loc></image:image>
+  </url>
+  <url><loc>https://example.com/b</loc></url>
+</urlset>"""
+
+
+def test_select_by_local_name():
+    doc = rusticsoup.parse_xml(SITEMAP)
+    assert [e.text() for e in doc.select("url > loc")] == ["https://example.com/a?x=1&y=2", "https://example.com/b"]
+    assert doc.select_one("image loc").text() == "https://example.com/a.jpg"
+    assert doc.xpath_getall("//url/loc/text()")[1] == "https://example.com/b"
+
+
+def test_names_are_case_sensitive():
+    doc = rusticsoup.parse_xml(SITEMAP)
+    assert doc.select("lastmod") == []
+    assert doc.select_one("lastMod").tag() == "lastMod"
+    doc = rusticsoup.parse_xml('<Root><Item Kind="A"/><item kind="b"/></Root>')
+    assert [e.attrs() for e in doc.select("Item")] == [{"Kind": "A"}]
+    assert len(doc.select("[Kind=A]")) == 1
+
+
+def test_no_html_fixups():
+    doc = rusticsoup.parse_xml("<feed><title>T</title><link href='/x'/><p><div>nested</div></p><table><td>1</td></table></feed>")
+    assert doc.select_one("link").attr("href") == "/x"
+    assert doc.select_one("p > div").text() == "nested"
+    assert doc.select_one("table > td").text() == "1"
+    assert doc.select("html, head, body") == []
+
+
+def test_element_select_and_html_keep_namespaces():
+    url = rusticsoup.parse_xml(SITEMAP).select_one("url")
+    assert url.html().startswith('<url xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">')
+    image = url.select_one("image")
+    assert image.html() == (
+        '<image:image xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">'
+        "<image:loc>https://example.com/a.jpg</image:loc></image:image>"
+    )
+    assert image.select_one("loc").text() == "https://example.com/a.jpg"
+    assert url.select_one("lastMod").text() == "2024-05-01"
+
+
+def test_document_html_round_trips():
+    xml = '<a x="1&amp;2"><!--c--><b>&lt;t&gt;</b><c/></a>'
+    doc = rusticsoup.parse_xml(xml)
+    assert doc.html() == xml
+    assert rusticsoup.parse_xml(doc.html()).html() == xml
+
+
+def test_bytes_and_gzip():
+    assert rusticsoup.parse_xml(gzip.compress(SITEMAP.encode())).select_one("loc").text().startswith("https://")
+    with pytest.raises(rusticsoup.LimitExceededError):
+        rusticsoup.parse_xml(SITEMAP, max_size=100)
+
+
+def test_malformed_xml():
+    with pytest.raises(rusticsoup.HTMLParseError) as info:
+        rusticsoup.parse_xml("<a><b></a>")
+    assert info.value.position == 8
@@ -0,0 +1,57 @@
+import pytest
+
+import rusticsoup
+
+# Not well-formed XML: void elements, unquoted attributes, unclosed <p>
+HTML = """
+<html><body>
+<div class=product data-id=1><h2>One</h2><br><p>cheap<a href="/1">buy</a>
+  <ul><li>a</li><li>b</li></ul></div>
+<div class=product data-id=2><h2>Two</h2><img src=x.png></div>
+</body></html>
+"""
+
+
+def test_xpath_on_html_that_is_not_xml():
+    doc = rusticsoup.parse_html(HTML)
+    assert [e.text() for e in doc.xpath("//h2")] == ["One", "Two"]
+    assert doc.xpath_getall("//div/@data-id") == ["1", "2"]
+    assert doc.xpath_get("//a/text()") == "buy"
+    assert doc.xpath_get("count(//li)") == "2"
+    assert doc.xpath_one("//div[h2='Two']").attr("data-id") == "2"
+
+
+def test_element_xpath_is_scoped():
+    doc = rusticsoup.parse_html(HTML)
+    second = doc.select("div.product")[1]
+    assert second.xpath_getall(".//h2/text()") == ["Two"]
+    assert [e.tag() for e in second.xpath("./img")] == ["img"]
+
+
+def test_invalid_xpath_raises_selector_error():
+    doc = rusticsoup.parse_html(HTML)
+    with pytest.raises(rusticsoup.SelectorError) as exc:
+        doc.xpath("//div[")
+    assert exc.value.selector == "//div["
+
+
+def test_xpath_field_specs_in_extract_data():
+    results = rusticsoup.extract_data(HTML, "div.product", {
+        "title": "xpath:./h2/text()",
+        "id": "xpath:@data-id",
+        "link": "xpath:.//a/@href",
+        "items": "xpath:.//li@get_all",
+        "css": "h2",
+    })
+    assert results == [
+        {"title": "One", "id": "1", "link": "/1", "items": ["a", "b"], "css": "One"},
+        {"title": "Two", "id": "2", "link": "", "items": [], "css": "Two"},
+    ]
+
+
+def test_invalid_xpath_field():
+    with pytest.raises(rusticsoup.SelectorError) as exc:
+        rusticsoup.extract_data(HTML, "div.product", {"title": "xpath:./h2["})
+    assert exc.value.field == "title"
+    problems = rusticsoup.validate_mappings("div", {"ok": "xpath:.//a/@href", "bad": "xpath:("})
+    assert [p["field"] for p in problems] == ["bad"]